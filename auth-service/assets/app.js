@@ -42,14 +42,23 @@ loginButton.addEventListener("click", (e) => {
     const email = loginForm.email.value;
     const password = loginForm.password.value;
 
-    fetch('/login', {
+    // When serving as the OpenID Connect authorization page, credentials are posted back
+    // to /authorize with the original query, which answers with the client redirect URI.
+    const authorizing = window.location.pathname === "/authorize";
+    const url = authorizing ? '/authorize' + window.location.search : '/login';
+
+    fetch(url, {
         method: 'POST',
         headers: {
             'Content-Type': 'application/json',
         },
         body: JSON.stringify({ email, password }),
     }).then(response => {
-        if (response.status === 206) {
+        if (authorizing && response.status === 200) {
+            response.json().then(data => {
+                window.location.assign(data.redirectUri);
+            });
+        } else if (response.status === 206) {
             TwoFAForm.email.value = email;
             response.json().then(data => {
                 TwoFAForm.login_attempt_id.value = data.loginAttemptId;
@@ -104,6 +113,7 @@ signupButton.addEventListener("click", (e) => {
             signupForm.password.value = "";
             signupForm.twoFA.checked = false;
             signupErrAlter.style.display = "none";
+            signupPasswordStrength.textContent = "";
             alert("You have successfully created a user.");
             loginSection.style.display = "block";
             twoFASection.style.display = "none";
@@ -112,7 +122,9 @@ signupButton.addEventListener("click", (e) => {
             response.json().then(data => {
                 let error_msg = data.error;
                 if (error_msg !== undefined && error_msg !== null && error_msg !== "") {
-                    signupErrAlter.innerHTML = `<span><strong>Error: </strong>${error_msg}</span>`;
+                    const advice = [data.warning, ...(data.suggestions || [])].filter(line => line);
+                    const advice_msg = advice.map(line => `<br><span>${line}</span>`).join("");
+                    signupErrAlter.innerHTML = `<span><strong>Error: </strong>${error_msg}</span>${advice_msg}`;
                     signupErrAlter.style.display = "block";
                 } else {
                     signupErrAlter.style.display = "none";
@@ -122,6 +134,36 @@ signupButton.addEventListener("click", (e) => {
     });
 });
 
+const signupPasswordStrength = document.getElementById("signup-password-strength");
+const strengthLabels = ["Very weak", "Weak", "Fair", "Strong", "Very strong"];
+let strengthTimeout;
+
+signupForm.password.addEventListener("input", () => {
+    clearTimeout(strengthTimeout);
+    const password = signupForm.password.value;
+    if (password === "") {
+        signupPasswordStrength.textContent = "";
+        return;
+    }
+    // Wait for a pause in typing, as estimates are rate-limited.
+    strengthTimeout = setTimeout(() => {
+        fetch('/api/password-strength', {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ email: signupForm.email.value, password }),
+        }).then(response => response.ok ? response.json() : null).then(data => {
+            if (data === null || signupForm.password.value !== password) {
+                return;
+            }
+            const advice = data.warning || (data.suggestions || [])[0] || "";
+            signupPasswordStrength.textContent = `${strengthLabels[data.score]}. ${advice}`.trim();
+            signupPasswordStrength.className = `form-text text-start d-block ${data.acceptable ? "text-success" : "text-danger"}`;
+        });
+    }, 300);
+});
+
 const TwoFAForm = document.getElementById("2fa-form");
 const TwoFAButton = document.getElementById("2fa-form-submit");
 const TwoFAErrAlter = document.getElementById("2fa-err-alert");
@@ -140,7 +182,12 @@ TwoFAButton.addEventListener("click", (e) => {
         },
         body: JSON.stringify({ email, loginAttemptId, "2FACode": TwoFACode }),
     }).then(response => {
-        if (response.ok) {
+        // A sign-in at /authorize is answered with the client redirect URI carrying the code.
+        if (window.location.pathname === "/authorize" && response.ok) {
+            response.json().then(data => {
+                window.location.assign(data.redirectUri);
+            });
+        } else if (response.ok) {
             TwoFAForm.email.value = "";
             TwoFAForm.email_code.value = "";
             TwoFAForm.login_attempt_id.value = "";
@@ -1,16 +1,282 @@
-use crate::services::HashmapUserStore;
+use crate::domain::{
+    EmailDomainPolicy, IdentityProvider, SignupMode, Tenant, FORGOT_PASSWORD_EMAIL_RATE_LIMIT,
+    FORGOT_PASSWORD_RATE_LIMIT,
+};
+use crate::services::{
+    AuditLog, EmailClient, GeoIpDatabase, HashmapApiKeyStore, HashmapAuthorizationCodeStore, HashmapBannedTokenStore,
+    HashmapClientStore, HashmapInvitationStore, HashmapLoginAttemptStore, HashmapPasswordResetStore,
+    HashmapSessionStore, HashmapUpstreamAuthorizationStore, HashmapUserStore, IdentityProviderConnector, Keyring,
+    LogEmailClient, PwnedPasswords, RateLimiter, DEFAULT_RATE_LIMIT,
+};
+use arc_swap::ArcSwap;
+use secrecy::SecretString;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+pub const DEFAULT_ISSUER: &str = "http://localhost:3000";
+
 pub type UserStoreType = Arc<RwLock<HashmapUserStore>>;
+pub type KeyringType = Arc<RwLock<Keyring>>;
+pub type ClientStoreType = Arc<RwLock<HashmapClientStore>>;
+pub type AuthorizationCodeStoreType = Arc<RwLock<HashmapAuthorizationCodeStore>>;
+pub type BannedTokenStoreType = Arc<RwLock<HashmapBannedTokenStore>>;
+pub type SessionStoreType = Arc<RwLock<HashmapSessionStore>>;
+pub type UpstreamAuthorizationStoreType = Arc<RwLock<HashmapUpstreamAuthorizationStore>>;
+pub type InvitationStoreType = Arc<RwLock<HashmapInvitationStore>>;
+pub type PasswordResetStoreType = Arc<RwLock<HashmapPasswordResetStore>>;
+pub type LoginAttemptStoreType = Arc<RwLock<HashmapLoginAttemptStore>>;
+pub type ApiKeyStoreType = Arc<RwLock<HashmapApiKeyStore>>;
+pub type AuditLogType = Arc<RwLock<AuditLog>>;
+pub type EmailClientType = Arc<RwLock<dyn EmailClient>>;
+pub type RateLimiterType = Arc<RwLock<RateLimiter>>;
+pub type EmailRateLimiterType = Arc<RwLock<RateLimiter<String>>>;
+pub type SettingsType = Arc<ArcSwap<Settings>>;
+pub type IdentityProvidersType = Arc<HashMap<String, IdentityProvider>>;
+
+/// Settings that can be reloaded while serving. They are replaced as a whole, so that no
+/// request sees a reload half-applied.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// Tenants by id, always including the default tenant, whose password policy is reloaded
+    /// unless configured in the tenants file.
+    pub tenants: HashMap<String, Tenant>,
+    /// Password strength estimates each client may ask for per minute.
+    pub password_strength_rate_limit: u32,
+    /// Email domains users may sign up with.
+    pub email_domain_policy: EmailDomainPolicy,
+}
 
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            tenants: tenants(Vec::new()),
+            password_strength_rate_limit: DEFAULT_RATE_LIMIT,
+            email_domain_policy: EmailDomainPolicy::default(),
+        }
+    }
+}
+
+/// Shared state of the service. Stores not passed to [`AppState::new`] start empty
+/// and can be replaced with the `with_*` methods.
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub user_store: UserStoreType,
+    pub keyring: KeyringType,
+    pub client_store: ClientStoreType,
+    pub authorization_code_store: AuthorizationCodeStoreType,
+    pub banned_token_store: BannedTokenStoreType,
+    pub session_store: SessionStoreType,
+    pub upstream_authorization_store: UpstreamAuthorizationStoreType,
+    pub invitation_store: InvitationStoreType,
+    pub password_reset_store: PasswordResetStoreType,
+    pub login_attempt_store: LoginAttemptStoreType,
+    pub api_key_store: ApiKeyStoreType,
+    pub audit_log: AuditLogType,
+    pub email_client: EmailClientType,
+    pub geoip_database: Arc<GeoIpDatabase>,
+    pub pwned_passwords: Arc<PwnedPasswords>,
+    /// Counts password strength estimates by client, against the limit of the settings.
+    pub password_strength_rate_limiter: RateLimiterType,
+    pub forgot_password_rate_limiter: RateLimiterType,
+    /// Limits password resets per account, keyed by tenant and email address.
+    pub forgot_password_email_rate_limiter: EmailRateLimiterType,
+    pub identity_providers: IdentityProvidersType,
+    pub identity_provider_connector: Arc<IdentityProviderConnector>,
+    /// Settings reloaded while serving, read by requests as one snapshot.
+    pub settings: SettingsType,
+    pub signup_mode: SignupMode,
+    /// Whether sign-ins from new devices or places require 2FA, even from users without it.
+    pub step_up_risky_logins: bool,
+    pub issuer: String,
+    pub admin_api_key: Option<SecretString>,
 }
 
 impl AppState {
-    pub fn new(user_store: UserStoreType) -> Self {
-        Self { user_store }
+    pub fn new(user_store: UserStoreType, keyring: KeyringType) -> Self {
+        Self {
+            user_store,
+            keyring,
+            client_store: Default::default(),
+            authorization_code_store: Default::default(),
+            banned_token_store: Default::default(),
+            session_store: Default::default(),
+            upstream_authorization_store: Default::default(),
+            invitation_store: Default::default(),
+            password_reset_store: Default::default(),
+            login_attempt_store: Default::default(),
+            api_key_store: Default::default(),
+            audit_log: Default::default(),
+            email_client: Arc::new(RwLock::new(LogEmailClient)),
+            geoip_database: Default::default(),
+            pwned_passwords: Default::default(),
+            password_strength_rate_limiter: Default::default(),
+            forgot_password_rate_limiter: Arc::new(RwLock::new(RateLimiter::new(FORGOT_PASSWORD_RATE_LIMIT))),
+            forgot_password_email_rate_limiter: Arc::new(RwLock::new(RateLimiter::new(
+                FORGOT_PASSWORD_EMAIL_RATE_LIMIT,
+            ))),
+            identity_providers: Default::default(),
+            identity_provider_connector: Default::default(),
+            settings: Default::default(),
+            signup_mode: SignupMode::default(),
+            step_up_risky_logins: false,
+            issuer: DEFAULT_ISSUER.to_string(),
+            admin_api_key: None,
+        }
+    }
+
+    pub fn with_client_store(mut self, client_store: ClientStoreType) -> Self {
+        self.client_store = client_store;
+        self
+    }
+
+    pub fn with_authorization_code_store(mut self, authorization_code_store: AuthorizationCodeStoreType) -> Self {
+        self.authorization_code_store = authorization_code_store;
+        self
+    }
+
+    pub fn with_banned_token_store(mut self, banned_token_store: BannedTokenStoreType) -> Self {
+        self.banned_token_store = banned_token_store;
+        self
+    }
+
+    pub fn with_session_store(mut self, session_store: SessionStoreType) -> Self {
+        self.session_store = session_store;
+        self
+    }
+
+    pub fn with_upstream_authorization_store(
+        mut self,
+        upstream_authorization_store: UpstreamAuthorizationStoreType,
+    ) -> Self {
+        self.upstream_authorization_store = upstream_authorization_store;
+        self
+    }
+
+    pub fn with_invitation_store(mut self, invitation_store: InvitationStoreType) -> Self {
+        self.invitation_store = invitation_store;
+        self
+    }
+
+    pub fn with_password_reset_store(mut self, password_reset_store: PasswordResetStoreType) -> Self {
+        self.password_reset_store = password_reset_store;
+        self
+    }
+
+    pub fn with_login_attempt_store(mut self, login_attempt_store: LoginAttemptStoreType) -> Self {
+        self.login_attempt_store = login_attempt_store;
+        self
+    }
+
+    pub fn with_api_key_store(mut self, api_key_store: ApiKeyStoreType) -> Self {
+        self.api_key_store = api_key_store;
+        self
+    }
+
+    pub fn with_audit_log(mut self, audit_log: AuditLogType) -> Self {
+        self.audit_log = audit_log;
+        self
+    }
+
+    /// Mail provider new sign-in and password reset emails are sent through. Emails are only
+    /// logged without one.
+    pub fn with_email_client(mut self, email_client: EmailClientType) -> Self {
+        self.email_client = email_client;
+        self
+    }
+
+    /// Offline GeoIP database locating sign-ins, to detect impossible travel.
+    pub fn with_geoip_database(mut self, geoip_database: GeoIpDatabase) -> Self {
+        self.geoip_database = Arc::new(geoip_database);
+        self
+    }
+
+    /// Breached passwords new passwords are checked against.
+    pub fn with_pwned_passwords(mut self, pwned_passwords: PwnedPasswords) -> Self {
+        self.pwned_passwords = Arc::new(pwned_passwords);
+        self
+    }
+
+    /// Limits how often each client may estimate the strength of a password.
+    pub fn with_password_strength_rate_limit(self, limit: u32) -> Self {
+        self.with_settings(|settings| settings.password_strength_rate_limit = limit)
+    }
+
+    /// Limits how often password resets may be asked for, by client address and by account.
+    pub fn with_forgot_password_rate_limiters(
+        mut self,
+        rate_limiter: RateLimiterType,
+        email_rate_limiter: EmailRateLimiterType,
+    ) -> Self {
+        self.forgot_password_rate_limiter = rate_limiter;
+        self.forgot_password_email_rate_limiter = email_rate_limiter;
+        self
+    }
+
+    /// Email domains users may sign up with.
+    pub fn with_email_domain_policy(self, email_domain_policy: EmailDomainPolicy) -> Self {
+        self.with_settings(|settings| settings.email_domain_policy = email_domain_policy)
+    }
+
+    /// Upstream identity providers users can sign in with, by name.
+    pub fn with_identity_providers(mut self, identity_providers: Vec<IdentityProvider>) -> Self {
+        let identity_providers = identity_providers
+            .into_iter()
+            .map(|provider| (provider.name.clone(), provider))
+            .collect();
+        self.identity_providers = Arc::new(identity_providers);
+        self
+    }
+
+    /// Tenants users are isolated in. The default tenant is added unless configured.
+    pub fn with_tenants(self, tenants: Vec<Tenant>) -> Self {
+        self.with_settings(|settings| settings.tenants = self::tenants(tenants))
+    }
+
+    /// Replaces the settings, before the state is shared.
+    fn with_settings(mut self, update: impl FnOnce(&mut Settings)) -> Self {
+        let mut settings = Settings::clone(&self.settings.load());
+        update(&mut settings);
+        self.settings = Arc::new(ArcSwap::from_pointee(settings));
+        self
     }
-}
\ No newline at end of file
+
+    pub fn tenant(&self, tenant_id: &str) -> Option<Tenant> {
+        self.settings.load().tenants.get(tenant_id).cloned()
+    }
+
+    pub fn tenant_for_host(&self, host: &str) -> Option<Tenant> {
+        let host = host.to_ascii_lowercase();
+        self.settings.load().tenants.values().find(|tenant| tenant.hosts.contains(&host)).cloned()
+    }
+
+    pub fn with_signup_mode(mut self, signup_mode: SignupMode) -> Self {
+        self.signup_mode = signup_mode;
+        self
+    }
+
+    pub fn with_step_up_risky_logins(mut self, step_up_risky_logins: bool) -> Self {
+        self.step_up_risky_logins = step_up_risky_logins;
+        self
+    }
+
+    pub fn with_issuer(mut self, issuer: &str) -> Self {
+        self.issuer = issuer.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn with_admin_api_key(mut self, admin_api_key: Option<SecretString>) -> Self {
+        self.admin_api_key = admin_api_key;
+        self
+    }
+}
+
+fn tenants(tenants: Vec<Tenant>) -> HashMap<String, Tenant> {
+    let mut tenants: HashMap<String, Tenant> = tenants
+        .into_iter()
+        .map(|tenant| (tenant.id.clone(), tenant))
+        .collect();
+    let default = Tenant::default();
+    tenants.entry(default.id.clone()).or_insert(default);
+    tenants
+}
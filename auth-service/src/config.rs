@@ -1,17 +1,70 @@
+use auth_service::domain::{
+    DenyList, DomainList, DomainListError, EmailDomainPolicy, PasswordPolicy, PasswordPolicyError, SignupMode,
+    MAX_PASSWORD_LENGTH, MAX_PASSWORD_SCORE, MIN_PASSWORD_LENGTH, MIN_PASSWORD_SCORE,
+};
+use auth_service::services::{
+    CertificateResolver, PostmarkEmailClient, TlsError, TlsSettings, DEFAULT_RATE_LIMIT, TOKEN_TTL_SECONDS,
+};
+use clap::parser::ValueSource;
 use clap::ArgGroup;
+use clap::ArgMatches;
+use clap::CommandFactory;
+use clap::FromArgMatches;
 use clap::Parser;
+use clap::Subcommand;
 use clap::ValueEnum;
+use email_address::EmailAddress;
 use fmt::{Display, Formatter};
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::convert::Infallible;
 use std::fmt;
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use thiserror::Error;
+use url::Url;
+
+pub const CONFIG_FILE: &str = "AUTH_SERVICE_CONFIG";
+const REDACTED: &str = "[REDACTED]";
 
 pub const CONFIG_HOST_IPV4: &str = "AUTH_SERVICE_HOST_IPV4";
 pub const CONFIG_HOST_IPV6: &str = "AUTH_SERVICE_HOST_IPV6";
 pub const CONFIG_PORT: &str = "AUTH_SERVICE_PORT";
 pub const CONFIG_LOG: &str = "AUTH_SERVICE_LOG";
+pub const CONFIG_TOKEN_TTL: &str = "AUTH_SERVICE_TOKEN_TTL";
+pub const CONFIG_ISSUER: &str = "AUTH_SERVICE_ISSUER";
+pub const CONFIG_TLS_CERT_FILE: &str = "AUTH_SERVICE_TLS_CERT_FILE";
+pub const CONFIG_TLS_KEY_FILE: &str = "AUTH_SERVICE_TLS_KEY_FILE";
+pub const CONFIG_HTTP_REDIRECT_PORT: &str = "AUTH_SERVICE_HTTP_REDIRECT_PORT";
+pub const CONFIG_CLIENTS_FILE: &str = "AUTH_SERVICE_CLIENTS_FILE";
+pub const CONFIG_PROVIDERS_FILE: &str = "AUTH_SERVICE_PROVIDERS_FILE";
+pub const CONFIG_TENANTS_FILE: &str = "AUTH_SERVICE_TENANTS_FILE";
+pub const CONFIG_SIGNUP_MODE: &str = "AUTH_SERVICE_SIGNUP_MODE";
+pub const CONFIG_EMAIL_DOMAIN_ALLOW_LIST_FILE: &str = "AUTH_SERVICE_EMAIL_DOMAIN_ALLOW_LIST_FILE";
+pub const CONFIG_EMAIL_DOMAIN_DENY_LIST_FILE: &str = "AUTH_SERVICE_EMAIL_DOMAIN_DENY_LIST_FILE";
+pub const CONFIG_ALLOW_DISPOSABLE_EMAILS: &str = "AUTH_SERVICE_ALLOW_DISPOSABLE_EMAILS";
+pub const CONFIG_ADMIN_API_KEY: &str = "AUTH_SERVICE_ADMIN_API_KEY";
+pub const CONFIG_ADMIN_API_KEY_FILE: &str = "AUTH_SERVICE_ADMIN_API_KEY_FILE";
+pub const CONFIG_AUDIT_LOG_FILE: &str = "AUTH_SERVICE_AUDIT_LOG_FILE";
+pub const CONFIG_AUDIT_CHECKPOINT_INTERVAL: &str = "AUTH_SERVICE_AUDIT_CHECKPOINT_INTERVAL";
+pub const CONFIG_EMAIL_API_URL: &str = "AUTH_SERVICE_EMAIL_API_URL";
+pub const CONFIG_EMAIL_SENDER: &str = "AUTH_SERVICE_EMAIL_SENDER";
+pub const CONFIG_EMAIL_SERVER_TOKEN: &str = "AUTH_SERVICE_EMAIL_SERVER_TOKEN";
+pub const CONFIG_GEOIP_FILE: &str = "AUTH_SERVICE_GEOIP_FILE";
+pub const CONFIG_STEP_UP_RISKY_LOGINS: &str = "AUTH_SERVICE_STEP_UP_RISKY_LOGINS";
+pub const CONFIG_PWNED_PASSWORDS: &str = "AUTH_SERVICE_PWNED_PASSWORDS";
+pub const CONFIG_PASSWORD_MIN_LENGTH: &str = "AUTH_SERVICE_PASSWORD_MIN_LENGTH";
+pub const CONFIG_PASSWORD_MAX_LENGTH: &str = "AUTH_SERVICE_PASSWORD_MAX_LENGTH";
+pub const CONFIG_PASSWORD_MIN_SCORE: &str = "AUTH_SERVICE_PASSWORD_MIN_SCORE";
+pub const CONFIG_PASSWORD_DENY_LIST_FILE: &str = "AUTH_SERVICE_PASSWORD_DENY_LIST_FILE";
+pub const CONFIG_PASSWORD_HISTORY_DEPTH: &str = "AUTH_SERVICE_PASSWORD_HISTORY_DEPTH";
+pub const CONFIG_PASSWORD_MAX_AGE: &str = "AUTH_SERVICE_PASSWORD_MAX_AGE";
+pub const CONFIG_PASSWORD_STRENGTH_RATE_LIMIT: &str = "AUTH_SERVICE_PASSWORD_STRENGTH_RATE_LIMIT";
 
-#[derive(ValueEnum, Clone, Debug)]
+#[derive(ValueEnum, Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[value(rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
 pub enum LogLevel {
     Trace,
     Debug,
@@ -32,6 +85,41 @@ impl Display for LogLevel {
     }
 }
 
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Verifies the hash chain and checkpoint signatures of a JSON-lines audit log, reporting
+    /// the first broken link.
+    VerifyAuditLog {
+        #[arg(help = "JSON-lines audit log file to verify.")]
+        file: PathBuf,
+        #[arg(
+            long,
+            help = "JWKS file with public keys checkpoints may be signed by, besides those of the keys file \
+                    written next to the log. Without either, checkpoints are only checked against the public key \
+                    they embed.",
+        )]
+        jwks_file: Option<PathBuf>,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read config file {0}: {1}")]
+    UnreadableFile(String, String),
+    #[error("Invalid config file {0}: {1}")]
+    InvalidFile(String, String),
+    #[error("Invalid {0}: {1}")]
+    InvalidValue(&'static str, String),
+    #[error("Failed to read {0} from {1}: {2}")]
+    UnreadableSecret(&'static str, String, String),
+    #[error("Invalid password policy: {0}")]
+    InvalidPasswordPolicy(#[from] PasswordPolicyError),
+    #[error("Invalid email domain list: {0}")]
+    InvalidDomainList(#[from] DomainListError),
+    #[error("Invalid TLS settings: {0}")]
+    InvalidTls(#[from] TlsError),
+}
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 #[command(group(
@@ -41,6 +129,15 @@ impl Display for LogLevel {
     .multiple(false)
 ))]
 pub struct Config {
+    #[arg(
+        long,
+        env = CONFIG_FILE,
+        help = "TOML file with the settings below, in a section per subsystem. Command-line flags and environment \
+                variables take precedence over it.",
+    )]
+    pub config: Option<PathBuf>,
+    #[arg(long, help = "Print the effective configuration as TOML, with secrets redacted, and exit.")]
+    pub print_config: bool,
     #[arg(
         long,
         env = CONFIG_HOST_IPV4,
@@ -68,17 +165,831 @@ pub struct Config {
         help = "Log level for the service.",
     )]
     pub log: LogLevel,
+    #[arg(
+        long,
+        env = CONFIG_TOKEN_TTL,
+        default_value_t = TOKEN_TTL_SECONDS,
+        help = "Lifetime of issued tokens, in seconds.",
+        value_parser = clap::value_parser!(u64).range(1..),
+    )]
+    pub token_ttl: u64,
+    #[arg(
+        long,
+        env = CONFIG_ISSUER,
+        default_value = "http://localhost:3000",
+        help = "Public base URL of the service, used as the OpenID Connect issuer.",
+    )]
+    pub issuer: String,
+    #[arg(
+        long,
+        env = CONFIG_TLS_CERT_FILE,
+        requires = "tls_key_file",
+        help = "PEM file of the certificate chain to serve HTTPS with, reloaded when it changes. \
+                Plain HTTP is served without it.",
+    )]
+    pub tls_cert_file: Option<PathBuf>,
+    #[arg(
+        long,
+        env = CONFIG_TLS_KEY_FILE,
+        requires = "tls_cert_file",
+        help = "PEM file of the private key of the certificate.",
+    )]
+    pub tls_key_file: Option<PathBuf>,
+    #[arg(
+        long,
+        env = CONFIG_HTTP_REDIRECT_PORT,
+        requires = "tls_cert_file",
+        help = "Port to redirect plain HTTP requests to HTTPS from.",
+        value_parser = clap::value_parser!(u16).range(1024..),
+    )]
+    pub http_redirect_port: Option<u16>,
+    #[arg(
+        long,
+        env = CONFIG_CLIENTS_FILE,
+        help = "JSON file with the OpenID Connect clients to register at startup.",
+    )]
+    pub clients_file: Option<PathBuf>,
+    #[arg(
+        long,
+        env = CONFIG_PROVIDERS_FILE,
+        help = "JSON file with the upstream identity providers users can sign in with.",
+    )]
+    pub providers_file: Option<PathBuf>,
+    #[arg(
+        long,
+        env = CONFIG_TENANTS_FILE,
+        help = "JSON file with the tenants users are isolated in, besides the default tenant.",
+    )]
+    pub tenants_file: Option<PathBuf>,
+    #[arg(
+        long,
+        env = CONFIG_SIGNUP_MODE,
+        default_value = "open",
+        help = "Who may sign up: open, invite-only or closed.",
+    )]
+    pub signup_mode: SignupMode,
+    #[arg(
+        long,
+        env = CONFIG_EMAIL_DOMAIN_ALLOW_LIST_FILE,
+        help = "File of email domains, one per line, users must sign up with. Subdomains are allowed too.",
+    )]
+    pub email_domain_allow_list_file: Option<PathBuf>,
+    #[arg(
+        long,
+        env = CONFIG_EMAIL_DOMAIN_DENY_LIST_FILE,
+        help = "File of email domains, one per line, users may not sign up with. Subdomains are denied too.",
+    )]
+    pub email_domain_deny_list_file: Option<PathBuf>,
+    #[arg(
+        long,
+        env = CONFIG_ALLOW_DISPOSABLE_EMAILS,
+        help = "Let users sign up with disposable email addresses, rejected by default.",
+    )]
+    pub allow_disposable_emails: bool,
+    #[arg(
+        long,
+        env = CONFIG_ADMIN_API_KEY,
+        hide_env_values = true,
+        help = "API key granting access to the admin endpoints. Admin endpoints are disabled without it.",
+    )]
+    pub admin_api_key: Option<Secret>,
+    #[arg(
+        long,
+        env = CONFIG_ADMIN_API_KEY_FILE,
+        conflicts_with = "admin_api_key",
+        help = "File holding the admin API key, such as a Docker or Kubernetes secret, in place of --admin-api-key.",
+    )]
+    pub admin_api_key_file: Option<PathBuf>,
+    #[arg(
+        long,
+        env = CONFIG_AUDIT_LOG_FILE,
+        help = "JSON-lines file the audit log is appended to. Audit events are kept in memory without it.",
+    )]
+    pub audit_log_file: Option<PathBuf>,
+    #[arg(
+        long,
+        env = CONFIG_AUDIT_CHECKPOINT_INTERVAL,
+        default_value = "100",
+        help = "Audit events between two checkpoints signed with the service signing key.",
+        value_parser = clap::value_parser!(u64).range(1..),
+    )]
+    pub audit_checkpoint_interval: u64,
+    #[arg(
+        long,
+        env = CONFIG_EMAIL_API_URL,
+        help = "Base URL of the Postmark-compatible API new sign-in and password reset emails are sent through, \
+                e.g. https://api.postmarkapp.com. Emails are dropped, and only logged, without it.",
+    )]
+    pub email_api_url: Option<String>,
+    #[arg(
+        long,
+        env = CONFIG_EMAIL_SENDER,
+        help = "Email address emails are sent from.",
+    )]
+    pub email_sender: Option<String>,
+    #[arg(
+        long,
+        env = CONFIG_EMAIL_SERVER_TOKEN,
+        hide_env_values = true,
+        help = "Server token authenticating to the email API.",
+    )]
+    pub email_server_token: Option<Secret>,
+    #[arg(
+        long,
+        env = CONFIG_GEOIP_FILE,
+        help = "CSV GeoIP database (network, latitude, longitude and optional country columns), used to flag \
+                sign-ins from impossibly far since the last one.",
+    )]
+    pub geoip_file: Option<PathBuf>,
+    #[arg(
+        long,
+        env = CONFIG_STEP_UP_RISKY_LOGINS,
+        help = "Require 2FA for sign-ins from a new device or an impossible location, even for users without 2FA.",
+    )]
+    pub step_up_risky_logins: bool,
+    #[arg(
+        long,
+        env = CONFIG_PWNED_PASSWORDS,
+        help = "Pwned Passwords SHA-1 dataset new passwords must not appear in: a directory of range files \
+                named after their hash prefix (e.g. 5BAA6.txt), or a single file of hashes sorted by hash.",
+    )]
+    pub pwned_passwords: Option<PathBuf>,
+    #[arg(
+        long,
+        env = CONFIG_PASSWORD_MIN_LENGTH,
+        default_value_t = MIN_PASSWORD_LENGTH,
+        help = "Minimum length of passwords of the default tenant. Other tenants set their own policy.",
+    )]
+    pub password_min_length: usize,
+    #[arg(
+        long,
+        env = CONFIG_PASSWORD_MAX_LENGTH,
+        default_value_t = MAX_PASSWORD_LENGTH,
+        help = "Maximum length of passwords of the default tenant.",
+    )]
+    pub password_max_length: usize,
+    #[arg(
+        long,
+        env = CONFIG_PASSWORD_MIN_SCORE,
+        default_value_t = MIN_PASSWORD_SCORE,
+        help = "Minimum zxcvbn score of passwords of the default tenant, from 0 to 4.",
+        value_parser = clap::value_parser!(u8).range(0..=MAX_PASSWORD_SCORE as i64),
+    )]
+    pub password_min_score: u8,
+    #[arg(
+        long,
+        env = CONFIG_PASSWORD_DENY_LIST_FILE,
+        help = "File of passwords, one per line, the default tenant refuses whatever their strength.",
+    )]
+    pub password_deny_list_file: Option<PathBuf>,
+    #[arg(
+        long,
+        env = CONFIG_PASSWORD_HISTORY_DEPTH,
+        default_value = "0",
+        help = "Previous passwords users of the default tenant may not reuse.",
+    )]
+    pub password_history_depth: usize,
+    #[arg(
+        long,
+        env = CONFIG_PASSWORD_MAX_AGE,
+        help = "Seconds after which passwords of the default tenant expire and must be reset.",
+        value_parser = clap::value_parser!(u64).range(1..),
+    )]
+    pub password_max_age: Option<u64>,
+    #[arg(
+        long,
+        env = CONFIG_PASSWORD_STRENGTH_RATE_LIMIT,
+        default_value_t = DEFAULT_RATE_LIMIT,
+        help = "Password strength estimates each client address may request per minute.",
+        value_parser = clap::value_parser!(u32).range(1..),
+    )]
+    pub password_strength_rate_limit: u32,
+    #[command(subcommand)]
+    pub command: Option<Command>,
 }
 
 impl Display for Config {
     fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
         write!(
             formatter,
-            "Config {{ ipv4:{:?}, ipv6:{:?}, port:{:?}, log:{:?} }}",
+            "Config {{ config:{:?}, ipv4:{:?}, ipv6:{:?}, port:{:?}, log:{:?}, token_ttl:{:?}, issuer:{:?}, tls_cert_file:{:?}, tls_key_file:{:?}, http_redirect_port:{:?}, clients_file:{:?}, providers_file:{:?}, tenants_file:{:?}, signup_mode:{}, email_domain_allow_list_file:{:?}, email_domain_deny_list_file:{:?}, allow_disposable_emails:{:?}, admin_api_key:{:?}, admin_api_key_file:{:?}, audit_log_file:{:?}, audit_checkpoint_interval:{:?}, email_api_url:{:?}, email_sender:{:?}, email_server_token:{:?}, geoip_file:{:?}, step_up_risky_logins:{:?}, pwned_passwords:{:?}, password_min_length:{:?}, password_max_length:{:?}, password_min_score:{:?}, password_deny_list_file:{:?}, password_history_depth:{:?}, password_max_age:{:?}, password_strength_rate_limit:{:?} }}",
+            self.config,
             self.ipv4,
             self.ipv6,
             self.port,
             self.log,
+            self.token_ttl,
+            self.issuer,
+            self.tls_cert_file,
+            self.tls_key_file,
+            self.http_redirect_port,
+            self.clients_file,
+            self.providers_file,
+            self.tenants_file,
+            self.signup_mode,
+            self.email_domain_allow_list_file,
+            self.email_domain_deny_list_file,
+            self.allow_disposable_emails,
+            self.admin_api_key,
+            self.admin_api_key_file,
+            self.audit_log_file,
+            self.audit_checkpoint_interval,
+            self.email_api_url,
+            self.email_sender,
+            self.email_server_token,
+            self.geoip_file,
+            self.step_up_risky_logins,
+            self.pwned_passwords,
+            self.password_min_length,
+            self.password_max_length,
+            self.password_min_score,
+            self.password_deny_list_file,
+            self.password_history_depth,
+            self.password_max_age,
+            self.password_strength_rate_limit,
         )
     }
-}
\ No newline at end of file
+}
+
+impl Config {
+    /// Parses the command line and the environment, over the config file if any, and
+    /// validates the effective settings.
+    pub fn load() -> Result<Self, ConfigError> {
+        Self::load_from(Self::command().get_matches())
+    }
+
+    fn load_from(matches: ArgMatches) -> Result<Self, ConfigError> {
+        let mut config = Self::from_arg_matches(&matches).unwrap_or_else(|error| error.exit());
+        if let Some(path) = config.config.clone() {
+            config.merge(ConfigFile::load(&path)?, &matches)?;
+        }
+        config.read_secrets()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Takes the settings of the file that neither the command line nor the environment set.
+    /// Settings are thus taken from, in order of precedence: the command line, the
+    /// environment, the file, and the defaults.
+    fn merge(&mut self, file: ConfigFile, matches: &ArgMatches) -> Result<(), ConfigError> {
+        fn layer<T>(setting: &mut T, value: Option<T>, is_set: bool) {
+            if let Some(value) = value
+                && !is_set
+            {
+                *setting = value;
+            }
+        }
+        let is_set = |id: &str| {
+            matches!(matches.value_source(id), Some(ValueSource::CommandLine | ValueSource::EnvVariable))
+        };
+        let ConfigFile { server, tls, tokens, oidc, tenants, signup, admin, audit, email, login, password } = file;
+
+        // The addresses are exclusive, so that setting either replaces both.
+        if !is_set("ipv4") && !is_set("ipv6") && (server.ipv4.is_some() || server.ipv6.is_some()) {
+            (self.ipv4, self.ipv6) = (server.ipv4, server.ipv6);
+        }
+        layer(&mut self.port, server.port, is_set("port"));
+        layer(&mut self.log, server.log, is_set("log"));
+        layer(&mut self.issuer, server.issuer, is_set("issuer"));
+        layer(&mut self.tls_cert_file, tls.cert_file.map(Some), is_set("tls_cert_file"));
+        layer(&mut self.tls_key_file, tls.key_file.map(Some), is_set("tls_key_file"));
+        layer(&mut self.http_redirect_port, tls.redirect_port.map(Some), is_set("http_redirect_port"));
+        layer(&mut self.token_ttl, tokens.ttl, is_set("token_ttl"));
+        layer(&mut self.clients_file, oidc.clients_file.map(Some), is_set("clients_file"));
+        layer(&mut self.providers_file, oidc.providers_file.map(Some), is_set("providers_file"));
+        layer(&mut self.tenants_file, tenants.file.map(Some), is_set("tenants_file"));
+        let signup_mode = signup.mode.map(|mode| mode.parse::<SignupMode>()).transpose();
+        let signup_mode = signup_mode.map_err(|error| ConfigError::InvalidValue("signup.mode", error.to_string()))?;
+        layer(&mut self.signup_mode, signup_mode, is_set("signup_mode"));
+        let allow_list_file = signup.email_domain_allow_list_file.map(Some);
+        layer(&mut self.email_domain_allow_list_file, allow_list_file, is_set("email_domain_allow_list_file"));
+        let deny_list_file = signup.email_domain_deny_list_file.map(Some);
+        layer(&mut self.email_domain_deny_list_file, deny_list_file, is_set("email_domain_deny_list_file"));
+        let allow_disposable_emails = signup.allow_disposable_emails;
+        layer(&mut self.allow_disposable_emails, allow_disposable_emails, is_set("allow_disposable_emails"));
+        // Likewise, the key and its file replace each other.
+        let is_admin_api_key_set = is_set("admin_api_key") || is_set("admin_api_key_file");
+        if !is_admin_api_key_set && (admin.api_key.is_some() || admin.api_key_file.is_some()) {
+            (self.admin_api_key, self.admin_api_key_file) = (admin.api_key, admin.api_key_file);
+        }
+        layer(&mut self.audit_log_file, audit.log_file.map(Some), is_set("audit_log_file"));
+        layer(&mut self.audit_checkpoint_interval, audit.checkpoint_interval, is_set("audit_checkpoint_interval"));
+        layer(&mut self.email_api_url, email.api_url.map(Some), is_set("email_api_url"));
+        layer(&mut self.email_sender, email.sender.map(Some), is_set("email_sender"));
+        layer(&mut self.email_server_token, email.server_token.map(Some), is_set("email_server_token"));
+        layer(&mut self.geoip_file, login.geoip_file.map(Some), is_set("geoip_file"));
+        layer(&mut self.step_up_risky_logins, login.step_up_risky_logins, is_set("step_up_risky_logins"));
+        layer(&mut self.pwned_passwords, password.pwned_passwords.map(Some), is_set("pwned_passwords"));
+        layer(&mut self.password_min_length, password.min_length, is_set("password_min_length"));
+        layer(&mut self.password_max_length, password.max_length, is_set("password_max_length"));
+        layer(&mut self.password_min_score, password.min_score, is_set("password_min_score"));
+        let deny_list_file = password.deny_list_file.map(Some);
+        layer(&mut self.password_deny_list_file, deny_list_file, is_set("password_deny_list_file"));
+        layer(&mut self.password_history_depth, password.history_depth, is_set("password_history_depth"));
+        layer(&mut self.password_max_age, password.max_age.map(Some), is_set("password_max_age"));
+        let strength_rate_limit = password.strength_rate_limit;
+        layer(&mut self.password_strength_rate_limit, strength_rate_limit, is_set("password_strength_rate_limit"));
+        Ok(())
+    }
+
+    /// Reads the secrets given as files in place of values.
+    fn read_secrets(&mut self) -> Result<(), ConfigError> {
+        if let Some(path) = &self.admin_api_key_file {
+            if self.admin_api_key.is_some() {
+                let message = "only one of the key and its file may be set".to_string();
+                return Err(ConfigError::InvalidValue("admin API key", message));
+            }
+            self.admin_api_key = Some(Secret::read("admin API key", path)?);
+        }
+        Ok(())
+    }
+
+    /// Checks what the command line checks when parsing, since settings of the file skip it,
+    /// then loads the policies to catch unreadable lists before serving.
+    fn validate(&self) -> Result<(), ConfigError> {
+        let at_least = |name, value: u64, min: u64| match value >= min {
+            true => Ok(()),
+            false => Err(ConfigError::InvalidValue(name, format!("{} is less than {}", value, min))),
+        };
+        if self.ipv4.is_some() && self.ipv6.is_some() {
+            let message = "only one of ipv4 and ipv6 may be set".to_string();
+            return Err(ConfigError::InvalidValue("listening address", message));
+        }
+        at_least("port", self.port.into(), 1024)?;
+        if let Some(http_redirect_port) = self.http_redirect_port {
+            at_least("HTTP redirect port", http_redirect_port.into(), 1024)?;
+            if http_redirect_port == self.port {
+                let message = "it is the port HTTPS is served on".to_string();
+                return Err(ConfigError::InvalidValue("HTTP redirect port", message));
+            }
+        }
+        let message = match (&self.tls_cert_file, &self.tls_key_file) {
+            (Some(cert_file), Some(key_file)) => {
+                CertificateResolver::load(cert_file, key_file)?;
+                None
+            }
+            (None, None) if self.http_redirect_port.is_some() => Some("redirecting to HTTPS takes a certificate"),
+            (None, None) => None,
+            _ => Some("the certificate and private key files go together"),
+        };
+        if let Some(message) = message {
+            return Err(ConfigError::InvalidValue("TLS settings", message.to_string()));
+        }
+        at_least("token TTL", self.token_ttl, 1)?;
+        Url::parse(&self.issuer).map_err(|error| ConfigError::InvalidValue("issuer", error.to_string()))?;
+        at_least("audit checkpoint interval", self.audit_checkpoint_interval, 1)?;
+        at_least("password strength rate limit", self.password_strength_rate_limit.into(), 1)?;
+        if self.admin_api_key.as_ref().is_some_and(Secret::is_blank) {
+            return Err(ConfigError::InvalidValue("admin API key", "it is blank".to_string()));
+        }
+        match (&self.email_api_url, &self.email_sender, &self.email_server_token) {
+            (Some(api_url), Some(sender), Some(server_token)) => {
+                Url::parse(api_url).map_err(|error| ConfigError::InvalidValue("email API URL", error.to_string()))?;
+                EmailAddress::from_str(sender)
+                    .map_err(|error| ConfigError::InvalidValue("email sender", error.to_string()))?;
+                if server_token.is_blank() {
+                    return Err(ConfigError::InvalidValue("email server token", "it is blank".to_string()));
+                }
+            }
+            (None, None, None) => {}
+            _ => {
+                let message = "the API URL, sender and server token go together".to_string();
+                return Err(ConfigError::InvalidValue("email settings", message));
+            }
+        }
+        self.password_policy()?;
+        self.email_domain_policy()?;
+        Ok(())
+    }
+
+    /// The effective settings, as a config file. Secrets are redacted.
+    pub fn to_file(&self) -> ConfigFile {
+        ConfigFile {
+            server: ServerSection {
+                ipv4: self.ipv4,
+                ipv6: self.ipv6,
+                port: Some(self.port),
+                log: Some(self.log.clone()),
+                issuer: Some(self.issuer.clone()),
+            },
+            tls: TlsSection {
+                cert_file: self.tls_cert_file.clone(),
+                key_file: self.tls_key_file.clone(),
+                redirect_port: self.http_redirect_port,
+            },
+            tokens: TokensSection { ttl: Some(self.token_ttl) },
+            oidc: OidcSection {
+                clients_file: self.clients_file.clone(),
+                providers_file: self.providers_file.clone(),
+            },
+            tenants: TenantsSection { file: self.tenants_file.clone() },
+            signup: SignupSection {
+                mode: Some(self.signup_mode.to_string()),
+                email_domain_allow_list_file: self.email_domain_allow_list_file.clone(),
+                email_domain_deny_list_file: self.email_domain_deny_list_file.clone(),
+                allow_disposable_emails: Some(self.allow_disposable_emails),
+            },
+            admin: AdminSection {
+                // The key read from its file is not repeated.
+                api_key: self.admin_api_key.clone().filter(|_| self.admin_api_key_file.is_none()),
+                api_key_file: self.admin_api_key_file.clone(),
+            },
+            audit: AuditSection {
+                log_file: self.audit_log_file.clone(),
+                checkpoint_interval: Some(self.audit_checkpoint_interval),
+            },
+            email: EmailSection {
+                api_url: self.email_api_url.clone(),
+                sender: self.email_sender.clone(),
+                server_token: self.email_server_token.clone(),
+            },
+            login: LoginSection {
+                geoip_file: self.geoip_file.clone(),
+                step_up_risky_logins: Some(self.step_up_risky_logins),
+            },
+            password: PasswordSection {
+                pwned_passwords: self.pwned_passwords.clone(),
+                min_length: Some(self.password_min_length),
+                max_length: Some(self.password_max_length),
+                min_score: Some(self.password_min_score),
+                deny_list_file: self.password_deny_list_file.clone(),
+                history_depth: Some(self.password_history_depth),
+                max_age: self.password_max_age,
+                strength_rate_limit: Some(self.password_strength_rate_limit),
+            },
+        }
+    }
+
+    /// Password policy of the default tenant.
+    pub fn password_policy(&self) -> Result<PasswordPolicy, PasswordPolicyError> {
+        let deny_list = match &self.password_deny_list_file {
+            Some(path) => DenyList::load(path)?,
+            None => DenyList::default(),
+        };
+        PasswordPolicy::try_new(self.password_min_length, self.password_max_length)?
+            .with_min_score(self.password_min_score)?
+            .with_deny_list(deny_list)
+            .with_history_depth(self.password_history_depth)
+            .with_max_age(self.password_max_age)
+    }
+
+    /// Email domains users may sign up with.
+    pub fn email_domain_policy(&self) -> Result<EmailDomainPolicy, DomainListError> {
+        let load = |path: &Option<PathBuf>| match path {
+            Some(path) => DomainList::load(path),
+            None => Ok(DomainList::default()),
+        };
+        Ok(EmailDomainPolicy {
+            allow_list: load(&self.email_domain_allow_list_file)?,
+            deny_list: load(&self.email_domain_deny_list_file)?,
+            reject_disposable: !self.allow_disposable_emails,
+        })
+    }
+
+    /// Client of the email API, unless emails are only logged.
+    pub fn email_client(&self) -> Option<PostmarkEmailClient> {
+        let api_url = Url::parse(self.email_api_url.as_deref()?).ok()?;
+        let server_token = SecretString::from(self.email_server_token.clone()?);
+        Some(PostmarkEmailClient::new(api_url, self.email_sender.as_deref()?, server_token))
+    }
+
+    /// HTTPS settings, unless plain HTTP is served.
+    pub fn tls_settings(&self, ip_address: IpAddr) -> Option<TlsSettings> {
+        let settings = TlsSettings::new(self.tls_cert_file.as_deref()?, self.tls_key_file.as_deref()?);
+        match self.http_redirect_port {
+            Some(port) => Some(settings.with_redirect_address(SocketAddr::new(ip_address, port))),
+            None => Some(settings),
+        }
+    }
+
+    pub fn reloadable_settings(&self) -> Result<ReloadableSettings, ConfigError> {
+        Ok(ReloadableSettings {
+            log: self.log.clone(),
+            password_strength_rate_limit: self.password_strength_rate_limit,
+            password_policy: self.password_policy()?,
+            email_domain_policy: self.email_domain_policy()?,
+        })
+    }
+}
+
+/// Settings that are reloaded on SIGHUP, while serving. The others take a restart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReloadableSettings {
+    pub log: LogLevel,
+    pub password_strength_rate_limit: u32,
+    /// Password policy of the default tenant, unless the tenants file sets it.
+    pub password_policy: PasswordPolicy,
+    pub email_domain_policy: EmailDomainPolicy,
+}
+
+impl ReloadableSettings {
+    /// Describes the settings that differ in the other settings, one per line.
+    pub fn diff(&self, other: &Self) -> Vec<String> {
+        fn change<T: PartialEq + fmt::Debug>(changes: &mut Vec<String>, name: &str, old: &T, new: &T) {
+            if old != new {
+                changes.push(format!("{}: {:?} -> {:?}", name, old, new));
+            }
+        }
+        let mut changes = Vec::new();
+        change(&mut changes, "log", &self.log, &other.log);
+        let (old, new) = (self.password_strength_rate_limit, other.password_strength_rate_limit);
+        change(&mut changes, "password strength rate limit", &old, &new);
+        change(&mut changes, "password policy", &self.password_policy, &other.password_policy);
+        let (old, new) = (&self.email_domain_policy, &other.email_domain_policy);
+        change(&mut changes, "email domain allow list", &old.allow_list, &new.allow_list);
+        change(&mut changes, "email domain deny list", &old.deny_list, &new.deny_list);
+        change(&mut changes, "disposable email rejection", &old.reject_disposable, &new.reject_disposable);
+        changes
+    }
+}
+
+/// A setting such as a key or a password. It is redacted wherever it is printed, including
+/// the printed configuration, which therefore cannot be loaded back with the secret.
+#[derive(Clone)]
+pub struct Secret(SecretString);
+
+impl Secret {
+    /// Reads the secret from a file, without the trailing line break editors and
+    /// `kubectl create secret --from-file` leave.
+    pub fn read(name: &'static str, path: &Path) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|error| ConfigError::UnreadableSecret(name, path.display().to_string(), error.to_string()))?;
+        Ok(Self::from(contents.trim_end_matches(['\r', '\n'])))
+    }
+
+    pub fn expose(&self) -> &str {
+        self.0.expose_secret()
+    }
+
+    pub fn is_blank(&self) -> bool {
+        self.expose().trim().is_empty()
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(secret: &str) -> Self {
+        Self(SecretString::from(secret))
+    }
+}
+
+impl FromStr for Secret {
+    type Err = Infallible;
+
+    fn from_str(secret: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(secret))
+    }
+}
+
+impl From<Secret> for SecretString {
+    fn from(secret: Secret) -> Self {
+        secret.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        formatter.write_str(REDACTED)
+    }
+}
+
+impl Display for Secret {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        formatter.write_str(REDACTED)
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(REDACTED)
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self::from(String::deserialize(deserializer)?.as_str()))
+    }
+}
+
+/// Settings of the TOML config file, in a section per subsystem. Every setting is optional
+/// and named after its command-line flag, without the section prefix.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub server: ServerSection,
+    pub tls: TlsSection,
+    pub tokens: TokensSection,
+    pub oidc: OidcSection,
+    pub tenants: TenantsSection,
+    pub signup: SignupSection,
+    pub admin: AdminSection,
+    pub audit: AuditSection,
+    pub email: EmailSection,
+    pub login: LoginSection,
+    pub password: PasswordSection,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSection {
+    pub ipv4: Option<Ipv4Addr>,
+    pub ipv6: Option<Ipv6Addr>,
+    pub port: Option<u16>,
+    pub log: Option<LogLevel>,
+    pub issuer: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsSection {
+    pub cert_file: Option<PathBuf>,
+    pub key_file: Option<PathBuf>,
+    pub redirect_port: Option<u16>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TokensSection {
+    pub ttl: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OidcSection {
+    pub clients_file: Option<PathBuf>,
+    pub providers_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TenantsSection {
+    pub file: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SignupSection {
+    pub mode: Option<String>,
+    pub email_domain_allow_list_file: Option<PathBuf>,
+    pub email_domain_deny_list_file: Option<PathBuf>,
+    pub allow_disposable_emails: Option<bool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminSection {
+    pub api_key: Option<Secret>,
+    pub api_key_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditSection {
+    pub log_file: Option<PathBuf>,
+    pub checkpoint_interval: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmailSection {
+    pub api_url: Option<String>,
+    pub sender: Option<String>,
+    pub server_token: Option<Secret>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoginSection {
+    pub geoip_file: Option<PathBuf>,
+    pub step_up_risky_logins: Option<bool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PasswordSection {
+    pub pwned_passwords: Option<PathBuf>,
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    pub min_score: Option<u8>,
+    pub deny_list_file: Option<PathBuf>,
+    pub history_depth: Option<usize>,
+    pub max_age: Option<u64>,
+    pub strength_rate_limit: Option<u32>,
+}
+
+impl ConfigFile {
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|error| ConfigError::UnreadableFile(path.display().to_string(), error.to_string()))?;
+        toml::from_str(&contents)
+            .map_err(|error| ConfigError::InvalidFile(path.display().to_string(), error.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(contents: &str, args: &[&str]) -> Result<Config, ConfigError> {
+        let path = std::env::temp_dir().join(format!("auth-service-{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(&path, contents).unwrap();
+        let config = path.display().to_string();
+        let matches = Config::command().try_get_matches_from(["auth-service", "--config", &config].iter().chain(args));
+        let result = Config::load_from(matches.unwrap());
+        std::fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn test_config_file_layers_under_command_line() {
+        let contents = "[server]\nport = 4000\nlog = \"debug\"\n\n[signup]\nmode = \"invite-only\"\n\n\
+                        [password]\nmin_length = 12\n";
+        let config = load(contents, &["--port", "5000"]).unwrap();
+        assert_eq!((config.port, config.log), (5000, LogLevel::Debug));
+        assert_eq!(config.signup_mode, SignupMode::InviteOnly);
+        assert_eq!(config.password_min_length, 12);
+        assert_eq!(config.token_ttl, TOKEN_TTL_SECONDS);
+    }
+
+    #[test]
+    fn test_config_file_is_validated() {
+        let error = load("[server]\nprot = 4000\n", &[]).unwrap_err();
+        assert!(matches!(error, ConfigError::InvalidFile(_, message) if message.contains("unknown field `prot`")));
+        let error = load("[server]\nport = 80\n", &[]).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid port: 80 is less than 1024");
+        let error = load("[signup]\nmode = \"anyone\"\n", &[]).unwrap_err();
+        assert!(matches!(error, ConfigError::InvalidValue("signup.mode", _)));
+        let error = load("[password]\nmin_length = 80\n", &[]).unwrap_err();
+        assert!(matches!(error, ConfigError::InvalidPasswordPolicy(_)));
+        let error = load("[email]\napi_url = \"https://api.postmarkapp.com\"\n", &[]).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid email settings: the API URL, sender and server token go together");
+        let error = load("[tls]\nredirect_port = 8080\n", &[]).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid TLS settings: redirecting to HTTPS takes a certificate");
+        let error = load("[tls]\ncert_file = \"missing.pem\"\nkey_file = \"missing.pem\"\n", &[]).unwrap_err();
+        assert!(matches!(error, ConfigError::InvalidTls(TlsError::UnreadableFile(_, _))));
+        let config = load("[server]\nipv6 = \"::1\"\n", &["--ipv4", "127.0.0.1"]).unwrap();
+        assert_eq!((config.ipv4, config.ipv6), (Some(Ipv4Addr::LOCALHOST), None));
+    }
+
+    #[test]
+    fn test_printed_config_round_trips_without_secrets() {
+        let config = load("[admin]\napi_key = \"secret\"\n", &["--token-ttl", "60"]).unwrap();
+        let printed = toml::to_string_pretty(&config.to_file()).unwrap();
+        assert!(!printed.contains("secret") && printed.contains("[REDACTED]"));
+        let file: ConfigFile = toml::from_str(&printed).unwrap();
+        assert_eq!(toml::to_string_pretty(&file).unwrap(), printed);
+        assert_eq!(file.tokens.ttl, Some(60));
+    }
+
+    #[test]
+    fn test_secrets_are_redacted() {
+        let config = load("[admin]\napi_key = \"secret\"\n", &[]).unwrap();
+        assert_eq!(config.admin_api_key.as_ref().map(Secret::expose), Some("secret"));
+        assert!(!config.to_string().contains("secret") && !format!("{:?}", config).contains("secret"));
+        assert_eq!(config.admin_api_key.unwrap().to_string(), "[REDACTED]");
+    }
+
+    #[test]
+    fn test_email_settings() {
+        assert!(load("", &[]).unwrap().email_client().is_none());
+        let contents = "[email]\napi_url = \"https://api.postmarkapp.com\"\nsender = \"auth@example.com\"\n\
+                        server_token = \"secret\"\n";
+        let config = load(contents, &[]).unwrap();
+        assert!(config.email_client().is_some());
+        assert!(!toml::to_string_pretty(&config.to_file()).unwrap().contains("secret"));
+        let error = load(contents, &["--email-sender", "auth"]).unwrap_err();
+        assert!(matches!(error, ConfigError::InvalidValue("email sender", _)));
+    }
+
+    #[test]
+    fn test_secrets_are_read_from_files() {
+        let path = std::env::temp_dir().join(format!("admin-api-key-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "secret\n").unwrap();
+        let key_file = format!("[admin]\napi_key_file = \"{}\"\n", path.display());
+        let config = load(&key_file, &[]).unwrap();
+        assert_eq!(config.admin_api_key.as_ref().map(Secret::expose), Some("secret"));
+        let printed = toml::to_string_pretty(&config.to_file()).unwrap();
+        assert!(printed.contains("api_key_file") && !printed.contains("[REDACTED]"));
+        let config = load(&key_file, &["--admin-api-key", "other"]).unwrap();
+        assert_eq!(config.admin_api_key.as_ref().map(Secret::expose), Some("other"));
+        let error = load(&format!("{}api_key = \"other\"\n", key_file), &[]).unwrap_err();
+        assert!(matches!(error, ConfigError::InvalidValue("admin API key", _)));
+        std::fs::write(&path, "  \n").unwrap();
+        let error = load(&key_file, &[]).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid admin API key: it is blank");
+        std::fs::remove_file(&path).unwrap();
+        let error = load(&key_file, &[]).unwrap_err();
+        assert!(matches!(error, ConfigError::UnreadableSecret("admin API key", _, _)));
+    }
+
+    #[test]
+    fn test_reloadable_settings_diff() {
+        let settings = load("", &[]).unwrap().reloadable_settings().unwrap();
+        assert!(settings.diff(&settings.clone()).is_empty());
+        let contents = "[server]\nlog = \"debug\"\n\n[signup]\nallow_disposable_emails = true\n";
+        let reloaded = load(contents, &["--port", "4000"]).unwrap().reloadable_settings().unwrap();
+        assert_eq!(
+            settings.diff(&reloaded),
+            ["log: Info -> Debug", "disposable email rejection: true -> false"]
+        );
+    }
+}
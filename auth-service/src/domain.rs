@@ -1,5 +1,43 @@
+mod api_key;
+mod audit_event;
+mod audit_record;
+mod authorization_code;
+mod claims;
+mod client;
+mod device;
+mod email;
+mod email_domain_policy;
+mod identity_provider;
+mod invitation;
+mod login_attempt;
+mod permission;
+mod role;
+mod session;
+mod tenant;
+mod upstream_authorization;
 mod user;
 mod password;
+mod password_policy;
+mod password_reset;
 
+pub use api_key::*;
+pub use audit_event::*;
+pub use audit_record::*;
+pub use authorization_code::*;
+pub use claims::*;
+pub use client::*;
+pub use device::*;
+pub use email::*;
+pub use email_domain_policy::*;
+pub use identity_provider::*;
+pub use invitation::*;
+pub use login_attempt::*;
 pub use password::*;
+pub use password_policy::*;
+pub use password_reset::*;
+pub use permission::*;
+pub use role::*;
+pub use session::*;
+pub use tenant::*;
+pub use upstream_authorization::*;
 pub use user::*;
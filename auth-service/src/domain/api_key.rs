@@ -0,0 +1,163 @@
+use crate::domain::{hash_secret, Permission};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use secrecy::SecretString;
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+
+/// Marks a bearer token as an API key rather than a JWT.
+pub const API_KEY_PREFIX: &str = "ak_";
+pub const MAX_API_KEY_NAME_LENGTH: usize = 64;
+/// Longest lifetime a key may be created with. Keys may still be created without expiry.
+pub const MAX_API_KEY_TTL_SECONDS: u64 = 5 * 365 * 24 * 60 * 60;
+
+/// Long-lived credential a user scripts against protected services with, formatted as
+/// `ak_{id}_{secret}`. The id is public and names the key in listings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiKey {
+    pub id: String,
+    pub tenant_id: String,
+    pub email: String,
+    pub name: String,
+    /// Permissions the key is limited to, sorted and without duplicates.
+    pub scopes: Vec<Permission>,
+    pub secret_hash: String,
+    pub created_at: u64,
+    pub expires_at: Option<u64>,
+    pub last_used_at: Option<u64>,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ApiKeyError {
+    #[error("Name must be between 1 and {MAX_API_KEY_NAME_LENGTH} characters")]
+    InvalidName,
+    #[error("Expiry must be between 1 and {MAX_API_KEY_TTL_SECONDS} seconds")]
+    InvalidExpiry,
+}
+
+impl ApiKey {
+    /// Creates a key with a freshly generated secret, returning the full key. The key is
+    /// only returned here; the store keeps the hash of its secret.
+    pub fn try_new(
+        tenant_id: &str,
+        email: &str,
+        name: &str,
+        scopes: &[Permission],
+        now: u64,
+        ttl: Option<u64>,
+    ) -> Result<(Self, SecretString), ApiKeyError> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_API_KEY_NAME_LENGTH {
+            return Err(ApiKeyError::InvalidName);
+        }
+        let expires_at = match ttl {
+            Some(ttl) if !(1..=MAX_API_KEY_TTL_SECONDS).contains(&ttl) => return Err(ApiKeyError::InvalidExpiry),
+            Some(ttl) => Some(now.checked_add(ttl).ok_or(ApiKeyError::InvalidExpiry)?),
+            None => None,
+        };
+        let mut scopes = scopes.to_vec();
+        scopes.sort();
+        scopes.dedup();
+        let id: [u8; 8] = rand::random();
+        let id: String = id.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let secret: [u8; 32] = rand::random();
+        let secret = URL_SAFE_NO_PAD.encode(secret);
+        let api_key = Self {
+            id: id.clone(),
+            tenant_id: tenant_id.to_string(),
+            email: email.to_string(),
+            name: name.to_string(),
+            scopes,
+            secret_hash: hash_secret(&secret),
+            created_at: now,
+            expires_at,
+            last_used_at: None,
+        };
+        Ok((api_key, SecretString::from(format!("{}{}_{}", API_KEY_PREFIX, id, secret))))
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+
+    /// The comparison is constant-time.
+    pub fn verify_secret(&self, secret: &str) -> bool {
+        hash_secret(secret).as_bytes().ct_eq(self.secret_hash.as_bytes()).into()
+    }
+
+    /// Permissions granted by the key: its scopes, as far as the user still holds them.
+    pub fn permissions(&self, user_permissions: &[Permission]) -> Vec<Permission> {
+        self.scopes
+            .iter()
+            .filter(|scope| user_permissions.contains(scope))
+            .copied()
+            .collect()
+    }
+}
+
+/// Splits an API key into its id and secret. Other tokens, such as JWTs, give `None`.
+pub fn parse_api_key(token: &str) -> Option<(&str, &str)> {
+    let (id, secret) = token.strip_prefix(API_KEY_PREFIX)?.split_once('_')?;
+    if id.is_empty() || secret.is_empty() {
+        return None;
+    }
+    Some((id, secret))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::DEFAULT_TENANT_ID;
+    use secrecy::ExposeSecret;
+
+    fn api_key(scopes: &[Permission], ttl: Option<u64>) -> (ApiKey, SecretString) {
+        ApiKey::try_new(DEFAULT_TENANT_ID, "alice@example.com", " ci ", scopes, 100, ttl).unwrap()
+    }
+
+    #[test]
+    fn should_create_api_key() {
+        let (api_key, key) = api_key(&[Permission::ProfileWrite, Permission::ProfileRead], None);
+        assert_eq!(api_key.name, "ci");
+        assert_eq!(api_key.scopes, vec![Permission::ProfileRead, Permission::ProfileWrite]);
+        let (id, secret) = parse_api_key(key.expose_secret()).unwrap();
+        assert_eq!(id, api_key.id);
+        assert!(api_key.verify_secret(secret));
+        assert!(!api_key.verify_secret("wrong-secret"));
+        assert!(!api_key.is_expired(u64::MAX));
+        assert_ne!(api_key.id, self::api_key(&[], None).0.id);
+    }
+
+    #[test]
+    fn should_expire_api_key() {
+        let (api_key, _) = api_key(&[], Some(600));
+        assert!(!api_key.is_expired(699));
+        assert!(api_key.is_expired(700));
+    }
+
+    #[test]
+    fn should_reject_invalid_api_key() {
+        let create = |name: &str, ttl| ApiKey::try_new(DEFAULT_TENANT_ID, "alice@example.com", name, &[], 100, ttl);
+        assert_eq!(create(" ", None).unwrap_err(), ApiKeyError::InvalidName);
+        assert_eq!(create(&"a".repeat(65), None).unwrap_err(), ApiKeyError::InvalidName);
+        assert_eq!(create("ci", Some(0)).unwrap_err(), ApiKeyError::InvalidExpiry);
+        assert_eq!(create("ci", Some(MAX_API_KEY_TTL_SECONDS + 1)).unwrap_err(), ApiKeyError::InvalidExpiry);
+        assert_eq!(create("ci", Some(u64::MAX)).unwrap_err(), ApiKeyError::InvalidExpiry);
+        assert!(create("ci", Some(MAX_API_KEY_TTL_SECONDS)).is_ok());
+    }
+
+    #[test]
+    fn should_limit_permissions_to_the_user() {
+        let (api_key, _) = api_key(&[Permission::ProfileRead, Permission::UsersRead], None);
+        assert_eq!(
+            api_key.permissions(&[Permission::ProfileRead, Permission::ProfileWrite]),
+            vec![Permission::ProfileRead]
+        );
+    }
+
+    #[test]
+    fn should_parse_only_api_keys() {
+        assert_eq!(parse_api_key("ak_0123_secret_with_underscores"), Some(("0123", "secret_with_underscores")));
+        assert_eq!(parse_api_key("ak_0123_"), None);
+        assert_eq!(parse_api_key("eyJhbGciOiJFZERTQSJ9.e30.c2ln"), None);
+    }
+}
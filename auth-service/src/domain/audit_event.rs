@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use uuid::Uuid;
+
+/// Actor of admin actions authorized by the bootstrap admin API key.
+pub const ADMIN_API_KEY_ACTOR: &str = "admin-api-key";
+
+/// Why a sign-in was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoginFailureReason {
+    InvalidCredentials,
+    AccountDisabled,
+    AccountLocked,
+    PasswordResetRequired,
+}
+
+/// What happened, with the details specific to each kind of event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEventKind {
+    Signup,
+    /// `method` is `password`, or the name of the upstream identity provider.
+    LoginSucceeded {
+        method: String,
+    },
+    LoginFailed {
+        reason: LoginFailureReason,
+    },
+    TwoFactorChallenged,
+    /// A sign-in from a device the user never used, or from impossibly far since the last one.
+    LoginAnomaly {
+        new_device: bool,
+        impossible_travel: bool,
+    },
+    Logout,
+    PasswordChanged,
+    AccountLocked,
+    /// A change requested through the admin API, e.g. `POST /admin/users/{email}/disable`.
+    AdminAction {
+        action: String,
+        status: u16,
+    },
+}
+
+/// Where a request came from, as recorded with the events it causes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuditContext {
+    pub ip_address: Option<IpAddr>,
+    pub user_agent: Option<String>,
+    /// Taken from the `X-Request-ID` header, or generated.
+    pub correlation_id: String,
+}
+
+/// A security-relevant event, recorded in the audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEvent {
+    pub id: String,
+    pub timestamp: u64,
+    pub tenant_id: String,
+    /// Who acted: the user signing in, or the admin making a change.
+    pub actor: String,
+    /// User the event is about, when not the actor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    pub ip_address: Option<IpAddr>,
+    pub user_agent: Option<String>,
+    pub correlation_id: String,
+    #[serde(flatten)]
+    pub kind: AuditEventKind,
+}
+
+impl AuditEvent {
+    pub fn new(tenant_id: &str, actor: &str, kind: AuditEventKind, context: &AuditContext, now: u64) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            timestamp: now,
+            tenant_id: tenant_id.to_string(),
+            actor: actor.to_string(),
+            subject: None,
+            ip_address: context.ip_address,
+            user_agent: context.user_agent.clone(),
+            correlation_id: context.correlation_id.clone(),
+            kind,
+        }
+    }
+
+    pub fn with_subject(mut self, subject: Option<&str>) -> Self {
+        self.subject = subject.filter(|subject| *subject != self.actor).map(str::to_string);
+        self
+    }
+
+    /// Whether the user of the tenant acted or was acted upon.
+    pub fn concerns(&self, tenant_id: &str, email: &str) -> bool {
+        self.tenant_id == tenant_id && (self.actor == email || self.subject.as_deref() == Some(email))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::DEFAULT_TENANT_ID;
+    use serde_json::json;
+
+    fn context() -> AuditContext {
+        AuditContext {
+            ip_address: Some("127.0.0.1".parse().unwrap()),
+            user_agent: Some("curl/8.0".to_string()),
+            correlation_id: "request-1".to_string(),
+        }
+    }
+
+    #[test]
+    fn should_serialize_event_with_its_details() {
+        let kind = AuditEventKind::LoginFailed { reason: LoginFailureReason::AccountLocked };
+        let event = AuditEvent::new(DEFAULT_TENANT_ID, "alice@example.com", kind, &context(), 100);
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["event"], json!("login_failed"));
+        assert_eq!(value["reason"], json!("account_locked"));
+        assert_eq!(value["ipAddress"], json!("127.0.0.1"));
+        assert_eq!(value["correlationId"], json!("request-1"));
+        assert!(value.get("subject").is_none());
+        assert_eq!(serde_json::from_value::<AuditEvent>(value).unwrap(), event);
+    }
+
+    #[test]
+    fn should_concern_actor_and_subject() {
+        let kind = AuditEventKind::AdminAction { action: "DELETE /admin/users/{email}".to_string(), status: 204 };
+        let event = AuditEvent::new(DEFAULT_TENANT_ID, "admin@example.com", kind, &context(), 100)
+            .with_subject(Some("alice@example.com"));
+        assert!(event.concerns(DEFAULT_TENANT_ID, "admin@example.com"));
+        assert!(event.concerns(DEFAULT_TENANT_ID, "alice@example.com"));
+        assert!(!event.concerns(DEFAULT_TENANT_ID, "bob@example.com"));
+        assert!(!event.concerns("acme", "alice@example.com"));
+
+        let event = AuditEvent::new(DEFAULT_TENANT_ID, "alice@example.com", AuditEventKind::Logout, &context(), 100)
+            .with_subject(Some("alice@example.com"));
+        assert_eq!(event.subject, None);
+    }
+}
@@ -0,0 +1,326 @@
+use crate::domain::AuditEvent;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// `previousHash` of the first record of an audit log.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Signature over the hash chain up to the checkpoint, by the service signing key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditCheckpoint {
+    pub timestamp: u64,
+    pub key_id: String,
+    /// Ed25519 public key of the signing key, base64url encoded as in its JWK.
+    pub public_key: String,
+    /// Signature of the checkpoint message, base64url encoded.
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+pub enum AuditEntry {
+    Event(AuditEvent),
+    Checkpoint(AuditCheckpoint),
+}
+
+/// A line of the audit log. Each record holds the hash of the record before it, so that
+/// editing, inserting or removing a record breaks the chain from there on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRecord {
+    pub sequence: u64,
+    pub previous_hash: String,
+    #[serde(flatten)]
+    pub entry: AuditEntry,
+    /// SHA-256 of the record without this field, as JSON with sorted keys, hex encoded.
+    pub hash: String,
+}
+
+impl AuditRecord {
+    /// Chains the entry after the previous record, or starts a log without one.
+    pub fn chain(previous: Option<&AuditRecord>, entry: AuditEntry) -> Result<Self, serde_json::Error> {
+        let (sequence, previous_hash) = match previous {
+            Some(previous) => (previous.sequence + 1, previous.hash.clone()),
+            None => (0, GENESIS_HASH.to_string()),
+        };
+        let mut record = Self {
+            sequence,
+            previous_hash,
+            entry,
+            hash: String::new(),
+        };
+        record.hash = record_hash(serde_json::to_value(&record)?);
+        Ok(record)
+    }
+}
+
+/// What a checkpoint signs: its position in the chain, and through the previous hash,
+/// every record before it.
+pub fn checkpoint_message(sequence: u64, previous_hash: &str) -> String {
+    format!("{}.{}", sequence, previous_hash)
+}
+
+/// Hashes every field of the record but `hash`, including fields unknown to this version.
+fn record_hash(mut record: Value) -> String {
+    if let Value::Object(fields) = &mut record {
+        fields.remove("hash");
+    }
+    // Without the `preserve_order` feature, objects serialize with sorted keys.
+    let digest = Sha256::digest(record.to_string().as_bytes());
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum AuditChainError {
+    #[error("Record is malformed: {0}")]
+    MalformedRecord(String),
+    #[error("Hash does not match the contents of the record")]
+    HashMismatch,
+    #[error("Expected sequence {expected}, found {found}")]
+    SequenceMismatch { expected: u64, found: u64 },
+    #[error("Previous hash does not match the hash of the previous record")]
+    PreviousHashMismatch,
+    #[error("Checkpoint signature is invalid")]
+    InvalidSignature,
+    #[error("Checkpoint was signed by an untrusted key: {0}")]
+    UntrustedKey(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BrokenLink {
+    /// Line of the audit log, starting at 1.
+    pub line: usize,
+    pub error: AuditChainError,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct AuditChainReport {
+    /// Records verified before the first broken link, if any.
+    pub records: u64,
+    pub checkpoints: u64,
+    /// Sequence of the last checkpoint verified. Records after it are only protected by
+    /// the hash chain.
+    pub last_checkpoint: Option<u64>,
+    pub broken_link: Option<BrokenLink>,
+}
+
+/// Walks a JSON-lines audit log up to the first broken link. With trusted keys, public
+/// keys by key id, checkpoints must be signed by one of them. Otherwise they are only
+/// checked against the public key they embed.
+pub fn verify_audit_chain(contents: &str, trusted_keys: Option<&HashMap<String, String>>) -> AuditChainReport {
+    let mut report = AuditChainReport::default();
+    let mut previous: Option<AuditRecord> = None;
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match verify_record(line, previous.as_ref(), trusted_keys) {
+            Ok(record) => {
+                report.records += 1;
+                if matches!(record.entry, AuditEntry::Checkpoint(_)) {
+                    report.checkpoints += 1;
+                    report.last_checkpoint = Some(record.sequence);
+                }
+                previous = Some(record);
+            }
+            Err(error) => {
+                report.broken_link = Some(BrokenLink { line: index + 1, error });
+                break;
+            }
+        }
+    }
+    report
+}
+
+fn verify_record(
+    line: &str,
+    previous: Option<&AuditRecord>,
+    trusted_keys: Option<&HashMap<String, String>>,
+) -> Result<AuditRecord, AuditChainError> {
+    let malformed = |error: serde_json::Error| AuditChainError::MalformedRecord(error.to_string());
+    let value: Value = serde_json::from_str(line).map_err(malformed)?;
+    let record: AuditRecord = serde_json::from_value(value.clone()).map_err(malformed)?;
+    if record_hash(value) != record.hash {
+        return Err(AuditChainError::HashMismatch);
+    }
+    let (expected, previous_hash) = match previous {
+        Some(previous) => (previous.sequence + 1, previous.hash.as_str()),
+        None => (0, GENESIS_HASH),
+    };
+    if record.sequence != expected {
+        return Err(AuditChainError::SequenceMismatch {
+            expected,
+            found: record.sequence,
+        });
+    }
+    if record.previous_hash != previous_hash {
+        return Err(AuditChainError::PreviousHashMismatch);
+    }
+    if let AuditEntry::Checkpoint(checkpoint) = &record.entry {
+        let trusted = trusted_keys.is_none_or(|keys| keys.get(&checkpoint.key_id) == Some(&checkpoint.public_key));
+        if !trusted {
+            return Err(AuditChainError::UntrustedKey(checkpoint.key_id.clone()));
+        }
+        let message = checkpoint_message(record.sequence, &record.previous_hash);
+        if !verify_signature(&checkpoint.public_key, &checkpoint.signature, message.as_bytes()) {
+            return Err(AuditChainError::InvalidSignature);
+        }
+    }
+    Ok(record)
+}
+
+fn verify_signature(public_key: &str, signature: &str, message: &[u8]) -> bool {
+    let public_key = URL_SAFE_NO_PAD.decode(public_key).ok().and_then(|bytes| bytes.try_into().ok());
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok();
+    let (Some(public_key), Some(signature)) = (public_key, signature) else {
+        return false;
+    };
+    let Ok(public_key) = VerifyingKey::from_bytes(&public_key) else {
+        return false;
+    };
+    let Ok(signature) = Signature::from_slice(&signature) else {
+        return false;
+    };
+    public_key.verify_strict(message, &signature).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{AuditContext, AuditEventKind, DEFAULT_TENANT_ID};
+    use ed25519_dalek::{Signer, SigningKey};
+
+    const KEY_ID: &str = "test-key";
+
+    fn event(email: &str) -> AuditEntry {
+        let event = AuditEvent::new(DEFAULT_TENANT_ID, email, AuditEventKind::Signup, &AuditContext::default(), 100);
+        AuditEntry::Event(event)
+    }
+
+    fn checkpoint(key: &SigningKey, previous: &AuditRecord) -> AuditEntry {
+        let message = checkpoint_message(previous.sequence + 1, &previous.hash);
+        AuditEntry::Checkpoint(AuditCheckpoint {
+            timestamp: 100,
+            key_id: KEY_ID.to_string(),
+            public_key: URL_SAFE_NO_PAD.encode(key.verifying_key().to_bytes()),
+            signature: URL_SAFE_NO_PAD.encode(key.sign(message.as_bytes()).to_bytes()),
+        })
+    }
+
+    /// Two events, a checkpoint and another event, one JSON object per line.
+    fn log(key: &SigningKey) -> Vec<String> {
+        let mut records: Vec<AuditRecord> = Vec::new();
+        for email in ["alice@example.com", "bob@example.com"] {
+            records.push(AuditRecord::chain(records.last(), event(email)).unwrap());
+        }
+        let entry = checkpoint(key, records.last().unwrap());
+        records.push(AuditRecord::chain(records.last(), entry).unwrap());
+        records.push(AuditRecord::chain(records.last(), event("carol@example.com")).unwrap());
+        records.iter().map(|record| serde_json::to_string(record).unwrap()).collect()
+    }
+
+    fn trusted_keys(key: &SigningKey) -> HashMap<String, String> {
+        HashMap::from([(KEY_ID.to_string(), URL_SAFE_NO_PAD.encode(key.verifying_key().as_bytes()))])
+    }
+
+    fn verify(lines: &[String], trusted_keys: Option<&HashMap<String, String>>) -> AuditChainReport {
+        verify_audit_chain(&lines.join("\n"), trusted_keys)
+    }
+
+    fn broken_link(lines: &[String]) -> Option<BrokenLink> {
+        verify(lines, None).broken_link
+    }
+
+    #[test]
+    fn should_verify_intact_chain() {
+        let key = SigningKey::from_bytes(&rand::random());
+        let lines = log(&key);
+        let record: AuditRecord = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(record.previous_hash, GENESIS_HASH);
+        assert!(matches!(record.entry, AuditEntry::Event(_)));
+
+        let report = verify(&lines, None);
+        assert_eq!(report.records, 4);
+        assert_eq!(report.checkpoints, 1);
+        assert_eq!(report.last_checkpoint, Some(2));
+        assert_eq!(report.broken_link, None);
+
+        let trusted_keys = trusted_keys(&key);
+        assert_eq!(verify(&lines, Some(&trusted_keys)).broken_link, None);
+    }
+
+    #[test]
+    fn should_report_edited_record() {
+        let mut lines = log(&SigningKey::from_bytes(&rand::random()));
+        lines[1] = lines[1].replace("bob@example.com", "mallory@example.com");
+        assert_eq!(broken_link(&lines), Some(BrokenLink { line: 2, error: AuditChainError::HashMismatch }));
+
+        // Rehashing the edited record breaks the link to the next one instead.
+        let mut record: AuditRecord = serde_json::from_str(&lines[1]).unwrap();
+        let previous: AuditRecord = serde_json::from_str(&lines[0]).unwrap();
+        record = AuditRecord::chain(Some(&previous), record.entry).unwrap();
+        lines[1] = serde_json::to_string(&record).unwrap();
+        let error = AuditChainError::PreviousHashMismatch;
+        assert_eq!(broken_link(&lines), Some(BrokenLink { line: 3, error }));
+    }
+
+    #[test]
+    fn should_report_added_field() {
+        let mut lines = log(&SigningKey::from_bytes(&rand::random()));
+        lines[0] = lines[0].replacen('{', r#"{"note":"edited","#, 1);
+        assert_eq!(broken_link(&lines), Some(BrokenLink { line: 1, error: AuditChainError::HashMismatch }));
+    }
+
+    #[test]
+    fn should_report_removed_record() {
+        let mut lines = log(&SigningKey::from_bytes(&rand::random()));
+        lines.remove(1);
+        let error = AuditChainError::SequenceMismatch { expected: 1, found: 2 };
+        assert_eq!(broken_link(&lines), Some(BrokenLink { line: 2, error }));
+
+        let lines = log(&SigningKey::from_bytes(&rand::random()));
+        let error = AuditChainError::SequenceMismatch { expected: 0, found: 1 };
+        assert_eq!(broken_link(&lines[1..]), Some(BrokenLink { line: 1, error }));
+    }
+
+    #[test]
+    fn should_report_malformed_record() {
+        let mut lines = log(&SigningKey::from_bytes(&rand::random()));
+        lines[3] = "{\"sequence\":".to_string();
+        let broken_link = broken_link(&lines).unwrap();
+        assert_eq!(broken_link.line, 4);
+        assert!(matches!(broken_link.error, AuditChainError::MalformedRecord(_)));
+    }
+
+    #[test]
+    fn should_report_forged_checkpoint() {
+        let key = SigningKey::from_bytes(&rand::random());
+        let mut lines = log(&key);
+        let previous: AuditRecord = serde_json::from_str(&lines[1]).unwrap();
+        let mut record: AuditRecord = serde_json::from_str(&lines[2]).unwrap();
+        if let AuditEntry::Checkpoint(checkpoint) = &mut record.entry {
+            checkpoint.signature = URL_SAFE_NO_PAD.encode([0u8; 64]);
+        }
+        record = AuditRecord::chain(Some(&previous), record.entry).unwrap();
+        let mut forged = lines.clone();
+        forged[2] = serde_json::to_string(&record).unwrap();
+        let error = AuditChainError::InvalidSignature;
+        assert_eq!(verify(&forged[..3], None).broken_link, Some(BrokenLink { line: 3, error }));
+
+        // A checkpoint re-signed with another key only passes without trusted keys.
+        let other = SigningKey::from_bytes(&rand::random());
+        lines[2] = serde_json::to_string(&AuditRecord::chain(Some(&previous), checkpoint(&other, &previous)).unwrap())
+            .unwrap();
+        assert_eq!(verify(&lines[..3], None).broken_link, None);
+        let trusted_keys = trusted_keys(&key);
+        let error = AuditChainError::UntrustedKey(KEY_ID.to_string());
+        assert_eq!(verify(&lines[..3], Some(&trusted_keys)).broken_link, Some(BrokenLink { line: 3, error }));
+    }
+}
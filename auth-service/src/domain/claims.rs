@@ -0,0 +1,115 @@
+use crate::domain::{effective_permissions, Permission, Role, Session, User, ADMIN_API_KEY_ACTOR, DEFAULT_TENANT_ID};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub iat: u64,
+    pub exp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    /// Session the token was issued for, when the user signed in interactively.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+    /// Tenant of the user the token was issued for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+    /// Roles of the signed-in user, and the permissions they grant. Tokens issued to
+    /// clients carry scopes instead.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<Role>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub permissions: Vec<Permission>,
+}
+
+impl Claims {
+    pub fn new(sub: &str, iat: u64, ttl: u64) -> Self {
+        Self {
+            sub: sub.to_string(),
+            iat,
+            exp: iat + ttl,
+            scope: None,
+            client_id: None,
+            sid: None,
+            tenant_id: None,
+            roles: Vec::new(),
+            permissions: Vec::new(),
+        }
+    }
+
+    /// Claims for a user signed in on a device, with the roles the user holds.
+    pub fn for_session(session: &Session, roles: &[Role], iat: u64, ttl: u64) -> Self {
+        Self {
+            sid: Some(session.id.clone()),
+            tenant_id: Some(session.tenant_id.clone()),
+            roles: roles.to_vec(),
+            permissions: effective_permissions(roles),
+            ..Self::new(&session.email, iat, ttl)
+        }
+    }
+
+    /// Claims standing for the bootstrap admin API key, which acts as an admin of the tenant.
+    pub fn for_admin_api_key(tenant_id: &str, iat: u64) -> Self {
+        Self {
+            tenant_id: Some(tenant_id.to_string()),
+            roles: vec![Role::Admin],
+            permissions: effective_permissions(&[Role::Admin]),
+            ..Self::new(ADMIN_API_KEY_ACTOR, iat, 0)
+        }
+    }
+
+    /// Tenant of the user, the default one for tokens issued before tenants existed.
+    pub fn tenant_id(&self) -> &str {
+        self.tenant_id.as_deref().unwrap_or(DEFAULT_TENANT_ID)
+    }
+
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+
+    /// Claims for a client acting on its own behalf (RFC 6749, section 4.4).
+    pub fn for_client(client_id: &str, scope: &str, iat: u64, ttl: u64) -> Self {
+        Self {
+            sub: client_id.to_string(),
+            iat,
+            exp: iat + ttl,
+            scope: Some(scope.to_string()),
+            client_id: Some(client_id.to_string()),
+            sid: None,
+            tenant_id: None,
+            roles: Vec::new(),
+            permissions: Vec::new(),
+        }
+    }
+}
+
+/// OpenID Connect ID token (OpenID Connect Core 1.0, section 2).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    pub aud: String,
+    pub iat: u64,
+    pub exp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    pub email: String,
+    pub email_verified: bool,
+}
+
+impl IdTokenClaims {
+    pub fn new(iss: &str, user: &User, aud: &str, nonce: Option<&str>, iat: u64, ttl: u64) -> Self {
+        Self {
+            iss: iss.to_string(),
+            sub: user.email.to_string(),
+            aud: aud.to_string(),
+            iat,
+            exp: iat + ttl,
+            nonce: nonce.map(str::to_string),
+            email: user.email.to_string(),
+            email_verified: false,
+        }
+    }
+}
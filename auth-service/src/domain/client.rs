@@ -0,0 +1,232 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use secrecy::SecretString;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+use url::Url;
+use uuid::Uuid;
+
+/// An application allowed to obtain tokens from this service.
+///
+/// Public clients delegate user login through `/authorize` and prove possession with PKCE.
+/// Confidential clients also hold a secret, and may request tokens on their own behalf
+/// for the scopes they were granted.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "ClientRegistration")]
+pub struct Client {
+    pub client_id: String,
+    pub secret_hash: Option<String>,
+    pub redirect_uris: Vec<Url>,
+    pub scopes: Vec<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("Client id is empty")]
+    EmptyClientId,
+    #[error("Client has no redirect URIs")]
+    MissingRedirectUri,
+    #[error("Invalid redirect URI: {0}")]
+    InvalidRedirectUri(String),
+    #[error("Invalid scope: {0}")]
+    InvalidScope(String),
+}
+
+impl Client {
+    /// Creates a public client, for user login with the authorization code flow.
+    pub fn try_new(client_id: &str, redirect_uris: &[&str]) -> Result<Self, ClientError> {
+        if redirect_uris.is_empty() {
+            return Err(ClientError::MissingRedirectUri);
+        }
+        Ok(Self {
+            client_id: parse_client_id(client_id)?,
+            secret_hash: None,
+            redirect_uris: parse_redirect_uris(redirect_uris)?,
+            scopes: Vec::new(),
+        })
+    }
+
+    /// Creates a confidential client with a freshly generated secret. The secret is only
+    /// returned here; the client keeps its hash.
+    pub fn try_new_confidential(
+        client_id: Option<&str>,
+        scopes: &[&str],
+        redirect_uris: &[&str],
+    ) -> Result<(Self, SecretString), ClientError> {
+        let client_id = match client_id {
+            Some(client_id) => parse_client_id(client_id)?,
+            None => Uuid::new_v4().to_string(),
+        };
+        let scopes = scopes
+            .iter()
+            .map(|scope| parse_scope(scope))
+            .collect::<Result<Vec<_>, _>>()?;
+        let secret: [u8; 32] = rand::random();
+        let secret = URL_SAFE_NO_PAD.encode(secret);
+        let client = Self {
+            client_id,
+            secret_hash: Some(hash_secret(&secret)),
+            redirect_uris: parse_redirect_uris(redirect_uris)?,
+            scopes,
+        };
+        Ok((client, SecretString::from(secret)))
+    }
+
+    pub fn is_confidential(&self) -> bool {
+        self.secret_hash.is_some()
+    }
+
+    /// Secrets are 256-bit random values, so a single SHA-256 is enough to store them;
+    /// the comparison is constant-time.
+    pub fn verify_secret(&self, secret: &str) -> bool {
+        match &self.secret_hash {
+            Some(secret_hash) => hash_secret(secret).as_bytes().ct_eq(secret_hash.as_bytes()).into(),
+            None => false,
+        }
+    }
+
+    /// Redirect URIs are compared as exact strings, as required by OAuth 2.0 Security BCP.
+    pub fn redirect_uri(&self, uri: &str) -> Option<&Url> {
+        self.redirect_uris.iter().find(|url| url.as_str() == uri)
+    }
+
+    pub fn allows_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|allowed| allowed == scope)
+    }
+}
+
+/// Hash of a 256-bit random secret.
+pub(crate) fn hash_secret(secret: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(secret.as_bytes()))
+}
+
+fn parse_client_id(client_id: &str) -> Result<String, ClientError> {
+    if client_id.trim().is_empty() {
+        return Err(ClientError::EmptyClientId);
+    }
+    Ok(client_id.to_string())
+}
+
+fn parse_redirect_uris(redirect_uris: &[&str]) -> Result<Vec<Url>, ClientError> {
+    redirect_uris
+        .iter()
+        .map(|uri| {
+            let url = Url::parse(uri).map_err(|_| ClientError::InvalidRedirectUri(uri.to_string()))?;
+            // OAuth 2.0 forbids fragments in redirection endpoints (RFC 6749, section 3.1.2).
+            if url.fragment().is_some() || !url.has_host() {
+                return Err(ClientError::InvalidRedirectUri(uri.to_string()));
+            }
+            Ok(url)
+        })
+        .collect()
+}
+
+/// Scope tokens are printable ASCII without spaces, quotes or backslashes (RFC 6749, section 3.3).
+fn parse_scope(scope: &str) -> Result<String, ClientError> {
+    let valid = !scope.is_empty()
+        && scope
+            .bytes()
+            .all(|byte| byte == 0x21 || (0x23..=0x5B).contains(&byte) || (0x5D..=0x7E).contains(&byte));
+    if valid {
+        Ok(scope.to_string())
+    } else {
+        Err(ClientError::InvalidScope(scope.to_string()))
+    }
+}
+
+#[derive(Deserialize)]
+struct ClientRegistration {
+    client_id: String,
+    redirect_uris: Vec<String>,
+}
+
+impl TryFrom<ClientRegistration> for Client {
+    type Error = ClientError;
+
+    fn try_from(registration: ClientRegistration) -> Result<Self, Self::Error> {
+        let redirect_uris: Vec<&str> = registration.redirect_uris.iter().map(String::as_str).collect();
+        Client::try_new(&registration.client_id, &redirect_uris)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secrecy::ExposeSecret;
+
+    #[test]
+    fn should_return_ok_for_valid_input() {
+        let client = Client::try_new("app", &["http://localhost:8000/callback"]).unwrap();
+        assert!(!client.is_confidential());
+        assert!(client.redirect_uri("http://localhost:8000/callback").is_some());
+        assert!(client.redirect_uri("http://localhost:8000/callback/").is_none());
+    }
+
+    #[test]
+    fn should_return_error_for_empty_client_id() {
+        let result = Client::try_new(" ", &["http://localhost:8000/callback"]);
+        assert!(matches!(result, Err(ClientError::EmptyClientId)));
+    }
+
+    #[test]
+    fn should_return_error_for_missing_redirect_uri() {
+        let result = Client::try_new("app", &[]);
+        assert!(matches!(result, Err(ClientError::MissingRedirectUri)));
+    }
+
+    #[test]
+    fn should_return_error_for_invalid_redirect_uri() {
+        for uri in ["callback", "http://localhost:8000/callback#fragment", "mailto:alice@example.com"] {
+            let result = Client::try_new("app", &[uri]);
+            assert!(matches!(result, Err(ClientError::InvalidRedirectUri(_))), "Input: {}", uri);
+        }
+    }
+
+    #[test]
+    fn should_deserialize_registration() {
+        let json = r#"{"client_id": "app", "redirect_uris": ["http://localhost:8000/callback"]}"#;
+        let client: Client = serde_json::from_str(json).unwrap();
+        assert_eq!(client.client_id, "app");
+        assert!(serde_json::from_str::<Client>(r#"{"client_id": "app", "redirect_uris": []}"#).is_err());
+    }
+
+    #[test]
+    fn should_verify_confidential_client_secret() {
+        let (client, secret) = Client::try_new_confidential(Some("job"), &["reports:read"], &[]).unwrap();
+        assert!(client.is_confidential());
+        assert!(client.verify_secret(secret.expose_secret()));
+        assert!(!client.verify_secret("wrong-secret"));
+        assert_ne!(client.secret_hash.as_deref(), Some(secret.expose_secret()));
+    }
+
+    #[test]
+    fn should_generate_client_id_and_unique_secrets() {
+        let (client_1, secret_1) = Client::try_new_confidential(None, &[], &[]).unwrap();
+        let (client_2, secret_2) = Client::try_new_confidential(None, &[], &[]).unwrap();
+        assert_ne!(client_1.client_id, client_2.client_id);
+        assert_ne!(secret_1.expose_secret(), secret_2.expose_secret());
+    }
+
+    #[test]
+    fn should_return_error_for_invalid_scope() {
+        for scope in ["", "reports read", "reports\"read", "reports\\read"] {
+            let result = Client::try_new_confidential(None, &[scope], &[]);
+            assert!(matches!(result, Err(ClientError::InvalidScope(_))), "Input: {:?}", scope);
+        }
+    }
+
+    #[test]
+    fn should_allow_only_granted_scopes() {
+        let (client, _) = Client::try_new_confidential(None, &["reports:read"], &[]).unwrap();
+        assert!(client.allows_scope("reports:read"));
+        assert!(!client.allows_scope("reports:write"));
+    }
+
+    #[test]
+    fn public_client_never_verifies_secret() {
+        let client = Client::try_new("app", &["http://localhost:8000/callback"]).unwrap();
+        assert!(!client.verify_secret(""));
+    }
+}
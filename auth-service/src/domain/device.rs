@@ -0,0 +1,203 @@
+use ipnet::IpNet;
+use std::net::IpAddr;
+
+/// Devices remembered per user; the least recently seen is forgotten first.
+pub const MAX_KNOWN_DEVICES: usize = 20;
+/// Faster than any airliner: sign-ins further apart than this speed allows are flagged.
+pub const MAX_TRAVEL_SPEED_KMH: f64 = 1000.0;
+/// Slack for the accuracy of GeoIP locations, so that nearby cities never look like travel.
+pub const GEOIP_ACCURACY_KM: f64 = 100.0;
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoLocation {
+    /// ISO 3166-1 country code, when the GeoIP database has one.
+    pub country: Option<String>,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl GeoLocation {
+    /// Great-circle distance, by the haversine formula.
+    pub fn distance_km(&self, other: &GeoLocation) -> f64 {
+        let (latitude, other_latitude) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let delta_latitude = other_latitude - latitude;
+        let delta_longitude = (other.longitude - self.longitude).to_radians();
+        let a = (delta_latitude / 2.0).sin().powi(2)
+            + latitude.cos() * other_latitude.cos() * (delta_longitude / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+    }
+}
+
+/// A device a user signed in from, identified by the family of its user agent and the
+/// prefix of its IP address, so that minor browser updates and address changes within a
+/// network do not make it a new device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnownDevice {
+    pub fingerprint: String,
+    /// Family of the user agent, e.g. `Firefox on Linux`.
+    pub user_agent_family: String,
+    pub ip_address: IpAddr,
+    pub location: Option<GeoLocation>,
+    pub first_seen_at: u64,
+    pub last_seen_at: u64,
+}
+
+impl KnownDevice {
+    pub fn observed(user_agent: Option<&str>, ip_address: IpAddr, location: Option<GeoLocation>, now: u64) -> Self {
+        let user_agent_family = user_agent_family(user_agent);
+        Self {
+            fingerprint: format!("{}|{}", user_agent_family, ip_prefix(ip_address)),
+            user_agent_family,
+            ip_address,
+            location,
+            first_seen_at: now,
+            last_seen_at: now,
+        }
+    }
+}
+
+/// Browser and operating system of a user agent. Other clients, such as `curl/8.0`, are
+/// named after their first product.
+pub fn user_agent_family(user_agent: Option<&str>) -> String {
+    let Some(user_agent) = user_agent.map(str::trim).filter(|user_agent| !user_agent.is_empty()) else {
+        return "Unknown".to_string();
+    };
+    // Most specific first: Edge and Opera also claim to be Chrome, and Chrome to be Safari.
+    const BROWSERS: [(&str, &str); 5] = [
+        ("Edg/", "Edge"),
+        ("OPR/", "Opera"),
+        ("Firefox/", "Firefox"),
+        ("Chrome/", "Chrome"),
+        ("Safari/", "Safari"),
+    ];
+    const SYSTEMS: [(&str, &str); 6] = [
+        ("Android", "Android"),
+        ("iPhone", "iOS"),
+        ("iPad", "iOS"),
+        ("Windows", "Windows"),
+        ("Mac OS X", "macOS"),
+        ("Linux", "Linux"),
+    ];
+    let find = |names: &[(&str, &'static str)]| {
+        names.iter().find(|(token, _)| user_agent.contains(token)).map(|(_, name)| *name)
+    };
+    match (find(&BROWSERS), find(&SYSTEMS)) {
+        (Some(browser), Some(system)) => format!("{} on {}", browser, system),
+        (Some(browser), None) => browser.to_string(),
+        _ => user_agent.split(['/', ' ']).next().unwrap_or(user_agent).to_string(),
+    }
+}
+
+/// Network of the address: its /24 for IPv4, its /48 for IPv6.
+pub fn ip_prefix(ip_address: IpAddr) -> IpNet {
+    let prefix_length = if ip_address.is_ipv4() { 24 } else { 48 };
+    IpNet::new(ip_address, prefix_length).expect("Prefix length is valid").trunc()
+}
+
+/// What is unusual about a sign-in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoginRisk {
+    /// The user signed in before, but never from this device.
+    pub new_device: bool,
+    /// The device is further from the last sign-in than anyone could have travelled since.
+    pub impossible_travel: bool,
+}
+
+impl LoginRisk {
+    /// Assesses a sign-in from the device, given the devices the user is known to use.
+    pub fn assess(known_devices: &[KnownDevice], device: &KnownDevice) -> Self {
+        let new_device =
+            !known_devices.is_empty() && !known_devices.iter().any(|known| known.fingerprint == device.fingerprint);
+        let impossible_travel = known_devices
+            .iter()
+            .max_by_key(|known| known.last_seen_at)
+            .and_then(|last| Some((last, last.location.as_ref()?, device.location.as_ref()?)))
+            .is_some_and(|(last, from, to)| {
+                let hours = device.last_seen_at.saturating_sub(last.last_seen_at) as f64 / 3600.0;
+                from.distance_km(to) > hours * MAX_TRAVEL_SPEED_KMH + GEOIP_ACCURACY_KM
+            });
+        Self {
+            new_device,
+            impossible_travel,
+        }
+    }
+
+    pub fn is_risky(&self) -> bool {
+        self.new_device || self.impossible_travel
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIREFOX: &str = "Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0";
+    const CHROME: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) \
+                          Chrome/126.0.0.0 Safari/537.36";
+    const EDGE: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) \
+                        Chrome/126.0.0.0 Safari/537.36 Edg/126.0.0.0";
+
+    fn location(latitude: f64, longitude: f64) -> Option<GeoLocation> {
+        Some(GeoLocation {
+            country: None,
+            latitude,
+            longitude,
+        })
+    }
+
+    fn device(user_agent: &str, ip_address: &str, location: Option<GeoLocation>, now: u64) -> KnownDevice {
+        KnownDevice::observed(Some(user_agent), ip_address.parse().unwrap(), location, now)
+    }
+
+    #[test]
+    fn should_name_user_agent_family() {
+        assert_eq!(user_agent_family(Some(FIREFOX)), "Firefox on Linux");
+        assert_eq!(user_agent_family(Some(CHROME)), "Chrome on Windows");
+        assert_eq!(user_agent_family(Some(EDGE)), "Edge on Windows");
+        assert_eq!(user_agent_family(Some("curl/8.0")), "curl");
+        assert_eq!(user_agent_family(Some(" ")), "Unknown");
+        assert_eq!(user_agent_family(None), "Unknown");
+    }
+
+    #[test]
+    fn should_fingerprint_by_family_and_network() {
+        let device = device(FIREFOX, "203.0.113.7", None, 100);
+        assert_eq!(device.fingerprint, "Firefox on Linux|203.0.113.0/24");
+        let updated = FIREFOX.replace("128.0", "129.0");
+        assert_eq!(self::device(&updated, "203.0.113.200", None, 100).fingerprint, device.fingerprint);
+        assert_ne!(self::device(FIREFOX, "198.51.100.7", None, 100).fingerprint, device.fingerprint);
+        assert_eq!(ip_prefix("2001:db8:1:2::1".parse().unwrap()).to_string(), "2001:db8:1::/48");
+    }
+
+    #[test]
+    fn should_measure_distance() {
+        let london = location(51.5074, -0.1278).unwrap();
+        let sydney = location(-33.8688, 151.2093).unwrap();
+        assert!((london.distance_km(&sydney) - 16_990.0).abs() < 50.0);
+        assert_eq!(london.distance_km(&london), 0.0);
+    }
+
+    #[test]
+    fn should_flag_new_device_once_user_has_one() {
+        let known = device(FIREFOX, "203.0.113.7", None, 100);
+        assert_eq!(LoginRisk::assess(&[], &known), LoginRisk::default());
+        let same_network = device(FIREFOX, "203.0.113.8", None, 200);
+        assert!(!LoginRisk::assess(std::slice::from_ref(&known), &same_network).is_risky());
+        let risk = LoginRisk::assess(&[known], &device(CHROME, "203.0.113.7", None, 200));
+        assert!(risk.new_device && !risk.impossible_travel);
+    }
+
+    #[test]
+    fn should_flag_impossible_travel() {
+        let london = device(FIREFOX, "203.0.113.7", location(51.5074, -0.1278), 0);
+        let sydney = |now| device(FIREFOX, "203.0.113.7", location(-33.8688, 151.2093), now);
+        assert!(LoginRisk::assess(std::slice::from_ref(&london), &sydney(3600)).impossible_travel);
+        assert!(!LoginRisk::assess(std::slice::from_ref(&london), &sydney(24 * 3600)).impossible_travel);
+        let paris = device(FIREFOX, "203.0.113.7", location(48.8566, 2.3522), 60);
+        assert!(LoginRisk::assess(std::slice::from_ref(&london), &paris).impossible_travel);
+        let unknown = device(FIREFOX, "203.0.113.7", None, 60);
+        assert!(!LoginRisk::assess(&[london], &unknown).impossible_travel);
+    }
+}
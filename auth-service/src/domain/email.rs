@@ -0,0 +1,112 @@
+use email_address::{EmailAddress, Options};
+use std::fmt;
+
+/// An email address in canonical form, as users are stored and looked up by. The domain is
+/// converted to lower case ASCII, internationalized domains to punycode, so that
+/// `Alice@Bücher.example` and `Alice@xn--bcher-kva.example` are the same address. The local
+/// part is lowercased too, unless it is case-sensitive: RFC 5321 allows it, but few mail
+/// servers tell `Alice` from `alice`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Email(EmailAddress);
+
+impl Email {
+    /// Parses an address with a case-insensitive local part.
+    pub fn parse(email: &str) -> Result<Self, email_address::Error> {
+        Self::parse_with_case(email, false)
+    }
+
+    pub fn parse_with_case(email: &str, case_sensitive_local_part: bool) -> Result<Self, email_address::Error> {
+        let email = email.trim();
+        let (local_part, domain) = email.rsplit_once('@').ok_or(email_address::Error::MissingSeparator)?;
+        let domain = idna::domain_to_ascii(domain).map_err(|_| email_address::Error::InvalidCharacter)?;
+        let local_part = if case_sensitive_local_part { local_part.to_string() } else { local_part.to_lowercase() };
+        let options = Options {
+            minimum_sub_domains: 2,
+            allow_domain_literal: false,
+            allow_display_text: false,
+        };
+        let email_address = EmailAddress::parse_with_options(&format!("{}@{}", local_part, domain), options)?;
+        Ok(Self(email_address))
+    }
+
+    /// Parses an address already in canonical form, e.g. one the service handed out in an
+    /// access token or a password reset, leaving its case alone.
+    pub fn parse_canonical(email: &str) -> Result<Self, email_address::Error> {
+        Self::parse_with_case(email, true)
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    pub fn local_part(&self) -> &str {
+        self.0.local_part()
+    }
+
+    /// The domain, in ASCII.
+    pub fn domain(&self) -> &str {
+        self.0.domain()
+    }
+}
+
+impl fmt::Display for Email {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl AsRef<str> for Email {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn test_email_canonical_form() {
+        let email = Email::parse("  Alice.Smith@Example.COM ").unwrap();
+        assert_eq!(email.as_str(), "alice.smith@example.com");
+        assert_eq!((email.local_part(), email.domain()), ("alice.smith", "example.com"));
+        let email = Email::parse_with_case("Alice.Smith@Example.COM", true).unwrap();
+        assert_eq!(email.as_str(), "Alice.Smith@example.com");
+    }
+
+    #[test]
+    fn test_email_collisions() {
+        let alice = Email::parse("alice@example.com").unwrap();
+        for spelling in ["Alice@example.com", "ALICE@EXAMPLE.COM", "alice@Example.Com"] {
+            assert_eq!(Email::parse(spelling).unwrap(), alice, "Spelling: {}", spelling);
+        }
+        assert_ne!(Email::parse("alice2@example.com").unwrap(), alice);
+        assert_ne!(Email::parse_with_case("Alice@example.com", true).unwrap(), alice);
+        assert_eq!(Email::parse_with_case("alice@EXAMPLE.com", true).unwrap(), alice);
+    }
+
+    #[test]
+    fn test_email_internationalized_domain() {
+        let email = Email::parse("bob@Bücher.example").unwrap();
+        assert_eq!(email.as_str(), "bob@xn--bcher-kva.example");
+        assert_eq!(Email::parse("BOB@xn--bcher-kva.EXAMPLE").unwrap(), email);
+        assert_eq!(Email::parse("ユーザー@例え.テスト").unwrap().domain(), "xn--r8jz45g.xn--zckzah");
+    }
+
+    #[test]
+    fn test_email_invalid() {
+        for email in ["", "alice", "alice@", "@example.com", "alice@localhost", "alice@exa mple.com", "a@b@c"] {
+            assert!(Email::parse(email).is_err(), "Email: {}", email);
+        }
+    }
+
+    #[quickcheck]
+    fn prop_email_canonical_form_is_stable(local_part: String, case_sensitive: bool) -> bool {
+        let Ok(email) = Email::parse_with_case(&format!("{}@Example.org", local_part), case_sensitive) else {
+            return true;
+        };
+        Email::parse_with_case(email.as_str(), case_sensitive).as_ref() == Ok(&email)
+            && Email::parse_with_case(email.as_str(), true).as_ref() == Ok(&email)
+    }
+}
@@ -0,0 +1,200 @@
+use crate::domain::Email;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, LazyLock};
+use thiserror::Error;
+
+/// Throwaway email domains, bundled with the service.
+static DISPOSABLE_DOMAINS: LazyLock<DomainList> = LazyLock::new(|| {
+    DomainList::new(include_str!("disposable_domains.txt").lines()).expect("Invalid bundled disposable domain")
+});
+
+/// Email domains users may sign up with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmailDomainPolicy {
+    /// Domains users must sign up with, if any.
+    pub allow_list: DomainList,
+    pub deny_list: DomainList,
+    /// Rejects the bundled disposable domains, unless allowed explicitly.
+    pub reject_disposable: bool,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum EmailDomainError {
+    #[error("Email addresses at {0} are not accepted")]
+    Denied(String),
+    #[error("Email addresses at {0} are not accepted, only at allowed domains")]
+    NotAllowed(String),
+    #[error("Disposable email addresses, such as at {0}, are not accepted")]
+    Disposable(String),
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum DomainListError {
+    #[error("Failed to read domain list {0}: {1}")]
+    Unreadable(String, String),
+    #[error("Invalid domain in domain list {0}: {1}")]
+    InvalidDomain(String, String),
+}
+
+impl Default for EmailDomainPolicy {
+    fn default() -> Self {
+        Self {
+            allow_list: DomainList::default(),
+            deny_list: DomainList::default(),
+            reject_disposable: true,
+        }
+    }
+}
+
+impl EmailDomainPolicy {
+    /// Checks the domain of a signing up user. The deny list comes first, then the allow
+    /// list, then the disposable domains.
+    pub fn check(&self, email: &Email) -> Result<(), EmailDomainError> {
+        let domain = email.domain();
+        if self.deny_list.contains(domain) {
+            Err(EmailDomainError::Denied(domain.to_string()))
+        } else if self.allow_list.contains(domain) {
+            Ok(())
+        } else if !self.allow_list.is_empty() {
+            Err(EmailDomainError::NotAllowed(domain.to_string()))
+        } else if self.reject_disposable && DISPOSABLE_DOMAINS.contains(domain) {
+            Err(EmailDomainError::Disposable(domain.to_string()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Email domains, matching their subdomains too. They are kept in ASCII, internationalized
+/// domains in punycode, as in [`Email`].
+#[derive(Clone, Default, PartialEq)]
+pub struct DomainList(Arc<HashSet<String>>);
+
+impl DomainList {
+    /// Parses one domain per entry, skipping blank entries and `#` comments. Returns the
+    /// first invalid entry otherwise.
+    pub fn new<S: AsRef<str>>(domains: impl IntoIterator<Item = S>) -> Result<Self, String> {
+        let mut list = HashSet::new();
+        for domain in domains {
+            let domain = domain.as_ref().trim();
+            if domain.is_empty() || domain.starts_with('#') {
+                continue;
+            }
+            match idna::domain_to_ascii(domain.trim_start_matches('@')) {
+                Ok(ascii) if is_domain_name(&ascii) => list.insert(ascii),
+                _ => return Err(domain.to_string()),
+            };
+        }
+        Ok(Self(Arc::new(list)))
+    }
+
+    /// Loads a file of one domain per line.
+    pub fn load(path: &Path) -> Result<Self, DomainListError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|error| DomainListError::Unreadable(path.display().to_string(), error.to_string()))?;
+        Self::new(contents.lines()).map_err(|domain| DomainListError::InvalidDomain(path.display().to_string(), domain))
+    }
+
+    /// Whether the list holds the domain or one of its parents.
+    pub fn contains(&self, domain: &str) -> bool {
+        let mut domain = Some(domain);
+        while let Some(name) = domain {
+            if self.0.contains(name) {
+                return true;
+            }
+            domain = name.split_once('.').map(|(_, parent)| parent);
+        }
+        false
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Whether the ASCII domain has at least two labels, of letters, digits and hyphens.
+fn is_domain_name(domain: &str) -> bool {
+    let is_label = |label: &str| !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    domain.contains('.') && domain.split('.').all(is_label)
+}
+
+// Domain lists could be long.
+impl fmt::Debug for DomainList {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "DomainList({} domains)", self.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email(email: &str) -> Email {
+        Email::parse(email).unwrap()
+    }
+
+    #[test]
+    fn test_domain_list_matches_subdomains() {
+        let list = DomainList::new(["# Partners", "", " Example.COM ", "@bücher.example"]).unwrap();
+        assert_eq!(list.len(), 2);
+        assert!(list.contains("example.com") && list.contains("mail.example.com"));
+        assert!(list.contains("xn--bcher-kva.example"));
+        assert!(!list.contains("notexample.com") && !list.contains("com"));
+        assert_eq!(DomainList::new(["example.com", "exa mple.com"]), Err("exa mple.com".to_string()));
+        assert_eq!(DomainList::new(["localhost"]), Err("localhost".to_string()));
+    }
+
+    #[test]
+    fn test_domain_list_load() {
+        let path = std::env::temp_dir().join(format!("domains-{}.txt", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "example.com\nexample.org\n").unwrap();
+        assert_eq!(DomainList::load(&path).unwrap().len(), 2);
+        std::fs::write(&path, "example.com\nexample..org\n").unwrap();
+        let result = DomainList::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result, Err(DomainListError::InvalidDomain(path.display().to_string(), "example..org".to_string())));
+        assert!(matches!(DomainList::load(&path), Err(DomainListError::Unreadable(_, _))));
+    }
+
+    #[test]
+    fn test_email_domain_policy_rejects_disposable_domains() {
+        assert!(!DISPOSABLE_DOMAINS.is_empty());
+        let policy = EmailDomainPolicy::default();
+        assert!(policy.check(&email("alice@example.com")).is_ok());
+        assert_eq!(
+            policy.check(&email("alice@Mailinator.com")),
+            Err(EmailDomainError::Disposable("mailinator.com".to_string()))
+        );
+        let policy = EmailDomainPolicy {
+            reject_disposable: false,
+            ..EmailDomainPolicy::default()
+        };
+        assert!(policy.check(&email("alice@mailinator.com")).is_ok());
+    }
+
+    #[test]
+    fn test_email_domain_policy_lists() {
+        let policy = EmailDomainPolicy {
+            allow_list: DomainList::new(["example.com", "mailinator.com"]).unwrap(),
+            deny_list: DomainList::new(["spam.example.com"]).unwrap(),
+            reject_disposable: true,
+        };
+        assert!(policy.check(&email("alice@example.com")).is_ok());
+        assert!(policy.check(&email("alice@eu.example.com")).is_ok());
+        assert!(policy.check(&email("alice@mailinator.com")).is_ok());
+        assert_eq!(
+            policy.check(&email("alice@spam.example.com")),
+            Err(EmailDomainError::Denied("spam.example.com".to_string()))
+        );
+        assert_eq!(
+            policy.check(&email("alice@example.org")),
+            Err(EmailDomainError::NotAllowed("example.org".to_string()))
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use secrecy::SecretString;
+use serde::Deserialize;
+use thiserror::Error;
+use url::Url;
+
+/// An upstream OAuth 2.0 or OpenID Connect provider users can sign in with.
+///
+/// With a `jwks_uri` the provider is treated as an OpenID Connect provider: the ID token is
+/// required and validated. The userinfo endpoint, when present, is fetched in both cases.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "IdentityProviderRegistration")]
+pub struct IdentityProvider {
+    pub name: String,
+    pub issuer: Option<String>,
+    pub client_id: String,
+    pub client_secret: SecretString,
+    pub authorization_endpoint: Url,
+    pub token_endpoint: Url,
+    pub userinfo_endpoint: Option<Url>,
+    pub jwks_uri: Option<Url>,
+    pub scopes: Vec<String>,
+    /// Claim holding the user's stable identifier at the provider.
+    pub subject_claim: String,
+    /// Whether the provider only ever returns verified email addresses, for providers
+    /// that do not send an `email_verified` claim.
+    pub trust_email: bool,
+}
+
+/// A user's account at an upstream identity provider.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExternalIdentity {
+    pub provider: String,
+    pub subject: String,
+}
+
+#[derive(Error, Debug)]
+pub enum IdentityProviderError {
+    #[error("Invalid provider name: {0}")]
+    InvalidName(String),
+    #[error("Invalid URL for {0}: {1}")]
+    InvalidUrl(&'static str, String),
+    #[error("Issuer is required to validate ID tokens")]
+    MissingIssuer,
+    #[error("Either jwks_uri or userinfo_endpoint is required")]
+    MissingProfileSource,
+}
+
+impl IdentityProvider {
+    /// URL of the provider's authorization endpoint for a login started here
+    /// (RFC 6749, section 4.1.1 and RFC 7636, section 4.3).
+    pub fn authorization_url(&self, redirect_uri: &str, state: &str, nonce: &str, code_challenge: &str) -> Url {
+        let mut url = self.authorization_endpoint.clone();
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.client_id)
+            .append_pair("redirect_uri", redirect_uri)
+            .append_pair("scope", &self.scopes.join(" "))
+            .append_pair("state", state)
+            .append_pair("code_challenge", code_challenge)
+            .append_pair("code_challenge_method", "S256");
+        if self.jwks_uri.is_some() {
+            url.query_pairs_mut().append_pair("nonce", nonce);
+        }
+        url
+    }
+}
+
+impl ExternalIdentity {
+    pub fn new(provider: &str, subject: &str) -> Self {
+        Self {
+            provider: provider.to_string(),
+            subject: subject.to_string(),
+        }
+    }
+}
+
+fn default_scopes() -> Vec<String> {
+    vec!["openid".to_string(), "email".to_string()]
+}
+
+fn default_subject_claim() -> String {
+    "sub".to_string()
+}
+
+#[derive(Deserialize)]
+struct IdentityProviderRegistration {
+    name: String,
+    issuer: Option<String>,
+    client_id: String,
+    client_secret: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    userinfo_endpoint: Option<String>,
+    jwks_uri: Option<String>,
+    #[serde(default = "default_scopes")]
+    scopes: Vec<String>,
+    #[serde(default = "default_subject_claim")]
+    subject_claim: String,
+    #[serde(default)]
+    trust_email: bool,
+}
+
+fn parse_url(name: &'static str, url: &str) -> Result<Url, IdentityProviderError> {
+    Url::parse(url).map_err(|_| IdentityProviderError::InvalidUrl(name, url.to_string()))
+}
+
+impl TryFrom<IdentityProviderRegistration> for IdentityProvider {
+    type Error = IdentityProviderError;
+
+    fn try_from(registration: IdentityProviderRegistration) -> Result<Self, Self::Error> {
+        // The name is used as a path segment of the login and callback URLs.
+        let valid_name = !registration.name.is_empty()
+            && registration
+                .name
+                .bytes()
+                .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'-');
+        if !valid_name {
+            return Err(IdentityProviderError::InvalidName(registration.name));
+        }
+        let jwks_uri = registration
+            .jwks_uri
+            .as_deref()
+            .map(|url| parse_url("jwks_uri", url))
+            .transpose()?;
+        let userinfo_endpoint = registration
+            .userinfo_endpoint
+            .as_deref()
+            .map(|url| parse_url("userinfo_endpoint", url))
+            .transpose()?;
+        if jwks_uri.is_some() && registration.issuer.is_none() {
+            return Err(IdentityProviderError::MissingIssuer);
+        }
+        if jwks_uri.is_none() && userinfo_endpoint.is_none() {
+            return Err(IdentityProviderError::MissingProfileSource);
+        }
+        Ok(Self {
+            authorization_endpoint: parse_url("authorization_endpoint", &registration.authorization_endpoint)?,
+            token_endpoint: parse_url("token_endpoint", &registration.token_endpoint)?,
+            name: registration.name,
+            issuer: registration.issuer,
+            client_id: registration.client_id,
+            client_secret: SecretString::from(registration.client_secret),
+            userinfo_endpoint,
+            jwks_uri,
+            scopes: registration.scopes,
+            subject_claim: registration.subject_claim,
+            trust_email: registration.trust_email,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn registration() -> serde_json::Value {
+        json!({
+            "name": "google",
+            "issuer": "https://accounts.google.com",
+            "client_id": "client",
+            "client_secret": "secret",
+            "authorization_endpoint": "https://accounts.google.com/o/oauth2/v2/auth",
+            "token_endpoint": "https://oauth2.googleapis.com/token",
+            "userinfo_endpoint": "https://openidconnect.googleapis.com/v1/userinfo",
+            "jwks_uri": "https://www.googleapis.com/oauth2/v3/certs",
+        })
+    }
+
+    #[test]
+    fn should_deserialize_registration_with_defaults() {
+        let provider: IdentityProvider = serde_json::from_value(registration()).unwrap();
+        assert_eq!(provider.name, "google");
+        assert_eq!(provider.scopes, vec!["openid", "email"]);
+        assert_eq!(provider.subject_claim, "sub");
+        assert!(!provider.trust_email);
+    }
+
+    #[test]
+    fn should_return_error_for_invalid_registration() {
+        let mut invalid_name = registration();
+        invalid_name["name"] = json!("Google/../admin");
+        let mut missing_issuer = registration();
+        missing_issuer.as_object_mut().unwrap().remove("issuer");
+        let mut missing_profile = registration();
+        missing_profile.as_object_mut().unwrap().remove("jwks_uri");
+        missing_profile.as_object_mut().unwrap().remove("userinfo_endpoint");
+        let mut invalid_url = registration();
+        invalid_url["token_endpoint"] = json!("not a url");
+        for input in [invalid_name, missing_issuer, missing_profile, invalid_url] {
+            assert!(serde_json::from_value::<IdentityProvider>(input.clone()).is_err(), "Input: {}", input);
+        }
+    }
+
+    #[test]
+    fn should_build_authorization_url() {
+        let provider: IdentityProvider = serde_json::from_value(registration()).unwrap();
+        let url = provider.authorization_url("http://localhost:3000/login/google/callback", "xyz", "n-0S6", "abc");
+        let query: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        assert!(query.contains(&("client_id".to_string(), "client".to_string())));
+        assert!(query.contains(&("scope".to_string(), "openid email".to_string())));
+        assert!(query.contains(&("state".to_string(), "xyz".to_string())));
+        assert!(query.contains(&("nonce".to_string(), "n-0S6".to_string())));
+        assert!(query.contains(&("code_challenge_method".to_string(), "S256".to_string())));
+    }
+}
@@ -0,0 +1,158 @@
+use crate::domain::{Email, Role};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Lifetime of invitations created without one.
+pub const INVITATION_TTL_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+/// Who may sign up with a password.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SignupMode {
+    /// Anyone may sign up.
+    #[default]
+    Open,
+    /// Only invited email addresses may sign up, with the invitation token.
+    InviteOnly,
+    /// Nobody may sign up; users are only created by upstream identity providers.
+    Closed,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum SignupModeError {
+    #[error("Unknown signup mode: {0}")]
+    UnknownSignupMode(String),
+}
+
+impl SignupMode {
+    pub const ALL: [SignupMode; 3] = [SignupMode::Open, SignupMode::InviteOnly, SignupMode::Closed];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignupMode::Open => "open",
+            SignupMode::InviteOnly => "invite-only",
+            SignupMode::Closed => "closed",
+        }
+    }
+}
+
+impl fmt::Display for SignupMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SignupMode {
+    type Err = SignupModeError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        SignupMode::ALL
+            .into_iter()
+            .find(|mode| mode.as_str() == value)
+            .ok_or_else(|| SignupModeError::UnknownSignupMode(value.to_string()))
+    }
+}
+
+/// Single-use invitation for an email address to sign up to a tenant.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Invitation {
+    pub token: String,
+    pub tenant_id: String,
+    /// In canonical form, the local part in lower case.
+    pub email: String,
+    /// Role assigned to the user at signup, instead of [`Role::User`].
+    pub role: Option<Role>,
+    pub created_at: u64,
+    pub expires_at: u64,
+}
+
+#[derive(Error, Debug)]
+pub enum InvitationError {
+    #[error("Invalid email: {0}")]
+    InvalidEmail(email_address::Error),
+    #[error("Expiry must be at least one second, and not overflow")]
+    InvalidExpiry,
+}
+
+impl Invitation {
+    pub fn try_new(
+        tenant_id: &str,
+        email: &str,
+        role: Option<Role>,
+        now: u64,
+        ttl: u64,
+    ) -> Result<Self, InvitationError> {
+        let email = Email::parse(email).map_err(InvitationError::InvalidEmail)?;
+        let expires_at = match now.checked_add(ttl) {
+            Some(expires_at) if ttl > 0 => expires_at,
+            _ => return Err(InvitationError::InvalidExpiry),
+        };
+        let token: [u8; 32] = rand::random();
+        Ok(Self {
+            token: URL_SAFE_NO_PAD.encode(token),
+            tenant_id: tenant_id.to_string(),
+            email: email.to_string(),
+            role,
+            created_at: now,
+            expires_at,
+        })
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.expires_at
+    }
+
+    /// Whether the invitation lets the email address sign up to the tenant. Invitations are
+    /// for an address regardless of case, even in tenants with case-sensitive emails.
+    pub fn admits(&self, tenant_id: &str, email: &Email, now: u64) -> bool {
+        let invited = Email::parse(email.as_str()).is_ok_and(|email| email.as_str() == self.email);
+        self.tenant_id == tenant_id && invited && !self.is_expired(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::DEFAULT_TENANT_ID;
+
+    #[test]
+    fn test_signup_mode_round_trip() {
+        for mode in SignupMode::ALL {
+            assert_eq!(mode.as_str().parse::<SignupMode>(), Ok(mode));
+        }
+        assert_eq!(
+            "invite".parse::<SignupMode>(),
+            Err(SignupModeError::UnknownSignupMode("invite".to_string()))
+        );
+    }
+
+    #[test]
+    fn should_admit_invited_email_until_expiry() {
+        let invitation = Invitation::try_new(DEFAULT_TENANT_ID, "Alice@Example.com", None, 100, 600).unwrap();
+        let email = |email: &str| Email::parse_with_case(email, true).unwrap();
+        assert_eq!(invitation.email, "alice@example.com");
+        assert!(invitation.admits(DEFAULT_TENANT_ID, &email("alice@example.com"), 100));
+        assert!(invitation.admits(DEFAULT_TENANT_ID, &email("ALICE@example.com"), 699));
+        assert!(!invitation.admits(DEFAULT_TENANT_ID, &email("alice@example.com"), 700));
+        assert!(!invitation.admits(DEFAULT_TENANT_ID, &email("bob@example.com"), 100));
+        assert!(!invitation.admits("acme", &email("alice@example.com"), 100));
+    }
+
+    #[test]
+    fn should_reject_invalid_invitation() {
+        assert!(matches!(
+            Invitation::try_new(DEFAULT_TENANT_ID, "alice", None, 100, 600),
+            Err(InvitationError::InvalidEmail(_))
+        ));
+        assert!(matches!(
+            Invitation::try_new(DEFAULT_TENANT_ID, "alice@example.com", Some(Role::Admin), 100, 0),
+            Err(InvitationError::InvalidExpiry)
+        ));
+        assert!(matches!(
+            Invitation::try_new(DEFAULT_TENANT_ID, "alice@example.com", None, 100, u64::MAX),
+            Err(InvitationError::InvalidExpiry)
+        ));
+    }
+}
@@ -0,0 +1,95 @@
+use crate::domain::{Email, KnownDevice, LoginRisk, PendingAuthorization};
+use subtle::ConstantTimeEq;
+use uuid::Uuid;
+
+/// Time a user has to enter the code emailed when signing in requires 2FA.
+pub const LOGIN_ATTEMPT_TTL_SECONDS: u64 = 10 * 60;
+/// Wrong codes after which a login attempt is abandoned, and the user must sign in again.
+pub const MAX_2FA_CODE_ATTEMPTS: u32 = 5;
+
+/// Sign-in that passed the first factor and waits for the code emailed to the user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoginAttempt {
+    pub id: String,
+    pub tenant_id: String,
+    pub email: Email,
+    /// Six-digit code emailed to the user.
+    pub code: String,
+    /// How the first factor was passed, e.g. `password` or the identity provider.
+    pub method: String,
+    pub device: KnownDevice,
+    pub risk: LoginRisk,
+    /// Authorization request the user signs in for at `/authorize`, which is answered with a
+    /// code instead of a session.
+    pub authorization: Option<PendingAuthorization>,
+    pub failed_attempts: u32,
+    pub created_at: u64,
+    pub expires_at: u64,
+}
+
+impl LoginAttempt {
+    pub fn new(tenant_id: &str, email: &Email, method: &str, device: KnownDevice, risk: LoginRisk, now: u64) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            tenant_id: tenant_id.to_string(),
+            email: email.clone(),
+            code: format!("{:06}", rand::random_range(0..1_000_000)),
+            method: method.to_string(),
+            device,
+            risk,
+            authorization: None,
+            failed_attempts: 0,
+            created_at: now,
+            expires_at: now + LOGIN_ATTEMPT_TTL_SECONDS,
+        }
+    }
+
+    pub fn with_authorization(mut self, authorization: PendingAuthorization) -> Self {
+        self.authorization = Some(authorization);
+        self
+    }
+
+    /// Whether the attempt is the user's, in the tenant, and still open.
+    pub fn admits(&self, tenant_id: &str, email: &Email, now: u64) -> bool {
+        self.tenant_id == tenant_id && &self.email == email && now < self.expires_at
+    }
+
+    /// Compares the code in constant time.
+    pub fn verify_code(&self, code: &str) -> bool {
+        code.as_bytes().ct_eq(self.code.as_bytes()).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::DEFAULT_TENANT_ID;
+
+    fn login_attempt(now: u64) -> LoginAttempt {
+        let email = Email::parse("alice@example.com").unwrap();
+        let device = KnownDevice::observed(None, "127.0.0.1".parse().unwrap(), None, now);
+        LoginAttempt::new(DEFAULT_TENANT_ID, &email, "password", device, LoginRisk::default(), now)
+    }
+
+    #[test]
+    fn should_admit_the_user_until_expiry() {
+        let attempt = login_attempt(100);
+        let email = Email::parse("alice@example.com").unwrap();
+        let other = Email::parse("bob@example.com").unwrap();
+        assert!(attempt.admits(DEFAULT_TENANT_ID, &email, 100 + LOGIN_ATTEMPT_TTL_SECONDS - 1));
+        assert!(!attempt.admits(DEFAULT_TENANT_ID, &email, 100 + LOGIN_ATTEMPT_TTL_SECONDS));
+        assert!(!attempt.admits(DEFAULT_TENANT_ID, &other, 100));
+        assert!(!attempt.admits("acme", &email, 100));
+    }
+
+    #[test]
+    fn should_verify_six_digit_code() {
+        let attempt = login_attempt(0);
+        assert_eq!(attempt.code.len(), 6);
+        assert!(attempt.code.chars().all(|c| c.is_ascii_digit()));
+        assert!(attempt.verify_code(&attempt.code.clone()));
+        assert!(!attempt.verify_code(""));
+        assert!(!attempt.verify_code(&format!("{}0", attempt.code)));
+        assert_ne!(attempt.id, login_attempt(0).id);
+    }
+}
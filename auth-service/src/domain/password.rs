@@ -1,42 +1,187 @@
+use crate::domain::PasswordPolicy;
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
 use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use subtle::ConstantTimeEq;
 use thiserror::Error;
-use zxcvbn::{zxcvbn, Score};
-
-// NIST Special Publication 800-63B
-// Section 3.1.1.2 Password Verifiers
-// https://pages.nist.gov/800-63-4/sp800-63b.html
-const MIN_PASSWORD_LENGTH: usize = 8;
-const MAX_PASSWORD_LENGTH: usize = 64;
+use unicode_normalization::UnicodeNormalization;
+use zxcvbn::time_estimates::CrackTimeSeconds;
+use zxcvbn::{zxcvbn, Entropy};
 
 #[derive(Error, Debug)]
 pub enum PasswordError {
-    #[error("Password is too short (min length is {MIN_PASSWORD_LENGTH})")]
-    TooShort,
-    #[error("Password is too long (max length is {MAX_PASSWORD_LENGTH})")]
-    TooLong,
+    #[error("Password is too short (min length is {0})")]
+    TooShort(usize),
+    #[error("Password is too long (max length is {0})")]
+    TooLong(usize),
     #[error("Password is weak")]
-    Weak,
+    Weak(PasswordFeedback),
+    #[error("Password is not allowed")]
+    Denied,
+    #[error("Password has appeared in a data breach")]
+    Breached,
+    #[error("Password is one of the last {0} passwords of the user")]
+    RecentlyUsed(usize),
+}
+
+impl PasswordError {
+    /// Short machine-readable name of the error, e.g. `too_short`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            PasswordError::TooShort(_) => "too_short",
+            PasswordError::TooLong(_) => "too_long",
+            PasswordError::Weak(_) => "weak",
+            PasswordError::Denied => "denied",
+            PasswordError::Breached => "breached",
+            PasswordError::RecentlyUsed(_) => "recently_used",
+        }
+    }
+
+    /// How to choose a better password, when zxcvbn has advice.
+    pub fn feedback(&self) -> Option<&PasswordFeedback> {
+        match self {
+            PasswordError::Weak(feedback) => Some(feedback),
+            _ => None,
+        }
+    }
+}
+
+/// zxcvbn's explanation of why a password is guessable, and how to make it stronger.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PasswordFeedback {
+    pub warning: Option<String>,
+    pub suggestions: Vec<String>,
+}
+
+impl From<&Entropy> for PasswordFeedback {
+    fn from(entropy: &Entropy) -> Self {
+        let Some(feedback) = entropy.feedback() else {
+            return Self::default();
+        };
+        Self {
+            warning: feedback.warning().map(|warning| warning.to_string()),
+            suggestions: feedback.suggestions().iter().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+/// Time an attacker would need to guess a password.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrackTime {
+    pub seconds: f64,
+    /// Rounded for people, e.g. `3 hours`.
+    pub display: String,
+}
+
+impl From<CrackTimeSeconds> for CrackTime {
+    fn from(crack_time: CrackTimeSeconds) -> Self {
+        let seconds = match crack_time {
+            CrackTimeSeconds::Integer(seconds) => seconds as f64,
+            CrackTimeSeconds::Float(seconds) => seconds,
+        };
+        Self {
+            seconds,
+            display: crack_time.to_string(),
+        }
+    }
+}
+
+/// zxcvbn's crack time estimates, for attacks of increasing speed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrackTimes {
+    /// Online attack on a service that limits sign-in attempts.
+    pub online_throttling_100_per_hour: CrackTime,
+    pub online_no_throttling_10_per_second: CrackTime,
+    /// Offline attack on salted hashes of a slow function such as bcrypt.
+    pub offline_slow_hashing_1e4_per_second: CrackTime,
+    /// Offline attack on salted hashes of a fast function such as SHA-256.
+    pub offline_fast_hashing_1e10_per_second: CrackTime,
 }
 
+/// How guessable a password is, as estimated by zxcvbn.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordStrength {
+    /// From 0 (too guessable) to 4 (very unguessable).
+    pub score: u8,
+    pub guesses_log10: f64,
+    pub crack_times: CrackTimes,
+    #[serde(flatten)]
+    pub feedback: PasswordFeedback,
+}
+
+impl From<&Entropy> for PasswordStrength {
+    fn from(entropy: &Entropy) -> Self {
+        let crack_times = entropy.crack_times();
+        Self {
+            score: entropy.score().into(),
+            guesses_log10: entropy.guesses_log10(),
+            crack_times: CrackTimes {
+                online_throttling_100_per_hour: crack_times.online_throttling_100_per_hour().into(),
+                online_no_throttling_10_per_second: crack_times.online_no_throttling_10_per_second().into(),
+                offline_slow_hashing_1e4_per_second: crack_times.offline_slow_hashing_1e4_per_second().into(),
+                offline_fast_hashing_1e10_per_second: crack_times.offline_fast_hashing_1e10_per_second().into(),
+            },
+            feedback: PasswordFeedback::from(entropy),
+        }
+    }
+}
+
+/// A password, kept in NFKC form: the same characters, composed or decomposed, or typed
+/// as compatibility variants, are the same password.
 #[derive(Debug, Clone)]
 pub struct Password(SecretString);
 
 impl Password {
     pub fn parse(raw: &str, user: &str) -> Result<Self, PasswordError> {
-        if raw.len() < MIN_PASSWORD_LENGTH {
-            return Err(PasswordError::TooShort);
+        Self::parse_with_policy(raw, user, &PasswordPolicy::default())
+    }
+
+    /// Lengths are counted in characters of the normalized password, as NIST SP 800-63B
+    /// requires.
+    pub fn parse_with_policy(raw: &str, user: &str, policy: &PasswordPolicy) -> Result<Self, PasswordError> {
+        let normalized = normalize_password(raw);
+        let length = normalized.chars().count();
+        if length < policy.min_length {
+            return Err(PasswordError::TooShort(policy.min_length));
+        }
+        if length > policy.max_length {
+            return Err(PasswordError::TooLong(policy.max_length));
         }
-        if raw.len() > MAX_PASSWORD_LENGTH {
-            return Err(PasswordError::TooLong);
+        if policy.deny_list.contains(&normalized) {
+            return Err(PasswordError::Denied);
         }
 
-        let entropy = zxcvbn(raw, &[user]);
-        // Score 3 mean that the password can be cracked with 10^10 guesses or fewer.
-        if entropy.score() < Score::Three {
-            return Err(PasswordError::Weak);
+        let entropy = entropy(&normalized, user);
+        if u8::from(entropy.score()) < policy.min_score {
+            return Err(PasswordError::Weak(PasswordFeedback::from(&entropy)));
         }
 
-        Ok(Self(SecretString::from(raw)))
+        Ok(Self(SecretString::from(normalized)))
+    }
+
+    /// Estimates how guessable the password is, as [`Password::parse`] does. Passwords
+    /// longer than the maximum length of the policy must not be estimated; zxcvbn gets slow.
+    pub fn strength(raw: &str, user: &str) -> PasswordStrength {
+        PasswordStrength::from(&entropy(&normalize_password(raw), user))
+    }
+
+    /// Whether the password typed is this password, in constant time.
+    pub fn matches(&self, raw: &str) -> bool {
+        let normalized = normalize_password(raw);
+        self.expose().as_bytes().ct_eq(normalized.as_bytes()).into()
+    }
+
+    /// An unguessable password for accounts that were not created with one.
+    pub fn random() -> Self {
+        let password: [u8; 32] = rand::random();
+        Self(SecretString::from(URL_SAFE_NO_PAD.encode(password)))
     }
 
     pub fn expose(&self) -> &str {
@@ -44,9 +189,49 @@ impl Password {
     }
 }
 
+/// Argon2id hash of a password the user had, in PHC string format, so that a new password
+/// can be told apart from old ones without keeping them. Argon2 is slow on purpose, so that
+/// leaked hashes cannot be brute-forced offline.
+#[derive(Clone, PartialEq)]
+pub struct PasswordHash(String);
+
+impl PasswordHash {
+    pub fn new(password: &Password) -> Self {
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = Argon2::default()
+            .hash_password(password.expose().as_bytes(), &salt)
+            .expect("Failed to hash password");
+        Self(hash.to_string())
+    }
+
+    /// Whether the hash is of the password, in constant time.
+    pub fn matches(&self, password: &Password) -> bool {
+        argon2::PasswordHash::new(&self.0)
+            .is_ok_and(|hash| Argon2::default().verify_password(password.expose().as_bytes(), &hash).is_ok())
+    }
+}
+
+// Users are traced; hashes of passwords must not end up in logs.
+impl fmt::Debug for PasswordHash {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("PasswordHash([REDACTED])")
+    }
+}
+
+/// The password in Unicode Normalization Form KC.
+pub fn normalize_password(raw: &str) -> String {
+    raw.nfkc().collect()
+}
+
+/// zxcvbn's estimate for the password of the user, counting words of their email as guessable.
+fn entropy(raw: &str, user: &str) -> Entropy {
+    zxcvbn(raw, &[user])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::{DenyList, MAX_PASSWORD_LENGTH, MIN_PASSWORD_LENGTH, MIN_PASSWORD_SCORE};
     use fake::faker::internet::en::SafeEmail;
     use fake::Fake;
     use quickcheck_macros::quickcheck;
@@ -58,7 +243,7 @@ mod tests {
         let user: String = SafeEmail().fake();
         let password = "a".repeat(MIN_PASSWORD_LENGTH - 1);
         let result = Password::parse(&password, &user);
-        assert!(matches!(result, Err(PasswordError::TooShort)));
+        assert!(matches!(result, Err(PasswordError::TooShort(MIN_PASSWORD_LENGTH))));
     }
 
     #[test]
@@ -66,15 +251,48 @@ mod tests {
         let user: String = SafeEmail().fake();
         let password = "a".repeat(MAX_PASSWORD_LENGTH + 1);
         let result = Password::parse(&password, &user);
-        assert!(matches!(result, Err(PasswordError::TooLong)));
+        assert!(matches!(result, Err(PasswordError::TooLong(MAX_PASSWORD_LENGTH))));
+    }
+
+    #[test]
+    fn test_password_policy() {
+        let user: String = SafeEmail().fake();
+        let policy = PasswordPolicy::try_new(32, 40).unwrap();
+        let result = Password::parse_with_policy(VALID_PASSWORD, &user, &policy);
+        assert!(matches!(result, Err(PasswordError::TooShort(32))));
+        let password = "CorrectHorseBatteryStaple123!AndMore!";
+        assert!(Password::parse_with_policy(password, &user, &policy).is_ok());
+        let password = "CorrectHorseBatteryStaple123!AndMuchMore!";
+        assert!(matches!(Password::parse_with_policy(password, &user, &policy), Err(PasswordError::TooLong(40))));
     }
 
     #[test]
     fn test_password_weak() {
         let user: String = SafeEmail().fake();
-        assert!(matches!(Password::parse("password123", &user), Err(PasswordError::Weak)));
-        assert!(matches!(Password::parse("12345678", &user), Err(PasswordError::Weak)));
-        assert!(matches!(Password::parse("qwertyuiop", &user), Err(PasswordError::Weak)));
+        assert!(matches!(Password::parse("password123", &user), Err(PasswordError::Weak(_))));
+        assert!(matches!(Password::parse("12345678", &user), Err(PasswordError::Weak(_))));
+        assert!(matches!(Password::parse("qwertyuiop", &user), Err(PasswordError::Weak(_))));
+    }
+
+    #[test]
+    fn test_password_weak_feedback() {
+        let error = Password::parse("password123", "alice@example.com").unwrap_err();
+        assert_eq!(error.code(), "weak");
+        let feedback = error.feedback().unwrap();
+        assert_eq!(feedback.warning.as_deref(), Some("This is a very common password."));
+        assert!(!feedback.suggestions.is_empty());
+    }
+
+    #[test]
+    fn test_password_policy_score_and_deny_list() {
+        let user: String = SafeEmail().fake();
+        let policy = PasswordPolicy::default().with_min_score(0).unwrap();
+        assert!(Password::parse_with_policy("password123", &user, &policy).is_ok());
+        let policy = policy.with_deny_list(DenyList::new(["Password123"]));
+        assert!(matches!(Password::parse_with_policy("PASSWORD123", &user, &policy), Err(PasswordError::Denied)));
+        let policy = PasswordPolicy::default().with_min_score(4).unwrap();
+        let result = Password::parse_with_policy("StrongPassword123!", &user, &policy);
+        assert!(matches!(result, Err(PasswordError::Weak(_))));
     }
 
     #[test]
@@ -85,6 +303,21 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_password_strength() {
+        let strength = Password::strength("password123", "alice@example.com");
+        assert_eq!(strength.score, 0);
+        let error = Password::parse("password123", "alice@example.com").unwrap_err();
+        assert_eq!(Some(&strength.feedback), error.feedback());
+        let email = "zaphod.beeblebrox.42@example.com";
+        assert!(Password::strength(email, "alice@example.com").score >= MIN_PASSWORD_SCORE);
+        assert!(Password::strength(email, email).score < MIN_PASSWORD_SCORE);
+        let strength = Password::strength(VALID_PASSWORD, "alice@example.com");
+        assert_eq!(strength.score, 4);
+        assert_eq!(strength.feedback, PasswordFeedback::default());
+        assert_eq!(strength.crack_times.offline_slow_hashing_1e4_per_second.display, "centuries");
+    }
+
     #[test]
     fn test_password_exposure() {
         let user: String = SafeEmail().fake();
@@ -93,10 +326,80 @@ mod tests {
         assert_eq!(password.expose(), password_str);
     }
 
+    #[test]
+    fn test_password_random() {
+        let password = Password::random();
+        assert!(Password::parse(password.expose(), "").is_ok());
+        assert_ne!(password.expose(), Password::random().expose());
+    }
+
+    #[test]
+    fn test_password_length_in_characters() {
+        let user: String = SafeEmail().fake();
+        // 23 characters, 69 bytes.
+        let password = "正确的马电池订书钉密码安全测试长度字符统计方法";
+        assert!(password.len() > MAX_PASSWORD_LENGTH);
+        assert!(Password::parse(password, &user).is_ok());
+        let password = "🔒🌍🎉🦀🚀".repeat(13);
+        assert!(matches!(Password::parse(&password, &user), Err(PasswordError::TooLong(MAX_PASSWORD_LENGTH))));
+        // Five characters once normalized, six decomposed.
+        let result = Password::parse("ﬃne\u{0301}", &user);
+        assert!(matches!(result, Err(PasswordError::TooShort(MIN_PASSWORD_LENGTH))));
+    }
+
+    #[test]
+    fn test_password_normalization() {
+        let user: String = SafeEmail().fake();
+        let composed = "Caf\u{00e9}CorrectHorseBattery123!";
+        let decomposed = "Cafe\u{0301}CorrectHorseBattery123!";
+        let password = Password::parse(decomposed, &user).unwrap();
+        assert_eq!(password.expose(), composed);
+        assert!(password.matches(composed) && password.matches(decomposed));
+        assert!(password.matches("Caf\u{00e9}Correct\u{ff28}orseBattery123!"));
+        assert!(!password.matches("CafeCorrectHorseBattery123!"));
+        let policy = PasswordPolicy::default().with_deny_list(DenyList::new([composed]));
+        assert!(matches!(Password::parse_with_policy(decomposed, &user, &policy), Err(PasswordError::Denied)));
+    }
+
+    #[test]
+    fn test_password_hash() {
+        let password = Password::parse("CorrectHorseBattery123!", "").unwrap();
+        let hash = PasswordHash::new(&password);
+        assert!(hash.matches(&password));
+        assert!(!hash.matches(&Password::parse("CorrectHorseBattery124!", "").unwrap()));
+        // Salted: the same password hashes differently every time.
+        assert_ne!(hash, PasswordHash::new(&password));
+        assert!(hash.0.starts_with("$argon2id$"));
+        assert_eq!(format!("{:?}", hash), "PasswordHash([REDACTED])");
+    }
+
     #[quickcheck]
     fn prop_password_parse_never_panics(password: String) -> bool {
         let user: String = SafeEmail().fake();
         let _ = Password::parse(&password, &user);
         true
     }
+
+    #[quickcheck]
+    fn prop_password_length_is_measured_in_characters(password: String) -> bool {
+        let length = normalize_password(&password).chars().count();
+        match Password::parse(&password, "alice@example.com") {
+            Err(PasswordError::TooShort(_)) => length < MIN_PASSWORD_LENGTH,
+            Err(PasswordError::TooLong(_)) => length > MAX_PASSWORD_LENGTH,
+            _ => (MIN_PASSWORD_LENGTH..=MAX_PASSWORD_LENGTH).contains(&length),
+        }
+    }
+
+    #[quickcheck]
+    fn prop_equivalent_compositions_are_the_same_password(suffix: String) -> bool {
+        let raw: String = VALID_PASSWORD.chars().chain(suffix.chars().take(10)).collect();
+        let (composed, decomposed): (String, String) = (raw.nfc().collect(), raw.nfd().collect());
+        if normalize_password(&composed) != normalize_password(&decomposed) {
+            return false;
+        }
+        match Password::parse(&decomposed, "") {
+            Ok(password) => password.expose() == normalize_password(&composed) && password.matches(&composed),
+            Err(_) => true,
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,235 @@
+use crate::domain::normalize_password;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+
+// NIST Special Publication 800-63B
+// Section 3.1.1.2 Password Verifiers
+// https://pages.nist.gov/800-63-4/sp800-63b.html
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+pub const MAX_PASSWORD_LENGTH: usize = 64;
+/// zxcvbn score 3: cracking the password takes more than 10^8 guesses.
+pub const MIN_PASSWORD_SCORE: u8 = 3;
+/// Highest zxcvbn score.
+pub const MAX_PASSWORD_SCORE: u8 = 4;
+
+/// Rules new passwords must follow. Each tenant may set its own.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, try_from = "PasswordPolicyRegistration")]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    /// Minimum zxcvbn score, from 0 (too guessable) to 4 (very unguessable).
+    pub min_score: u8,
+    pub deny_list: DenyList,
+    /// Previous passwords of a user a new password may not reuse; 0 allows any.
+    pub history_depth: usize,
+    /// Seconds after which a password expires and must be reset before signing in.
+    pub max_age: Option<u64>,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PasswordPolicyError {
+    #[error("Minimum length must be at least 1 and at most the maximum length")]
+    InvalidLength,
+    #[error("Minimum score must be at most {MAX_PASSWORD_SCORE}")]
+    InvalidScore,
+    #[error("Maximum age must be at least 1 second")]
+    InvalidMaxAge,
+    #[error("Failed to read deny list {0}: {1}")]
+    UnreadableDenyList(String, String),
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: MIN_PASSWORD_LENGTH,
+            max_length: MAX_PASSWORD_LENGTH,
+            min_score: MIN_PASSWORD_SCORE,
+            deny_list: DenyList::default(),
+            history_depth: 0,
+            max_age: None,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    pub fn try_new(min_length: usize, max_length: usize) -> Result<Self, PasswordPolicyError> {
+        if min_length == 0 || min_length > max_length {
+            return Err(PasswordPolicyError::InvalidLength);
+        }
+        Ok(Self {
+            min_length,
+            max_length,
+            ..Self::default()
+        })
+    }
+
+    pub fn with_min_score(mut self, min_score: u8) -> Result<Self, PasswordPolicyError> {
+        if min_score > MAX_PASSWORD_SCORE {
+            return Err(PasswordPolicyError::InvalidScore);
+        }
+        self.min_score = min_score;
+        Ok(self)
+    }
+
+    pub fn with_deny_list(mut self, deny_list: DenyList) -> Self {
+        self.deny_list = deny_list;
+        self
+    }
+
+    pub fn with_history_depth(mut self, history_depth: usize) -> Self {
+        self.history_depth = history_depth;
+        self
+    }
+
+    pub fn with_max_age(mut self, max_age: Option<u64>) -> Result<Self, PasswordPolicyError> {
+        if max_age == Some(0) {
+            return Err(PasswordPolicyError::InvalidMaxAge);
+        }
+        self.max_age = max_age;
+        Ok(self)
+    }
+
+    /// Whether a password set at the time has outlived the maximum age.
+    pub fn is_expired(&self, password_changed_at: u64, now: u64) -> bool {
+        self.max_age.is_some_and(|max_age| password_changed_at.saturating_add(max_age) <= now)
+    }
+}
+
+/// Passwords refused whatever their strength, e.g. the name of the company. They are
+/// compared in NFKC form, regardless of case.
+#[derive(Clone, Default, PartialEq)]
+pub struct DenyList(Arc<HashSet<String>>);
+
+impl DenyList {
+    pub fn new<S: AsRef<str>>(passwords: impl IntoIterator<Item = S>) -> Self {
+        let passwords = passwords
+            .into_iter()
+            .map(|password| normalize_password(password.as_ref().trim()).to_lowercase())
+            .filter(|password| !password.is_empty())
+            .collect();
+        Self(Arc::new(passwords))
+    }
+
+    /// Loads a file of one password per line.
+    pub fn load(path: &Path) -> Result<Self, PasswordPolicyError> {
+        let contents = std::fs::read_to_string(path).map_err(|error| {
+            PasswordPolicyError::UnreadableDenyList(path.display().to_string(), error.to_string())
+        })?;
+        Ok(Self::new(contents.lines()))
+    }
+
+    pub fn contains(&self, password: &str) -> bool {
+        self.0.contains(&normalize_password(password).to_lowercase())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+// Tenants are traced with their settings; the list itself could be long.
+impl fmt::Debug for DenyList {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "DenyList({} passwords)", self.len())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+struct PasswordPolicyRegistration {
+    min_length: usize,
+    max_length: usize,
+    min_score: u8,
+    /// File of one denied password per line.
+    deny_list_file: Option<PathBuf>,
+    history_depth: usize,
+    max_age: Option<u64>,
+}
+
+impl Default for PasswordPolicyRegistration {
+    fn default() -> Self {
+        let policy = PasswordPolicy::default();
+        Self {
+            min_length: policy.min_length,
+            max_length: policy.max_length,
+            min_score: policy.min_score,
+            deny_list_file: None,
+            history_depth: policy.history_depth,
+            max_age: policy.max_age,
+        }
+    }
+}
+
+impl TryFrom<PasswordPolicyRegistration> for PasswordPolicy {
+    type Error = PasswordPolicyError;
+
+    fn try_from(registration: PasswordPolicyRegistration) -> Result<Self, Self::Error> {
+        let deny_list = match &registration.deny_list_file {
+            Some(path) => DenyList::load(path)?,
+            None => DenyList::default(),
+        };
+        Self::try_new(registration.min_length, registration.max_length)?
+            .with_min_score(registration.min_score)?
+            .with_deny_list(deny_list)
+            .with_history_depth(registration.history_depth)
+            .with_max_age(registration.max_age)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password_policy_defaults() {
+        let policy: PasswordPolicy = serde_json::from_str("{}").unwrap();
+        assert_eq!(policy, PasswordPolicy::default());
+        let policy: PasswordPolicy = serde_json::from_str(r#"{"min_length": 12}"#).unwrap();
+        assert_eq!(policy, PasswordPolicy::try_new(12, MAX_PASSWORD_LENGTH).unwrap());
+    }
+
+    #[test]
+    fn test_password_policy_invalid_length() {
+        assert_eq!(PasswordPolicy::try_new(0, 10), Err(PasswordPolicyError::InvalidLength));
+        assert_eq!(PasswordPolicy::try_new(12, 10), Err(PasswordPolicyError::InvalidLength));
+        assert!(serde_json::from_str::<PasswordPolicy>(r#"{"min_length": 100}"#).is_err());
+    }
+
+    #[test]
+    fn test_password_policy_registration() {
+        let path = std::env::temp_dir().join(format!("deny-list-{}.txt", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "Acme2024!\n\n  letmein  \n").unwrap();
+        let registration = serde_json::json!({
+            "min_score": 4,
+            "deny_list_file": path,
+            "history_depth": 5,
+            "max_age": 7776000,
+        });
+        let policy: PasswordPolicy = serde_json::from_value(registration).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((policy.min_score, policy.history_depth, policy.max_age), (4, 5, Some(7776000)));
+        assert_eq!(policy.deny_list.len(), 2);
+        assert!(policy.deny_list.contains("ACME2024!") && policy.deny_list.contains("letmein"));
+
+        assert!(serde_json::from_str::<PasswordPolicy>(r#"{"min_score": 5}"#).is_err());
+        assert!(serde_json::from_str::<PasswordPolicy>(r#"{"max_age": 0}"#).is_err());
+        assert!(serde_json::from_value::<PasswordPolicy>(serde_json::json!({"deny_list_file": path})).is_err());
+    }
+
+    #[test]
+    fn test_password_policy_max_age() {
+        assert!(!PasswordPolicy::default().is_expired(0, u64::MAX));
+        let policy = PasswordPolicy::default().with_max_age(Some(100)).unwrap();
+        assert!(!policy.is_expired(1000, 1099));
+        assert!(policy.is_expired(1000, 1100));
+    }
+}
@@ -0,0 +1,57 @@
+use crate::domain::Email;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+
+/// Lifetime of password reset tokens.
+pub const PASSWORD_RESET_TTL_SECONDS: u64 = 60 * 60;
+/// Password resets each client address may ask for per minute.
+pub const FORGOT_PASSWORD_RATE_LIMIT: u32 = 10;
+/// Password resets that may be asked for per minute for the same account, so that its owner
+/// is not flooded with emails.
+pub const FORGOT_PASSWORD_EMAIL_RATE_LIMIT: u32 = 3;
+
+/// Single-use token emailed to a user who forgot their password, or must reset it, to choose
+/// a new one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasswordReset {
+    pub token: String,
+    pub tenant_id: String,
+    pub email: Email,
+    pub created_at: u64,
+    pub expires_at: u64,
+}
+
+impl PasswordReset {
+    pub fn new(tenant_id: &str, email: &Email, now: u64) -> Self {
+        let token: [u8; 32] = rand::random();
+        Self {
+            token: URL_SAFE_NO_PAD.encode(token),
+            tenant_id: tenant_id.to_string(),
+            email: email.clone(),
+            created_at: now,
+            expires_at: now + PASSWORD_RESET_TTL_SECONDS,
+        }
+    }
+
+    /// Whether the token lets its user reset their password in the tenant.
+    pub fn admits(&self, tenant_id: &str, now: u64) -> bool {
+        self.tenant_id == tenant_id && now < self.expires_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::DEFAULT_TENANT_ID;
+
+    #[test]
+    fn should_admit_until_expiry() {
+        let email = Email::parse("alice@example.com").unwrap();
+        let password_reset = PasswordReset::new(DEFAULT_TENANT_ID, &email, 100);
+        assert!(password_reset.admits(DEFAULT_TENANT_ID, 100));
+        assert!(password_reset.admits(DEFAULT_TENANT_ID, 100 + PASSWORD_RESET_TTL_SECONDS - 1));
+        assert!(!password_reset.admits(DEFAULT_TENANT_ID, 100 + PASSWORD_RESET_TTL_SECONDS));
+        assert!(!password_reset.admits("acme", 100));
+        assert_ne!(password_reset.token, PasswordReset::new(DEFAULT_TENANT_ID, &email, 100).token);
+    }
+}
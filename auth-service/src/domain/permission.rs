@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// An action a user may be allowed to perform. Permissions are granted through [`Role`]s
+/// and serialized as `resource:action`.
+///
+/// [`Role`]: crate::domain::Role
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Permission {
+    ProfileRead,
+    ProfileWrite,
+    UsersRead,
+    UsersWrite,
+    UsersDelete,
+    RolesAssign,
+    ClientsManage,
+    KeysRotate,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PermissionError {
+    #[error("Unknown permission: {0}")]
+    UnknownPermission(String),
+}
+
+impl Permission {
+    pub const ALL: [Permission; 8] = [
+        Permission::ProfileRead,
+        Permission::ProfileWrite,
+        Permission::UsersRead,
+        Permission::UsersWrite,
+        Permission::UsersDelete,
+        Permission::RolesAssign,
+        Permission::ClientsManage,
+        Permission::KeysRotate,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::ProfileRead => "profile:read",
+            Permission::ProfileWrite => "profile:write",
+            Permission::UsersRead => "users:read",
+            Permission::UsersWrite => "users:write",
+            Permission::UsersDelete => "users:delete",
+            Permission::RolesAssign => "roles:assign",
+            Permission::ClientsManage => "clients:manage",
+            Permission::KeysRotate => "keys:rotate",
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Permission {
+    type Err = PermissionError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Permission::ALL
+            .into_iter()
+            .find(|permission| permission.as_str() == value)
+            .ok_or_else(|| PermissionError::UnknownPermission(value.to_string()))
+    }
+}
+
+impl TryFrom<String> for Permission {
+    type Error = PermissionError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Permission> for String {
+    fn from(permission: Permission) -> Self {
+        permission.as_str().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permission_round_trip() {
+        for permission in Permission::ALL {
+            assert_eq!(permission.to_string().parse::<Permission>(), Ok(permission));
+            let json = serde_json::to_string(&permission).unwrap();
+            assert_eq!(serde_json::from_str::<Permission>(&json).unwrap(), permission);
+        }
+        assert_eq!(serde_json::to_string(&Permission::UsersRead).unwrap(), "\"users:read\"");
+    }
+
+    #[test]
+    fn test_unknown_permission() {
+        let result = "users:everything".parse::<Permission>();
+        assert_eq!(result, Err(PermissionError::UnknownPermission("users:everything".to_string())));
+        assert!(serde_json::from_str::<Permission>("\"users:everything\"").is_err());
+    }
+}
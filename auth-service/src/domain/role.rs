@@ -0,0 +1,97 @@
+use crate::domain::Permission;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// A named set of permissions assigned to users.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Admin,
+    Support,
+    User,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum RoleError {
+    #[error("Unknown role: {0}")]
+    UnknownRole(String),
+}
+
+impl Role {
+    pub const ALL: [Role; 3] = [Role::Admin, Role::Support, Role::User];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Support => "support",
+            Role::User => "user",
+        }
+    }
+
+    pub fn permissions(&self) -> &'static [Permission] {
+        match self {
+            Role::Admin => &Permission::ALL,
+            Role::Support => &[
+                Permission::ProfileRead,
+                Permission::ProfileWrite,
+                Permission::UsersRead,
+                Permission::UsersWrite,
+            ],
+            Role::User => &[Permission::ProfileRead, Permission::ProfileWrite],
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = RoleError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Role::ALL
+            .into_iter()
+            .find(|role| role.as_str() == value)
+            .ok_or_else(|| RoleError::UnknownRole(value.to_string()))
+    }
+}
+
+/// Union of the permissions granted by the roles, sorted and without duplicates.
+pub fn effective_permissions(roles: &[Role]) -> Vec<Permission> {
+    roles
+        .iter()
+        .flat_map(|role| role.permissions().iter().copied())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_round_trip() {
+        for role in Role::ALL {
+            assert_eq!(role.to_string().parse::<Role>(), Ok(role));
+        }
+        assert_eq!(serde_json::to_string(&Role::Support).unwrap(), "\"support\"");
+        assert_eq!("superuser".parse::<Role>(), Err(RoleError::UnknownRole("superuser".to_string())));
+    }
+
+    #[test]
+    fn test_effective_permissions() {
+        assert_eq!(effective_permissions(&[]), Vec::new());
+        assert_eq!(effective_permissions(&[Role::User]), Role::User.permissions());
+        let permissions = effective_permissions(&[Role::User, Role::Support]);
+        assert_eq!(permissions, Role::Support.permissions());
+        let permissions = effective_permissions(&[Role::Support, Role::Admin]);
+        assert_eq!(permissions, Permission::ALL);
+    }
+}
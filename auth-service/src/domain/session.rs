@@ -0,0 +1,58 @@
+use std::net::IpAddr;
+use uuid::Uuid;
+
+/// A signed-in device: each login opens a session, and the tokens issued for it
+/// stop verifying as soon as it is removed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub id: String,
+    pub tenant_id: String,
+    pub email: String,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<IpAddr>,
+    pub created_at: u64,
+    pub last_seen_at: u64,
+    pub expires_at: u64,
+}
+
+impl Session {
+    pub fn new(
+        tenant_id: &str,
+        email: &str,
+        user_agent: Option<&str>,
+        ip_address: Option<IpAddr>,
+        now: u64,
+        ttl: u64,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            tenant_id: tenant_id.to_string(),
+            email: email.to_string(),
+            user_agent: user_agent.map(str::to_string),
+            ip_address,
+            created_at: now,
+            last_seen_at: now,
+            expires_at: now + ttl,
+        }
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.expires_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::DEFAULT_TENANT_ID;
+
+    #[test]
+    fn should_create_session() {
+        let session = Session::new(DEFAULT_TENANT_ID, "alice@example.com", Some("curl/8.0"), None, 100, 600);
+        assert_eq!(session.created_at, 100);
+        assert_eq!(session.last_seen_at, 100);
+        assert!(!session.is_expired(699));
+        assert!(session.is_expired(700));
+        assert_ne!(session.id, Session::new(DEFAULT_TENANT_ID, "alice@example.com", None, None, 100, 600).id);
+    }
+}
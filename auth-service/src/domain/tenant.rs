@@ -0,0 +1,138 @@
+use crate::domain::{Email, PasswordPolicy};
+use serde::Deserialize;
+use thiserror::Error;
+
+/// Tenant of requests that do not name one, and of every user before tenants existed.
+pub const DEFAULT_TENANT_ID: &str = "default";
+
+/// A customer organization. Users, sessions and tokens belong to one tenant, and the same
+/// email address may sign up to several tenants as unrelated users.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "TenantRegistration")]
+pub struct Tenant {
+    pub id: String,
+    pub name: String,
+    /// Host names resolving to the tenant, e.g. `acme.auth.example.com`.
+    pub hosts: Vec<String>,
+    pub settings: TenantSettings,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct TenantSettings {
+    pub password_policy: PasswordPolicy,
+    /// Requires 2FA from every user, whatever they chose at signup.
+    pub require_2fa: bool,
+    /// Tells `Alice@example.com` and `alice@example.com` apart, as separate users.
+    pub case_sensitive_emails: bool,
+}
+
+#[derive(Error, Debug)]
+pub enum TenantError {
+    #[error("Invalid tenant id: {0}")]
+    InvalidId(String),
+    #[error("Invalid host: {0}")]
+    InvalidHost(String),
+}
+
+impl Tenant {
+    pub fn try_new(id: &str, name: &str, hosts: &[&str], settings: TenantSettings) -> Result<Self, TenantError> {
+        // The id is used as a path segment and in tokens.
+        let valid_id = !id.is_empty()
+            && id.len() <= 63
+            && id
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        if !valid_id {
+            return Err(TenantError::InvalidId(id.to_string()));
+        }
+        let hosts = hosts
+            .iter()
+            .map(|host| {
+                let host = host.to_ascii_lowercase();
+                if host.is_empty() || host.contains(['/', ':', ' ']) {
+                    Err(TenantError::InvalidHost(host))
+                } else {
+                    Ok(host)
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            hosts,
+            settings,
+        })
+    }
+
+    /// Parses an email address in the canonical form users of the tenant are looked up by.
+    pub fn parse_email(&self, email: &str) -> Result<Email, email_address::Error> {
+        Email::parse_with_case(email, self.settings.case_sensitive_emails)
+    }
+}
+
+impl Default for Tenant {
+    fn default() -> Self {
+        Self {
+            id: DEFAULT_TENANT_ID.to_string(),
+            name: "Default".to_string(),
+            hosts: Vec::new(),
+            settings: TenantSettings::default(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TenantRegistration {
+    id: String,
+    name: Option<String>,
+    #[serde(default)]
+    hosts: Vec<String>,
+    #[serde(default)]
+    settings: TenantSettings,
+}
+
+impl TryFrom<TenantRegistration> for Tenant {
+    type Error = TenantError;
+
+    fn try_from(registration: TenantRegistration) -> Result<Self, Self::Error> {
+        let name = registration.name.as_deref().unwrap_or(&registration.id);
+        let hosts: Vec<&str> = registration.hosts.iter().map(String::as_str).collect();
+        Self::try_new(&registration.id, name, &hosts, registration.settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_tenant_registration() {
+        let tenant: Tenant = serde_json::from_value(json!({
+            "id": "acme",
+            "name": "ACME Corp.",
+            "hosts": ["ACME.auth.example.com"],
+            "settings": {"require_2fa": true, "password_policy": {"min_length": 12}},
+        }))
+        .unwrap();
+        assert_eq!(tenant.id, "acme");
+        assert_eq!(tenant.hosts, vec!["acme.auth.example.com"]);
+        assert!(tenant.settings.require_2fa);
+        assert_eq!(tenant.settings.password_policy.min_length, 12);
+
+        let tenant: Tenant = serde_json::from_value(json!({"id": "globex"})).unwrap();
+        assert_eq!(tenant.name, "globex");
+        assert_eq!(tenant.settings, TenantSettings::default());
+    }
+
+    #[test]
+    fn test_tenant_invalid() {
+        for id in ["", "ACME", "acme/admin", "acme corp", &"a".repeat(64)] {
+            let result = Tenant::try_new(id, "ACME", &[], TenantSettings::default());
+            assert!(matches!(result, Err(TenantError::InvalidId(_))), "Id: {}", id);
+        }
+        let result = Tenant::try_new("acme", "ACME", &["acme.example.com:443"], TenantSettings::default());
+        assert!(matches!(result, Err(TenantError::InvalidHost(_))));
+    }
+}
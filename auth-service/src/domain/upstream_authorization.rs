@@ -0,0 +1,67 @@
+use crate::domain::code_challenge;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+
+pub const UPSTREAM_AUTHORIZATION_TTL_SECONDS: u64 = 600;
+
+/// A login in progress at an upstream identity provider, keyed by its `state` parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpstreamAuthorization {
+    pub state: String,
+    /// Tenant the user signs in to, as the callback URL is shared by all tenants.
+    pub tenant_id: String,
+    pub provider: String,
+    pub nonce: String,
+    pub code_verifier: String,
+    pub expires_at: u64,
+}
+
+fn random_string() -> String {
+    let value: [u8; 32] = rand::random();
+    URL_SAFE_NO_PAD.encode(value)
+}
+
+impl UpstreamAuthorization {
+    pub fn new(tenant_id: &str, provider: &str, now: u64) -> Self {
+        Self {
+            state: random_string(),
+            tenant_id: tenant_id.to_string(),
+            provider: provider.to_string(),
+            nonce: random_string(),
+            code_verifier: random_string(),
+            expires_at: now + UPSTREAM_AUTHORIZATION_TTL_SECONDS,
+        }
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.expires_at
+    }
+
+    pub fn code_challenge(&self) -> String {
+        code_challenge(&self.code_verifier)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::DEFAULT_TENANT_ID;
+
+    #[test]
+    fn should_generate_unique_values() {
+        let authorization_1 = UpstreamAuthorization::new(DEFAULT_TENANT_ID, "google", 0);
+        let authorization_2 = UpstreamAuthorization::new(DEFAULT_TENANT_ID, "google", 0);
+        assert_ne!(authorization_1.state, authorization_2.state);
+        assert_ne!(authorization_1.nonce, authorization_2.nonce);
+        assert_ne!(authorization_1.code_verifier, authorization_2.code_verifier);
+        // RFC 7636 requires a code verifier of 43 to 128 characters.
+        assert_eq!(authorization_1.code_verifier.len(), 43);
+    }
+
+    #[test]
+    fn should_expire() {
+        let authorization = UpstreamAuthorization::new(DEFAULT_TENANT_ID, "google", 100);
+        assert!(!authorization.is_expired(100 + UPSTREAM_AUTHORIZATION_TTL_SECONDS - 1));
+        assert!(authorization.is_expired(100 + UPSTREAM_AUTHORIZATION_TTL_SECONDS));
+    }
+}
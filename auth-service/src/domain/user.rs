@@ -1,12 +1,35 @@
-use crate::domain::{Password, PasswordError};
-use email_address::{EmailAddress, Options};
+use crate::domain::{
+    effective_permissions, Email, EmailDomainError, ExternalIdentity, KnownDevice, Password, PasswordError,
+    PasswordHash, Permission, Role, Tenant, MAX_KNOWN_DEVICES,
+};
+use jsonwebtoken::get_current_timestamp;
 use thiserror::Error;
 
+/// Consecutive failed password attempts after which the account is locked.
+pub const MAX_FAILED_LOGIN_ATTEMPTS: u32 = 5;
+
 #[derive(Debug, Clone)]
 pub struct User {
-    pub email: EmailAddress,
+    pub tenant_id: String,
+    /// In canonical form; users are stored and looked up by it.
+    pub email: Email,
     pub password: Password,
     pub requires_2fa: bool,
+    pub identities: Vec<ExternalIdentity>,
+    /// Sorted and without duplicates; new users get [`Role::User`].
+    pub roles: Vec<Role>,
+    /// Disabled users cannot sign in.
+    pub disabled: bool,
+    /// Set by an operator; the user must reset their password before signing in again.
+    pub password_reset_required: bool,
+    /// When the password was last set, to expire it after the maximum age of the policy.
+    pub password_changed_at: u64,
+    /// Hashes of the last passwords of the user, the current one included, most recent last.
+    /// As many are kept as the history depth of the tenant's password policy, and at least one.
+    pub password_history: Vec<PasswordHash>,
+    pub failed_login_attempts: u32,
+    /// Devices the user signed in from, most recently seen last.
+    pub known_devices: Vec<KnownDevice>,
 }
 
 #[derive(Error, Debug)]
@@ -15,26 +38,126 @@ pub enum UserError {
     InvalidEmail(email_address::Error),
     #[error("Invalid password: {0}")]
     InvalidPassword(PasswordError),
+    #[error("{0}")]
+    RejectedEmailDomain(EmailDomainError),
 }
 
 impl User {
+    /// Creates a user of the default tenant.
     pub fn try_new(email: &str, password: &str, requires_2fa: bool) -> Result<Self, UserError> {
-        let options = Options {
-            minimum_sub_domains: 2,
-            allow_domain_literal: false,
-            allow_display_text: false,
-        };
-        let email_address = EmailAddress::parse_with_options(email, options)
-            .map_err(UserError::InvalidEmail)?;
-        let password = Password::parse(password, email)
+        Self::try_new_for_tenant(&Tenant::default(), email, password, requires_2fa)
+    }
+
+    /// Creates a user following the settings of the tenant.
+    pub fn try_new_for_tenant(
+        tenant: &Tenant,
+        email: &str,
+        password: &str,
+        requires_2fa: bool,
+    ) -> Result<Self, UserError> {
+        let email_address = tenant.parse_email(email).map_err(UserError::InvalidEmail)?;
+        let password = Password::parse_with_policy(password, email, &tenant.settings.password_policy)
             .map_err(UserError::InvalidPassword)?;
-        Ok(Self { email: email_address, password, requires_2fa })
+        Ok(Self {
+            tenant_id: tenant.id.clone(),
+            email: email_address,
+            password_history: vec![PasswordHash::new(&password)],
+            password,
+            requires_2fa: requires_2fa || tenant.settings.require_2fa,
+            identities: Vec::new(),
+            roles: vec![Role::User],
+            disabled: false,
+            password_reset_required: false,
+            password_changed_at: get_current_timestamp(),
+            failed_login_attempts: 0,
+            known_devices: Vec::new(),
+        })
+    }
+
+    /// Creates a user signing up through an upstream identity provider. The account gets
+    /// a random password, which the user can replace through a password reset.
+    pub fn try_new_external(tenant: &Tenant, email: &str, identity: ExternalIdentity) -> Result<Self, UserError> {
+        let email_address = tenant.parse_email(email).map_err(UserError::InvalidEmail)?;
+        let password = Password::random();
+        Ok(Self {
+            tenant_id: tenant.id.clone(),
+            email: email_address,
+            password_history: vec![PasswordHash::new(&password)],
+            password,
+            requires_2fa: false,
+            identities: vec![identity],
+            roles: vec![Role::User],
+            disabled: false,
+            password_reset_required: false,
+            password_changed_at: get_current_timestamp(),
+            failed_login_attempts: 0,
+            known_devices: Vec::new(),
+        })
+    }
+
+    pub fn has_identity(&self, identity: &ExternalIdentity) -> bool {
+        self.identities.contains(identity)
+    }
+
+    /// Replaces the roles of the user.
+    pub fn set_roles(&mut self, roles: &[Role]) {
+        let mut roles = roles.to_vec();
+        roles.sort();
+        roles.dedup();
+        self.roles = roles;
+    }
+
+    pub fn has_role(&self, role: Role) -> bool {
+        self.roles.contains(&role)
+    }
+
+    /// Rejects a new password that is one of the last passwords of the user, the current one
+    /// included, up to the history depth.
+    pub fn check_password_reuse(&self, password: &Password, history_depth: usize) -> Result<(), PasswordError> {
+        if self.password_history.iter().rev().take(history_depth).any(|hash| hash.matches(password)) {
+            return Err(PasswordError::RecentlyUsed(history_depth));
+        }
+        Ok(())
+    }
+
+    /// Replaces the password of the user, remembering it in the password history, and lifts
+    /// any required reset.
+    pub fn set_password(&mut self, password: Password, history_depth: usize, now: u64) {
+        self.password_history.push(PasswordHash::new(&password));
+        let excess = self.password_history.len().saturating_sub(history_depth.max(1));
+        self.password_history.drain(..excess);
+        self.password = password;
+        self.password_changed_at = now;
+        self.password_reset_required = false;
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.failed_login_attempts >= MAX_FAILED_LOGIN_ATTEMPTS
+    }
+
+    /// Permissions granted by all the roles of the user.
+    pub fn permissions(&self) -> Vec<Permission> {
+        effective_permissions(&self.roles)
+    }
+
+    /// Remembers the device the user signed in from, forgetting the least recently seen
+    /// device beyond [`MAX_KNOWN_DEVICES`].
+    pub fn remember_device(&mut self, device: KnownDevice) {
+        let first_seen_at = match self.known_devices.iter().position(|known| known.fingerprint == device.fingerprint) {
+            Some(index) => self.known_devices.remove(index).first_seen_at,
+            None => device.first_seen_at,
+        };
+        self.known_devices.push(KnownDevice { first_seen_at, ..device });
+        if self.known_devices.len() > MAX_KNOWN_DEVICES {
+            self.known_devices.remove(0);
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::{PasswordPolicy, TenantSettings, DEFAULT_TENANT_ID};
     use fake::faker::internet::en::SafeEmail;
     use fake::{rand, Fake};
 
@@ -55,7 +178,7 @@ mod tests {
         let email = "";
         let password = VALID_PASSWORD;
         let requires_2fa = rand::random();
-        let result = User::try_new(email, &password, requires_2fa);
+        let result = User::try_new(email, password, requires_2fa);
         assert!(matches!(result, Err(UserError::InvalidEmail(_))));
     }
 
@@ -77,6 +200,119 @@ mod tests {
         assert!(matches!(result, Err(UserError::InvalidEmail(_))));
     }
 
+    #[test]
+    fn should_store_email_in_canonical_form() {
+        let user = User::try_new(" Alice@Bücher.Example ", VALID_PASSWORD, false).unwrap();
+        assert_eq!(user.email.as_str(), "alice@xn--bcher-kva.example");
+        let settings = TenantSettings {
+            case_sensitive_emails: true,
+            ..TenantSettings::default()
+        };
+        let tenant = Tenant::try_new("acme", "ACME", &[], settings).unwrap();
+        let user = User::try_new_for_tenant(&tenant, "Alice@Example.com", VALID_PASSWORD, false).unwrap();
+        assert_eq!(user.email.as_str(), "Alice@example.com");
+    }
+
+    #[test]
+    fn should_create_external_user_with_identity() {
+        let email: String = SafeEmail().fake();
+        let identity = ExternalIdentity::new("google", "1234");
+        let user = User::try_new_external(&Tenant::default(), &email, identity.clone()).unwrap();
+        assert!(user.has_identity(&identity));
+        assert!(!user.has_identity(&ExternalIdentity::new("github", "1234")));
+        let result = User::try_new_external(&Tenant::default(), "invalid-email", identity);
+        assert!(matches!(result, Err(UserError::InvalidEmail(_))));
+    }
+
+    #[test]
+    fn should_follow_tenant_settings() {
+        let email: String = SafeEmail().fake();
+        let settings = TenantSettings {
+            password_policy: PasswordPolicy::try_new(24, 64).unwrap(),
+            require_2fa: true,
+            case_sensitive_emails: false,
+        };
+        let tenant = Tenant::try_new("acme", "ACME", &[], settings).unwrap();
+        let result = User::try_new_for_tenant(&tenant, &email, VALID_PASSWORD, false);
+        assert!(matches!(result, Err(UserError::InvalidPassword(PasswordError::TooShort(24)))));
+        let user = User::try_new_for_tenant(&tenant, &email, "CorrectHorseBatteryStaple123!", false).unwrap();
+        assert_eq!(user.tenant_id, "acme");
+        assert!(user.requires_2fa);
+        assert_eq!(User::try_new(&email, VALID_PASSWORD, false).unwrap().tenant_id, DEFAULT_TENANT_ID);
+    }
+
+    #[test]
+    fn should_assign_roles() {
+        let email: String = SafeEmail().fake();
+        let mut user = User::try_new(&email, VALID_PASSWORD, false).unwrap();
+        assert_eq!(user.roles, vec![Role::User]);
+        assert!(!user.permissions().contains(&Permission::UsersRead));
+        user.set_roles(&[Role::User, Role::Support, Role::User]);
+        assert_eq!(user.roles, vec![Role::Support, Role::User]);
+        assert!(user.has_role(Role::Support));
+        assert!(user.permissions().contains(&Permission::UsersRead));
+        user.set_roles(&[]);
+        assert!(user.permissions().is_empty());
+    }
+
+    #[test]
+    fn should_lock_after_failed_login_attempts() {
+        let email: String = SafeEmail().fake();
+        let mut user = User::try_new(&email, VALID_PASSWORD, false).unwrap();
+        assert!(!user.is_locked() && !user.disabled && !user.password_reset_required);
+        user.failed_login_attempts = MAX_FAILED_LOGIN_ATTEMPTS - 1;
+        assert!(!user.is_locked());
+        user.failed_login_attempts += 1;
+        assert!(user.is_locked());
+    }
+
+    #[test]
+    fn should_reject_recently_used_passwords() {
+        let email: String = SafeEmail().fake();
+        let mut user = User::try_new(&email, VALID_PASSWORD, false).unwrap();
+        user.password_reset_required = true;
+        let passwords: Vec<Password> = ["CorrectHorseBattery1!", "CorrectHorseBattery2!", "CorrectHorseBattery3!"]
+            .iter()
+            .map(|password| Password::parse(password, &email).unwrap())
+            .collect();
+        let current = Password::parse(VALID_PASSWORD, &email).unwrap();
+        assert!(matches!(user.check_password_reuse(&current, 2), Err(PasswordError::RecentlyUsed(2))));
+        assert!(user.check_password_reuse(&current, 0).is_ok());
+
+        for password in &passwords {
+            user.set_password(password.clone(), 2, 100);
+        }
+        assert_eq!(user.password_history.len(), 2);
+        assert_eq!((user.password.expose(), user.password_changed_at), ("CorrectHorseBattery3!", 100));
+        assert!(!user.password_reset_required);
+        assert!(user.check_password_reuse(&passwords[2], 2).is_err());
+        assert!(user.check_password_reuse(&passwords[1], 2).is_err());
+        assert!(user.check_password_reuse(&passwords[1], 1).is_ok());
+        assert!(user.check_password_reuse(&passwords[0], 2).is_ok());
+
+        // Without a history, only the current password is remembered.
+        user.set_password(passwords[0].clone(), 0, 200);
+        assert_eq!(user.password_history.len(), 1);
+    }
+
+    #[test]
+    fn should_remember_recent_devices() {
+        let email: String = SafeEmail().fake();
+        let mut user = User::try_new(&email, VALID_PASSWORD, false).unwrap();
+        let device =
+            |ip_address: String, now| KnownDevice::observed(Some("curl/8.0"), ip_address.parse().unwrap(), None, now);
+        for index in 0..=MAX_KNOWN_DEVICES as u64 {
+            user.remember_device(device(format!("10.0.{}.1", index), index));
+        }
+        assert_eq!(user.known_devices.len(), MAX_KNOWN_DEVICES);
+        assert_eq!(user.known_devices[0].ip_address.to_string(), "10.0.1.1");
+
+        user.remember_device(device("10.0.1.2".to_string(), 100));
+        assert_eq!(user.known_devices.len(), MAX_KNOWN_DEVICES);
+        let last = user.known_devices.last().unwrap();
+        assert_eq!((last.ip_address.to_string().as_str(), last.first_seen_at, last.last_seen_at), ("10.0.1.2", 1, 100));
+    }
+
     #[test]
     fn should_return_invalid_password_error() {
         let email: String = SafeEmail().fake();
@@ -1,30 +1,64 @@
 use crate::app_state::AppState;
-use axum::routing::{get, post};
-use axum::serve::Serve;
+use crate::services::{server_config, CertificateResolver, TlsListener, TlsSettings};
+use axum::extract::State;
+use axum::http::header::HOST;
+use axum::http::uri::Authority;
+use axum::http::{HeaderMap, StatusCode, Uri};
+use axum::middleware;
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::routing::{delete, get, post, put};
+use axum::serve::ListenerExt;
 use axum::Router;
 use std::error::Error;
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
 use tokio::signal;
+use tokio::sync::watch;
 use tower_http::services::{ServeDir, ServeFile};
 use tower_http::trace::TraceLayer;
 use tracing::Level;
-use tracing::{info, instrument};
+use tracing::{error, info, instrument};
 
 pub mod app_state;
 pub mod domain;
 pub mod routes;
 pub mod services;
+pub mod utils;
 
 #[derive(Debug)]
 pub struct Application {
-    server: Serve<TcpListener, Router, Router>,
+    router: Router,
+    listener: Listener,
+    /// Listener redirecting plain HTTP requests to HTTPS, if any.
+    redirect_listener: Option<TcpListener>,
+    /// Certificate served over HTTPS, and how often its files are checked for changes.
+    certificates: Option<(Arc<CertificateResolver>, Duration)>,
+    /// Counts the reload signals received.
+    reloads: watch::Sender<u64>,
     pub address: SocketAddr,
+    pub redirect_address: Option<SocketAddr>,
+}
+
+#[derive(Debug)]
+enum Listener {
+    Http(TcpListener),
+    Https(TlsListener),
 }
 
 impl Application {
-    #[instrument(level = Level::TRACE, skip(state))]
+    /// Builds an application serving plain HTTP, e.g. behind a proxy terminating TLS.
     pub async fn build(state: AppState, address: SocketAddr) -> Result<Self, Box<dyn Error>> {
+        Self::build_with_tls(state, address, None).await
+    }
+
+    #[instrument(level = Level::TRACE, skip(state))]
+    pub async fn build_with_tls(
+        state: AppState,
+        address: SocketAddr,
+        tls: Option<TlsSettings>,
+    ) -> Result<Self, Box<dyn Error>> {
         let assets_dir =
             ServeDir::new("assets").not_found_service(ServeFile::new("assets/index.html"));
         info!("Initialized: Assets directory");
@@ -32,33 +66,137 @@ impl Application {
             .route("/signup", post(routes::signup))
             .route("/login", post(routes::login))
             .route("/logout", post(routes::logout))
+            .route("/change-password", post(routes::change_password))
+            .route("/forgot-password", post(routes::forgot_password))
+            .route("/reset-password", post(routes::reset_password))
+            .route("/password-strength", post(routes::password_strength))
             .route("/verify-2fa", post(routes::verify_2fa))
-            .route("/verify-token", post(routes::verify_token));
+            .route("/verify-token", post(routes::verify_token))
+            .route("/sessions", get(routes::list_sessions).delete(routes::revoke_all_sessions))
+            .route("/sessions/{session_id}", delete(routes::revoke_session))
+            .route("/api-keys", get(routes::list_api_keys).post(routes::create_api_key))
+            .route("/api-keys/{api_key_id}", delete(routes::revoke_api_key));
         info!("Initialized: API routes");
+        let admin = Router::new()
+            .route("/clients", post(routes::admin::create_client))
+            .route("/clients/{client_id}", delete(routes::admin::revoke_client))
+            .route("/invitations", post(routes::admin::create_invitation))
+            .route("/keys/rotate", post(routes::admin::rotate_signing_key))
+            .route("/users", get(routes::admin::list_users))
+            .route("/users/{email}", get(routes::admin::get_user).delete(routes::admin::delete_user))
+            .route("/users/{email}/audit-events", get(routes::admin::list_user_audit_events))
+            .route("/users/{email}/disable", post(routes::admin::disable_user))
+            .route("/users/{email}/enable", post(routes::admin::enable_user))
+            .route("/users/{email}/force-password-reset", post(routes::admin::force_password_reset))
+            .route("/users/{email}/reset-2fa", post(routes::admin::reset_2fa))
+            .route("/users/{email}/roles", put(routes::admin::set_roles))
+            .route("/users/{email}/unlock", post(routes::admin::unlock_user))
+            .route_layer(middleware::from_fn_with_state(state.clone(), utils::require_admin));
+        info!("Initialized: Admin routes");
+        let tenants = middleware::from_fn_with_state(state.clone(), utils::resolve_tenant);
         let router = Router::new()
             .route("/health", get(routes::health))
+            .route("/.well-known/jwks.json", get(routes::jwks))
+            .route("/.well-known/openid-configuration", get(routes::openid_configuration))
+            .route("/authorize", get(routes::authorize).post(routes::authorize_login))
+            .route("/token", post(routes::token))
+            .route("/oauth/token", post(routes::token))
+            .route("/oauth/introspect", post(routes::introspect))
+            .route("/oauth/revoke", post(routes::revoke))
+            .route("/userinfo", get(routes::userinfo))
+            .route("/login/{provider}", get(routes::social_login))
+            .route("/login/{provider}/callback", get(routes::social_login_callback))
             .fallback_service(assets_dir)
             .nest("/api", apis)
-            .with_state(state)
+            .nest("/admin", admin)
+            .with_state(state);
+        // The tenant path prefix is stripped before routing, so the tenant is resolved
+        // outside of the router.
+        let router = Router::new()
+            .fallback_service(router)
+            .layer(tenants)
             .layer(TraceLayer::new_for_http());
         info!("Initialized: Router");
-        let listener = TcpListener::bind(address).await?;
-        let address = listener.local_addr()?;
+        let tcp_listener = TcpListener::bind(address).await?;
+        let address = tcp_listener.local_addr()?;
+        let (listener, certificates, redirect_listener) = match tls {
+            Some(tls) => {
+                let resolver = Arc::new(CertificateResolver::load(&tls.cert_file, &tls.key_file)?);
+                let config = Arc::new(server_config(resolver.clone())?);
+                let redirect_listener = match tls.redirect_address {
+                    Some(redirect_address) => Some(TcpListener::bind(redirect_address).await?),
+                    None => None,
+                };
+                let listener = Listener::Https(TlsListener::new(tcp_listener, config)?);
+                (listener, Some((resolver, tls.reload_interval)), redirect_listener)
+            }
+            None => (Listener::Http(tcp_listener), None, None),
+        };
         info!("Initialized: Listener");
-        let server = axum::serve(listener, router);
-        info!("Initialized: Server");
-        let application = Self { server, address };
+        let redirect_address = redirect_listener.as_ref().map(TcpListener::local_addr).transpose()?;
+        if let Some(redirect_address) = redirect_address {
+            info!("Initialized: Redirect to HTTPS from {}", redirect_address);
+        }
+        let (reloads, _) = watch::channel(0);
+        let application = Self {
+            router,
+            listener,
+            redirect_listener,
+            certificates,
+            reloads,
+            address,
+            redirect_address,
+        };
         info!("Initialized: Application");
         Ok(application)
     }
 
+    /// Notifies of every reload signal, so that settings can be reloaded too.
+    pub fn subscribe_to_reloads(&self) -> watch::Receiver<u64> {
+        self.reloads.subscribe()
+    }
+
     #[instrument(level = Level::TRACE, skip(self))]
     pub async fn run(self) -> Result<(), std::io::Error> {
-        info!("Server listening on {}", self.address);
-        self.server.with_graceful_shutdown(shutdown_signal()).await
+        if let Some((resolver, reload_interval)) = self.certificates {
+            tokio::spawn(reload_certificates(resolver, reload_interval, self.reloads.subscribe()));
+        }
+        if let Some(redirect_listener) = self.redirect_listener {
+            let redirect = Router::new().fallback(redirect_to_https).with_state(self.address.port());
+            tokio::spawn(async move { axum::serve(redirect_listener, redirect).await });
+        }
+        tokio::spawn(reload_signal(self.reloads));
+        // Client addresses are recorded in sessions.
+        let service = self.router.into_make_service_with_connect_info::<SocketAddr>();
+        match self.listener {
+            Listener::Http(listener) => {
+                info!("Server listening on http://{}", self.address);
+                axum::serve(listener, service).with_graceful_shutdown(shutdown_signal()).await
+            }
+            Listener::Https(listener) => {
+                info!("Server listening on https://{}", self.address);
+                // axum only tells the client addresses of its own listeners, and of tapped ones.
+                let listener = listener.tap_io(|_| {});
+                axum::serve(listener, service).with_graceful_shutdown(shutdown_signal()).await
+            }
+        }
     }
 }
 
+/// Redirects a request to the same URL over HTTPS, on the given port.
+async fn redirect_to_https(State(port): State<u16>, headers: HeaderMap, uri: Uri) -> Response {
+    let host = headers.get(HOST).and_then(|host| host.to_str().ok()?.parse::<Authority>().ok());
+    let Some(host) = host else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let authority = match port {
+        443 => host.host().to_string(),
+        port => format!("{}:{}", host.host(), port),
+    };
+    let path_and_query = uri.path_and_query().map_or("/", |path_and_query| path_and_query.as_str());
+    Redirect::permanent(&format!("https://{}{}", authority, path_and_query)).into_response()
+}
+
 #[instrument(level = Level::TRACE)]
 async fn shutdown_signal() {
     let ctrl_c = async {
@@ -81,4 +219,41 @@ async fn shutdown_signal() {
     }
 
     info!("Shutdown signal received!");
+}
+
+/// Reloads the certificate when its files change, and on every reload signal.
+#[instrument(level = Level::TRACE, skip(resolver, reloads))]
+async fn reload_certificates(
+    resolver: Arc<CertificateResolver>,
+    reload_interval: Duration,
+    mut reloads: watch::Receiver<u64>,
+) {
+    let mut interval = tokio::time::interval(reload_interval);
+    loop {
+        let result = tokio::select! {
+            _ = interval.tick() => resolver.reload_if_changed().map(|_| ()),
+            Ok(()) = reloads.changed() => resolver.reload(),
+        };
+        if let Err(error) = result {
+            error!("Failed to reload certificate, keeping the previous one: {}", error);
+        }
+    }
+}
+
+/// Notifies the subscribers to reloads every time SIGHUP is received. The signing key is
+/// rotated through the admin API instead.
+#[instrument(level = Level::TRACE, skip(reloads))]
+async fn reload_signal(reloads: watch::Sender<u64>) {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut sighup = signal(SignalKind::hangup()).expect("failed to install SIGHUP handler");
+        while sighup.recv().await.is_some() {
+            info!("Reload signal received!");
+            reloads.send_modify(|count| *count += 1);
+        }
+    }
+
+    #[cfg(not(unix))]
+    drop(reloads);
 }
\ No newline at end of file
@@ -1,27 +1,49 @@
 mod config;
 
-use crate::config::Config;
-use auth_service::app_state::AppState;
-use auth_service::services::HashmapUserStore;
+use crate::config::{Command, Config, ReloadableSettings};
+use auth_service::app_state::{AppState, EmailClientType, Settings};
+use auth_service::domain::{verify_audit_chain, Client, IdentityProvider, Tenant, DEFAULT_TENANT_ID};
+use auth_service::services::{
+    audit_keys_file, AuditLog, ClientStore, GeoIpDatabase, HashmapAuthorizationCodeStore, HashmapBannedTokenStore,
+    HashmapClientStore, HashmapSessionStore, HashmapUserStore, JsonLinesAuditSink, Keyring, LogEmailClient,
+    PwnedPasswords,
+};
 use auth_service::Application;
-use clap::Parser;
 use dotenvy::dotenv_override;
 use fmt::format::FmtSpan;
+use jsonwebtoken::jwk::{AlgorithmParameters, Jwk, JwkSet};
+use secrecy::SecretString;
+use std::collections::HashMap;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::Path;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::info;
+use tokio::sync::{watch, RwLock};
+use tracing::{error, info, warn};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
-use tracing_subscriber::{fmt, EnvFilter};
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
+
+type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
 
 #[tokio::main]
 async fn main() {
     let dotenv = dotenv_override().ok();
-    let config = Config::parse();
+    let config = Config::load().unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(2);
+    });
+    if config.print_config {
+        print!("{}", toml::to_string_pretty(&config.to_file()).expect("Failed to print configuration"));
+        return;
+    }
+    if let Some(Command::VerifyAuditLog { file, jwks_file }) = &config.command {
+        std::process::exit(verify_audit_log(file, jwks_file.as_deref()));
+    }
 
+    // The filter can be reloaded.
+    let (log_filter, log_filter_handle) = reload::Layer::new(EnvFilter::from(config.log.to_string()));
     tracing_subscriber::registry()
-        .with(EnvFilter::from(config.log.to_string()))
+        .with(log_filter)
         .with(fmt::layer().with_span_events(FmtSpan::NEW | FmtSpan::CLOSE))
         .init();
     info!("Initialized: Tracing");
@@ -34,7 +56,100 @@ async fn main() {
     let user_store = HashmapUserStore::default();
     info!("Initialized: User store");
 
-    let app_state = AppState::new(Arc::new(RwLock::new(user_store)));
+    let keyring = Keyring::new(config.token_ttl).expect("Failed to generate signing key");
+    info!("Initialized: Keyring");
+
+    let mut client_store = HashmapClientStore::default();
+    if let Some(clients_file) = &config.clients_file {
+        let clients = std::fs::read_to_string(clients_file).expect("Failed to read clients file");
+        let clients: Vec<Client> = serde_json::from_str(&clients).expect("Failed to parse clients file");
+        for client in clients {
+            client_store.add_client(client).await.expect("Failed to register client");
+        }
+    }
+    info!("Initialized: Client store");
+
+    let mut identity_providers: Vec<IdentityProvider> = Vec::new();
+    if let Some(providers_file) = &config.providers_file {
+        let providers = std::fs::read_to_string(providers_file).expect("Failed to read providers file");
+        identity_providers = serde_json::from_str(&providers).expect("Failed to parse providers file");
+    }
+    info!("Initialized: {} identity providers", identity_providers.len());
+
+    let mut tenants: Vec<Tenant> = Vec::new();
+    if let Some(tenants_file) = &config.tenants_file {
+        let contents = std::fs::read_to_string(tenants_file).expect("Failed to read tenants file");
+        tenants = serde_json::from_str(&contents).expect("Failed to parse tenants file");
+    }
+    let settings = config.reloadable_settings().expect("Invalid settings");
+    let configures_default_tenant = !tenants.iter().any(|tenant| tenant.id == DEFAULT_TENANT_ID);
+    if configures_default_tenant {
+        let mut default = Tenant::default();
+        default.settings.password_policy = settings.password_policy.clone();
+        tenants.push(default);
+    }
+    info!("Initialized: {} tenants", tenants.len());
+
+    let authorization_code_store = HashmapAuthorizationCodeStore::default();
+    info!("Initialized: Authorization code store");
+
+    let banned_token_store = HashmapBannedTokenStore::default();
+    info!("Initialized: Banned token store");
+
+    let session_store = HashmapSessionStore::default();
+    info!("Initialized: Session store");
+
+    let audit_log = match &config.audit_log_file {
+        Some(audit_log_file) => {
+            let sink = JsonLinesAuditSink::open(audit_log_file).await.expect("Failed to open audit log file");
+            AuditLog::new(sink)
+        }
+        None => AuditLog::default(),
+    }
+    .with_checkpoint_interval(config.audit_checkpoint_interval);
+    info!("Initialized: Audit log");
+
+    let email_client: EmailClientType = match config.email_client() {
+        Some(email_client) => Arc::new(RwLock::new(email_client)),
+        None => {
+            warn!("No email API configured: new sign-in and password reset emails are only logged");
+            Arc::new(RwLock::new(LogEmailClient))
+        }
+    };
+    info!("Initialized: Email client");
+
+    let mut geoip_database = GeoIpDatabase::default();
+    if let Some(geoip_file) = &config.geoip_file {
+        geoip_database = GeoIpDatabase::load(geoip_file).expect("Failed to load GeoIP database");
+    }
+    info!("Initialized: GeoIP database");
+
+    let mut pwned_passwords = PwnedPasswords::default();
+    if let Some(path) = &config.pwned_passwords {
+        pwned_passwords = PwnedPasswords::open(path).expect("Failed to open Pwned Passwords dataset");
+    }
+    info!("Initialized: Pwned Passwords dataset");
+
+    info!("Initialized: Password strength rate limit: {}", settings.password_strength_rate_limit);
+    info!("Initialized: {:?}", settings.email_domain_policy);
+
+    let app_state = AppState::new(Arc::new(RwLock::new(user_store)), Arc::new(RwLock::new(keyring)))
+        .with_client_store(Arc::new(RwLock::new(client_store)))
+        .with_authorization_code_store(Arc::new(RwLock::new(authorization_code_store)))
+        .with_banned_token_store(Arc::new(RwLock::new(banned_token_store)))
+        .with_session_store(Arc::new(RwLock::new(session_store)))
+        .with_audit_log(Arc::new(RwLock::new(audit_log)))
+        .with_email_client(email_client)
+        .with_geoip_database(geoip_database)
+        .with_pwned_passwords(pwned_passwords)
+        .with_password_strength_rate_limit(settings.password_strength_rate_limit)
+        .with_email_domain_policy(settings.email_domain_policy.clone())
+        .with_step_up_risky_logins(config.step_up_risky_logins)
+        .with_identity_providers(identity_providers)
+        .with_tenants(tenants)
+        .with_signup_mode(config.signup_mode)
+        .with_issuer(&config.issuer)
+        .with_admin_api_key(config.admin_api_key.clone().map(SecretString::from));
     info!("Initialized: App state");
 
     let ip_address = if let Some(v6) = config.ipv6 {
@@ -47,10 +162,114 @@ async fn main() {
     let socket_addr = SocketAddr::new(ip_address, config.port);
     info!("Initialized: Listening address: {}", socket_addr);
 
-    Application::build(app_state, socket_addr)
-        .await
-        .expect("Failed to build app")
-        .run()
-        .await
-        .expect("Failed to run app")
+    let tls_settings = config.tls_settings(ip_address);
+    let application =
+        Application::build_with_tls(app_state.clone(), socket_addr, tls_settings).await.expect("Failed to build app");
+    let reloads = application.subscribe_to_reloads();
+    tokio::spawn(reload_settings(reloads, app_state, settings, configures_default_tenant, log_filter_handle));
+    application.run().await.expect("Failed to run app")
+}
+
+/// Reloads the configuration on every reload signal, applying the settings that can change
+/// while serving and logging those that changed. The previous settings are kept when the
+/// configuration is invalid. The password policy is left alone when the tenants file sets
+/// the default tenant.
+async fn reload_settings(
+    mut reloads: watch::Receiver<u64>,
+    state: AppState,
+    mut settings: ReloadableSettings,
+    configures_default_tenant: bool,
+    log_filter_handle: LogFilterHandle,
+) {
+    while reloads.changed().await.is_ok() {
+        let dotenv = dotenv_override().ok();
+        let mut reloaded = match Config::load().and_then(|config| config.reloadable_settings()) {
+            Ok(reloaded) => reloaded,
+            Err(error) => {
+                error!("Failed to reload settings, keeping the previous ones: {}", error);
+                continue;
+            }
+        };
+        if let Some(dotenv) = dotenv {
+            info!("Reloaded: {}", dotenv.display());
+        }
+        if !configures_default_tenant {
+            reloaded.password_policy = settings.password_policy.clone();
+        }
+        let changes = settings.diff(&reloaded);
+        if changes.is_empty() {
+            info!("Reloaded: No settings changed");
+            continue;
+        }
+
+        if let Err(error) = log_filter_handle.reload(EnvFilter::from(reloaded.log.to_string())) {
+            error!("Failed to reload log level: {}", error);
+        }
+        // Swapped in one step, so that requests see either the previous settings or these.
+        state.settings.rcu(|current| {
+            let mut next = Settings::clone(current);
+            next.password_strength_rate_limit = reloaded.password_strength_rate_limit;
+            if configures_default_tenant && let Some(default) = next.tenants.get_mut(DEFAULT_TENANT_ID) {
+                default.settings.password_policy = reloaded.password_policy.clone();
+            }
+            next.email_domain_policy = reloaded.email_domain_policy.clone();
+            next
+        });
+        for change in changes {
+            info!("Reloaded: {}", change);
+        }
+        settings = reloaded;
+    }
+}
+
+/// Verifies an audit log file, printing what was verified. Returns the exit code, which
+/// is 1 when a link of the chain is broken.
+///
+/// Checkpoints must be signed by a key of the JWKS file or of the keys file written next
+/// to the log. Without either, they are only checked against the key they embed, which
+/// proves nothing, and a warning says so.
+fn verify_audit_log(file: &Path, jwks_file: Option<&Path>) -> i32 {
+    let contents = std::fs::read_to_string(file).expect("Failed to read audit log file");
+    let mut trusted_keys: Option<HashMap<String, String>> = None;
+    if let Some(jwks_file) = jwks_file {
+        let jwks = std::fs::read_to_string(jwks_file).expect("Failed to read JWKS file");
+        let jwks: JwkSet = serde_json::from_str(&jwks).expect("Failed to parse JWKS file");
+        trusted_keys.get_or_insert_default().extend(jwks.keys.into_iter().filter_map(public_key));
+    }
+    let keys_file = audit_keys_file(file);
+    if keys_file.exists() {
+        let keys = std::fs::read_to_string(&keys_file).expect("Failed to read audit keys file");
+        let keys = keys.lines().filter(|line| !line.trim().is_empty());
+        let keys = keys.map(|line| serde_json::from_str::<Jwk>(line).expect("Failed to parse audit keys file"));
+        trusted_keys.get_or_insert_default().extend(keys.filter_map(public_key));
+    }
+    if trusted_keys.is_none() {
+        eprintln!(
+            "WARNING: No trusted keys, neither --jwks-file nor {}. Checkpoint signatures are only checked \
+             against the keys they embed, which anyone rewriting the log can replace.",
+            keys_file.display()
+        );
+    }
+
+    let report = verify_audit_chain(&contents, trusted_keys.as_ref());
+    println!("Verified {} records, including {} checkpoints", report.records, report.checkpoints);
+    match report.last_checkpoint {
+        Some(sequence) => println!("Last checkpoint at sequence {}", sequence),
+        None => println!("No checkpoint: records are only protected by the hash chain"),
+    }
+    match report.broken_link {
+        Some(broken_link) => {
+            println!("First broken link at line {}: {}", broken_link.line, broken_link.error);
+            1
+        }
+        None => 0,
+    }
+}
+
+/// Key id and public key of an Ed25519 JWK.
+fn public_key(jwk: Jwk) -> Option<(String, String)> {
+    match (jwk.common.key_id, jwk.algorithm) {
+        (Some(key_id), AlgorithmParameters::OctetKeyPair(parameters)) => Some((key_id, parameters.x)),
+        _ => None,
+    }
 }
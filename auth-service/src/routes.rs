@@ -1,13 +1,40 @@
+pub mod admin;
+mod api_keys;
+mod authorize;
+mod change_password;
 mod health;
+mod introspect;
+mod jwks;
 mod login;
 mod logout;
+mod openid_configuration;
+mod password_reset;
+mod password_strength;
+mod revoke;
+mod sessions;
 mod signup;
+mod social_login;
+mod token;
+mod userinfo;
 mod verify_2fa;
 mod verify_token;
 
+pub use api_keys::*;
+pub use authorize::*;
+pub use change_password::*;
 pub use health::*;
+pub use introspect::*;
+pub use jwks::*;
 pub use login::*;
 pub use logout::*;
+pub use openid_configuration::*;
+pub use password_reset::*;
+pub use password_strength::*;
+pub use revoke::*;
+pub use sessions::*;
 pub use signup::*;
+pub use social_login::*;
+pub use token::*;
+pub use userinfo::*;
 pub use verify_2fa::*;
 pub use verify_token::*;
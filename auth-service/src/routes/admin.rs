@@ -0,0 +1,20 @@
+mod audit_events;
+mod clients;
+mod invitations;
+mod keys;
+mod roles;
+mod users;
+
+pub use audit_events::*;
+pub use clients::*;
+pub use invitations::*;
+pub use keys::*;
+pub use roles::*;
+pub use users::*;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct AdminErrorResponse {
+    pub error: String,
+}
@@ -0,0 +1,39 @@
+use crate::app_state::AppState;
+use crate::domain::{AuditEvent, Tenant};
+use crate::routes::admin::AdminErrorResponse;
+use crate::utils::{Authorized, RequireUsersRead};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use serde::{Deserialize, Serialize};
+use tracing::{error, instrument};
+
+#[allow(unused_imports)]
+use tracing::Level;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuditEventsResponse {
+    pub events: Vec<AuditEvent>,
+}
+
+/// Lists the audit events the user acted in or was the subject of, oldest first.
+#[instrument(level = Level::TRACE, skip(_authorized))]
+pub async fn list_user_audit_events(
+    State(state): State<AppState>,
+    _authorized: Authorized<RequireUsersRead>,
+    Extension(tenant): Extension<Tenant>,
+    Path(email): Path<String>,
+) -> Response {
+    // Users act under their canonical email address; other actors under their name.
+    let email = tenant.parse_email(&email).map(|email| email.to_string()).unwrap_or(email);
+    let audit_log = state.audit_log.read().await;
+    match audit_log.user_events(&tenant.id, &email).await {
+        Ok(events) => (StatusCode::OK, Json(AuditEventsResponse { events })).into_response(),
+        Err(error) => {
+            error!("Unexpected error when reading audit log: {}", error);
+            let response = AdminErrorResponse { error: "Unexpected error".to_string() };
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(response)).into_response()
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use crate::app_state::AppState;
+use crate::domain::Client;
+use crate::routes::admin::AdminErrorResponse;
+use crate::services::{ClientStore, ClientStoreError};
+use crate::utils::{Authorized, RequireClientsManage};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, instrument};
+
+#[allow(unused_imports)]
+use tracing::Level;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateClientRequest {
+    pub client_id: Option<String>,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    #[serde(default)]
+    pub redirect_uris: Vec<String>,
+}
+
+/// The client secret is only ever returned in this response.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateClientResponse {
+    pub client_id: String,
+    pub client_secret: String,
+    pub scopes: Vec<String>,
+    pub redirect_uris: Vec<String>,
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    (status, Json(AdminErrorResponse { error: message })).into_response()
+}
+
+#[instrument(level = Level::TRACE, skip(_authorized))]
+pub async fn create_client(
+    State(state): State<AppState>,
+    _authorized: Authorized<RequireClientsManage>,
+    Json(request): Json<CreateClientRequest>,
+) -> Response {
+    let scopes: Vec<&str> = request.scopes.iter().map(String::as_str).collect();
+    let redirect_uris: Vec<&str> = request.redirect_uris.iter().map(String::as_str).collect();
+    let (client, client_secret) =
+        match Client::try_new_confidential(request.client_id.as_deref(), &scopes, &redirect_uris) {
+            Ok(client) => client,
+            Err(error) => return error_response(StatusCode::BAD_REQUEST, format!("Invalid client: {}", error)),
+        };
+    let response = CreateClientResponse {
+        client_id: client.client_id.clone(),
+        client_secret: client_secret.expose_secret().to_string(),
+        scopes: client.scopes.clone(),
+        redirect_uris: client.redirect_uris.iter().map(|uri| uri.to_string()).collect(),
+    };
+
+    let mut client_store = state.client_store.write().await;
+    match client_store.add_client(client).await {
+        Ok(()) => {
+            info!("Created client {}", response.client_id);
+            (StatusCode::CREATED, Json(response)).into_response()
+        }
+        Err(ClientStoreError::ClientAlreadyExists(_)) => {
+            error_response(StatusCode::CONFLICT, "Client already exists".to_string())
+        }
+        Err(error) => {
+            error!("Unexpected error when adding client to store: {}", error);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error".to_string())
+        }
+    }
+}
+
+#[instrument(level = Level::TRACE, skip(_authorized))]
+pub async fn revoke_client(
+    State(state): State<AppState>,
+    _authorized: Authorized<RequireClientsManage>,
+    Path(client_id): Path<String>,
+) -> Response {
+    let mut client_store = state.client_store.write().await;
+    match client_store.remove_client(&client_id).await {
+        Ok(()) => {
+            info!("Revoked client {}", client_id);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(ClientStoreError::ClientNotFound(_)) => {
+            error_response(StatusCode::NOT_FOUND, "Client not found".to_string())
+        }
+        Err(error) => {
+            error!("Unexpected error when removing client from store: {}", error);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error".to_string())
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::app_state::AppState;
+use crate::domain::{Invitation, Permission, Role, Tenant, INVITATION_TTL_SECONDS};
+use crate::routes::admin::AdminErrorResponse;
+use crate::services::InvitationStore;
+use crate::utils::{Authorized, RequireUsersWrite};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use jsonwebtoken::get_current_timestamp;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, instrument};
+
+#[allow(unused_imports)]
+use tracing::Level;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateInvitationRequest {
+    pub email: String,
+    /// Role assigned at signup, instead of `user`.
+    pub role: Option<Role>,
+    /// Lifetime of the invitation, in seconds.
+    pub expires_in: Option<u64>,
+}
+
+/// The invitation token is only ever returned in this response.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvitationResponse {
+    pub token: String,
+    pub email: String,
+    pub role: Option<Role>,
+    pub expires_at: u64,
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    (status, Json(AdminErrorResponse { error: message })).into_response()
+}
+
+/// Invites an email address to sign up to the tenant. Inviting with a role requires
+/// the permission to assign roles.
+#[instrument(level = Level::TRACE, skip(authorized))]
+pub async fn create_invitation(
+    State(state): State<AppState>,
+    authorized: Authorized<RequireUsersWrite>,
+    Extension(tenant): Extension<Tenant>,
+    Json(request): Json<CreateInvitationRequest>,
+) -> Response {
+    if request.role.is_some() && !authorized.claims.has_permission(Permission::RolesAssign) {
+        return error_response(StatusCode::FORBIDDEN, format!("Missing permission {}", Permission::RolesAssign));
+    }
+    let ttl = request.expires_in.unwrap_or(INVITATION_TTL_SECONDS);
+    let invitation = match Invitation::try_new(&tenant.id, &request.email, request.role, get_current_timestamp(), ttl)
+    {
+        Ok(invitation) => invitation,
+        Err(error) => return error_response(StatusCode::BAD_REQUEST, format!("Invalid invitation: {}", error)),
+    };
+    let response = InvitationResponse {
+        token: invitation.token.clone(),
+        email: invitation.email.clone(),
+        role: invitation.role,
+        expires_at: invitation.expires_at,
+    };
+
+    let mut invitation_store = state.invitation_store.write().await;
+    match invitation_store.add_invitation(invitation).await {
+        Ok(()) => {
+            info!("Invited {} to tenant {}", response.email, tenant.id);
+            (StatusCode::CREATED, Json(response)).into_response()
+        }
+        Err(error) => {
+            error!("Unexpected error when adding invitation to store: {}", error);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error".to_string())
+        }
+    }
+}
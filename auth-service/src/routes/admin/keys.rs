@@ -0,0 +1,49 @@
+use crate::app_state::AppState;
+use crate::domain::DEFAULT_TENANT_ID;
+use crate::routes::admin::AdminErrorResponse;
+use crate::utils::{Authorized, RequireKeysRotate};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, instrument};
+
+#[allow(unused_imports)]
+use tracing::Level;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct RotateSigningKeyResponse {
+    /// Key id of the signing key now active.
+    pub kid: String,
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    (status, Json(AdminErrorResponse { error: message })).into_response()
+}
+
+/// Makes a fresh signing key active. Tokens signed with the previous key keep verifying
+/// until they expire, and both keys are published in the JWKS meanwhile.
+#[instrument(level = Level::TRACE, skip(authorized))]
+pub async fn rotate_signing_key(
+    State(state): State<AppState>,
+    authorized: Authorized<RequireKeysRotate>,
+) -> Response {
+    // The keyring signs the tokens of every tenant.
+    if authorized.claims.tenant_id() != DEFAULT_TENANT_ID {
+        let message = "Signing keys are managed from the default tenant".to_string();
+        return error_response(StatusCode::FORBIDDEN, message);
+    }
+    let mut keyring = state.keyring.write().await;
+    match keyring.rotate() {
+        Ok(key) => {
+            info!("Rotated to signing key {}", key.kid());
+            let response = RotateSigningKeyResponse { kid: key.kid().to_string() };
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(error) => {
+            error!("Unexpected error when rotating signing key: {}", error);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error".to_string())
+        }
+    }
+}
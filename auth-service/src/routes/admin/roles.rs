@@ -0,0 +1,79 @@
+use crate::app_state::AppState;
+use crate::domain::{effective_permissions, Permission, Role, Tenant};
+use crate::routes::admin::AdminErrorResponse;
+use crate::services::{SessionStore, UserStore, UserStoreError};
+use crate::utils::{Authorized, RequireRolesAssign};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, instrument};
+
+#[allow(unused_imports)]
+use tracing::Level;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SetRolesRequest {
+    pub roles: Vec<Role>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SetRolesResponse {
+    pub email: String,
+    pub roles: Vec<Role>,
+    pub permissions: Vec<Permission>,
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    (status, Json(AdminErrorResponse { error: message })).into_response()
+}
+
+/// Replaces the roles of a user. Tokens carry the roles they were issued with, so the
+/// user's sessions are revoked and the new roles apply from the next sign-in.
+#[instrument(level = Level::TRACE, skip(_authorized))]
+pub async fn set_roles(
+    State(state): State<AppState>,
+    _authorized: Authorized<RequireRolesAssign>,
+    Extension(tenant): Extension<Tenant>,
+    Path(email): Path<String>,
+    Json(request): Json<SetRolesRequest>,
+) -> Response {
+    let email = match tenant.parse_email(&email) {
+        Ok(email) => email,
+        Err(error) => return error_response(StatusCode::BAD_REQUEST, format!("Invalid email: {}", error)),
+    };
+    let roles = {
+        let mut user_store = state.user_store.write().await;
+        match user_store.set_roles(&tenant.id, &email, &request.roles).await {
+            Ok(()) => {}
+            Err(UserStoreError::UserNotFound(_)) => {
+                return error_response(StatusCode::NOT_FOUND, "User not found".to_string());
+            }
+            Err(error) => {
+                error!("Unexpected error when setting user roles: {}", error);
+                return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error".to_string());
+            }
+        }
+        match user_store.get_user(&tenant.id, &email).await {
+            Ok(user) => user.roles.clone(),
+            Err(error) => {
+                error!("Unexpected error when reading user roles: {}", error);
+                return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error".to_string());
+            }
+        }
+    };
+
+    let mut session_store = state.session_store.write().await;
+    if let Err(error) = session_store.remove_user_sessions(&tenant.id, email.as_str()).await {
+        error!("Unexpected error when revoking user sessions: {}", error);
+        return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error".to_string());
+    }
+    info!("Set roles of {} to {:?}", email, roles);
+    let response = SetRolesResponse {
+        email: email.to_string(),
+        permissions: effective_permissions(&roles),
+        roles,
+    };
+    (StatusCode::OK, Json(response)).into_response()
+}
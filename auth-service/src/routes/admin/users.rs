@@ -0,0 +1,326 @@
+use crate::app_state::AppState;
+use crate::domain::{Claims, Email, Role, Tenant, User};
+use crate::routes::admin::AdminErrorResponse;
+use crate::services::{ApiKeyStore, SessionStore, UserStore, UserStoreError};
+use crate::utils::{Authorized, RequireUsersDelete, RequireUsersRead, RequireUsersWrite};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, instrument};
+
+#[allow(unused_imports)]
+use tracing::Level;
+
+pub const DEFAULT_PAGE_SIZE: usize = 20;
+pub const MAX_PAGE_SIZE: usize = 100;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ListUsersQuery {
+    /// Part of the email address to search for.
+    pub q: Option<String>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ListUsersResponse {
+    pub users: Vec<AdminUserResponse>,
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct AdminIdentityResponse {
+    pub provider: String,
+    pub subject: String,
+}
+
+/// What operators see of an account. Built field by field, so password material never
+/// leaves the service.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminUserResponse {
+    pub email: String,
+    pub roles: Vec<Role>,
+    #[serde(rename = "requires2FA")]
+    pub requires_2fa: bool,
+    pub disabled: bool,
+    pub locked: bool,
+    pub failed_login_attempts: u32,
+    pub password_reset_required: bool,
+    pub identities: Vec<AdminIdentityResponse>,
+}
+
+impl From<&User> for AdminUserResponse {
+    fn from(user: &User) -> Self {
+        Self {
+            email: user.email.to_string(),
+            roles: user.roles.clone(),
+            requires_2fa: user.requires_2fa,
+            disabled: user.disabled,
+            locked: user.is_locked(),
+            failed_login_attempts: user.failed_login_attempts,
+            password_reset_required: user.password_reset_required,
+            identities: user
+                .identities
+                .iter()
+                .map(|identity| AdminIdentityResponse {
+                    provider: identity.provider.clone(),
+                    subject: identity.subject.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    (status, Json(AdminErrorResponse { error: message })).into_response()
+}
+
+fn store_error(error: UserStoreError) -> Response {
+    match error {
+        UserStoreError::UserNotFound(_) => error_response(StatusCode::NOT_FOUND, "User not found".to_string()),
+        error => {
+            error!("Unexpected error when managing user: {}", error);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error".to_string())
+        }
+    }
+}
+
+fn invalid_email(error: email_address::Error) -> Response {
+    error_response(StatusCode::BAD_REQUEST, format!("Invalid email: {}", error))
+}
+
+async fn user_response(state: &AppState, tenant_id: &str, email: &Email) -> Response {
+    let user_store = state.user_store.read().await;
+    match user_store.get_user(tenant_id, email).await {
+        Ok(user) => (StatusCode::OK, Json(AdminUserResponse::from(user))).into_response(),
+        Err(error) => store_error(error),
+    }
+}
+
+/// Signs the user out everywhere, so that a change to the account applies immediately.
+async fn revoke_sessions(state: &AppState, tenant_id: &str, email: &Email) -> Result<(), Response> {
+    let result = state.session_store.write().await.remove_user_sessions(tenant_id, email.as_str()).await;
+    result.map(|_| ()).map_err(|error| {
+        error!("Unexpected error when revoking user sessions: {}", error);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error".to_string())
+    })
+}
+
+/// Revokes the user's API keys, which would otherwise keep working without a session.
+async fn revoke_api_keys(state: &AppState, tenant_id: &str, email: &Email) -> Result<(), Response> {
+    let result = state.api_key_store.write().await.remove_user_api_keys(tenant_id, email.as_str()).await;
+    result.map(|_| ()).map_err(|error| {
+        error!("Unexpected error when revoking user API keys: {}", error);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error".to_string())
+    })
+}
+
+/// Only admins may manage admin accounts: resetting an admin's 2FA or lock would otherwise
+/// let a support account take over the admin's permissions.
+async fn check_target(state: &AppState, claims: &Claims, tenant_id: &str, email: &Email) -> Result<(), Response> {
+    let user_store = state.user_store.read().await;
+    let user = user_store.get_user(tenant_id, email).await.map_err(store_error)?;
+    if user.roles.contains(&Role::Admin) && !claims.roles.contains(&Role::Admin) {
+        return Err(error_response(StatusCode::FORBIDDEN, "Only admins may manage admin accounts".to_string()));
+    }
+    Ok(())
+}
+
+#[instrument(level = Level::TRACE, skip(_authorized))]
+pub async fn list_users(
+    State(state): State<AppState>,
+    _authorized: Authorized<RequireUsersRead>,
+    Extension(tenant): Extension<Tenant>,
+    Query(query): Query<ListUsersQuery>,
+) -> Response {
+    let offset = query.offset.unwrap_or_default();
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        let message = format!("limit must be between 1 and {}", MAX_PAGE_SIZE);
+        return error_response(StatusCode::BAD_REQUEST, message);
+    }
+    let user_store = state.user_store.read().await;
+    match user_store.list_users(&tenant.id, query.q.as_deref(), offset, limit).await {
+        Ok(page) => {
+            let response = ListUsersResponse {
+                users: page.users.iter().map(AdminUserResponse::from).collect(),
+                total: page.total,
+                offset,
+                limit,
+            };
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(error) => store_error(error),
+    }
+}
+
+#[instrument(level = Level::TRACE, skip(_authorized))]
+pub async fn get_user(
+    State(state): State<AppState>,
+    _authorized: Authorized<RequireUsersRead>,
+    Extension(tenant): Extension<Tenant>,
+    Path(email): Path<String>,
+) -> Response {
+    let email = match tenant.parse_email(&email) {
+        Ok(email) => email,
+        Err(error) => return invalid_email(error),
+    };
+    user_response(&state, &tenant.id, &email).await
+}
+
+#[instrument(level = Level::TRACE, skip(authorized))]
+pub async fn delete_user(
+    State(state): State<AppState>,
+    authorized: Authorized<RequireUsersDelete>,
+    Extension(tenant): Extension<Tenant>,
+    Path(email): Path<String>,
+) -> Response {
+    let email = match tenant.parse_email(&email) {
+        Ok(email) => email,
+        Err(error) => return invalid_email(error),
+    };
+    if let Err(response) = check_target(&state, &authorized.claims, &tenant.id, &email).await {
+        return response;
+    }
+    let result = state.user_store.write().await.delete_user(&tenant.id, &email).await;
+    if let Err(error) = result {
+        return store_error(error);
+    }
+    if let Err(response) = revoke_sessions(&state, &tenant.id, &email).await {
+        return response;
+    }
+    // A user signing up again with the same email address must not inherit the keys.
+    if let Err(response) = revoke_api_keys(&state, &tenant.id, &email).await {
+        return response;
+    }
+    info!("Deleted user {}", email);
+    StatusCode::NO_CONTENT.into_response()
+}
+
+#[instrument(level = Level::TRACE, skip(authorized))]
+pub async fn disable_user(
+    State(state): State<AppState>,
+    authorized: Authorized<RequireUsersWrite>,
+    Extension(tenant): Extension<Tenant>,
+    Path(email): Path<String>,
+) -> Response {
+    let email = match tenant.parse_email(&email) {
+        Ok(email) => email,
+        Err(error) => return invalid_email(error),
+    };
+    if let Err(response) = check_target(&state, &authorized.claims, &tenant.id, &email).await {
+        return response;
+    }
+    let result = state.user_store.write().await.set_disabled(&tenant.id, &email, true).await;
+    if let Err(error) = result {
+        return store_error(error);
+    }
+    if let Err(response) = revoke_sessions(&state, &tenant.id, &email).await {
+        return response;
+    }
+    info!("Disabled user {}", email);
+    user_response(&state, &tenant.id, &email).await
+}
+
+#[instrument(level = Level::TRACE, skip(authorized))]
+pub async fn enable_user(
+    State(state): State<AppState>,
+    authorized: Authorized<RequireUsersWrite>,
+    Extension(tenant): Extension<Tenant>,
+    Path(email): Path<String>,
+) -> Response {
+    let email = match tenant.parse_email(&email) {
+        Ok(email) => email,
+        Err(error) => return invalid_email(error),
+    };
+    if let Err(response) = check_target(&state, &authorized.claims, &tenant.id, &email).await {
+        return response;
+    }
+    let result = state.user_store.write().await.set_disabled(&tenant.id, &email, false).await;
+    if let Err(error) = result {
+        return store_error(error);
+    }
+    info!("Enabled user {}", email);
+    user_response(&state, &tenant.id, &email).await
+}
+
+/// Requires the user to reset their password before signing in again. Signs them out and
+/// revokes their API keys, so the account cannot be used until then.
+#[instrument(level = Level::TRACE, skip(authorized))]
+pub async fn force_password_reset(
+    State(state): State<AppState>,
+    authorized: Authorized<RequireUsersWrite>,
+    Extension(tenant): Extension<Tenant>,
+    Path(email): Path<String>,
+) -> Response {
+    let email = match tenant.parse_email(&email) {
+        Ok(email) => email,
+        Err(error) => return invalid_email(error),
+    };
+    if let Err(response) = check_target(&state, &authorized.claims, &tenant.id, &email).await {
+        return response;
+    }
+    let result = state.user_store.write().await.set_password_reset_required(&tenant.id, &email, true).await;
+    if let Err(error) = result {
+        return store_error(error);
+    }
+    if let Err(response) = revoke_sessions(&state, &tenant.id, &email).await {
+        return response;
+    }
+    if let Err(response) = revoke_api_keys(&state, &tenant.id, &email).await {
+        return response;
+    }
+    info!("Forced password reset for user {}", email);
+    user_response(&state, &tenant.id, &email).await
+}
+
+/// Turns off 2FA for a user who lost their second factor.
+#[instrument(level = Level::TRACE, skip(authorized))]
+pub async fn reset_2fa(
+    State(state): State<AppState>,
+    authorized: Authorized<RequireUsersWrite>,
+    Extension(tenant): Extension<Tenant>,
+    Path(email): Path<String>,
+) -> Response {
+    let email = match tenant.parse_email(&email) {
+        Ok(email) => email,
+        Err(error) => return invalid_email(error),
+    };
+    if let Err(response) = check_target(&state, &authorized.claims, &tenant.id, &email).await {
+        return response;
+    }
+    let result = state.user_store.write().await.set_requires_2fa(&tenant.id, &email, false).await;
+    if let Err(error) = result {
+        return store_error(error);
+    }
+    info!("Reset 2FA for user {}", email);
+    user_response(&state, &tenant.id, &email).await
+}
+
+/// Unlocks an account locked after too many failed password attempts.
+#[instrument(level = Level::TRACE, skip(authorized))]
+pub async fn unlock_user(
+    State(state): State<AppState>,
+    authorized: Authorized<RequireUsersWrite>,
+    Extension(tenant): Extension<Tenant>,
+    Path(email): Path<String>,
+) -> Response {
+    let email = match tenant.parse_email(&email) {
+        Ok(email) => email,
+        Err(error) => return invalid_email(error),
+    };
+    if let Err(response) = check_target(&state, &authorized.claims, &tenant.id, &email).await {
+        return response;
+    }
+    let result = state.user_store.write().await.reset_failed_logins(&tenant.id, &email).await;
+    if let Err(error) = result {
+        return store_error(error);
+    }
+    info!("Unlocked user {}", email);
+    user_response(&state, &tenant.id, &email).await
+}
@@ -0,0 +1,167 @@
+use crate::app_state::AppState;
+use crate::domain::{ApiKey, Permission};
+use crate::services::{ApiKeyStore, ApiKeyStoreError};
+use crate::utils::Authenticated;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use jsonwebtoken::get_current_timestamp;
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, instrument};
+
+#[allow(unused_imports)]
+use tracing::Level;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    /// Permissions the key is limited to. Defaults to every permission of the user.
+    pub scopes: Option<Vec<Permission>>,
+    /// Lifetime of the key, in seconds. Keys without one never expire.
+    pub expires_in: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyResponse {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<Permission>,
+    pub created_at: u64,
+    pub expires_at: Option<u64>,
+    pub last_used_at: Option<u64>,
+}
+
+/// The key is only ever returned in this response.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiKeyResponse {
+    pub key: String,
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ApiKeysResponse {
+    ApiKeys(Vec<ApiKeyResponse>),
+    Error(String),
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(api_key: ApiKey) -> Self {
+        Self {
+            id: api_key.id,
+            name: api_key.name,
+            scopes: api_key.scopes,
+            created_at: api_key.created_at,
+            expires_at: api_key.expires_at,
+            last_used_at: api_key.last_used_at,
+        }
+    }
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(ApiKeysResponse::Error(message.to_string()))).into_response()
+}
+
+fn unexpected_error(error: ApiKeyStoreError) -> Response {
+    error!("Unexpected error when accessing API key store: {}", error);
+    error_response(StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error")
+}
+
+/// Keys are managed from a signed-in session, so that neither a key nor a token issued
+/// to an application can mint more keys.
+fn signed_in_user(authenticated: &Authenticated) -> Result<(), Box<Response>> {
+    if authenticated.claims.sid.is_none() {
+        let message = "API keys are managed from a signed-in session";
+        return Err(Box::new(error_response(StatusCode::FORBIDDEN, message)));
+    }
+    Ok(())
+}
+
+/// Creates a personal API key for the signed-in user.
+#[instrument(level = Level::TRACE, skip(authenticated))]
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    authenticated: Authenticated,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> Response {
+    if let Err(response) = signed_in_user(&authenticated) {
+        return *response;
+    }
+    let claims = &authenticated.claims;
+    let scopes = request.scopes.unwrap_or_else(|| claims.permissions.clone());
+    if let Some(scope) = scopes.iter().find(|scope| !claims.permissions.contains(scope)) {
+        return error_response(StatusCode::BAD_REQUEST, &format!("Missing permission {}", scope));
+    }
+    let now = get_current_timestamp();
+    let (api_key, key) =
+        match ApiKey::try_new(claims.tenant_id(), &claims.sub, &request.name, &scopes, now, request.expires_in) {
+            Ok(api_key) => api_key,
+            Err(error) => return error_response(StatusCode::BAD_REQUEST, &format!("Invalid API key: {}", error)),
+        };
+    let response = CreateApiKeyResponse {
+        key: key.expose_secret().to_string(),
+        api_key: api_key.clone().into(),
+    };
+
+    let mut api_key_store = state.api_key_store.write().await;
+    match api_key_store.add_api_key(api_key).await {
+        Ok(()) => {
+            info!("Created API key {} for {}", response.api_key.id, claims.sub);
+            (StatusCode::CREATED, Json(response)).into_response()
+        }
+        Err(error) => unexpected_error(error),
+    }
+}
+
+/// Lists the API keys of the signed-in user, without their secrets.
+#[instrument(level = Level::TRACE, skip(authenticated))]
+pub async fn list_api_keys(State(state): State<AppState>, authenticated: Authenticated) -> Response {
+    if let Err(response) = signed_in_user(&authenticated) {
+        return *response;
+    }
+    let claims = &authenticated.claims;
+    let api_key_store = state.api_key_store.read().await;
+    match api_key_store.get_user_api_keys(claims.tenant_id(), &claims.sub).await {
+        Ok(api_keys) => {
+            let api_keys = api_keys.into_iter().map(ApiKeyResponse::from).collect();
+            Json(ApiKeysResponse::ApiKeys(api_keys)).into_response()
+        }
+        Err(error) => unexpected_error(error),
+    }
+}
+
+/// Revokes one of the API keys of the signed-in user.
+#[instrument(level = Level::TRACE, skip(authenticated))]
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    authenticated: Authenticated,
+    Path(api_key_id): Path<String>,
+) -> Response {
+    if let Err(response) = signed_in_user(&authenticated) {
+        return *response;
+    }
+    let claims = &authenticated.claims;
+    let mut api_key_store = state.api_key_store.write().await;
+    // Keys of other users are reported as missing, so that their ids cannot be probed.
+    match api_key_store.get_api_key(&api_key_id).await {
+        Ok(api_key) if api_key.tenant_id == claims.tenant_id() && api_key.email == claims.sub => {}
+        Ok(_) | Err(ApiKeyStoreError::ApiKeyNotFound(_)) => {
+            return error_response(StatusCode::NOT_FOUND, "API key not found");
+        }
+        Err(error) => return unexpected_error(error),
+    }
+    match api_key_store.remove_api_key(&api_key_id).await {
+        Ok(_) => {
+            info!("Revoked API key {} of {}", api_key_id, claims.sub);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(ApiKeyStoreError::ApiKeyNotFound(_)) => error_response(StatusCode::NOT_FOUND, "API key not found"),
+        Err(error) => unexpected_error(error),
+    }
+}
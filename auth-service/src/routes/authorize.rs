@@ -0,0 +1,248 @@
+use crate::app_state::AppState;
+use crate::domain::{AuditContext, AuditEvent, AuditEventKind, PendingAuthorization, Tenant};
+use crate::routes::two_factor_required;
+use crate::services::{AuthorizationCodeStore, ClientStore, UserStoreError};
+use crate::utils::{
+    account_status_message, assess_sign_in, audit, challenge_2fa, check_password, complete_sign_in,
+    login_failure_reason, requires_2fa,
+};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::{Extension, Json};
+use jsonwebtoken::get_current_timestamp;
+use serde::{Deserialize, Serialize};
+use tracing::{error, instrument};
+use url::Url;
+
+#[allow(unused_imports)]
+use tracing::Level;
+
+const LOGIN_PAGE: &str = "assets/index.html";
+
+/// Authorization request parameters (RFC 6749, section 4.1.1 and RFC 7636, section 4.3).
+///
+/// Every parameter is optional here so that missing ones are reported as OAuth errors
+/// instead of being rejected by the extractor.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuthorizationRequest {
+    pub response_type: Option<String>,
+    pub client_id: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub scope: Option<String>,
+    pub state: Option<String>,
+    pub nonce: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuthorizeCredentials {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AuthorizeResponse {
+    RedirectUri(String),
+    Error(String),
+}
+
+/// Errors are redirected back to the client, unless its identity or its redirection
+/// endpoint could not be established (RFC 6749, section 4.1.2.1).
+#[derive(Debug)]
+enum AuthorizeError {
+    InvalidClient(String),
+    InvalidRedirectUri(String),
+    Redirect(Url),
+}
+
+impl AuthorizeError {
+    fn redirect(redirect_uri: &Url, error: &str, description: &str, state: Option<&str>) -> Self {
+        let mut url = redirect_uri.clone();
+        url.query_pairs_mut()
+            .append_pair("error", error)
+            .append_pair("error_description", description);
+        if let Some(state) = state {
+            url.query_pairs_mut().append_pair("state", state);
+        }
+        AuthorizeError::Redirect(url)
+    }
+
+    fn into_error_response(self) -> (StatusCode, Json<AuthorizeResponse>) {
+        match self {
+            AuthorizeError::InvalidClient(message) | AuthorizeError::InvalidRedirectUri(message) => {
+                (StatusCode::BAD_REQUEST, Json(AuthorizeResponse::Error(message)))
+            }
+            AuthorizeError::Redirect(url) => {
+                (StatusCode::OK, Json(AuthorizeResponse::RedirectUri(url.to_string())))
+            }
+        }
+    }
+}
+
+async fn validate(state: &AppState, request: &AuthorizationRequest) -> Result<PendingAuthorization, AuthorizeError> {
+    let client_id = request
+        .client_id
+        .as_deref()
+        .ok_or(AuthorizeError::InvalidClient("Missing client_id".to_string()))?;
+    let client_store = state.client_store.read().await;
+    let client = client_store
+        .get_client(client_id)
+        .await
+        .map_err(|_| AuthorizeError::InvalidClient(format!("Unknown client: {}", client_id)))?;
+    let redirect_uri = request
+        .redirect_uri
+        .as_deref()
+        .and_then(|uri| client.redirect_uri(uri))
+        .ok_or(AuthorizeError::InvalidRedirectUri("Unregistered redirect_uri".to_string()))?
+        .clone();
+
+    let request_state = request.state.as_deref();
+    if request.response_type.as_deref() != Some("code") {
+        return Err(AuthorizeError::redirect(
+            &redirect_uri,
+            "unsupported_response_type",
+            "Only the authorization code flow is supported",
+            request_state,
+        ));
+    }
+    let scope = request.scope.clone().unwrap_or_default();
+    if !scope.split_whitespace().any(|scope| scope == "openid") {
+        return Err(AuthorizeError::redirect(
+            &redirect_uri,
+            "invalid_scope",
+            "The openid scope is required",
+            request_state,
+        ));
+    }
+    let Some(code_challenge) = request.code_challenge.clone() else {
+        return Err(AuthorizeError::redirect(
+            &redirect_uri,
+            "invalid_request",
+            "PKCE code_challenge is required",
+            request_state,
+        ));
+    };
+    if request.code_challenge_method.as_deref() != Some("S256") {
+        return Err(AuthorizeError::redirect(
+            &redirect_uri,
+            "invalid_request",
+            "Only the S256 code_challenge_method is supported",
+            request_state,
+        ));
+    }
+
+    Ok(PendingAuthorization {
+        client_id: client_id.to_string(),
+        redirect_uri,
+        scope,
+        state: request.state.clone(),
+        nonce: request.nonce.clone(),
+        code_challenge,
+    })
+}
+
+/// Serves the login UI once the authorization request has been validated.
+#[instrument(level = Level::TRACE)]
+pub async fn authorize(State(state): State<AppState>, Query(request): Query<AuthorizationRequest>) -> Response {
+    match validate(&state, &request).await {
+        Ok(_) => match tokio::fs::read_to_string(LOGIN_PAGE).await {
+            Ok(page) => Html(page).into_response(),
+            Err(error) => {
+                error!("Unexpected error when reading login page: {}", error);
+                let response = Json(AuthorizeResponse::Error("Unexpected error".to_string()));
+                (StatusCode::INTERNAL_SERVER_ERROR, response).into_response()
+            }
+        },
+        Err(AuthorizeError::Redirect(url)) => Redirect::to(url.as_str()).into_response(),
+        Err(error) => error.into_error_response().into_response(),
+    }
+}
+
+/// Authenticates the user submitted from the login UI and issues an authorization code.
+#[instrument(level = Level::TRACE, skip(context, credentials))]
+pub async fn authorize_login(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<Tenant>,
+    context: AuditContext,
+    Query(request): Query<AuthorizationRequest>,
+    Json(credentials): Json<AuthorizeCredentials>,
+) -> Response {
+    let request = match validate(&state, &request).await {
+        Ok(request) => request,
+        Err(error) => return error.into_error_response().into_response(),
+    };
+
+    // No user has an invalid email address.
+    let Ok(email) = tenant.parse_email(&credentials.email) else {
+        return response(StatusCode::UNAUTHORIZED, AuthorizeResponse::Error("Incorrect credentials".to_string()));
+    };
+    let event = |kind| AuditEvent::new(&tenant.id, email.as_str(), kind, &context, get_current_timestamp());
+    let result = check_password(&state, &tenant.id, &email, &credentials.password, &context).await;
+    if let Some(reason) = result.as_ref().err().and_then(login_failure_reason) {
+        audit(&state, event(AuditEventKind::LoginFailed { reason })).await;
+    }
+    match result {
+        Ok(()) => {}
+        Err(UserStoreError::UserNotFound(_)) | Err(UserStoreError::InvalidCredentials(_)) => {
+            return response(StatusCode::UNAUTHORIZED, AuthorizeResponse::Error("Incorrect credentials".to_string()));
+        }
+        Err(
+            error @ (UserStoreError::AccountDisabled(_)
+            | UserStoreError::AccountLocked(_)
+            | UserStoreError::PasswordResetRequired(_)),
+        ) => {
+            let message = AuthorizeResponse::Error(account_status_message(&error).to_string());
+            return response(StatusCode::FORBIDDEN, message);
+        }
+        Err(error) => {
+            error!("Unexpected error when validating user: {}", error);
+            return unexpected_error();
+        }
+    }
+
+    let assessment = match assess_sign_in(&state, &tenant.id, &email, &context).await {
+        Ok(assessment) => assessment,
+        Err(error) => {
+            error!("Unexpected error when assessing sign-in: {}", error);
+            return unexpected_error();
+        }
+    };
+    // No code is issued until the second factor is passed.
+    if requires_2fa(&state, &tenant, &email, &assessment).await {
+        return match challenge_2fa(&state, &tenant.id, &email, "password", assessment, Some(request), &context).await {
+            Ok(login_attempt_id) => two_factor_required(login_attempt_id),
+            Err(error) => {
+                error!("Unexpected error when challenging 2FA: {}", error);
+                unexpected_error()
+            }
+        };
+    }
+
+    let (code, redirect_uri) = request.issue_code(
+        &tenant.id,
+        email.as_str(),
+        context.user_agent.as_deref(),
+        context.ip_address,
+        get_current_timestamp(),
+    );
+    let mut code_store = state.authorization_code_store.write().await;
+    if let Err(error) = code_store.add_code(code).await {
+        error!("Unexpected error when storing authorization code: {}", error);
+        return unexpected_error();
+    }
+    drop(code_store);
+    audit(&state, event(AuditEventKind::LoginSucceeded { method: "password".to_string() })).await;
+    complete_sign_in(&state, &tenant.id, &email, assessment).await;
+    response(StatusCode::OK, AuthorizeResponse::RedirectUri(redirect_uri.to_string()))
+}
+
+fn response(status: StatusCode, response: AuthorizeResponse) -> Response {
+    (status, Json(response)).into_response()
+}
+
+fn unexpected_error() -> Response {
+    response(StatusCode::INTERNAL_SERVER_ERROR, AuthorizeResponse::Error("Unexpected error".to_string()))
+}
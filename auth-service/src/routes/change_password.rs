@@ -0,0 +1,106 @@
+use crate::app_state::AppState;
+use crate::domain::{AuditContext, AuditEvent, AuditEventKind, Email, Tenant, UserError};
+use crate::routes::PasswordRejection;
+use crate::services::{UserStore, UserStoreError};
+use crate::utils::{account_status_message, audit, check_password, parse_new_password, Authenticated};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use jsonwebtoken::get_current_timestamp;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, instrument};
+
+#[allow(unused_imports)]
+use tracing::Level;
+
+/// Not `Debug`, so that the passwords cannot end up in logs.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangePasswordResponse {
+    Message(String),
+    Error(String),
+}
+
+fn response(status: StatusCode, response: ChangePasswordResponse) -> Response {
+    (status, Json(response)).into_response()
+}
+
+fn unexpected_error() -> Response {
+    response(StatusCode::INTERNAL_SERVER_ERROR, ChangePasswordResponse::Error("Unexpected error".to_string()))
+}
+
+/// Replaces the password of the signed-in user, who must confirm the current one. Wrong
+/// current passwords count towards locking the account, as when signing in.
+#[instrument(level = Level::TRACE, skip(tenant, authenticated, context, request))]
+pub async fn change_password(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<Tenant>,
+    authenticated: Authenticated,
+    context: AuditContext,
+    Json(request): Json<ChangePasswordRequest>,
+) -> Response {
+    let user_not_found = || {
+        response(StatusCode::NOT_FOUND, ChangePasswordResponse::Error("User not found".to_string()))
+    };
+    let Ok(email) = Email::parse_canonical(&authenticated.claims.sub) else {
+        return user_not_found();
+    };
+    match check_password(&state, &tenant.id, &email, &request.current_password, &context).await {
+        Ok(()) => {}
+        Err(UserStoreError::UserNotFound(_)) => return user_not_found(),
+        Err(UserStoreError::InvalidCredentials(_)) => {
+            let message = ChangePasswordResponse::Error("Incorrect password".to_string());
+            return response(StatusCode::FORBIDDEN, message);
+        }
+        Err(
+            error @ (UserStoreError::AccountDisabled(_)
+            | UserStoreError::AccountLocked(_)
+            | UserStoreError::PasswordResetRequired(_)),
+        ) => {
+            let message = ChangePasswordResponse::Error(account_status_message(&error).to_string());
+            return response(StatusCode::FORBIDDEN, message);
+        }
+        Err(error) => {
+            error!("Unexpected error when validating user: {}", error);
+            return unexpected_error();
+        }
+    }
+
+    let mut user_store = state.user_store.write().await;
+    let password = match user_store.get_user(&tenant.id, &email).await {
+        Ok(user) => parse_new_password(&state, &tenant, user, &request.new_password),
+        Err(error) => {
+            error!("Unexpected error when fetching user: {}", error);
+            return unexpected_error();
+        }
+    };
+    let password = match password {
+        Ok(password) => password,
+        Err(UserError::InvalidPassword(error)) => {
+            return (StatusCode::BAD_REQUEST, Json(PasswordRejection::from(&error))).into_response();
+        }
+        Err(error) => {
+            error!("Unexpected error when checking new password: {}", error);
+            return unexpected_error();
+        }
+    };
+    let history_depth = tenant.settings.password_policy.history_depth;
+    let now = get_current_timestamp();
+    if let Err(error) = user_store.set_password(&tenant.id, &email, password, history_depth, now).await {
+        error!("Unexpected error when setting password: {}", error);
+        return unexpected_error();
+    }
+    drop(user_store);
+
+    info!("Changed password of {}", email);
+    audit(&state, AuditEvent::new(&tenant.id, email.as_str(), AuditEventKind::PasswordChanged, &context, now)).await;
+    response(StatusCode::OK, ChangePasswordResponse::Message("Password changed successfully!".to_string()))
+}
@@ -0,0 +1,83 @@
+use crate::app_state::AppState;
+use crate::routes::token::{authenticate_client, client_credentials, required, TokenError, NO_STORE};
+use crate::utils::{verify_access_token, TokenVerificationError};
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{Form, Json};
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+#[allow(unused_imports)]
+use tracing::Level;
+
+/// Introspection request (RFC 7662, section 2.1).
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct IntrospectionRequest {
+    pub token: Option<String>,
+    pub token_type_hint: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+/// Introspection response (RFC 7662, section 2.2). Inactive tokens only report `active`.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct IntrospectionResponse {
+    pub active: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exp: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+}
+
+/// Only confidential clients, such as resource servers, may introspect tokens.
+#[instrument(level = Level::TRACE, skip(headers, request))]
+pub async fn introspect(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(request): Form<IntrospectionRequest>,
+) -> Response {
+    match introspect_token(&state, &headers, &request).await {
+        Ok(response) => (StatusCode::OK, NO_STORE, Json(response)).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+async fn introspect_token(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: &IntrospectionRequest,
+) -> Result<IntrospectionResponse, TokenError> {
+    let (client_id, client_secret) = client_credentials(headers, &request.client_id, &request.client_secret)
+        .ok_or_else(|| TokenError::InvalidClient("Missing client credentials".to_string()))?;
+    let client = authenticate_client(state, &client_id, client_secret.as_deref()).await?;
+    if !client.is_confidential() {
+        return Err(TokenError::InvalidClient("Client authentication failed".to_string()));
+    }
+    let token = required(&request.token, "token")?;
+
+    match verify_access_token(state, token).await {
+        Ok(claims) => Ok(IntrospectionResponse {
+            active: true,
+            scope: claims.scope,
+            client_id: claims.client_id,
+            token_type: Some("Bearer".to_string()),
+            exp: Some(claims.exp),
+            iat: Some(claims.iat),
+            sub: Some(claims.sub),
+            iss: Some(state.issuer.clone()),
+        }),
+        Err(TokenVerificationError::UnexpectedError(error)) => Err(TokenError::UnexpectedError(error)),
+        Err(_) => Ok(IntrospectionResponse::default()),
+    }
+}
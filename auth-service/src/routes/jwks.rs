@@ -0,0 +1,14 @@
+use crate::app_state::AppState;
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::Json;
+use tracing::instrument;
+
+#[allow(unused_imports)]
+use tracing::Level;
+
+#[instrument(level = Level::TRACE)]
+pub async fn jwks(State(state): State<AppState>) -> impl IntoResponse {
+    let keyring = state.keyring.read().await;
+    Json(keyring.jwks())
+}
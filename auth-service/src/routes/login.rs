@@ -1,11 +1,106 @@
-use axum::http::StatusCode;
-use axum::response::IntoResponse;
-use tracing::instrument;
+use crate::app_state::AppState;
+use crate::domain::{AuditContext, AuditEvent, AuditEventKind, Tenant};
+use crate::services::UserStoreError;
+use crate::routes::two_factor_required;
+use crate::utils::{
+    account_status_message, assess_sign_in, audit, challenge_2fa, check_password, complete_sign_in, jwt_cookie,
+    login_failure_reason, open_session, requires_2fa,
+};
+use axum::extract::{ConnectInfo, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use axum_extra::extract::CookieJar;
+use jsonwebtoken::get_current_timestamp;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use tracing::{error, instrument};
 
 #[allow(unused_imports)]
 use tracing::Level;
 
-#[instrument(level = Level::TRACE)]
-pub async fn login() -> impl IntoResponse {
-    StatusCode::OK.into_response() // TODO: dummy response for task 4
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct LoginRequest {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LoginResponse {
+    Error(String),
+}
+
+fn response(status: StatusCode, response: LoginResponse) -> Response {
+    (status, Json(response)).into_response()
+}
+
+#[instrument(level = Level::TRACE, skip(headers, context, jar, request))]
+pub async fn login(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<Tenant>,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    context: AuditContext,
+    jar: CookieJar,
+    Json(request): Json<LoginRequest>,
+) -> Response {
+    let email = match tenant.parse_email(&request.email) {
+        Ok(email) if !request.password.is_empty() => email,
+        _ => return response(StatusCode::BAD_REQUEST, LoginResponse::Error("Invalid input".to_string())),
+    };
+
+    let event = |kind| AuditEvent::new(&tenant.id, email.as_str(), kind, &context, get_current_timestamp());
+    let result = check_password(&state, &tenant.id, &email, &request.password, &context).await;
+    if let Some(reason) = result.as_ref().err().and_then(login_failure_reason) {
+        audit(&state, event(AuditEventKind::LoginFailed { reason })).await;
+    }
+    match result {
+        Ok(()) => {}
+        Err(UserStoreError::UserNotFound(_)) | Err(UserStoreError::InvalidCredentials(_)) => {
+            let message = LoginResponse::Error("Incorrect credentials".to_string());
+            return response(StatusCode::UNAUTHORIZED, message);
+        }
+        Err(
+            error @ (UserStoreError::AccountDisabled(_)
+            | UserStoreError::AccountLocked(_)
+            | UserStoreError::PasswordResetRequired(_)),
+        ) => {
+            let message = LoginResponse::Error(account_status_message(&error).to_string());
+            return response(StatusCode::FORBIDDEN, message);
+        }
+        Err(error) => {
+            error!("Unexpected error when validating user: {}", error);
+            return response(StatusCode::INTERNAL_SERVER_ERROR, LoginResponse::Error("Unexpected error".to_string()));
+        }
+    }
+    let assessment = match assess_sign_in(&state, &tenant.id, &email, &context).await {
+        Ok(assessment) => assessment,
+        Err(error) => {
+            error!("Unexpected error when assessing sign-in: {}", error);
+            return response(StatusCode::INTERNAL_SERVER_ERROR, LoginResponse::Error("Unexpected error".to_string()));
+        }
+    };
+    if requires_2fa(&state, &tenant, &email, &assessment).await {
+        return match challenge_2fa(&state, &tenant.id, &email, "password", assessment, None, &context).await {
+            Ok(login_attempt_id) => two_factor_required(login_attempt_id),
+            Err(error) => {
+                error!("Unexpected error when challenging 2FA: {}", error);
+                response(StatusCode::INTERNAL_SERVER_ERROR, LoginResponse::Error("Unexpected error".to_string()))
+            }
+        };
+    }
+
+    match open_session(&state, &tenant.id, &email, &headers, address.ip()).await {
+        Ok(token) => {
+            let method = "password".to_string();
+            audit(&state, event(AuditEventKind::LoginSucceeded { method })).await;
+            complete_sign_in(&state, &tenant.id, &email, assessment).await;
+            (StatusCode::OK, jar.add(jwt_cookie(token))).into_response()
+        }
+        Err(error) => {
+            error!("Unexpected error when opening session: {}", error);
+            response(StatusCode::INTERNAL_SERVER_ERROR, LoginResponse::Error("Unexpected error".to_string()))
+        }
+    }
 }
@@ -1,11 +1,56 @@
+use crate::app_state::AppState;
+use crate::domain::{AuditContext, AuditEvent, AuditEventKind};
+use crate::services::{BannedTokenStore, SessionStore};
+use crate::utils::{audit, expired_jwt_cookie, verify_access_token, TokenVerificationError, JWT_COOKIE_NAME};
+use axum::extract::State;
 use axum::http::StatusCode;
-use axum::response::IntoResponse;
-use tracing::instrument;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use axum_extra::extract::CookieJar;
+use jsonwebtoken::get_current_timestamp;
+use serde::{Deserialize, Serialize};
+use tracing::{error, instrument};
 
 #[allow(unused_imports)]
 use tracing::Level;
 
-#[instrument(level = Level::TRACE)]
-pub async fn logout() -> impl IntoResponse {
-    StatusCode::OK.into_response() // TODO: dummy response for task 4
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LogoutResponse {
+    Error(String),
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(LogoutResponse::Error(message.to_string()))).into_response()
+}
+
+/// Ends the session of the `jwt` cookie, and bans its token.
+#[instrument(level = Level::TRACE, skip(context, jar))]
+pub async fn logout(State(state): State<AppState>, context: AuditContext, jar: CookieJar) -> Response {
+    let Some(token) = jar.get(JWT_COOKIE_NAME).map(|cookie| cookie.value().to_string()) else {
+        return error_response(StatusCode::BAD_REQUEST, "Missing token");
+    };
+    let claims = match verify_access_token(&state, &token).await {
+        Ok(claims) => claims,
+        Err(TokenVerificationError::UnexpectedError(error)) => {
+            error!("Unexpected error when verifying token: {}", error);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error");
+        }
+        Err(_) => return error_response(StatusCode::UNAUTHORIZED, "Invalid token"),
+    };
+
+    if let Some(session_id) = &claims.sid {
+        // The session may have been revoked concurrently, which is just as good.
+        let _ = state.session_store.write().await.remove_session(session_id).await;
+    }
+    let mut banned_token_store = state.banned_token_store.write().await;
+    if let Err(error) = banned_token_store.add_token(&token, claims.exp).await {
+        error!("Unexpected error when banning token: {}", error);
+        return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error");
+    }
+    drop(banned_token_store);
+    let event =
+        AuditEvent::new(claims.tenant_id(), &claims.sub, AuditEventKind::Logout, &context, get_current_timestamp());
+    audit(&state, event).await;
+    (StatusCode::OK, jar.add(expired_jwt_cookie())).into_response()
 }
@@ -0,0 +1,56 @@
+use crate::app_state::AppState;
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+#[allow(unused_imports)]
+use tracing::Level;
+
+/// OpenID Provider Metadata (OpenID Connect Discovery 1.0, section 3), with the
+/// introspection and revocation endpoints from RFC 8414.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct OpenIdConfiguration {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub userinfo_endpoint: String,
+    pub jwks_uri: String,
+    pub introspection_endpoint: String,
+    pub revocation_endpoint: String,
+    pub response_types_supported: Vec<String>,
+    pub subject_types_supported: Vec<String>,
+    pub id_token_signing_alg_values_supported: Vec<String>,
+    pub scopes_supported: Vec<String>,
+    pub claims_supported: Vec<String>,
+    pub grant_types_supported: Vec<String>,
+    pub token_endpoint_auth_methods_supported: Vec<String>,
+    pub code_challenge_methods_supported: Vec<String>,
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+#[instrument(level = Level::TRACE)]
+pub async fn openid_configuration(State(state): State<AppState>) -> impl IntoResponse {
+    let issuer = &state.issuer;
+    Json(OpenIdConfiguration {
+        issuer: issuer.clone(),
+        authorization_endpoint: format!("{}/authorize", issuer),
+        token_endpoint: format!("{}/token", issuer),
+        userinfo_endpoint: format!("{}/userinfo", issuer),
+        jwks_uri: format!("{}/.well-known/jwks.json", issuer),
+        introspection_endpoint: format!("{}/oauth/introspect", issuer),
+        revocation_endpoint: format!("{}/oauth/revoke", issuer),
+        response_types_supported: strings(&["code"]),
+        subject_types_supported: strings(&["public"]),
+        id_token_signing_alg_values_supported: strings(&["EdDSA"]),
+        scopes_supported: strings(&["openid", "email"]),
+        claims_supported: strings(&["iss", "sub", "aud", "iat", "exp", "nonce", "email", "email_verified"]),
+        grant_types_supported: strings(&["authorization_code", "client_credentials"]),
+        token_endpoint_auth_methods_supported: strings(&["none", "client_secret_basic", "client_secret_post"]),
+        code_challenge_methods_supported: strings(&["S256"]),
+    })
+}
@@ -0,0 +1,178 @@
+use crate::app_state::AppState;
+use crate::domain::{AuditContext, AuditEvent, AuditEventKind, PasswordReset, Tenant, UserError};
+use crate::routes::PasswordRejection;
+use crate::services::{
+    EmailMessage, PasswordResetStore, PasswordResetStoreError, SessionStore, UserStore, UserStoreError,
+};
+use crate::utils::{audit, parse_new_password};
+use axum::extract::{ConnectInfo, State};
+use axum::http::header::RETRY_AFTER;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use jsonwebtoken::get_current_timestamp;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use tracing::{error, info, instrument};
+
+#[allow(unused_imports)]
+use tracing::Level;
+
+pub const PASSWORD_RESET_SUBJECT: &str = "Reset your password";
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+/// Not `Debug`, so that the token and the password cannot end up in logs.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PasswordResetResponse {
+    Message(String),
+    Error(String),
+}
+
+fn response(status: StatusCode, response: PasswordResetResponse) -> Response {
+    (status, Json(response)).into_response()
+}
+
+fn unexpected_error() -> Response {
+    response(StatusCode::INTERNAL_SERVER_ERROR, PasswordResetResponse::Error("Unexpected error".to_string()))
+}
+
+fn too_many_requests(retry_after: u64) -> Response {
+    let response = Json(PasswordResetResponse::Error("Too many requests".to_string()));
+    (StatusCode::TOO_MANY_REQUESTS, [(RETRY_AFTER, retry_after.to_string())], response).into_response()
+}
+
+fn password_reset_email(password_reset: &PasswordReset) -> EmailMessage {
+    EmailMessage {
+        recipient: password_reset.email.to_string(),
+        subject: PASSWORD_RESET_SUBJECT.to_string(),
+        content: format!(
+            "Use this token to choose a new password within the hour: {}\n\n\
+             If you did not ask to reset your password, you can ignore this email.",
+            password_reset.token
+        ),
+    }
+}
+
+/// Emails a password reset token to the user. The response is the same whether the user
+/// exists or not, so that it does not tell which email addresses have accounts. Requests are
+/// limited by client address and by email address, whether the account exists or not.
+#[instrument(level = Level::TRACE, skip(tenant))]
+pub async fn forgot_password(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<Tenant>,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
+    Json(request): Json<ForgotPasswordRequest>,
+) -> Response {
+    let now = get_current_timestamp();
+    if let Err(retry_after) = state.forgot_password_rate_limiter.write().await.check(address.ip(), now) {
+        return too_many_requests(retry_after);
+    }
+    let accepted = response(
+        StatusCode::ACCEPTED,
+        PasswordResetResponse::Message("If the account exists, a password reset email was sent".to_string()),
+    );
+    let Ok(email) = tenant.parse_email(&request.email) else {
+        return accepted;
+    };
+    let account = format!("{}/{}", tenant.id, email);
+    if let Err(retry_after) = state.forgot_password_email_rate_limiter.write().await.check(account, now) {
+        return too_many_requests(retry_after);
+    }
+    match state.user_store.read().await.get_user(&tenant.id, &email).await {
+        Ok(user) if !user.disabled => {}
+        Ok(_) | Err(UserStoreError::UserNotFound(_)) => return accepted,
+        Err(error) => {
+            error!("Unexpected error when fetching user: {}", error);
+            return unexpected_error();
+        }
+    }
+
+    let password_reset = PasswordReset::new(&tenant.id, &email, now);
+    let message = password_reset_email(&password_reset);
+    if let Err(error) = state.password_reset_store.write().await.add_password_reset(password_reset).await {
+        error!("Unexpected error when adding password reset to store: {}", error);
+        return unexpected_error();
+    }
+    if let Err(error) = state.email_client.write().await.send_email(message).await {
+        error!("Unexpected error when sending password reset email: {}", error);
+        return unexpected_error();
+    }
+    accepted
+}
+
+/// Replaces the password of the user the token was emailed to. Resetting the password lifts
+/// a required reset, unlocks the account and signs the user out of every session.
+#[instrument(level = Level::TRACE, skip(tenant, context, request))]
+pub async fn reset_password(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<Tenant>,
+    context: AuditContext,
+    Json(request): Json<ResetPasswordRequest>,
+) -> Response {
+    // Held until the password is replaced, so that the token is used only once.
+    let mut password_reset_store = state.password_reset_store.write().await;
+    let now = get_current_timestamp();
+    let email = match password_reset_store.get_password_reset(&request.token).await {
+        Ok(password_reset) if password_reset.admits(&tenant.id, now) => password_reset.email.clone(),
+        Ok(_) | Err(PasswordResetStoreError::PasswordResetNotFound) => {
+            let message = PasswordResetResponse::Error("Invalid or expired token".to_string());
+            return response(StatusCode::BAD_REQUEST, message);
+        }
+        Err(error) => {
+            error!("Unexpected error when fetching password reset: {}", error);
+            return unexpected_error();
+        }
+    };
+
+    let mut user_store = state.user_store.write().await;
+    let password = match user_store.get_user(&tenant.id, &email).await {
+        Ok(user) => parse_new_password(&state, &tenant, user, &request.new_password),
+        Err(error) => {
+            error!("Unexpected error when fetching user: {}", error);
+            return unexpected_error();
+        }
+    };
+    let password = match password {
+        Ok(password) => password,
+        Err(UserError::InvalidPassword(error)) => {
+            return (StatusCode::BAD_REQUEST, Json(PasswordRejection::from(&error))).into_response();
+        }
+        Err(error) => {
+            error!("Unexpected error when checking new password: {}", error);
+            return unexpected_error();
+        }
+    };
+    let history_depth = tenant.settings.password_policy.history_depth;
+    let result = match user_store.set_password(&tenant.id, &email, password, history_depth, now).await {
+        Ok(()) => user_store.reset_failed_logins(&tenant.id, &email).await,
+        Err(error) => Err(error),
+    };
+    if let Err(error) = result {
+        error!("Unexpected error when setting password: {}", error);
+        return unexpected_error();
+    }
+    drop(user_store);
+    if let Err(error) = password_reset_store.take_password_reset(&request.token).await {
+        error!("Unexpected error when removing password reset: {}", error);
+    }
+    drop(password_reset_store);
+    if let Err(error) = state.session_store.write().await.remove_user_sessions(&tenant.id, email.as_str()).await {
+        error!("Unexpected error when revoking sessions: {}", error);
+    }
+
+    info!("Reset password of {}", email);
+    audit(&state, AuditEvent::new(&tenant.id, email.as_str(), AuditEventKind::PasswordChanged, &context, now)).await;
+    response(StatusCode::OK, PasswordResetResponse::Message("Password reset successfully!".to_string()))
+}
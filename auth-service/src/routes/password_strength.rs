@@ -0,0 +1,65 @@
+use crate::app_state::AppState;
+use crate::domain::{normalize_password, Password, PasswordError, PasswordStrength, Tenant};
+use crate::routes::PasswordRejection;
+use crate::utils::reject_breached_password;
+use axum::extract::{ConnectInfo, State};
+use axum::http::header::RETRY_AFTER;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use jsonwebtoken::get_current_timestamp;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::net::SocketAddr;
+use tracing::instrument;
+
+#[allow(unused_imports)]
+use tracing::Level;
+
+/// Not `Debug`, so that the password cannot end up in logs.
+#[derive(Serialize, Deserialize)]
+pub struct PasswordStrengthRequest {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordStrengthResponse {
+    #[serde(flatten)]
+    pub strength: PasswordStrength,
+    /// Whether signing up with the password would succeed, as far as the password goes.
+    pub acceptable: bool,
+}
+
+/// Estimates the strength of a password being typed, for the signup form to show.
+#[instrument(level = Level::TRACE, skip(tenant, request))]
+pub async fn password_strength(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<Tenant>,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
+    Json(request): Json<PasswordStrengthRequest>,
+) -> Response {
+    let limit = state.settings.load().password_strength_rate_limit;
+    let mut rate_limiter = state.password_strength_rate_limiter.write().await;
+    let limited = rate_limiter.check_limit(address.ip(), limit, get_current_timestamp());
+    drop(rate_limiter);
+    if let Err(retry_after) = limited {
+        let response = Json(json!({"error": "Too many requests"}));
+        return (StatusCode::TOO_MANY_REQUESTS, [(RETRY_AFTER, retry_after.to_string())], response).into_response();
+    }
+
+    let policy = &tenant.settings.password_policy;
+    if normalize_password(&request.password).chars().count() > policy.max_length {
+        let rejection = PasswordRejection::from(&PasswordError::TooLong(policy.max_length));
+        return (StatusCode::BAD_REQUEST, Json(rejection)).into_response();
+    }
+    let acceptable = Password::parse_with_policy(&request.password, &request.email, policy)
+        .and_then(|password| reject_breached_password(&state, &password))
+        .is_ok();
+    let response = PasswordStrengthResponse {
+        strength: Password::strength(&request.password, &request.email),
+        acceptable,
+    };
+    (StatusCode::OK, Json(response)).into_response()
+}
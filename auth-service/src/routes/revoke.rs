@@ -0,0 +1,60 @@
+use crate::app_state::AppState;
+use crate::routes::token::{authenticate_client, client_credentials, required, TokenError};
+use crate::services::BannedTokenStore;
+use crate::utils::{verify_access_token, TokenVerificationError};
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Form;
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument};
+
+#[allow(unused_imports)]
+use tracing::Level;
+
+/// Revocation request (RFC 7009, section 2.1).
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RevocationRequest {
+    pub token: Option<String>,
+    pub token_type_hint: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+/// Clients may only revoke the tokens issued to them. Tokens that are already invalid
+/// are reported as revoked, as there is nothing left to do (RFC 7009, section 2.2).
+#[instrument(level = Level::TRACE, skip(headers, request))]
+pub async fn revoke(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(request): Form<RevocationRequest>,
+) -> Response {
+    match revoke_token(&state, &headers, &request).await {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+async fn revoke_token(state: &AppState, headers: &HeaderMap, request: &RevocationRequest) -> Result<(), TokenError> {
+    let (client_id, client_secret) = client_credentials(headers, &request.client_id, &request.client_secret)
+        .ok_or_else(|| TokenError::InvalidClient("Missing client credentials".to_string()))?;
+    let client = authenticate_client(state, &client_id, client_secret.as_deref()).await?;
+    let token = required(&request.token, "token")?;
+
+    let claims = match verify_access_token(state, token).await {
+        Ok(claims) => claims,
+        Err(TokenVerificationError::UnexpectedError(error)) => return Err(TokenError::UnexpectedError(error)),
+        Err(_) => return Ok(()),
+    };
+    if claims.client_id.as_deref() != Some(client.client_id.as_str()) {
+        return Err(TokenError::UnauthorizedClient("Token was not issued to this client".to_string()));
+    }
+
+    let mut banned_token_store = state.banned_token_store.write().await;
+    banned_token_store
+        .add_token(token, claims.exp)
+        .await
+        .map_err(|error| TokenError::UnexpectedError(error.to_string()))?;
+    info!("Revoked token issued to client {}", client.client_id);
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use crate::app_state::AppState;
+use crate::domain::Session;
+use crate::services::{SessionStore, SessionStoreError};
+use crate::utils::{expired_jwt_cookie, Authenticated};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use axum_extra::extract::CookieJar;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, instrument};
+
+#[allow(unused_imports)]
+use tracing::Level;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionResponse {
+    pub id: String,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: u64,
+    pub last_seen_at: u64,
+    /// Whether this is the session making the request.
+    pub current: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionsResponse {
+    Sessions(Vec<SessionResponse>),
+    Error(String),
+}
+
+impl SessionResponse {
+    fn new(session: Session, current_session_id: Option<&str>) -> Self {
+        Self {
+            current: current_session_id == Some(session.id.as_str()),
+            id: session.id,
+            user_agent: session.user_agent,
+            ip_address: session.ip_address.map(|ip_address| ip_address.to_string()),
+            created_at: session.created_at,
+            last_seen_at: session.last_seen_at,
+        }
+    }
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(SessionsResponse::Error(message.to_string()))).into_response()
+}
+
+fn unexpected_error(error: SessionStoreError) -> Response {
+    error!("Unexpected error when accessing session store: {}", error);
+    error_response(StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error")
+}
+
+/// Lists the devices the user is signed in on.
+#[instrument(level = Level::TRACE, skip(authenticated))]
+pub async fn list_sessions(State(state): State<AppState>, authenticated: Authenticated) -> Response {
+    let session_store = state.session_store.read().await;
+    match session_store.get_user_sessions(authenticated.claims.tenant_id(), &authenticated.claims.sub).await {
+        Ok(sessions) => {
+            let current_session_id = authenticated.claims.sid.as_deref();
+            let sessions = sessions
+                .into_iter()
+                .map(|session| SessionResponse::new(session, current_session_id))
+                .collect();
+            Json(SessionsResponse::Sessions(sessions)).into_response()
+        }
+        Err(error) => unexpected_error(error),
+    }
+}
+
+/// Signs the user out of one of their devices.
+#[instrument(level = Level::TRACE, skip(authenticated))]
+pub async fn revoke_session(
+    State(state): State<AppState>,
+    authenticated: Authenticated,
+    Path(session_id): Path<String>,
+) -> Response {
+    let mut session_store = state.session_store.write().await;
+    // Sessions of other users are reported as missing, so that their ids cannot be probed.
+    match session_store.get_session(&session_id).await {
+        Ok(session)
+            if session.tenant_id == authenticated.claims.tenant_id() && session.email == authenticated.claims.sub => {}
+        Ok(_) | Err(SessionStoreError::SessionNotFound(_)) => {
+            return error_response(StatusCode::NOT_FOUND, "Session not found");
+        }
+        Err(error) => return unexpected_error(error),
+    }
+    match session_store.remove_session(&session_id).await {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(SessionStoreError::SessionNotFound(_)) => error_response(StatusCode::NOT_FOUND, "Session not found"),
+        Err(error) => unexpected_error(error),
+    }
+}
+
+/// Signs the user out of every device, including the one making the request.
+#[instrument(level = Level::TRACE, skip(authenticated, jar))]
+pub async fn revoke_all_sessions(
+    State(state): State<AppState>,
+    authenticated: Authenticated,
+    jar: CookieJar,
+) -> Response {
+    let mut session_store = state.session_store.write().await;
+    match session_store.remove_user_sessions(authenticated.claims.tenant_id(), &authenticated.claims.sub).await {
+        Ok(count) => {
+            info!("Revoked {} sessions of {}", count, authenticated.claims.sub);
+            (StatusCode::NO_CONTENT, jar.add(expired_jwt_cookie())).into_response()
+        }
+        Err(error) => unexpected_error(error),
+    }
+}
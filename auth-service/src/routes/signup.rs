@@ -1,10 +1,14 @@
 use crate::app_state::AppState;
-use crate::domain::{User, UserError};
-use crate::services::{UserStore, UserStoreError};
+use crate::domain::{
+    AuditContext, AuditEvent, AuditEventKind, Invitation, PasswordError, SignupMode, Tenant, User, UserError,
+};
+use crate::services::{HashmapInvitationStore, InvitationStore, InvitationStoreError, UserStore, UserStoreError};
+use crate::utils::{audit, reject_breached_password};
 use axum::extract::State;
 use axum::http::StatusCode;
-use axum::response::IntoResponse;
-use axum::Json;
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use jsonwebtoken::get_current_timestamp;
 use serde::{Deserialize, Serialize};
 use tracing::{error, instrument};
 
@@ -17,6 +21,9 @@ pub struct SignupRequest {
     pub password: String,
     #[serde(rename = "requires2FA")]
     pub requires_2fa: bool,
+    /// Token of the invitation for the email address, required when signup is invite-only.
+    #[serde(rename = "inviteToken", default, skip_serializing_if = "Option::is_none")]
+    pub invite_token: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -26,28 +33,123 @@ pub enum SignupResponse {
     Error(String),
 }
 
-#[instrument(level = Level::TRACE)]
-pub async fn signup(State(state): State<AppState>, Json(request): Json<SignupRequest>) -> impl IntoResponse {
-    match User::try_new(
+/// Body of the response rejecting a password. Besides the `error` message of other errors,
+/// it tells the user how to choose a better password.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct PasswordRejection {
+    pub error: String,
+    /// Why the password was rejected, e.g. `too_short` or `weak`.
+    pub code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+}
+
+impl From<&PasswordError> for PasswordRejection {
+    fn from(error: &PasswordError) -> Self {
+        let feedback = error.feedback().cloned().unwrap_or_default();
+        Self {
+            error: format!("Invalid password: {}", error),
+            code: error.code().to_string(),
+            warning: feedback.warning,
+            suggestions: feedback.suggestions,
+        }
+    }
+}
+
+fn forbidden(message: &str) -> (StatusCode, Json<SignupResponse>) {
+    (StatusCode::FORBIDDEN, Json(SignupResponse::Error(message.to_string())))
+}
+
+/// Checks that the signup mode lets the request create a user, returning the invitation
+/// it signs up with. Invitations are honored in every mode but closed.
+async fn admitting_invitation(
+    signup_mode: SignupMode,
+    invitation_store: &HashmapInvitationStore,
+    tenant: &Tenant,
+    request: &SignupRequest,
+) -> Result<Option<Invitation>, (StatusCode, Json<SignupResponse>)> {
+    let token = match (signup_mode, &request.invite_token) {
+        (SignupMode::Closed, _) => return Err(forbidden("Signup is closed")),
+        (SignupMode::InviteOnly, None) => return Err(forbidden("Invitation required")),
+        (SignupMode::Open, None) => return Ok(None),
+        (_, Some(token)) => token,
+    };
+    let admits = |invitation: &Invitation| {
+        let email = tenant.parse_email(&request.email);
+        email.is_ok_and(|email| invitation.admits(&tenant.id, &email, get_current_timestamp()))
+    };
+    match invitation_store.get_invitation(token).await {
+        Ok(invitation) if admits(invitation) => Ok(Some(invitation.clone())),
+        Ok(_) | Err(InvitationStoreError::InvitationNotFound) => Err(forbidden("Invalid invitation")),
+        Err(InvitationStoreError::UnexpectedError(error)) => {
+            error!("Unexpected error when fetching invitation: {}", error);
+            let response = Json(SignupResponse::Error("Unexpected error".to_string()));
+            Err((StatusCode::INTERNAL_SERVER_ERROR, response))
+        }
+    }
+}
+
+#[instrument(level = Level::TRACE, skip(context))]
+pub async fn signup(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<Tenant>,
+    context: AuditContext,
+    Json(request): Json<SignupRequest>,
+) -> Response {
+    // Checked before the slow password checks, and again once the user is valid.
+    let invitation_store = state.invitation_store.read().await;
+    if let Err(response) = admitting_invitation(state.signup_mode, &invitation_store, &tenant, &request).await {
+        return response.into_response();
+    }
+    drop(invitation_store);
+    let email_domain_policy = state.settings.load().email_domain_policy.clone();
+    let user = User::try_new_for_tenant(
+        &tenant,
         request.email.as_str(),
         request.password.as_str(),
         request.requires_2fa)
-    {
-        Ok(user) => {
+    .and_then(|user| {
+        email_domain_policy.check(&user.email).map_err(UserError::RejectedEmailDomain)?;
+        reject_breached_password(&state, &user.password).map_err(UserError::InvalidPassword)?;
+        Ok(user)
+    });
+    match user {
+        Ok(mut user) => {
+            // Held until the user is created, so that the invitation is used only once.
+            let mut invitation_store = state.invitation_store.write().await;
+            let invitation = match admitting_invitation(state.signup_mode, &invitation_store, &tenant, &request).await
+            {
+                Ok(invitation) => invitation,
+                Err(response) => return response.into_response(),
+            };
+            if let Some(role) = invitation.as_ref().and_then(|invitation| invitation.role) {
+                user.set_roles(&[role]);
+            }
+            let email = user.email.clone();
             let store = &mut state.user_store.write().await;
             match store.add_user(user).await {
                 Ok(()) => {
+                    if let Some(invitation) = invitation
+                        && let Err(error) = invitation_store.take_invitation(&invitation.token).await
+                    {
+                        error!("Unexpected error when removing invitation: {}", error);
+                    }
+                    let now = get_current_timestamp();
+                    let event = AuditEvent::new(&tenant.id, email.as_str(), AuditEventKind::Signup, &context, now);
+                    audit(&state, event).await;
                     let response = Json(SignupResponse::Message("User created successfully!".to_string()));
-                    (StatusCode::CREATED, response)
+                    (StatusCode::CREATED, response).into_response()
                 }
                 Err(UserStoreError::UserAlreadyExists(_)) => {
                     let response = Json(SignupResponse::Error("User already exists".to_string()));
-                    (StatusCode::CONFLICT, response)
+                    (StatusCode::CONFLICT, response).into_response()
                 }
                 Err(UserStoreError::UnexpectedError(error)) => {
                     error!("Unexpected error when adding user to store: {}", error);
                     let response = Json(SignupResponse::Error("Unexpected error".to_string()));
-                    (StatusCode::INTERNAL_SERVER_ERROR, response)
+                    (StatusCode::INTERNAL_SERVER_ERROR, response).into_response()
                 }
                 _ => {
                     unreachable!() // There are other errors at UserStoreError that should not happen here
@@ -56,11 +158,14 @@ pub async fn signup(State(state): State<AppState>, Json(request): Json<SignupReq
         }
         Err(UserError::InvalidEmail(error)) => {
             let response = Json(SignupResponse::Error(format!("Invalid email: {}", error)));
-            (StatusCode::BAD_REQUEST, response)
+            (StatusCode::BAD_REQUEST, response).into_response()
         }
         Err(UserError::InvalidPassword(error)) => {
-            let response = Json(SignupResponse::Error(format!("Invalid password: {}", error)));
-            (StatusCode::BAD_REQUEST, response)
+            (StatusCode::BAD_REQUEST, Json(PasswordRejection::from(&error))).into_response()
+        }
+        Err(UserError::RejectedEmailDomain(error)) => {
+            let response = Json(SignupResponse::Error(error.to_string()));
+            (StatusCode::BAD_REQUEST, response).into_response()
         }
     }
 }
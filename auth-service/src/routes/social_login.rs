@@ -0,0 +1,258 @@
+use crate::app_state::AppState;
+use crate::domain::{
+    AuditContext, AuditEvent, AuditEventKind, Email, IdentityProvider, SignupMode, Tenant, UpstreamAuthorization, User,
+};
+use crate::services::{ExternalProfile, UpstreamAuthorizationStore, UserStore, UserStoreError};
+use crate::routes::two_factor_required;
+use crate::utils::{
+    account_status_message, assess_sign_in, audit, challenge_2fa, complete_sign_in, jwt_cookie, login_failure_reason,
+    open_session, requires_2fa,
+};
+use axum::extract::{ConnectInfo, Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::{Extension, Json};
+use axum_extra::extract::CookieJar;
+use jsonwebtoken::get_current_timestamp;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use tracing::{error, info, instrument, warn};
+
+#[allow(unused_imports)]
+use tracing::Level;
+
+/// Authorization response from the upstream provider (RFC 6749, section 4.1.2).
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SocialLoginCallback {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SocialLoginResponse {
+    Error(String),
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    (status, Json(SocialLoginResponse::Error(message))).into_response()
+}
+
+fn redirect_uri(state: &AppState, provider: &IdentityProvider) -> String {
+    format!("{}/login/{}/callback", state.issuer, provider.name)
+}
+
+/// Starts a login at the upstream provider.
+#[instrument(level = Level::TRACE)]
+pub async fn social_login(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<Tenant>,
+    Path(provider): Path<String>,
+) -> Response {
+    let Some(provider) = state.identity_providers.get(&provider) else {
+        return error_response(StatusCode::NOT_FOUND, format!("Unknown provider: {}", provider));
+    };
+    let authorization = UpstreamAuthorization::new(&tenant.id, &provider.name, get_current_timestamp());
+    let url = provider.authorization_url(
+        &redirect_uri(&state, provider),
+        &authorization.state,
+        &authorization.nonce,
+        &authorization.code_challenge(),
+    );
+
+    let mut upstream_authorization_store = state.upstream_authorization_store.write().await;
+    if let Err(error) = upstream_authorization_store.add_authorization(authorization).await {
+        error!("Unexpected error when storing upstream authorization: {}", error);
+        return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error".to_string());
+    }
+    Redirect::to(url.as_str()).into_response()
+}
+
+/// Completes the login at the upstream provider, then signs the user in with the
+/// account the external identity is linked to.
+#[instrument(level = Level::TRACE, skip(callback, headers, context, jar))]
+pub async fn social_login_callback(
+    State(state): State<AppState>,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
+    Path(provider): Path<String>,
+    Query(callback): Query<SocialLoginCallback>,
+    headers: HeaderMap,
+    context: AuditContext,
+    jar: CookieJar,
+) -> Response {
+    let Some(provider) = state.identity_providers.get(&provider) else {
+        return error_response(StatusCode::NOT_FOUND, format!("Unknown provider: {}", provider));
+    };
+    let authorization = {
+        let mut upstream_authorization_store = state.upstream_authorization_store.write().await;
+        match callback.state.as_deref() {
+            Some(request_state) => upstream_authorization_store.take_authorization(request_state).await.ok(),
+            None => None,
+        }
+    };
+    let now = get_current_timestamp();
+    let Some(authorization) = authorization.filter(|authorization| {
+        authorization.provider == provider.name && !authorization.is_expired(now)
+    }) else {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid state".to_string());
+    };
+    if let Some(error) = callback.error {
+        return error_response(StatusCode::BAD_REQUEST, format!("Sign in with {} failed: {}", provider.name, error));
+    }
+    let Some(code) = callback.code else {
+        return error_response(StatusCode::BAD_REQUEST, "Missing code".to_string());
+    };
+
+    let profile = match state
+        .identity_provider_connector
+        .authenticate(
+            provider,
+            &code,
+            &authorization.code_verifier,
+            &authorization.nonce,
+            &redirect_uri(&state, provider),
+        )
+        .await
+    {
+        Ok(profile) => profile,
+        Err(error) => {
+            warn!("Sign in with {} failed: {}", provider.name, error);
+            return error_response(StatusCode::BAD_GATEWAY, format!("Sign in with {} failed", provider.name));
+        }
+    };
+
+    let Some(tenant) = state.tenant(&authorization.tenant_id) else {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid state".to_string());
+    };
+    let tenant_id = &tenant.id;
+    let email = match linked_user(&state, &tenant, &profile).await {
+        Ok(email) => email,
+        Err(LinkError::AccountStatus(email, error)) => {
+            if let Some(reason) = login_failure_reason(&error) {
+                let kind = AuditEventKind::LoginFailed { reason };
+                audit(&state, AuditEvent::new(tenant_id, email.as_str(), kind, &context, now)).await;
+            }
+            return error_response(StatusCode::FORBIDDEN, account_status_message(&error).to_string());
+        }
+        Err(LinkError::Response(response)) => return *response,
+    };
+    let assessment = match assess_sign_in(&state, tenant_id, &email, &context).await {
+        Ok(assessment) => assessment,
+        Err(error) => {
+            error!("Unexpected error when assessing sign-in: {}", error);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error".to_string());
+        }
+    };
+    // The provider stands in for the password only.
+    if requires_2fa(&state, &tenant, &email, &assessment).await {
+        return match challenge_2fa(&state, tenant_id, &email, &provider.name, assessment, None, &context).await {
+            Ok(login_attempt_id) => two_factor_required(login_attempt_id),
+            Err(error) => {
+                error!("Unexpected error when challenging 2FA: {}", error);
+                error_response(StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error".to_string())
+            }
+        };
+    }
+    match open_session(&state, tenant_id, &email, &headers, address.ip()).await {
+        Ok(token) => {
+            let method = provider.name.clone();
+            let kind = AuditEventKind::LoginSucceeded { method };
+            let event = AuditEvent::new(tenant_id, email.as_str(), kind, &context, now);
+            audit(&state, event).await;
+            complete_sign_in(&state, tenant_id, &email, assessment).await;
+            (jar.add(jwt_cookie(token)), Redirect::to("/")).into_response()
+        }
+        Err(error) => {
+            error!("Unexpected error when opening session: {}", error);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error".to_string())
+        }
+    }
+}
+
+/// Why signing in with an identity failed.
+enum LinkError {
+    /// The user the identity is linked to may not sign in.
+    AccountStatus(Email, UserStoreError),
+    Response(Box<Response>),
+}
+
+impl From<Response> for LinkError {
+    fn from(response: Response) -> Self {
+        LinkError::Response(Box::new(response))
+    }
+}
+
+/// Finds the user the identity is linked to. Otherwise, the identity is linked by its
+/// verified email address, to an existing user or to a new one.
+async fn linked_user(state: &AppState, tenant: &Tenant, profile: &ExternalProfile) -> Result<Email, LinkError> {
+    let tenant_id = &tenant.id;
+    let mut user_store = state.user_store.write().await;
+    match user_store.get_user_by_identity(tenant_id, &profile.identity).await {
+        Ok(user) => {
+            check_account(user)?;
+            return Ok(user.email.clone());
+        }
+        Err(UserStoreError::UserNotFound(_)) => {}
+        Err(error) => return Err(unexpected_error(error).into()),
+    }
+
+    let email = match (&profile.email, profile.email_verified) {
+        (Some(email), true) => email,
+        _ => {
+            let message = format!("Email address is not verified by {}", profile.identity.provider);
+            return Err(error_response(StatusCode::FORBIDDEN, message).into());
+        }
+    };
+    let email = match tenant.parse_email(email) {
+        Ok(email) => email,
+        Err(error) => return Err(error_response(StatusCode::BAD_REQUEST, format!("Invalid email: {}", error)).into()),
+    };
+    let result = match user_store.get_user(tenant_id, &email).await {
+        Ok(user) => {
+            check_account(user)?;
+            user_store.link_identity(tenant_id, &email, profile.identity.clone()).await
+        }
+        // Identities only create accounts when anyone may sign up.
+        Err(UserStoreError::UserNotFound(_)) if state.signup_mode != SignupMode::Open => {
+            return Err(error_response(StatusCode::FORBIDDEN, "Signup is not open".to_string()).into());
+        }
+        Err(UserStoreError::UserNotFound(_)) => {
+            if let Err(error) = state.settings.load().email_domain_policy.check(&email) {
+                return Err(error_response(StatusCode::BAD_REQUEST, error.to_string()).into());
+            }
+            let user = match User::try_new_external(tenant, email.as_str(), profile.identity.clone()) {
+                Ok(user) => user,
+                Err(error) => {
+                    return Err(error_response(StatusCode::BAD_REQUEST, error.to_string()).into());
+                }
+            };
+            user_store.add_user(user).await
+        }
+        Err(error) => Err(error),
+    };
+    match result {
+        Ok(()) => {
+            info!("Linked {} identity to {}", profile.identity.provider, email);
+            Ok(email)
+        }
+        Err(error) => Err(unexpected_error(error).into()),
+    }
+}
+
+/// Signing in with a provider skips the password, not the account status.
+fn check_account(user: &User) -> Result<(), LinkError> {
+    let error = if user.disabled {
+        UserStoreError::AccountDisabled(user.email.to_string())
+    } else if user.is_locked() {
+        UserStoreError::AccountLocked(user.email.to_string())
+    } else {
+        return Ok(());
+    };
+    Err(LinkError::AccountStatus(user.email.clone(), error))
+}
+
+fn unexpected_error(error: UserStoreError) -> Response {
+    error!("Unexpected error when linking identity: {}", error);
+    error_response(StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error".to_string())
+}
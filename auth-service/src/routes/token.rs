@@ -0,0 +1,279 @@
+use crate::app_state::AppState;
+use crate::domain::{Claims, Client, Email, IdTokenClaims, Session};
+use crate::services::{AuthorizationCodeStore, ClientStore, SessionStore, UserStore};
+use crate::utils::basic_credentials;
+use axum::extract::State;
+use axum::http::header::{CACHE_CONTROL, PRAGMA, WWW_AUTHENTICATE};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{Form, Json};
+use jsonwebtoken::get_current_timestamp;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{error, instrument};
+
+#[allow(unused_imports)]
+use tracing::Level;
+
+/// Responses carrying tokens or token metadata must not be cached (RFC 6749, section 5.1).
+pub(crate) const NO_STORE: [(HeaderName, &str); 2] = [(CACHE_CONTROL, "no-store"), (PRAGMA, "no-cache")];
+
+/// Access token request, for the authorization code grant (RFC 6749, section 4.1.3 and
+/// RFC 7636, section 4.5) and the client credentials grant (RFC 6749, section 4.4.2).
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenRequest {
+    pub grant_type: Option<String>,
+    pub code: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub code_verifier: Option<String>,
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+}
+
+/// Error response (RFC 6749, section 5.2).
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct TokenErrorResponse {
+    pub error: String,
+    pub error_description: String,
+}
+
+#[derive(Error, Debug)]
+pub enum TokenError {
+    #[error("{0}")]
+    InvalidRequest(String),
+    #[error("{0}")]
+    InvalidClient(String),
+    #[error("{0}")]
+    InvalidGrant(String),
+    #[error("Scope is not allowed: {0}")]
+    InvalidScope(String),
+    #[error("{0}")]
+    UnauthorizedClient(String),
+    #[error("Unsupported grant type: {0}")]
+    UnsupportedGrantType(String),
+    #[error("Unexpected error")]
+    UnexpectedError(String),
+}
+
+impl TokenError {
+    fn code(&self) -> &'static str {
+        match self {
+            TokenError::InvalidRequest(_) => "invalid_request",
+            TokenError::InvalidClient(_) => "invalid_client",
+            TokenError::InvalidGrant(_) => "invalid_grant",
+            TokenError::InvalidScope(_) => "invalid_scope",
+            TokenError::UnauthorizedClient(_) => "unauthorized_client",
+            TokenError::UnsupportedGrantType(_) => "unsupported_grant_type",
+            TokenError::UnexpectedError(_) => "server_error",
+        }
+    }
+}
+
+impl IntoResponse for TokenError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            TokenError::InvalidClient(_) => StatusCode::UNAUTHORIZED,
+            TokenError::UnexpectedError(error) => {
+                error!("Unexpected error when issuing token: {}", error);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            _ => StatusCode::BAD_REQUEST,
+        };
+        let response = Json(TokenErrorResponse {
+            error: self.code().to_string(),
+            error_description: self.to_string(),
+        });
+        let mut response = (status, NO_STORE, response).into_response();
+        if status == StatusCode::UNAUTHORIZED {
+            response
+                .headers_mut()
+                .insert(WWW_AUTHENTICATE, HeaderValue::from_static("Basic"));
+        }
+        response
+    }
+}
+
+pub(crate) fn required<'a>(value: &'a Option<String>, name: &str) -> Result<&'a str, TokenError> {
+    value
+        .as_deref()
+        .ok_or_else(|| TokenError::InvalidRequest(format!("Missing {}", name)))
+}
+
+/// Client credentials from the `Authorization: Basic` header (`client_secret_basic`),
+/// falling back to the request body (`client_secret_post`).
+pub(crate) fn client_credentials(
+    headers: &HeaderMap,
+    client_id: &Option<String>,
+    client_secret: &Option<String>,
+) -> Option<(String, Option<String>)> {
+    match basic_credentials(headers) {
+        Some((client_id, client_secret)) => Some((client_id, Some(client_secret))),
+        None => client_id.clone().map(|client_id| (client_id, client_secret.clone())),
+    }
+}
+
+/// Looks up the client and checks its secret. Public clients have no secret to check.
+pub(crate) async fn authenticate_client(
+    state: &AppState,
+    client_id: &str,
+    client_secret: Option<&str>,
+) -> Result<Client, TokenError> {
+    let client_store = state.client_store.read().await;
+    let client = client_store
+        .get_client(client_id)
+        .await
+        .map_err(|_| TokenError::InvalidClient(format!("Unknown client: {}", client_id)))?;
+    if client.is_confidential() && !client_secret.is_some_and(|secret| client.verify_secret(secret)) {
+        return Err(TokenError::InvalidClient("Client authentication failed".to_string()));
+    }
+    Ok(client.clone())
+}
+
+#[instrument(level = Level::TRACE, skip(headers, request))]
+pub async fn token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(request): Form<TokenRequest>,
+) -> Response {
+    let result = match request.grant_type.as_deref() {
+        Some("authorization_code") => authorization_code_grant(&state, &headers, &request).await,
+        Some("client_credentials") => client_credentials_grant(&state, &headers, &request).await,
+        Some(grant_type) => Err(TokenError::UnsupportedGrantType(grant_type.to_string())),
+        None => Err(TokenError::InvalidRequest("Missing grant_type".to_string())),
+    };
+    match result {
+        Ok(response) => (StatusCode::OK, NO_STORE, Json(response)).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+async fn authorization_code_grant(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: &TokenRequest,
+) -> Result<TokenResponse, TokenError> {
+    let code = required(&request.code, "code")?;
+    let redirect_uri = required(&request.redirect_uri, "redirect_uri")?;
+    let code_verifier = required(&request.code_verifier, "code_verifier")?;
+    let (client_id, client_secret) = client_credentials(headers, &request.client_id, &request.client_secret)
+        .ok_or_else(|| TokenError::InvalidRequest("Missing client_id".to_string()))?;
+    let client_id = client_id.as_str();
+    authenticate_client(state, client_id, client_secret.as_deref()).await?;
+
+    // The code is only consumed once it checks out, so that whoever presents a stolen or
+    // guessed code cannot burn it before the client it was issued to redeems it.
+    let now = get_current_timestamp();
+    let code = {
+        let mut code_store = state.authorization_code_store.write().await;
+        let stored = code_store
+            .get_code(code)
+            .await
+            .map_err(|_| TokenError::InvalidGrant("Invalid authorization code".to_string()))?;
+        if stored.client_id != client_id || stored.redirect_uri != redirect_uri || stored.is_expired(now) {
+            return Err(TokenError::InvalidGrant("Invalid authorization code".to_string()));
+        }
+        if !stored.verify_code_verifier(code_verifier) {
+            return Err(TokenError::InvalidGrant("Invalid code_verifier".to_string()));
+        }
+        code_store
+            .take_code(code)
+            .await
+            .map_err(|_| TokenError::InvalidGrant("Invalid authorization code".to_string()))?
+    };
+
+    let user = {
+        let user_store = state.user_store.read().await;
+        let email = Email::parse_canonical(&code.email)
+            .map_err(|_| TokenError::InvalidGrant("User no longer exists".to_string()))?;
+        user_store
+            .get_user(&code.tenant_id, &email)
+            .await
+            .map_err(|_| TokenError::InvalidGrant("User no longer exists".to_string()))?
+            .clone()
+    };
+    // The account may have been disabled, locked or sent to reset its password since the code was issued.
+    if user.disabled || user.is_locked() || user.password_reset_required {
+        return Err(TokenError::InvalidGrant("Account cannot sign in".to_string()));
+    }
+
+    // The access token is bound to a session, so that signing the user out revokes it. It
+    // carries the scope granted to the client rather than the user's roles.
+    let keyring = state.keyring.read().await;
+    let ttl = keyring.token_ttl();
+    let session = Session::new(&code.tenant_id, &code.email, code.user_agent.as_deref(), code.ip_address, now, ttl);
+    let mut claims = Claims::for_session(&session, &[], now, ttl);
+    claims.scope = Some(code.scope.clone());
+    claims.client_id = Some(client_id.to_string());
+    let access_token = keyring
+        .sign(&claims)
+        .map_err(|error| TokenError::UnexpectedError(error.to_string()))?;
+    let id_token = IdTokenClaims::new(&state.issuer, &user, client_id, code.nonce.as_deref(), now, ttl);
+    let id_token = keyring
+        .sign_id_token(&id_token)
+        .map_err(|error| TokenError::UnexpectedError(error.to_string()))?;
+    drop(keyring);
+    let mut session_store = state.session_store.write().await;
+    session_store
+        .add_session(session)
+        .await
+        .map_err(|error| TokenError::UnexpectedError(error.to_string()))?;
+
+    Ok(TokenResponse {
+        access_token,
+        token_type: "Bearer".to_string(),
+        expires_in: ttl,
+        id_token: Some(id_token),
+        scope: Some(code.scope),
+    })
+}
+
+async fn client_credentials_grant(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: &TokenRequest,
+) -> Result<TokenResponse, TokenError> {
+    let (client_id, client_secret) = client_credentials(headers, &request.client_id, &request.client_secret)
+        .ok_or_else(|| TokenError::InvalidClient("Missing client credentials".to_string()))?;
+    let client = authenticate_client(state, &client_id, client_secret.as_deref()).await?;
+    if !client.is_confidential() {
+        return Err(TokenError::InvalidClient("Client authentication failed".to_string()));
+    }
+
+    // Without an explicit scope, the client gets every scope it was granted.
+    let scope = match request.scope.as_deref() {
+        Some(scope) => {
+            if let Some(denied) = scope.split_whitespace().find(|scope| !client.allows_scope(scope)) {
+                return Err(TokenError::InvalidScope(denied.to_string()));
+            }
+            scope.split_whitespace().collect::<Vec<_>>().join(" ")
+        }
+        None => client.scopes.join(" "),
+    };
+
+    let keyring = state.keyring.read().await;
+    let ttl = keyring.token_ttl();
+    let claims = Claims::for_client(&client.client_id, &scope, get_current_timestamp(), ttl);
+    let access_token = keyring
+        .sign(&claims)
+        .map_err(|error| TokenError::UnexpectedError(error.to_string()))?;
+
+    Ok(TokenResponse {
+        access_token,
+        token_type: "Bearer".to_string(),
+        expires_in: ttl,
+        id_token: None,
+        scope: Some(scope),
+    })
+}
@@ -0,0 +1,59 @@
+use crate::app_state::AppState;
+use crate::domain::Email;
+use crate::services::{UserStore, UserStoreError};
+use crate::utils::{bearer_token, verify_access_token, TokenVerificationError};
+use axum::extract::State;
+use axum::http::header::WWW_AUTHENTICATE;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use tracing::{error, instrument};
+
+#[allow(unused_imports)]
+use tracing::Level;
+
+/// Standard claims about the authenticated user (OpenID Connect Core 1.0, section 5.3).
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct UserInfoResponse {
+    pub sub: String,
+    pub email: String,
+    pub email_verified: bool,
+}
+
+fn invalid_token() -> Response {
+    (StatusCode::UNAUTHORIZED, [(WWW_AUTHENTICATE, r#"Bearer error="invalid_token""#)]).into_response()
+}
+
+#[instrument(level = Level::TRACE, skip(headers))]
+pub async fn userinfo(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let Some(token) = bearer_token(&headers) else {
+        return (StatusCode::UNAUTHORIZED, [(WWW_AUTHENTICATE, "Bearer")]).into_response();
+    };
+    let claims = match verify_access_token(&state, token).await {
+        Ok(claims) => claims,
+        Err(TokenVerificationError::UnexpectedError(error)) => {
+            error!("Unexpected error when verifying token: {}", error);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+        Err(_) => return invalid_token(),
+    };
+
+    let Ok(email) = Email::parse_canonical(&claims.sub) else {
+        return invalid_token();
+    };
+    let user_store = state.user_store.read().await;
+    match user_store.get_user(claims.tenant_id(), &email).await {
+        Ok(user) => Json(UserInfoResponse {
+            sub: claims.sub,
+            email: user.email.to_string(),
+            email_verified: false,
+        })
+        .into_response(),
+        Err(UserStoreError::UserNotFound(_)) => invalid_token(),
+        Err(error) => {
+            error!("Unexpected error when fetching user: {}", error);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -1,11 +1,178 @@
-use axum::http::StatusCode;
-use axum::response::IntoResponse;
-use tracing::instrument;
+use crate::app_state::AppState;
+use crate::domain::{AuditContext, AuditEvent, AuditEventKind, LoginAttempt, LoginFailureReason, Tenant, User};
+use crate::services::{AuthorizationCodeStore, LoginAttemptStore, LoginAttemptStoreError, UserStore, UserStoreError};
+use crate::utils::{
+    account_status_message, audit, complete_sign_in, jwt_cookie, login_failure_reason, open_session, SignInAssessment,
+};
+use axum::extract::{ConnectInfo, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use axum_extra::extract::CookieJar;
+use jsonwebtoken::get_current_timestamp;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use tracing::{error, instrument};
 
 #[allow(unused_imports)]
 use tracing::Level;
 
-#[instrument(level = Level::TRACE)]
-pub async fn verify_2fa() -> impl IntoResponse {
-    StatusCode::OK.into_response() // TODO: dummy response for task 4
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Verify2FARequest {
+    pub email: String,
+    #[serde(rename = "loginAttemptId")]
+    pub login_attempt_id: String,
+    #[serde(rename = "2FACode")]
+    pub two_fa_code: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Verify2FAResponse {
+    /// Client redirect URI carrying the authorization code, for sign-ins at `/authorize`.
+    RedirectUri(String),
+    Error(String),
+}
+
+/// Answer to a sign-in that requires 2FA, naming the login attempt the emailed code is
+/// verified against.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorRequiredResponse {
+    pub message: String,
+    pub login_attempt_id: String,
+}
+
+pub fn two_factor_required(login_attempt_id: String) -> Response {
+    let response = TwoFactorRequiredResponse { message: "2FA required".to_string(), login_attempt_id };
+    (StatusCode::PARTIAL_CONTENT, Json(response)).into_response()
+}
+
+fn response(status: StatusCode, response: Verify2FAResponse) -> Response {
+    (status, Json(response)).into_response()
+}
+
+fn authentication_failed() -> Response {
+    response(StatusCode::UNAUTHORIZED, Verify2FAResponse::Error("Incorrect code".to_string()))
+}
+
+fn unexpected_error() -> Response {
+    response(StatusCode::INTERNAL_SERVER_ERROR, Verify2FAResponse::Error("Unexpected error".to_string()))
+}
+
+/// Completes a sign-in requiring 2FA with the code emailed to the user: opens a session or,
+/// for a sign-in at `/authorize`, issues the authorization code. Each code is used once, and
+/// the login attempt is abandoned after too many wrong codes.
+#[instrument(level = Level::TRACE, skip(headers, context, jar, request))]
+pub async fn verify_2fa(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<Tenant>,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    context: AuditContext,
+    jar: CookieJar,
+    Json(request): Json<Verify2FARequest>,
+) -> Response {
+    let email = match tenant.parse_email(&request.email) {
+        Ok(email) if !request.login_attempt_id.is_empty() && !request.two_fa_code.is_empty() => email,
+        _ => return response(StatusCode::BAD_REQUEST, Verify2FAResponse::Error("Invalid input".to_string())),
+    };
+    let event = |kind| AuditEvent::new(&tenant.id, email.as_str(), kind, &context, get_current_timestamp());
+
+    let login_attempt = {
+        let mut login_attempt_store = state.login_attempt_store.write().await;
+        let id = &request.login_attempt_id;
+        let verified = match login_attempt_store.get_login_attempt(id).await {
+            Ok(login_attempt) if login_attempt.admits(&tenant.id, &email, get_current_timestamp()) => {
+                login_attempt.verify_code(&request.two_fa_code)
+            }
+            Ok(_) | Err(LoginAttemptStoreError::LoginAttemptNotFound) => return authentication_failed(),
+            Err(error) => {
+                error!("Unexpected error when getting login attempt: {}", error);
+                return unexpected_error();
+            }
+        };
+        if !verified {
+            if let Err(error) = login_attempt_store.record_failed_code(id).await {
+                error!("Unexpected error when recording failed 2FA code: {}", error);
+            }
+            drop(login_attempt_store);
+            let reason = LoginFailureReason::InvalidCredentials;
+            audit(&state, event(AuditEventKind::LoginFailed { reason })).await;
+            return authentication_failed();
+        }
+        match login_attempt_store.take_login_attempt(id).await {
+            Ok(login_attempt) => login_attempt,
+            Err(error) => {
+                error!("Unexpected error when taking login attempt: {}", error);
+                return unexpected_error();
+            }
+        }
+    };
+
+    let account = {
+        let user_store = state.user_store.read().await;
+        user_store.get_user(&tenant.id, &email).await.and_then(|user| check_account(user, &login_attempt))
+    };
+    if let Err(error) = account {
+        if let Some(reason) = login_failure_reason(&error) {
+            audit(&state, event(AuditEventKind::LoginFailed { reason })).await;
+        }
+        return match error {
+            UserStoreError::UserNotFound(_) => authentication_failed(),
+            UserStoreError::AccountDisabled(_)
+            | UserStoreError::AccountLocked(_)
+            | UserStoreError::PasswordResetRequired(_) => {
+                let message = Verify2FAResponse::Error(account_status_message(&error).to_string());
+                response(StatusCode::FORBIDDEN, message)
+            }
+            error => {
+                error!("Unexpected error when validating user: {}", error);
+                unexpected_error()
+            }
+        };
+    }
+
+    let LoginAttempt { method, device, risk, authorization, .. } = login_attempt;
+    let succeeded = match authorization {
+        Some(authorization) => {
+            let (code, redirect_uri) = authorization.issue_code(
+                &tenant.id,
+                email.as_str(),
+                context.user_agent.as_deref(),
+                context.ip_address,
+                get_current_timestamp(),
+            );
+            let mut code_store = state.authorization_code_store.write().await;
+            if let Err(error) = code_store.add_code(code).await {
+                error!("Unexpected error when storing authorization code: {}", error);
+                return unexpected_error();
+            }
+            response(StatusCode::OK, Verify2FAResponse::RedirectUri(redirect_uri.to_string()))
+        }
+        None => match open_session(&state, &tenant.id, &email, &headers, address.ip()).await {
+            Ok(token) => (StatusCode::OK, jar.add(jwt_cookie(token))).into_response(),
+            Err(error) => {
+                error!("Unexpected error when opening session: {}", error);
+                return unexpected_error();
+            }
+        },
+    };
+    audit(&state, event(AuditEventKind::LoginSucceeded { method })).await;
+    complete_sign_in(&state, &tenant.id, &email, SignInAssessment { device, risk }).await;
+    succeeded
+}
+
+/// The account may have been disabled, locked or sent to reset its password since the first
+/// factor was passed. Identity providers stand in for the password, which may then be due.
+fn check_account(user: &User, login_attempt: &LoginAttempt) -> Result<(), UserStoreError> {
+    if user.disabled {
+        Err(UserStoreError::AccountDisabled(user.email.to_string()))
+    } else if user.is_locked() {
+        Err(UserStoreError::AccountLocked(user.email.to_string()))
+    } else if user.password_reset_required && login_attempt.method == "password" {
+        Err(UserStoreError::PasswordResetRequired(user.email.to_string()))
+    } else {
+        Ok(())
+    }
 }
@@ -1,11 +1,90 @@
-use axum::http::StatusCode;
-use axum::response::IntoResponse;
-use tracing::instrument;
+use crate::app_state::AppState;
+use crate::domain::{parse_api_key, Permission, Role};
+use crate::utils::{bearer_token, verify_access_token, verify_api_key, TokenVerificationError};
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use tracing::{error, instrument};
 
 #[allow(unused_imports)]
 use tracing::Level;
 
-#[instrument(level = Level::TRACE)]
-pub async fn verify_token() -> impl IntoResponse {
-    StatusCode::OK.into_response() // TODO: dummy response for task 4
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct VerifyTokenRequest {
+    pub token: String,
+}
+
+/// Who the token was issued to and what it allows, for the caller's authorization decisions.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifiedTokenResponse {
+    pub sub: String,
+    pub tenant_id: String,
+    pub roles: Vec<Role>,
+    pub permissions: Vec<Permission>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VerifyTokenResponse {
+    Error(String),
+}
+
+/// Verifies a JWT or a personal API key. API keys carry the permissions they were
+/// scoped to, as far as their user still holds them, but none of the user's roles.
+async fn verify(state: &AppState, token: &str) -> Result<VerifiedTokenResponse, TokenVerificationError> {
+    if parse_api_key(token).is_some() {
+        let verified = verify_api_key(state, token).await?;
+        let scope = verified.permissions.iter().map(Permission::as_str).collect::<Vec<_>>().join(" ");
+        return Ok(VerifiedTokenResponse {
+            sub: verified.api_key.email,
+            tenant_id: verified.api_key.tenant_id,
+            roles: Vec::new(),
+            permissions: verified.permissions,
+            scope: Some(scope),
+        });
+    }
+    let claims = verify_access_token(state, token).await?;
+    Ok(VerifiedTokenResponse {
+        tenant_id: claims.tenant_id().to_string(),
+        sub: claims.sub,
+        roles: claims.roles,
+        permissions: claims.permissions,
+        scope: claims.scope,
+    })
+}
+
+/// Compatibility shim over the token verification behind `/oauth/introspect`, returning
+/// the effective permissions of the token. Also accepts personal API keys. The token is
+/// read from the `Authorization: Bearer` header, or else from the request body.
+#[instrument(level = Level::TRACE, skip(headers, request))]
+pub async fn verify_token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    request: Option<Json<VerifyTokenRequest>>,
+) -> Response {
+    let token = match (bearer_token(&headers), &request) {
+        (Some(token), _) => token,
+        (None, Some(Json(request))) => request.token.as_str(),
+        (None, None) => {
+            let response = Json(VerifyTokenResponse::Error("Missing token".to_string()));
+            return (StatusCode::UNAUTHORIZED, response).into_response();
+        }
+    };
+    match verify(&state, token).await {
+        Ok(response) => (StatusCode::OK, Json(response)).into_response(),
+        Err(TokenVerificationError::UnexpectedError(error)) => {
+            error!("Unexpected error when verifying token: {}", error);
+            let response = Json(VerifyTokenResponse::Error("Unexpected error".to_string()));
+            (StatusCode::INTERNAL_SERVER_ERROR, response).into_response()
+        }
+        Err(_) => {
+            let response = Json(VerifyTokenResponse::Error("Invalid token".to_string()));
+            (StatusCode::UNAUTHORIZED, response).into_response()
+        }
+    }
 }
@@ -1,5 +1,67 @@
+mod api_key_store;
+mod audit_log;
+mod authorization_code_store;
+mod banned_token_store;
+mod client_store;
+mod email_client;
+mod geoip_database;
+mod hashmap_api_key_store;
+mod hashmap_authorization_code_store;
+mod hashmap_banned_token_store;
+mod hashmap_client_store;
+mod hashmap_invitation_store;
+mod hashmap_login_attempt_store;
+mod hashmap_password_reset_store;
+mod hashmap_session_store;
+mod hashmap_upstream_authorization_store;
 mod hashmap_user_store;
+mod identity_provider_connector;
+mod in_memory_audit_sink;
+mod invitation_store;
+mod json_lines_audit_sink;
+mod keyring;
+mod login_attempt_store;
+mod log_email_client;
+mod mock_email_client;
+mod password_reset_store;
+mod postmark_email_client;
+mod pwned_passwords;
+mod rate_limiter;
+mod session_store;
+mod tls;
+mod upstream_authorization_store;
 mod user_store;
 
+pub use api_key_store::*;
+pub use audit_log::*;
+pub use authorization_code_store::*;
+pub use banned_token_store::*;
+pub use client_store::*;
+pub use email_client::*;
+pub use geoip_database::*;
+pub use hashmap_api_key_store::*;
+pub use hashmap_authorization_code_store::*;
+pub use hashmap_banned_token_store::*;
+pub use hashmap_client_store::*;
+pub use hashmap_invitation_store::*;
+pub use hashmap_login_attempt_store::*;
+pub use hashmap_password_reset_store::*;
+pub use hashmap_session_store::*;
+pub use hashmap_upstream_authorization_store::*;
 pub use hashmap_user_store::*;
+pub use identity_provider_connector::*;
+pub use in_memory_audit_sink::*;
+pub use invitation_store::*;
+pub use json_lines_audit_sink::*;
+pub use keyring::*;
+pub use login_attempt_store::*;
+pub use log_email_client::*;
+pub use mock_email_client::*;
+pub use password_reset_store::*;
+pub use postmark_email_client::*;
+pub use pwned_passwords::*;
+pub use rate_limiter::*;
+pub use session_store::*;
+pub use tls::*;
+pub use upstream_authorization_store::*;
 pub use user_store::*;
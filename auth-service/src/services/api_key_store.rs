@@ -0,0 +1,23 @@
+use crate::domain::ApiKey;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ApiKeyStoreError {
+    #[error("API key was not found: {0}")]
+    ApiKeyNotFound(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+#[async_trait::async_trait]
+pub trait ApiKeyStore {
+    async fn add_api_key(&mut self, api_key: ApiKey) -> Result<(), ApiKeyStoreError>;
+    async fn get_api_key(&self, id: &str) -> Result<&ApiKey, ApiKeyStoreError>;
+    /// Records use of the key.
+    async fn touch_api_key(&mut self, id: &str, now: u64) -> Result<(), ApiKeyStoreError>;
+    /// Keys of the user, oldest first.
+    async fn get_user_api_keys(&self, tenant_id: &str, email: &str) -> Result<Vec<ApiKey>, ApiKeyStoreError>;
+    async fn remove_api_key(&mut self, id: &str) -> Result<ApiKey, ApiKeyStoreError>;
+    /// Removes every key of the user, returning how many there were.
+    async fn remove_user_api_keys(&mut self, tenant_id: &str, email: &str) -> Result<usize, ApiKeyStoreError>;
+}
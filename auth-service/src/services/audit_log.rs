@@ -0,0 +1,144 @@
+use crate::domain::{checkpoint_message, AuditCheckpoint, AuditEntry, AuditEvent, AuditRecord};
+use crate::services::{InMemoryAuditSink, SigningKey};
+use anyhow::Context;
+use jsonwebtoken::jwk::Jwk;
+use std::fmt::Debug;
+use thiserror::Error;
+
+/// Events recorded between two signed checkpoints, unless configured otherwise.
+pub const AUDIT_CHECKPOINT_INTERVAL: u64 = 100;
+
+#[derive(Error, Debug)]
+pub enum AuditLogError {
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+/// Where audit records are kept.
+#[async_trait::async_trait]
+pub trait AuditSink: Debug + Send + Sync {
+    async fn append(&mut self, record: &AuditRecord) -> Result<(), AuditLogError>;
+    /// Record the next one is chained to.
+    fn last_record(&self) -> Option<&AuditRecord>;
+    /// Keeps the public key checkpoints are about to be signed with, so that they can still
+    /// be verified once the process holding the key is gone.
+    async fn publish_key(&mut self, jwk: &Jwk) -> Result<(), AuditLogError>;
+    /// Events of the tenant concerning the user, oldest first.
+    async fn user_events(&self, tenant_id: &str, email: &str) -> Result<Vec<AuditEvent>, AuditLogError>;
+}
+
+/// Record of security-relevant events: sign-ups, sign-ins, credential changes and
+/// admin actions. Events are kept in memory unless another sink is configured.
+///
+/// Records form a hash chain, and a checkpoint signed with the service signing key is
+/// recorded every `checkpoint_interval` events. The sink is handed each signing key's
+/// public half before its first checkpoint.
+#[derive(Debug)]
+pub struct AuditLog {
+    sink: Box<dyn AuditSink>,
+    checkpoint_interval: u64,
+    /// Events recorded since the last checkpoint, or since startup.
+    uncheckpointed: u64,
+    /// Key id of the last key handed to the sink.
+    published_key_id: Option<String>,
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self::new(InMemoryAuditSink::default())
+    }
+}
+
+impl AuditLog {
+    pub fn new(sink: impl AuditSink + 'static) -> Self {
+        Self {
+            sink: Box::new(sink),
+            checkpoint_interval: AUDIT_CHECKPOINT_INTERVAL,
+            uncheckpointed: 0,
+            published_key_id: None,
+        }
+    }
+
+    pub fn with_checkpoint_interval(mut self, checkpoint_interval: u64) -> Self {
+        self.checkpoint_interval = checkpoint_interval;
+        self
+    }
+
+    pub async fn record(&mut self, event: AuditEvent) -> Result<(), AuditLogError> {
+        self.append(AuditEntry::Event(event)).await?;
+        self.uncheckpointed += 1;
+        Ok(())
+    }
+
+    pub fn checkpoint_due(&self) -> bool {
+        self.uncheckpointed >= self.checkpoint_interval
+    }
+
+    /// Signs the chain up to this point with the key.
+    pub async fn checkpoint(&mut self, key: &SigningKey, now: u64) -> Result<(), AuditLogError> {
+        let message = match self.sink.last_record() {
+            Some(previous) => checkpoint_message(previous.sequence + 1, &previous.hash),
+            None => return Ok(()),
+        };
+        if self.published_key_id.as_deref() != Some(key.kid()) {
+            self.sink.publish_key(&key.jwk()).await?;
+            self.published_key_id = Some(key.kid().to_string());
+        }
+        let signature = key.sign(message.as_bytes()).context("Failed to sign audit checkpoint")?;
+        let checkpoint = AuditCheckpoint {
+            timestamp: now,
+            key_id: key.kid().to_string(),
+            public_key: key.public_key(),
+            signature,
+        };
+        self.append(AuditEntry::Checkpoint(checkpoint)).await?;
+        self.uncheckpointed = 0;
+        Ok(())
+    }
+
+    pub async fn user_events(&self, tenant_id: &str, email: &str) -> Result<Vec<AuditEvent>, AuditLogError> {
+        self.sink.user_events(tenant_id, email).await
+    }
+
+    async fn append(&mut self, entry: AuditEntry) -> Result<(), AuditLogError> {
+        let record = AuditRecord::chain(self.sink.last_record(), entry).context("Failed to serialize audit record")?;
+        self.sink.append(&record).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{verify_audit_chain, AuditContext, AuditEventKind, DEFAULT_TENANT_ID};
+    use crate::services::{audit_keys_file, JsonLinesAuditSink, Keyring};
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_checkpoint_every_interval() {
+        let path = std::env::temp_dir().join(format!("audit-{}.jsonl", Uuid::new_v4()));
+        let sink = JsonLinesAuditSink::open(&path).await.unwrap();
+        let mut audit_log = AuditLog::new(sink).with_checkpoint_interval(2);
+        let keyring = Keyring::new(600).unwrap();
+        let context = AuditContext::default();
+        for _ in 0..5 {
+            let event = AuditEvent::new(DEFAULT_TENANT_ID, "alice@example.com", AuditEventKind::Logout, &context, 100);
+            audit_log.record(event).await.unwrap();
+            if audit_log.checkpoint_due() {
+                audit_log.checkpoint(keyring.active(), 100).await.unwrap();
+            }
+        }
+
+        let report = verify_audit_chain(&tokio::fs::read_to_string(&path).await.unwrap(), None);
+        assert_eq!(report.records, 7);
+        assert_eq!(report.checkpoints, 2);
+        assert_eq!(report.last_checkpoint, Some(5));
+        assert_eq!(report.broken_link, None);
+        assert_eq!(audit_log.user_events(DEFAULT_TENANT_ID, "alice@example.com").await.unwrap().len(), 5);
+        // The key is published once, before its first checkpoint.
+        let keys = tokio::fs::read_to_string(audit_keys_file(&path)).await.unwrap();
+        assert_eq!(keys.lines().count(), 1);
+        assert!(keys.contains(keyring.active().kid()));
+        tokio::fs::remove_file(&path).await.unwrap();
+        tokio::fs::remove_file(audit_keys_file(&path)).await.unwrap();
+    }
+}
@@ -0,0 +1,18 @@
+use crate::domain::AuthorizationCode;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum AuthorizationCodeStoreError {
+    #[error("Authorization code was not found")]
+    CodeNotFound,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+#[async_trait::async_trait]
+pub trait AuthorizationCodeStore {
+    async fn add_code(&mut self, code: AuthorizationCode) -> Result<(), AuthorizationCodeStoreError>;
+    async fn get_code(&self, code: &str) -> Result<&AuthorizationCode, AuthorizationCodeStoreError>;
+    /// Removes the code from the store, so that it can only be redeemed once.
+    async fn take_code(&mut self, code: &str) -> Result<AuthorizationCode, AuthorizationCodeStoreError>;
+}
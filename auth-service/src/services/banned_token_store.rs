@@ -0,0 +1,14 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum BannedTokenStoreError {
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+#[async_trait::async_trait]
+pub trait BannedTokenStore {
+    /// Bans the token until `expires_at`, after which the token no longer verifies anyway.
+    async fn add_token(&mut self, token: &str, expires_at: u64) -> Result<(), BannedTokenStoreError>;
+    async fn contains_token(&self, token: &str) -> Result<bool, BannedTokenStoreError>;
+}
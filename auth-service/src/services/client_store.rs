@@ -0,0 +1,19 @@
+use crate::domain::Client;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ClientStoreError {
+    #[error("Client already exists: {0}")]
+    ClientAlreadyExists(String),
+    #[error("Client was not found: {0}")]
+    ClientNotFound(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+#[async_trait::async_trait]
+pub trait ClientStore {
+    async fn add_client(&mut self, client: Client) -> Result<(), ClientStoreError>;
+    async fn get_client(&self, client_id: &str) -> Result<&Client, ClientStoreError>;
+    async fn remove_client(&mut self, client_id: &str) -> Result<(), ClientStoreError>;
+}
@@ -0,0 +1,21 @@
+use std::fmt::Debug;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum EmailClientError {
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmailMessage {
+    pub recipient: String,
+    pub subject: String,
+    pub content: String,
+}
+
+/// Sends notification emails to users.
+#[async_trait::async_trait]
+pub trait EmailClient: Debug + Send + Sync {
+    async fn send_email(&mut self, message: EmailMessage) -> Result<(), EmailClientError>;
+}
@@ -0,0 +1,140 @@
+use crate::domain::GeoLocation;
+use anyhow::Context;
+use ipnet::IpNet;
+use std::net::IpAddr;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum GeoIpError {
+    #[error("GeoIP database must have network, latitude and longitude columns")]
+    MissingColumns,
+    #[error("Invalid GeoIP record on line {0}")]
+    InvalidRecord(usize),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+#[derive(Debug)]
+struct GeoIpRange {
+    first: u128,
+    last: u128,
+    location: GeoLocation,
+}
+
+/// Offline GeoIP database, loaded from a CSV file with a header naming its columns:
+/// `network` (CIDR), `latitude`, `longitude` and optionally `country`. GeoLite2 City
+/// blocks files have this layout. Networks must not overlap; rows without coordinates
+/// are skipped.
+#[derive(Debug, Default)]
+pub struct GeoIpDatabase {
+    /// Sorted by first address, IPv4 addresses mapped to IPv6.
+    ranges: Vec<GeoIpRange>,
+}
+
+impl GeoIpDatabase {
+    pub fn load(path: &Path) -> Result<Self, GeoIpError> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read GeoIP database {}", path.display()))?;
+        Self::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, GeoIpError> {
+        let mut lines = contents.lines();
+        let header: Vec<&str> = lines.next().unwrap_or_default().split(',').map(str::trim).collect();
+        let column = |name| header.iter().position(|column| *column == name);
+        let (Some(network), Some(latitude), Some(longitude)) =
+            (column("network"), column("latitude"), column("longitude"))
+        else {
+            return Err(GeoIpError::MissingColumns);
+        };
+        let country = column("country").or_else(|| column("country_iso_code"));
+
+        let mut ranges = Vec::new();
+        for (index, line) in lines.enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let field = |column: usize| fields.get(column).copied().unwrap_or_default();
+            let (latitude, longitude) = (field(latitude), field(longitude));
+            if latitude.is_empty() || longitude.is_empty() {
+                continue;
+            }
+            let invalid = || GeoIpError::InvalidRecord(index + 2);
+            let network: IpNet = field(network).parse().map_err(|_| invalid())?;
+            let location = GeoLocation {
+                country: country.map(field).filter(|country| !country.is_empty()).map(str::to_string),
+                latitude: latitude.parse().map_err(|_| invalid())?,
+                longitude: longitude.parse().map_err(|_| invalid())?,
+            };
+            ranges.push(GeoIpRange {
+                first: address_key(network.network()),
+                last: address_key(network.broadcast()),
+                location,
+            });
+        }
+        ranges.sort_by_key(|range| range.first);
+        Ok(Self { ranges })
+    }
+
+    pub fn lookup(&self, ip_address: IpAddr) -> Option<&GeoLocation> {
+        let key = address_key(ip_address);
+        let index = self.ranges.partition_point(|range| range.first <= key).checked_sub(1)?;
+        let range = &self.ranges[index];
+        (key <= range.last).then_some(&range.location)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+}
+
+fn address_key(ip_address: IpAddr) -> u128 {
+    match ip_address {
+        IpAddr::V4(address) => u128::from(address.to_ipv6_mapped()),
+        IpAddr::V6(address) => u128::from(address),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATABASE: &str = "network,country,latitude,longitude
+203.0.113.0/24,AU,-33.8688,151.2093
+198.51.100.0/24,GB,51.5074,-0.1278
+192.0.2.0/24,,,
+2001:db8::/32,FR,48.8566,2.3522
+";
+
+    fn country(database: &GeoIpDatabase, ip_address: &str) -> Option<String> {
+        database.lookup(ip_address.parse().unwrap()).and_then(|location| location.country.clone())
+    }
+
+    #[test]
+    fn test_lookup() {
+        let database = GeoIpDatabase::parse(DATABASE).unwrap();
+        assert_eq!(country(&database, "203.0.113.7"), Some("AU".to_string()));
+        assert_eq!(country(&database, "198.51.100.255"), Some("GB".to_string()));
+        assert_eq!(country(&database, "2001:db8:1::1"), Some("FR".to_string()));
+        assert!(database.lookup("192.0.2.1".parse().unwrap()).is_none());
+        assert!(database.lookup("198.51.101.0".parse().unwrap()).is_none());
+        assert!(database.lookup("10.0.0.1".parse().unwrap()).is_none());
+        assert!(GeoIpDatabase::default().lookup("203.0.113.7".parse().unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_geolite2_blocks_layout() {
+        let contents = "network,geoname_id,registered_country_geoname_id,represented_country_geoname_id,\
+                        is_anonymous_proxy,is_satellite_provider,postal_code,latitude,longitude,accuracy_radius
+203.0.113.0/24,2147714,2077456,,0,0,2000,-33.8715,151.2006,1000";
+        let database = GeoIpDatabase::parse(contents).unwrap();
+        let location = database.lookup("203.0.113.7".parse().unwrap()).unwrap();
+        assert_eq!((location.latitude, location.longitude, location.country.clone()), (-33.8715, 151.2006, None));
+    }
+
+    #[test]
+    fn test_invalid_database() {
+        assert!(matches!(GeoIpDatabase::parse("network,country\n"), Err(GeoIpError::MissingColumns)));
+        let contents = "network,latitude,longitude\n203.0.113.0/24,1,2\nnot-a-network,1,2\n";
+        assert!(matches!(GeoIpDatabase::parse(contents), Err(GeoIpError::InvalidRecord(3))));
+    }
+}
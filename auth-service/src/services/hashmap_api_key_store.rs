@@ -0,0 +1,96 @@
+use crate::domain::ApiKey;
+use crate::services::{ApiKeyStore, ApiKeyStoreError};
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub struct HashmapApiKeyStore {
+    api_keys: HashMap<String, ApiKey>,
+}
+
+#[async_trait::async_trait]
+impl ApiKeyStore for HashmapApiKeyStore {
+    async fn add_api_key(&mut self, api_key: ApiKey) -> Result<(), ApiKeyStoreError> {
+        self.api_keys.insert(api_key.id.clone(), api_key);
+        Ok(())
+    }
+
+    async fn get_api_key(&self, id: &str) -> Result<&ApiKey, ApiKeyStoreError> {
+        self.api_keys
+            .get(id)
+            .ok_or(ApiKeyStoreError::ApiKeyNotFound(id.to_string()))
+    }
+
+    async fn touch_api_key(&mut self, id: &str, now: u64) -> Result<(), ApiKeyStoreError> {
+        let api_key = self
+            .api_keys
+            .get_mut(id)
+            .ok_or(ApiKeyStoreError::ApiKeyNotFound(id.to_string()))?;
+        api_key.last_used_at = api_key.last_used_at.max(Some(now));
+        Ok(())
+    }
+
+    async fn get_user_api_keys(&self, tenant_id: &str, email: &str) -> Result<Vec<ApiKey>, ApiKeyStoreError> {
+        let mut api_keys: Vec<ApiKey> = self
+            .api_keys
+            .values()
+            .filter(|api_key| api_key.tenant_id == tenant_id && api_key.email == email)
+            .cloned()
+            .collect();
+        api_keys.sort_by_key(|api_key| api_key.created_at);
+        Ok(api_keys)
+    }
+
+    async fn remove_api_key(&mut self, id: &str) -> Result<ApiKey, ApiKeyStoreError> {
+        self.api_keys
+            .remove(id)
+            .ok_or(ApiKeyStoreError::ApiKeyNotFound(id.to_string()))
+    }
+
+    async fn remove_user_api_keys(&mut self, tenant_id: &str, email: &str) -> Result<usize, ApiKeyStoreError> {
+        let count = self.api_keys.len();
+        self.api_keys
+            .retain(|_, api_key| api_key.tenant_id != tenant_id || api_key.email != email);
+        Ok(count - self.api_keys.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::DEFAULT_TENANT_ID;
+
+    fn api_key(tenant_id: &str, email: &str) -> ApiKey {
+        ApiKey::try_new(tenant_id, email, "ci", &[], 100, None).unwrap().0
+    }
+
+    #[tokio::test]
+    async fn test_add_and_touch_api_key() {
+        let api_key = api_key(DEFAULT_TENANT_ID, "alice@example.com");
+        let id = api_key.id.clone();
+        let mut store = HashmapApiKeyStore::default();
+        store.add_api_key(api_key).await.unwrap();
+        assert_eq!(store.get_api_key(&id).await.unwrap().last_used_at, None);
+        store.touch_api_key(&id, 200).await.unwrap();
+        store.touch_api_key(&id, 150).await.unwrap();
+        assert_eq!(store.get_api_key(&id).await.unwrap().last_used_at, Some(200));
+        assert!(matches!(
+            store.touch_api_key("unknown", 200).await,
+            Err(ApiKeyStoreError::ApiKeyNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_user_api_keys() {
+        let mut store = HashmapApiKeyStore::default();
+        store.add_api_key(api_key(DEFAULT_TENANT_ID, "alice@example.com")).await.unwrap();
+        store.add_api_key(api_key(DEFAULT_TENANT_ID, "alice@example.com")).await.unwrap();
+        store.add_api_key(api_key("acme", "alice@example.com")).await.unwrap();
+        let api_keys = store.get_user_api_keys(DEFAULT_TENANT_ID, "alice@example.com").await.unwrap();
+        assert_eq!(api_keys.len(), 2);
+
+        assert_eq!(store.remove_api_key(&api_keys[0].id).await.unwrap().id, api_keys[0].id);
+        assert!(store.remove_api_key(&api_keys[0].id).await.is_err());
+        assert_eq!(store.remove_user_api_keys(DEFAULT_TENANT_ID, "alice@example.com").await.unwrap(), 1);
+        assert_eq!(store.get_user_api_keys("acme", "alice@example.com").await.unwrap().len(), 1);
+    }
+}
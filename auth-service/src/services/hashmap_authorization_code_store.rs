@@ -0,0 +1,52 @@
+use crate::domain::AuthorizationCode;
+use crate::services::{AuthorizationCodeStore, AuthorizationCodeStoreError};
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub struct HashmapAuthorizationCodeStore {
+    codes: HashMap<String, AuthorizationCode>,
+}
+
+#[async_trait::async_trait]
+impl AuthorizationCodeStore for HashmapAuthorizationCodeStore {
+    async fn add_code(&mut self, code: AuthorizationCode) -> Result<(), AuthorizationCodeStoreError> {
+        self.codes.insert(code.code.clone(), code);
+        Ok(())
+    }
+
+    async fn get_code(&self, code: &str) -> Result<&AuthorizationCode, AuthorizationCodeStoreError> {
+        self.codes.get(code).ok_or(AuthorizationCodeStoreError::CodeNotFound)
+    }
+
+    async fn take_code(&mut self, code: &str) -> Result<AuthorizationCode, AuthorizationCodeStoreError> {
+        self.codes
+            .remove(code)
+            .ok_or(AuthorizationCodeStoreError::CodeNotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_take_code_only_once() {
+        let code = AuthorizationCode::new(
+            "app",
+            "http://localhost:8000/callback",
+            "alice@example.com",
+            "openid",
+            None,
+            "challenge",
+            0,
+        );
+        let value = code.code.clone();
+        let mut store = HashmapAuthorizationCodeStore::default();
+        store.add_code(code).await.unwrap();
+        assert!(store.take_code(&value).await.is_ok());
+        assert!(matches!(
+            store.take_code(&value).await,
+            Err(AuthorizationCodeStoreError::CodeNotFound)
+        ));
+    }
+}
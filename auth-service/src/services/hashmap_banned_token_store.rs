@@ -0,0 +1,50 @@
+use crate::services::{BannedTokenStore, BannedTokenStoreError};
+use jsonwebtoken::get_current_timestamp;
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub struct HashmapBannedTokenStore {
+    tokens: HashMap<String, u64>,
+}
+
+impl HashmapBannedTokenStore {
+    fn add_token_at(&mut self, token: &str, expires_at: u64, now: u64) {
+        // Expired tokens fail verification on their own, so there is no need to keep them.
+        self.tokens.retain(|_, expires_at| *expires_at > now);
+        self.tokens.insert(token.to_string(), expires_at);
+    }
+}
+
+#[async_trait::async_trait]
+impl BannedTokenStore for HashmapBannedTokenStore {
+    async fn add_token(&mut self, token: &str, expires_at: u64) -> Result<(), BannedTokenStoreError> {
+        self.add_token_at(token, expires_at, get_current_timestamp());
+        Ok(())
+    }
+
+    async fn contains_token(&self, token: &str) -> Result<bool, BannedTokenStoreError> {
+        Ok(self.tokens.contains_key(token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_add_token() {
+        let mut store = HashmapBannedTokenStore::default();
+        store.add_token("token", u64::MAX).await.unwrap();
+        assert!(store.contains_token("token").await.unwrap());
+        assert!(!store.contains_token("other").await.unwrap());
+    }
+
+    #[test]
+    fn test_add_token_prunes_expired_tokens() {
+        let mut store = HashmapBannedTokenStore::default();
+        store.add_token_at("expired", 100, 50);
+        store.add_token_at("active", 300, 200);
+        assert!(!store.tokens.contains_key("expired"));
+        assert!(store.tokens.contains_key("active"));
+    }
+}
@@ -0,0 +1,68 @@
+use crate::domain::Client;
+use crate::services::{ClientStore, ClientStoreError};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub struct HashmapClientStore {
+    clients: HashMap<String, Client>,
+}
+
+#[async_trait::async_trait]
+impl ClientStore for HashmapClientStore {
+    async fn add_client(&mut self, client: Client) -> Result<(), ClientStoreError> {
+        match self.clients.entry(client.client_id.clone()) {
+            Entry::Occupied(entry) => Err(ClientStoreError::ClientAlreadyExists(entry.key().clone())),
+            Entry::Vacant(entry) => {
+                entry.insert(client);
+                Ok(())
+            }
+        }
+    }
+
+    async fn get_client(&self, client_id: &str) -> Result<&Client, ClientStoreError> {
+        self.clients
+            .get(client_id)
+            .ok_or(ClientStoreError::ClientNotFound(client_id.to_string()))
+    }
+
+    async fn remove_client(&mut self, client_id: &str) -> Result<(), ClientStoreError> {
+        self.clients
+            .remove(client_id)
+            .map(|_| ())
+            .ok_or(ClientStoreError::ClientNotFound(client_id.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_add_client() {
+        let client_1 = Client::try_new("app", &["http://localhost:8000/callback"]).unwrap();
+        let client_2 = client_1.clone();
+        let mut store = HashmapClientStore::default();
+        assert!(store.add_client(client_1).await.is_ok());
+        assert!(store.add_client(client_2).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_client() {
+        let client = Client::try_new("app", &["http://localhost:8000/callback"]).unwrap();
+        let mut store = HashmapClientStore::default();
+        store.add_client(client).await.unwrap();
+        assert!(store.get_client("app").await.is_ok());
+        assert!(store.get_client("other").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_remove_client() {
+        let client = Client::try_new("app", &["http://localhost:8000/callback"]).unwrap();
+        let mut store = HashmapClientStore::default();
+        store.add_client(client).await.unwrap();
+        assert!(store.remove_client("app").await.is_ok());
+        assert!(store.get_client("app").await.is_err());
+        assert!(store.remove_client("app").await.is_err());
+    }
+}
@@ -0,0 +1,46 @@
+use crate::domain::Invitation;
+use crate::services::{InvitationStore, InvitationStoreError};
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub struct HashmapInvitationStore {
+    invitations: HashMap<String, Invitation>,
+}
+
+#[async_trait::async_trait]
+impl InvitationStore for HashmapInvitationStore {
+    async fn add_invitation(&mut self, invitation: Invitation) -> Result<(), InvitationStoreError> {
+        self.invitations.insert(invitation.token.clone(), invitation);
+        Ok(())
+    }
+
+    async fn get_invitation(&self, token: &str) -> Result<&Invitation, InvitationStoreError> {
+        self.invitations.get(token).ok_or(InvitationStoreError::InvitationNotFound)
+    }
+
+    async fn take_invitation(&mut self, token: &str) -> Result<Invitation, InvitationStoreError> {
+        self.invitations
+            .remove(token)
+            .ok_or(InvitationStoreError::InvitationNotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::DEFAULT_TENANT_ID;
+
+    #[tokio::test]
+    async fn test_take_invitation_only_once() {
+        let invitation = Invitation::try_new(DEFAULT_TENANT_ID, "alice@example.com", None, 0, 600).unwrap();
+        let token = invitation.token.clone();
+        let mut store = HashmapInvitationStore::default();
+        store.add_invitation(invitation).await.unwrap();
+        assert_eq!(store.get_invitation(&token).await.unwrap().email, "alice@example.com");
+        assert!(store.take_invitation(&token).await.is_ok());
+        assert!(matches!(
+            store.take_invitation(&token).await,
+            Err(InvitationStoreError::InvitationNotFound)
+        ));
+    }
+}
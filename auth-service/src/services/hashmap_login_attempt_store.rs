@@ -0,0 +1,102 @@
+use crate::domain::{LoginAttempt, MAX_2FA_CODE_ATTEMPTS};
+use crate::services::{LoginAttemptStore, LoginAttemptStoreError};
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub struct HashmapLoginAttemptStore {
+    login_attempts: HashMap<String, LoginAttempt>,
+}
+
+#[async_trait::async_trait]
+impl LoginAttemptStore for HashmapLoginAttemptStore {
+    async fn add_login_attempt(&mut self, login_attempt: LoginAttempt) -> Result<(), LoginAttemptStoreError> {
+        self.login_attempts.retain(|_, earlier| {
+            login_attempt.created_at < earlier.expires_at
+                && (earlier.tenant_id != login_attempt.tenant_id || earlier.email != login_attempt.email)
+        });
+        self.login_attempts.insert(login_attempt.id.clone(), login_attempt);
+        Ok(())
+    }
+
+    async fn get_login_attempt(&self, id: &str) -> Result<&LoginAttempt, LoginAttemptStoreError> {
+        self.login_attempts.get(id).ok_or(LoginAttemptStoreError::LoginAttemptNotFound)
+    }
+
+    async fn record_failed_code(&mut self, id: &str) -> Result<(), LoginAttemptStoreError> {
+        let login_attempt = self
+            .login_attempts
+            .get_mut(id)
+            .ok_or(LoginAttemptStoreError::LoginAttemptNotFound)?;
+        login_attempt.failed_attempts += 1;
+        if login_attempt.failed_attempts >= MAX_2FA_CODE_ATTEMPTS {
+            self.login_attempts.remove(id);
+        }
+        Ok(())
+    }
+
+    async fn take_login_attempt(&mut self, id: &str) -> Result<LoginAttempt, LoginAttemptStoreError> {
+        self.login_attempts
+            .remove(id)
+            .ok_or(LoginAttemptStoreError::LoginAttemptNotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Email, KnownDevice, LoginRisk, DEFAULT_TENANT_ID, LOGIN_ATTEMPT_TTL_SECONDS};
+
+    fn login_attempt(tenant_id: &str, email: &str, now: u64) -> LoginAttempt {
+        let email = Email::parse(email).unwrap();
+        let device = KnownDevice::observed(None, "127.0.0.1".parse().unwrap(), None, now);
+        LoginAttempt::new(tenant_id, &email, "password", device, LoginRisk::default(), now)
+    }
+
+    #[tokio::test]
+    async fn test_take_login_attempt_only_once() {
+        let login_attempt = login_attempt(DEFAULT_TENANT_ID, "alice@example.com", 0);
+        let id = login_attempt.id.clone();
+        let mut store = HashmapLoginAttemptStore::default();
+        store.add_login_attempt(login_attempt).await.unwrap();
+        assert_eq!(store.get_login_attempt(&id).await.unwrap().id, id);
+        assert!(store.take_login_attempt(&id).await.is_ok());
+        assert!(matches!(
+            store.take_login_attempt(&id).await,
+            Err(LoginAttemptStoreError::LoginAttemptNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_failed_codes_abandon_login_attempt() {
+        let login_attempt = login_attempt(DEFAULT_TENANT_ID, "alice@example.com", 0);
+        let id = login_attempt.id.clone();
+        let mut store = HashmapLoginAttemptStore::default();
+        store.add_login_attempt(login_attempt).await.unwrap();
+        for _ in 1..MAX_2FA_CODE_ATTEMPTS {
+            store.record_failed_code(&id).await.unwrap();
+        }
+        assert_eq!(store.get_login_attempt(&id).await.unwrap().failed_attempts, MAX_2FA_CODE_ATTEMPTS - 1);
+        store.record_failed_code(&id).await.unwrap();
+        assert!(store.get_login_attempt(&id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_new_login_attempt_replaces_earlier_and_expired_ones() {
+        let earlier = login_attempt(DEFAULT_TENANT_ID, "alice@example.com", 0);
+        let other_tenant = login_attempt("acme", "alice@example.com", 0);
+        let bob = login_attempt(DEFAULT_TENANT_ID, "bob@example.com", 0);
+        let (earlier_id, other_tenant_id, bob_id) =
+            (earlier.id.clone(), other_tenant.id.clone(), bob.id.clone());
+        let mut store = HashmapLoginAttemptStore::default();
+        store.add_login_attempt(earlier).await.unwrap();
+        store.add_login_attempt(other_tenant).await.unwrap();
+        store.add_login_attempt(bob).await.unwrap();
+        let now = LOGIN_ATTEMPT_TTL_SECONDS - 1;
+        store.add_login_attempt(login_attempt(DEFAULT_TENANT_ID, "alice@example.com", now)).await.unwrap();
+        assert!(store.get_login_attempt(&earlier_id).await.is_err());
+        assert!(store.get_login_attempt(&other_tenant_id).await.is_ok());
+        assert!(store.get_login_attempt(&bob_id).await.is_ok());
+        store.add_login_attempt(login_attempt("acme", "carol@example.com", LOGIN_ATTEMPT_TTL_SECONDS)).await.unwrap();
+        assert!(store.get_login_attempt(&bob_id).await.is_err());
+    }
+}
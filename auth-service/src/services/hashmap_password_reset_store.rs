@@ -0,0 +1,64 @@
+use crate::domain::PasswordReset;
+use crate::services::{PasswordResetStore, PasswordResetStoreError};
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub struct HashmapPasswordResetStore {
+    password_resets: HashMap<String, PasswordReset>,
+}
+
+#[async_trait::async_trait]
+impl PasswordResetStore for HashmapPasswordResetStore {
+    async fn add_password_reset(&mut self, password_reset: PasswordReset) -> Result<(), PasswordResetStoreError> {
+        self.password_resets.retain(|_, earlier| {
+            earlier.tenant_id != password_reset.tenant_id || earlier.email != password_reset.email
+        });
+        self.password_resets.insert(password_reset.token.clone(), password_reset);
+        Ok(())
+    }
+
+    async fn get_password_reset(&self, token: &str) -> Result<&PasswordReset, PasswordResetStoreError> {
+        self.password_resets.get(token).ok_or(PasswordResetStoreError::PasswordResetNotFound)
+    }
+
+    async fn take_password_reset(&mut self, token: &str) -> Result<PasswordReset, PasswordResetStoreError> {
+        self.password_resets
+            .remove(token)
+            .ok_or(PasswordResetStoreError::PasswordResetNotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Email, DEFAULT_TENANT_ID};
+
+    #[tokio::test]
+    async fn test_take_password_reset_only_once() {
+        let email = Email::parse("alice@example.com").unwrap();
+        let password_reset = PasswordReset::new(DEFAULT_TENANT_ID, &email, 0);
+        let token = password_reset.token.clone();
+        let mut store = HashmapPasswordResetStore::default();
+        store.add_password_reset(password_reset).await.unwrap();
+        assert_eq!(store.get_password_reset(&token).await.unwrap().email, email);
+        assert!(store.take_password_reset(&token).await.is_ok());
+        assert!(matches!(
+            store.take_password_reset(&token).await,
+            Err(PasswordResetStoreError::PasswordResetNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_new_password_reset_replaces_earlier_one() {
+        let email = Email::parse("alice@example.com").unwrap();
+        let earlier = PasswordReset::new(DEFAULT_TENANT_ID, &email, 0);
+        let other_tenant = PasswordReset::new("acme", &email, 0);
+        let (earlier_token, other_tenant_token) = (earlier.token.clone(), other_tenant.token.clone());
+        let mut store = HashmapPasswordResetStore::default();
+        store.add_password_reset(earlier).await.unwrap();
+        store.add_password_reset(other_tenant).await.unwrap();
+        store.add_password_reset(PasswordReset::new(DEFAULT_TENANT_ID, &email, 10)).await.unwrap();
+        assert!(store.get_password_reset(&earlier_token).await.is_err());
+        assert!(store.get_password_reset(&other_tenant_token).await.is_ok());
+    }
+}
@@ -0,0 +1,118 @@
+use crate::domain::Session;
+use crate::services::{SessionStore, SessionStoreError};
+use jsonwebtoken::get_current_timestamp;
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub struct HashmapSessionStore {
+    sessions: HashMap<String, Session>,
+}
+
+impl HashmapSessionStore {
+    fn add_session_at(&mut self, session: Session, now: u64) {
+        // Sessions outlive their tokens only until the tokens expire.
+        self.sessions.retain(|_, session| !session.is_expired(now));
+        self.sessions.insert(session.id.clone(), session);
+    }
+}
+
+#[async_trait::async_trait]
+impl SessionStore for HashmapSessionStore {
+    async fn add_session(&mut self, session: Session) -> Result<(), SessionStoreError> {
+        self.add_session_at(session, get_current_timestamp());
+        Ok(())
+    }
+
+    async fn get_session(&self, id: &str) -> Result<&Session, SessionStoreError> {
+        self.sessions
+            .get(id)
+            .ok_or(SessionStoreError::SessionNotFound(id.to_string()))
+    }
+
+    async fn touch_session(&mut self, id: &str, now: u64) -> Result<(), SessionStoreError> {
+        let session = self
+            .sessions
+            .get_mut(id)
+            .ok_or(SessionStoreError::SessionNotFound(id.to_string()))?;
+        session.last_seen_at = session.last_seen_at.max(now);
+        Ok(())
+    }
+
+    async fn get_user_sessions(&self, tenant_id: &str, email: &str) -> Result<Vec<Session>, SessionStoreError> {
+        let now = get_current_timestamp();
+        let mut sessions: Vec<Session> = self
+            .sessions
+            .values()
+            .filter(|session| session.tenant_id == tenant_id && session.email == email && !session.is_expired(now))
+            .cloned()
+            .collect();
+        sessions.sort_by_key(|session| session.created_at);
+        Ok(sessions)
+    }
+
+    async fn remove_session(&mut self, id: &str) -> Result<Session, SessionStoreError> {
+        self.sessions
+            .remove(id)
+            .ok_or(SessionStoreError::SessionNotFound(id.to_string()))
+    }
+
+    async fn remove_user_sessions(&mut self, tenant_id: &str, email: &str) -> Result<usize, SessionStoreError> {
+        let count = self.sessions.len();
+        self.sessions
+            .retain(|_, session| session.tenant_id != tenant_id || session.email != email);
+        Ok(count - self.sessions.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::DEFAULT_TENANT_ID;
+
+    fn session(tenant_id: &str, email: &str) -> Session {
+        Session::new(tenant_id, email, Some("curl/8.0"), None, get_current_timestamp(), 600)
+    }
+
+    #[tokio::test]
+    async fn test_add_and_touch_session() {
+        let session = session(DEFAULT_TENANT_ID, "alice@example.com");
+        let id = session.id.clone();
+        let mut store = HashmapSessionStore::default();
+        store.add_session(session).await.unwrap();
+        let last_seen_at = store.get_session(&id).await.unwrap().last_seen_at;
+        store.touch_session(&id, last_seen_at + 10).await.unwrap();
+        assert_eq!(store.get_session(&id).await.unwrap().last_seen_at, last_seen_at + 10);
+        assert!(store.touch_session("unknown", 0).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_remove_sessions() {
+        let mut store = HashmapSessionStore::default();
+        let alice_1 = session(DEFAULT_TENANT_ID, "alice@example.com");
+        let alice_1_id = alice_1.id.clone();
+        store.add_session(alice_1).await.unwrap();
+        store.add_session(session(DEFAULT_TENANT_ID, "alice@example.com")).await.unwrap();
+        store.add_session(session(DEFAULT_TENANT_ID, "bob@example.com")).await.unwrap();
+        store.add_session(session("acme", "alice@example.com")).await.unwrap();
+        assert_eq!(store.get_user_sessions(DEFAULT_TENANT_ID, "alice@example.com").await.unwrap().len(), 2);
+
+        store.remove_session(&alice_1_id).await.unwrap();
+        assert!(store.remove_session(&alice_1_id).await.is_err());
+        assert_eq!(store.get_user_sessions(DEFAULT_TENANT_ID, "alice@example.com").await.unwrap().len(), 1);
+        assert_eq!(store.remove_user_sessions(DEFAULT_TENANT_ID, "alice@example.com").await.unwrap(), 1);
+        assert!(store.get_user_sessions(DEFAULT_TENANT_ID, "alice@example.com").await.unwrap().is_empty());
+        assert_eq!(store.get_user_sessions(DEFAULT_TENANT_ID, "bob@example.com").await.unwrap().len(), 1);
+        assert_eq!(store.get_user_sessions("acme", "alice@example.com").await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_add_session_prunes_expired_sessions() {
+        let mut store = HashmapSessionStore::default();
+        let expired = Session::new(DEFAULT_TENANT_ID, "alice@example.com", None, None, 0, 600);
+        let expired_id = expired.id.clone();
+        store.add_session_at(expired, 0);
+        store.add_session_at(Session::new(DEFAULT_TENANT_ID, "alice@example.com", None, None, 600, 600), 600);
+        assert!(!store.sessions.contains_key(&expired_id));
+        assert_eq!(store.sessions.len(), 1);
+    }
+}
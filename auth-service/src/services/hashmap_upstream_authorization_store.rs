@@ -0,0 +1,65 @@
+use crate::domain::UpstreamAuthorization;
+use crate::services::{UpstreamAuthorizationStore, UpstreamAuthorizationStoreError};
+use jsonwebtoken::get_current_timestamp;
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub struct HashmapUpstreamAuthorizationStore {
+    authorizations: HashMap<String, UpstreamAuthorization>,
+}
+
+impl HashmapUpstreamAuthorizationStore {
+    fn add_authorization_at(&mut self, authorization: UpstreamAuthorization, now: u64) {
+        // Abandoned logins are dropped once they expire.
+        self.authorizations.retain(|_, authorization| !authorization.is_expired(now));
+        self.authorizations.insert(authorization.state.clone(), authorization);
+    }
+}
+
+#[async_trait::async_trait]
+impl UpstreamAuthorizationStore for HashmapUpstreamAuthorizationStore {
+    async fn add_authorization(
+        &mut self,
+        authorization: UpstreamAuthorization,
+    ) -> Result<(), UpstreamAuthorizationStoreError> {
+        self.add_authorization_at(authorization, get_current_timestamp());
+        Ok(())
+    }
+
+    async fn take_authorization(&mut self, state: &str) -> Result<UpstreamAuthorization, UpstreamAuthorizationStoreError> {
+        self.authorizations
+            .remove(state)
+            .ok_or(UpstreamAuthorizationStoreError::AuthorizationNotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{DEFAULT_TENANT_ID, UPSTREAM_AUTHORIZATION_TTL_SECONDS};
+
+    #[tokio::test]
+    async fn test_take_authorization_only_once() {
+        let authorization = UpstreamAuthorization::new(DEFAULT_TENANT_ID, "google", get_current_timestamp());
+        let state = authorization.state.clone();
+        let mut store = HashmapUpstreamAuthorizationStore::default();
+        store.add_authorization(authorization).await.unwrap();
+        assert!(store.take_authorization(&state).await.is_ok());
+        assert!(matches!(
+            store.take_authorization(&state).await,
+            Err(UpstreamAuthorizationStoreError::AuthorizationNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_add_authorization_prunes_expired_authorizations() {
+        let expired = UpstreamAuthorization::new(DEFAULT_TENANT_ID, "google", 0);
+        let active = UpstreamAuthorization::new(DEFAULT_TENANT_ID, "google", UPSTREAM_AUTHORIZATION_TTL_SECONDS);
+        let (expired_state, active_state) = (expired.state.clone(), active.state.clone());
+        let mut store = HashmapUpstreamAuthorizationStore::default();
+        store.add_authorization_at(expired, 0);
+        store.add_authorization_at(active, UPSTREAM_AUTHORIZATION_TTL_SECONDS);
+        assert!(!store.authorizations.contains_key(&expired_state));
+        assert!(store.authorizations.contains_key(&active_state));
+    }
+}
@@ -1,43 +1,176 @@
-use crate::domain::User;
-use crate::services::{UserStore, UserStoreError};
+use crate::domain::{Email, ExternalIdentity, KnownDevice, Password, Role, User};
+use crate::services::{UserPage, UserStore, UserStoreError};
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 
 #[derive(Debug, Default)]
 pub struct HashmapUserStore {
-    users: HashMap<String, User>,
+    /// Users by tenant, then by email in canonical form.
+    users: HashMap<String, HashMap<Email, User>>,
+}
+
+impl HashmapUserStore {
+    fn get_user_mut(&mut self, tenant_id: &str, email: &Email) -> Result<&mut User, UserStoreError> {
+        self.users
+            .get_mut(tenant_id)
+            .and_then(|users| users.get_mut(email))
+            .ok_or(UserStoreError::UserNotFound(email.to_string()))
+    }
 }
 
 #[async_trait::async_trait]
 impl UserStore for HashmapUserStore {
     async fn add_user(&mut self, user: User) -> Result<(), UserStoreError> {
-        let email = user.email.to_string();
-        if self.users.contains_key(&email) {
-            Err(UserStoreError::UserAlreadyExists(email))
-        } else {
-            self.users.insert(email, user);
-            Ok(())
+        let email = user.email.clone();
+        match self.users.entry(user.tenant_id.clone()).or_default().entry(email) {
+            Entry::Occupied(entry) => Err(UserStoreError::UserAlreadyExists(entry.key().to_string())),
+            Entry::Vacant(entry) => {
+                entry.insert(user);
+                Ok(())
+            }
         }
     }
 
-    async fn get_user(&self, email: &str) -> Result<&User, UserStoreError> {
+    async fn get_user(&self, tenant_id: &str, email: &Email) -> Result<&User, UserStoreError> {
+        self.users
+            .get(tenant_id)
+            .and_then(|users| users.get(email))
+            .ok_or(UserStoreError::UserNotFound(email.to_string()))
+    }
+
+    async fn list_users(
+        &self,
+        tenant_id: &str,
+        query: Option<&str>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<UserPage, UserStoreError> {
+        let query = query.map(str::to_lowercase).unwrap_or_default();
+        let mut users: Vec<&User> = self
+            .users
+            .get(tenant_id)
+            .into_iter()
+            .flat_map(|users| users.values())
+            .filter(|user| user.email.as_str().to_lowercase().contains(&query))
+            .collect();
+        users.sort_by(|a, b| a.email.as_str().cmp(b.email.as_str()));
+        Ok(UserPage {
+            total: users.len(),
+            users: users.into_iter().skip(offset).take(limit).cloned().collect(),
+        })
+    }
+
+    async fn delete_user(&mut self, tenant_id: &str, email: &Email) -> Result<User, UserStoreError> {
         self.users
-            .get(email)
+            .get_mut(tenant_id)
+            .and_then(|users| users.remove(email))
             .ok_or(UserStoreError::UserNotFound(email.to_string()))
     }
 
-    async fn validate_user(&self, email: &str, password: &str) -> Result<(), UserStoreError> {
-        let user = self.get_user(email).await?;
-        if user.password.expose() == password {
-            Ok(())
-        } else {
-            Err(UserStoreError::InvalidCredentials(email.to_string()))
+    async fn validate_user(&self, tenant_id: &str, email: &Email, password: &str) -> Result<(), UserStoreError> {
+        let user = self.get_user(tenant_id, email).await?;
+        // A locked account does not tell whether the password was right.
+        if user.is_locked() {
+            return Err(UserStoreError::AccountLocked(email.to_string()));
+        }
+        if !user.password.matches(password) {
+            return Err(UserStoreError::InvalidCredentials(email.to_string()));
+        }
+        if user.disabled {
+            return Err(UserStoreError::AccountDisabled(email.to_string()));
+        }
+        if user.password_reset_required {
+            return Err(UserStoreError::PasswordResetRequired(email.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn record_failed_login(&mut self, tenant_id: &str, email: &Email) -> Result<u32, UserStoreError> {
+        let user = self.get_user_mut(tenant_id, email)?;
+        user.failed_login_attempts = user.failed_login_attempts.saturating_add(1);
+        Ok(user.failed_login_attempts)
+    }
+
+    async fn reset_failed_logins(&mut self, tenant_id: &str, email: &Email) -> Result<(), UserStoreError> {
+        self.get_user_mut(tenant_id, email)?.failed_login_attempts = 0;
+        Ok(())
+    }
+
+    async fn set_disabled(&mut self, tenant_id: &str, email: &Email, disabled: bool) -> Result<(), UserStoreError> {
+        self.get_user_mut(tenant_id, email)?.disabled = disabled;
+        Ok(())
+    }
+
+    async fn set_password_reset_required(
+        &mut self,
+        tenant_id: &str,
+        email: &Email,
+        required: bool,
+    ) -> Result<(), UserStoreError> {
+        self.get_user_mut(tenant_id, email)?.password_reset_required = required;
+        Ok(())
+    }
+
+    async fn set_password(
+        &mut self,
+        tenant_id: &str,
+        email: &Email,
+        password: Password,
+        history_depth: usize,
+        now: u64,
+    ) -> Result<(), UserStoreError> {
+        self.get_user_mut(tenant_id, email)?.set_password(password, history_depth, now);
+        Ok(())
+    }
+
+    async fn set_requires_2fa(&mut self, tenant_id: &str, email: &Email, requires_2fa: bool) -> Result<(), UserStoreError> {
+        self.get_user_mut(tenant_id, email)?.requires_2fa = requires_2fa;
+        Ok(())
+    }
+
+    async fn get_user_by_identity(&self, tenant_id: &str, identity: &ExternalIdentity) -> Result<&User, UserStoreError> {
+        self.users
+            .get(tenant_id)
+            .and_then(|users| users.values().find(|user| user.has_identity(identity)))
+            .ok_or(UserStoreError::UserNotFound(format!("{}:{}", identity.provider, identity.subject)))
+    }
+
+    async fn link_identity(
+        &mut self,
+        tenant_id: &str,
+        email: &Email,
+        identity: ExternalIdentity,
+    ) -> Result<(), UserStoreError> {
+        if let Ok(user) = self.get_user_by_identity(tenant_id, &identity).await {
+            return if user.email == *email {
+                Ok(())
+            } else {
+                Err(UserStoreError::IdentityAlreadyLinked(format!("{}:{}", identity.provider, identity.subject)))
+            };
         }
+        self.get_user_mut(tenant_id, email)?.identities.push(identity);
+        Ok(())
+    }
+
+    async fn set_roles(&mut self, tenant_id: &str, email: &Email, roles: &[Role]) -> Result<(), UserStoreError> {
+        self.get_user_mut(tenant_id, email)?.set_roles(roles);
+        Ok(())
+    }
+
+    async fn remember_device(&mut self, tenant_id: &str, email: &Email, device: KnownDevice) -> Result<(), UserStoreError> {
+        self.get_user_mut(tenant_id, email)?.remember_device(device);
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::{Tenant, TenantSettings, DEFAULT_TENANT_ID, MAX_FAILED_LOGIN_ATTEMPTS};
+
+    fn email(email: &str) -> Email {
+        Email::parse(email).unwrap()
+    }
 
     #[tokio::test]
     async fn test_add_user() {
@@ -59,8 +192,8 @@ mod tests {
             false).unwrap();
         let mut store = HashmapUserStore::default();
         store.add_user(user).await.unwrap();
-        assert!(store.get_user("alice@example.com").await.is_ok());
-        assert!(store.get_user("bob@example.com").await.is_err());
+        assert!(store.get_user(DEFAULT_TENANT_ID, &email("alice@example.com")).await.is_ok());
+        assert!(store.get_user(DEFAULT_TENANT_ID, &email("bob@example.com")).await.is_err());
     }
 
     #[tokio::test]
@@ -71,7 +204,169 @@ mod tests {
             false).unwrap();
         let mut store = HashmapUserStore::default();
         store.add_user(user).await.unwrap();
-        assert!(store.validate_user("alice@example.com", "StrongPassword123!").await.is_ok());
-        assert!(store.validate_user("alice@example.com", "StrongPassword456!").await.is_err());
+        assert!(store.validate_user(DEFAULT_TENANT_ID, &email("alice@example.com"), "StrongPassword123!").await.is_ok());
+        assert!(store.validate_user(DEFAULT_TENANT_ID, &email("alice@example.com"), "StrongPassword456!").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_link_identity() {
+        let alice = User::try_new("alice@example.com", "StrongPassword123!", false).unwrap();
+        let bob = User::try_new("bob@example.com", "StrongPassword123!", false).unwrap();
+        let google = ExternalIdentity::new("google", "1234");
+        let github = ExternalIdentity::new("github", "1234");
+        let mut store = HashmapUserStore::default();
+        store.add_user(alice).await.unwrap();
+        store.add_user(bob).await.unwrap();
+        assert!(store.get_user_by_identity(DEFAULT_TENANT_ID, &google).await.is_err());
+
+        store.link_identity(DEFAULT_TENANT_ID, &email("alice@example.com"), google.clone()).await.unwrap();
+        store.link_identity(DEFAULT_TENANT_ID, &email("alice@example.com"), google.clone()).await.unwrap();
+        store.link_identity(DEFAULT_TENANT_ID, &email("alice@example.com"), github.clone()).await.unwrap();
+        let user = store.get_user_by_identity(DEFAULT_TENANT_ID, &github).await.unwrap();
+        assert_eq!(user.email.as_str(), "alice@example.com");
+        assert_eq!(user.identities, vec![google.clone(), github]);
+
+        assert!(matches!(
+            store.link_identity(DEFAULT_TENANT_ID, &email("bob@example.com"), google).await,
+            Err(UserStoreError::IdentityAlreadyLinked(_))
+        ));
+        assert!(matches!(
+            store.link_identity(DEFAULT_TENANT_ID, &email("carol@example.com"), ExternalIdentity::new("google", "5678")).await,
+            Err(UserStoreError::UserNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_set_roles() {
+        let alice = User::try_new("alice@example.com", "StrongPassword123!", false).unwrap();
+        let mut store = HashmapUserStore::default();
+        store.add_user(alice).await.unwrap();
+        store.set_roles(DEFAULT_TENANT_ID, &email("alice@example.com"), &[Role::Admin]).await.unwrap();
+        let user = store.get_user(DEFAULT_TENANT_ID, &email("alice@example.com")).await.unwrap();
+        assert_eq!(user.roles, vec![Role::Admin]);
+        assert!(matches!(
+            store.set_roles(DEFAULT_TENANT_ID, &email("bob@example.com"), &[Role::Admin]).await,
+            Err(UserStoreError::UserNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_list_users() {
+        let mut store = HashmapUserStore::default();
+        for email in ["carol@example.com", "alice@example.com", "bob@test.com"] {
+            store.add_user(User::try_new(email, "StrongPassword123!", false).unwrap()).await.unwrap();
+        }
+        let page = store.list_users(DEFAULT_TENANT_ID, None, 0, 2).await.unwrap();
+        assert_eq!(page.total, 3);
+        let emails: Vec<&str> = page.users.iter().map(|user| user.email.as_str()).collect();
+        assert_eq!(emails, vec!["alice@example.com", "bob@test.com"]);
+        let page = store.list_users(DEFAULT_TENANT_ID, None, 2, 2).await.unwrap();
+        assert_eq!(page.users.len(), 1);
+        let page = store.list_users(DEFAULT_TENANT_ID, Some("EXAMPLE"), 0, 10).await.unwrap();
+        assert_eq!(page.total, 2);
+        let page = store.list_users(DEFAULT_TENANT_ID, Some("dave"), 0, 10).await.unwrap();
+        assert_eq!(page.total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_validate_user_account_status() {
+        let alice = &email("alice@example.com");
+        let mut store = HashmapUserStore::default();
+        store.add_user(User::try_new(alice.as_str(), "StrongPassword123!", false).unwrap()).await.unwrap();
+
+        store.set_disabled(DEFAULT_TENANT_ID, alice, true).await.unwrap();
+        assert!(matches!(store.validate_user(DEFAULT_TENANT_ID, alice, "wrong").await, Err(UserStoreError::InvalidCredentials(_))));
+        assert!(matches!(
+            store.validate_user(DEFAULT_TENANT_ID, alice, "StrongPassword123!").await,
+            Err(UserStoreError::AccountDisabled(_))
+        ));
+        store.set_disabled(DEFAULT_TENANT_ID, alice, false).await.unwrap();
+
+        store.set_password_reset_required(DEFAULT_TENANT_ID, alice, true).await.unwrap();
+        assert!(matches!(
+            store.validate_user(DEFAULT_TENANT_ID, alice, "StrongPassword123!").await,
+            Err(UserStoreError::PasswordResetRequired(_))
+        ));
+        store.set_password_reset_required(DEFAULT_TENANT_ID, alice, false).await.unwrap();
+
+        for attempt in 1..=MAX_FAILED_LOGIN_ATTEMPTS {
+            assert_eq!(store.record_failed_login(DEFAULT_TENANT_ID, alice).await.unwrap(), attempt);
+        }
+        assert!(matches!(
+            store.validate_user(DEFAULT_TENANT_ID, alice, "StrongPassword123!").await,
+            Err(UserStoreError::AccountLocked(_))
+        ));
+        store.reset_failed_logins(DEFAULT_TENANT_ID, alice).await.unwrap();
+        assert!(store.validate_user(DEFAULT_TENANT_ID, alice, "StrongPassword123!").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_set_password() {
+        let alice = &email("alice@example.com");
+        let mut store = HashmapUserStore::default();
+        store.add_user(User::try_new(alice.as_str(), "StrongPassword123!", false).unwrap()).await.unwrap();
+        store.set_password_reset_required(DEFAULT_TENANT_ID, alice, true).await.unwrap();
+        let password = Password::parse("CorrectHorseBattery9!", alice.as_str()).unwrap();
+        store.set_password(DEFAULT_TENANT_ID, alice, password, 5, 100).await.unwrap();
+        assert!(store.validate_user(DEFAULT_TENANT_ID, alice, "CorrectHorseBattery9!").await.is_ok());
+        assert!(store.validate_user(DEFAULT_TENANT_ID, alice, "StrongPassword123!").await.is_err());
+        let user = store.get_user(DEFAULT_TENANT_ID, alice).await.unwrap();
+        assert_eq!((user.password_changed_at, user.password_history.len()), (100, 2));
+        let password = Password::parse("CorrectHorseBattery9!", alice.as_str()).unwrap();
+        assert!(matches!(
+            store.set_password(DEFAULT_TENANT_ID, &email("bob@example.com"), password, 5, 100).await,
+            Err(UserStoreError::UserNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_delete_user() {
+        let mut store = HashmapUserStore::default();
+        store.add_user(User::try_new("alice@example.com", "StrongPassword123!", false).unwrap()).await.unwrap();
+        assert!(store.delete_user(DEFAULT_TENANT_ID, &email("alice@example.com")).await.is_ok());
+        assert!(matches!(store.get_user(DEFAULT_TENANT_ID, &email("alice@example.com")).await, Err(UserStoreError::UserNotFound(_))));
+        assert!(matches!(store.delete_user(DEFAULT_TENANT_ID, &email("alice@example.com")).await, Err(UserStoreError::UserNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_users_are_scoped_by_tenant() {
+        let acme = Tenant::try_new("acme", "ACME", &[], TenantSettings::default()).unwrap();
+        let mut store = HashmapUserStore::default();
+        store.add_user(User::try_new("alice@example.com", "StrongPassword123!", false).unwrap()).await.unwrap();
+        let user = User::try_new_for_tenant(&acme, "alice@example.com", "CorrectHorseBattery9!", false).unwrap();
+        store.add_user(user.clone()).await.unwrap();
+        assert!(matches!(store.add_user(user).await, Err(UserStoreError::UserAlreadyExists(_))));
+
+        assert!(store.validate_user("acme", &email("alice@example.com"), "CorrectHorseBattery9!").await.is_ok());
+        assert!(store.validate_user("acme", &email("alice@example.com"), "StrongPassword123!").await.is_err());
+        store.set_disabled("acme", &email("alice@example.com"), true).await.unwrap();
+        assert!(!store.get_user(DEFAULT_TENANT_ID, &email("alice@example.com")).await.unwrap().disabled);
+        assert_eq!(store.list_users("acme", None, 0, 10).await.unwrap().total, 1);
+        assert!(store.get_user("globex", &email("alice@example.com")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_email_spellings_collide() {
+        let mut store = HashmapUserStore::default();
+        store.add_user(User::try_new("Alice@Example.com", "StrongPassword123!", false).unwrap()).await.unwrap();
+        let result = store.add_user(User::try_new("alice@EXAMPLE.COM", "CorrectHorseBattery9!", false).unwrap()).await;
+        assert!(matches!(result, Err(UserStoreError::UserAlreadyExists(_))));
+        let result = store.validate_user(DEFAULT_TENANT_ID, &email("ALICE@example.com"), "StrongPassword123!").await;
+        assert!(result.is_ok());
+        assert_eq!(store.list_users(DEFAULT_TENANT_ID, None, 0, 10).await.unwrap().total, 1);
+
+        let settings = TenantSettings {
+            case_sensitive_emails: true,
+            ..TenantSettings::default()
+        };
+        let acme = Tenant::try_new("acme", "ACME", &[], settings).unwrap();
+        for spelling in ["Alice@Example.com", "alice@EXAMPLE.COM"] {
+            let user = User::try_new_for_tenant(&acme, spelling, "StrongPassword123!", false).unwrap();
+            store.add_user(user).await.unwrap();
+        }
+        let user = User::try_new_for_tenant(&acme, "Alice@example.com", "StrongPassword123!", false).unwrap();
+        let result = store.add_user(user).await;
+        assert!(matches!(result, Err(UserStoreError::UserAlreadyExists(_))));
+        assert_eq!(store.list_users("acme", None, 0, 10).await.unwrap().total, 2);
     }
 }
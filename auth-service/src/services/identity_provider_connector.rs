@@ -0,0 +1,254 @@
+use crate::domain::{ExternalIdentity, IdentityProvider};
+use jsonwebtoken::jwk::{AlgorithmParameters, JwkSet};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use reqwest::header::{ACCEPT, USER_AGENT};
+use reqwest::redirect::Policy;
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+const HTTP_TIMEOUT_SECONDS: u64 = 10;
+
+/// Asymmetric algorithms accepted for upstream ID tokens. `none` and HMAC are never
+/// accepted, as they would let anyone holding the client secret forge a token.
+const ID_TOKEN_ALGORITHMS: [Algorithm; 9] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+#[derive(Error, Debug)]
+pub enum ConnectorError {
+    #[error("Token request failed: {0}")]
+    TokenRequest(String),
+    #[error("Invalid ID token: {0}")]
+    InvalidIdToken(String),
+    #[error("Userinfo request failed: {0}")]
+    UserInfo(String),
+    #[error("Provider did not return the {0} claim")]
+    MissingSubject(String),
+    #[error("Could not fetch provider keys: {0}")]
+    Jwks(String),
+}
+
+/// What an upstream identity provider asserted about the user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalProfile {
+    pub identity: ExternalIdentity,
+    pub email: Option<String>,
+    pub email_verified: bool,
+}
+
+#[derive(Deserialize)]
+struct ProviderTokenResponse {
+    access_token: String,
+    id_token: Option<String>,
+}
+
+/// Completes logins at upstream identity providers: redeems the authorization code,
+/// validates the ID token against the provider's JWKS and fetches the userinfo.
+#[derive(Debug)]
+pub struct IdentityProviderConnector {
+    http_client: reqwest::Client,
+    /// Provider keys by provider name, refreshed when a token names an unknown key.
+    jwks: RwLock<HashMap<String, JwkSet>>,
+}
+
+impl Default for IdentityProviderConnector {
+    fn default() -> Self {
+        let http_client = reqwest::Client::builder()
+            .redirect(Policy::none())
+            .timeout(Duration::from_secs(HTTP_TIMEOUT_SECONDS))
+            .build()
+            .expect("Failed to build HTTP client");
+        Self {
+            http_client,
+            jwks: RwLock::default(),
+        }
+    }
+}
+
+impl IdentityProviderConnector {
+    pub async fn authenticate(
+        &self,
+        provider: &IdentityProvider,
+        code: &str,
+        code_verifier: &str,
+        nonce: &str,
+        redirect_uri: &str,
+    ) -> Result<ExternalProfile, ConnectorError> {
+        let tokens = self.redeem_code(provider, code, code_verifier, redirect_uri).await?;
+
+        let mut claims = Map::new();
+        if provider.jwks_uri.is_some() {
+            let id_token = tokens
+                .id_token
+                .as_deref()
+                .ok_or_else(|| ConnectorError::InvalidIdToken("Missing id_token".to_string()))?;
+            claims = self.validate_id_token(provider, id_token, nonce).await?;
+        }
+        if provider.userinfo_endpoint.is_some() {
+            let userinfo = self.fetch_userinfo(provider, &tokens.access_token).await?;
+            // The userinfo must describe the user the ID token was issued for
+            // (OpenID Connect Core 1.0, section 5.3.2).
+            if let (Some(sub), Some(userinfo_sub)) = (claims.get("sub"), userinfo.get("sub"))
+                && sub != userinfo_sub
+            {
+                return Err(ConnectorError::UserInfo("Subject does not match the ID token".to_string()));
+            }
+            claims.extend(userinfo);
+        }
+
+        let subject = match claims.get(&provider.subject_claim) {
+            Some(Value::String(subject)) if !subject.is_empty() => subject.clone(),
+            Some(Value::Number(subject)) => subject.to_string(),
+            _ => return Err(ConnectorError::MissingSubject(provider.subject_claim.clone())),
+        };
+        let email = claims.get("email").and_then(Value::as_str).map(str::to_string);
+        let email_verified = provider.trust_email
+            || matches!(claims.get("email_verified"), Some(Value::Bool(true)))
+            || matches!(claims.get("email_verified"), Some(Value::String(value)) if value == "true");
+        Ok(ExternalProfile {
+            identity: ExternalIdentity::new(&provider.name, &subject),
+            email,
+            email_verified,
+        })
+    }
+
+    async fn redeem_code(
+        &self,
+        provider: &IdentityProvider,
+        code: &str,
+        code_verifier: &str,
+        redirect_uri: &str,
+    ) -> Result<ProviderTokenResponse, ConnectorError> {
+        let form = [
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+            ("client_id", &provider.client_id),
+            ("client_secret", provider.client_secret.expose_secret()),
+            ("code_verifier", code_verifier),
+        ];
+        let response = self
+            .http_client
+            .post(provider.token_endpoint.clone())
+            .header(ACCEPT, "application/json")
+            .form(&form)
+            .send()
+            .await
+            .map_err(|error| ConnectorError::TokenRequest(error.to_string()))?;
+        if !response.status().is_success() {
+            return Err(ConnectorError::TokenRequest(format!("Provider returned {}", response.status())));
+        }
+        response
+            .json::<ProviderTokenResponse>()
+            .await
+            .map_err(|error| ConnectorError::TokenRequest(error.to_string()))
+    }
+
+    async fn validate_id_token(
+        &self,
+        provider: &IdentityProvider,
+        id_token: &str,
+        nonce: &str,
+    ) -> Result<Map<String, Value>, ConnectorError> {
+        let header = decode_header(id_token).map_err(|error| ConnectorError::InvalidIdToken(error.to_string()))?;
+        if !ID_TOKEN_ALGORITHMS.contains(&header.alg) {
+            return Err(ConnectorError::InvalidIdToken(format!("Unsupported algorithm {:?}", header.alg)));
+        }
+        let kid = header
+            .kid
+            .ok_or_else(|| ConnectorError::InvalidIdToken("Missing kid".to_string()))?;
+        let key = self.decoding_key(provider, &kid).await?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[provider.issuer.as_deref().unwrap_or_default()]);
+        validation.set_audience(&[&provider.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        let claims = decode::<Map<String, Value>>(id_token, &key, &validation)
+            .map_err(|error| ConnectorError::InvalidIdToken(error.to_string()))?
+            .claims;
+        if claims.get("nonce").and_then(Value::as_str) != Some(nonce) {
+            return Err(ConnectorError::InvalidIdToken("Nonce does not match".to_string()));
+        }
+        Ok(claims)
+    }
+
+    async fn decoding_key(&self, provider: &IdentityProvider, kid: &str) -> Result<DecodingKey, ConnectorError> {
+        let cached = {
+            let jwks = self.jwks.read().await;
+            jwks.get(&provider.name).and_then(|jwks| jwks.find(kid)).cloned()
+        };
+        let jwk = match cached {
+            Some(jwk) => jwk,
+            None => {
+                // Providers rotate their keys, so an unknown key id triggers a refresh.
+                let jwks = self.fetch_jwks(provider).await?;
+                let jwk = jwks.find(kid).cloned();
+                self.jwks.write().await.insert(provider.name.clone(), jwks);
+                jwk.ok_or_else(|| ConnectorError::InvalidIdToken(format!("Unknown key {}", kid)))?
+            }
+        };
+        if matches!(jwk.algorithm, AlgorithmParameters::OctetKey(_)) {
+            return Err(ConnectorError::InvalidIdToken("Symmetric keys are not accepted".to_string()));
+        }
+        DecodingKey::from_jwk(&jwk).map_err(|error| ConnectorError::InvalidIdToken(error.to_string()))
+    }
+
+    async fn fetch_jwks(&self, provider: &IdentityProvider) -> Result<JwkSet, ConnectorError> {
+        let Some(jwks_uri) = &provider.jwks_uri else {
+            return Err(ConnectorError::Jwks("Provider has no jwks_uri".to_string()));
+        };
+        let response = self
+            .http_client
+            .get(jwks_uri.clone())
+            .send()
+            .await
+            .map_err(|error| ConnectorError::Jwks(error.to_string()))?;
+        if !response.status().is_success() {
+            return Err(ConnectorError::Jwks(format!("Provider returned {}", response.status())));
+        }
+        response
+            .json::<JwkSet>()
+            .await
+            .map_err(|error| ConnectorError::Jwks(error.to_string()))
+    }
+
+    async fn fetch_userinfo(
+        &self,
+        provider: &IdentityProvider,
+        access_token: &str,
+    ) -> Result<Map<String, Value>, ConnectorError> {
+        let Some(userinfo_endpoint) = &provider.userinfo_endpoint else {
+            return Ok(Map::new());
+        };
+        let response = self
+            .http_client
+            .get(userinfo_endpoint.clone())
+            .bearer_auth(access_token)
+            .header(ACCEPT, "application/json")
+            // Some providers, such as GitHub, reject requests without a user agent.
+            .header(USER_AGENT, env!("CARGO_PKG_NAME"))
+            .send()
+            .await
+            .map_err(|error| ConnectorError::UserInfo(error.to_string()))?;
+        if !response.status().is_success() {
+            return Err(ConnectorError::UserInfo(format!("Provider returned {}", response.status())));
+        }
+        response
+            .json::<Map<String, Value>>()
+            .await
+            .map_err(|error| ConnectorError::UserInfo(error.to_string()))
+    }
+}
@@ -0,0 +1,64 @@
+use crate::domain::{AuditEntry, AuditEvent, AuditRecord};
+use crate::services::{AuditLogError, AuditSink};
+use jsonwebtoken::jwk::Jwk;
+
+#[derive(Debug, Default)]
+pub struct InMemoryAuditSink {
+    records: Vec<AuditRecord>,
+}
+
+#[async_trait::async_trait]
+impl AuditSink for InMemoryAuditSink {
+    async fn append(&mut self, record: &AuditRecord) -> Result<(), AuditLogError> {
+        self.records.push(record.clone());
+        Ok(())
+    }
+
+    fn last_record(&self) -> Option<&AuditRecord> {
+        self.records.last()
+    }
+
+    /// Records kept in memory go away with the key, so there is nothing to keep.
+    async fn publish_key(&mut self, _jwk: &Jwk) -> Result<(), AuditLogError> {
+        Ok(())
+    }
+
+    async fn user_events(&self, tenant_id: &str, email: &str) -> Result<Vec<AuditEvent>, AuditLogError> {
+        Ok(user_events(&self.records, tenant_id, email))
+    }
+}
+
+/// Events among the records concerning the user of the tenant.
+pub(crate) fn user_events<'a>(
+    records: impl IntoIterator<Item = &'a AuditRecord>,
+    tenant_id: &str,
+    email: &str,
+) -> Vec<AuditEvent> {
+    records
+        .into_iter()
+        .filter_map(|record| match &record.entry {
+            AuditEntry::Event(event) if event.concerns(tenant_id, email) => Some(event.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{AuditContext, AuditEventKind, DEFAULT_TENANT_ID};
+
+    #[tokio::test]
+    async fn test_user_events() {
+        let mut sink = InMemoryAuditSink::default();
+        for email in ["alice@example.com", "bob@example.com", "alice@example.com"] {
+            let event =
+                AuditEvent::new(DEFAULT_TENANT_ID, email, AuditEventKind::Signup, &AuditContext::default(), 100);
+            let record = AuditRecord::chain(sink.last_record(), AuditEntry::Event(event)).unwrap();
+            sink.append(&record).await.unwrap();
+        }
+        assert_eq!(sink.last_record().unwrap().sequence, 2);
+        assert_eq!(sink.user_events(DEFAULT_TENANT_ID, "alice@example.com").await.unwrap().len(), 2);
+        assert!(sink.user_events("acme", "alice@example.com").await.unwrap().is_empty());
+    }
+}
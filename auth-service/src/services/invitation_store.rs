@@ -0,0 +1,18 @@
+use crate::domain::Invitation;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum InvitationStoreError {
+    #[error("Invitation was not found")]
+    InvitationNotFound,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+#[async_trait::async_trait]
+pub trait InvitationStore {
+    async fn add_invitation(&mut self, invitation: Invitation) -> Result<(), InvitationStoreError>;
+    async fn get_invitation(&self, token: &str) -> Result<&Invitation, InvitationStoreError>;
+    /// Removes the invitation from the store, so that it can only be used once.
+    async fn take_invitation(&mut self, token: &str) -> Result<Invitation, InvitationStoreError>;
+}
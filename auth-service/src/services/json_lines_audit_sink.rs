@@ -0,0 +1,124 @@
+use crate::domain::{AuditEvent, AuditRecord};
+use crate::services::{user_events, AuditLogError, AuditSink};
+use anyhow::Context;
+use jsonwebtoken::jwk::Jwk;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+
+/// File the public keys of an audit log's checkpoints are appended to, one JWK per line:
+/// the log file name with a `.keys` suffix.
+pub fn audit_keys_file(log_file: &Path) -> PathBuf {
+    let mut file_name = OsString::from(log_file.as_os_str());
+    file_name.push(".keys");
+    PathBuf::from(file_name)
+}
+
+/// Appends records to a file, one JSON object per line, and the public keys of its
+/// checkpoints to the [`audit_keys_file`] next to it.
+#[derive(Debug)]
+pub struct JsonLinesAuditSink {
+    path: PathBuf,
+    file: File,
+    last_record: Option<AuditRecord>,
+}
+
+impl JsonLinesAuditSink {
+    /// Opens the file for appending, continuing the hash chain of the records it holds.
+    pub async fn open(path: &Path) -> Result<Self, AuditLogError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .with_context(|| format!("Failed to open audit log {}", path.display()))?;
+        let mut sink = Self {
+            path: path.to_path_buf(),
+            file,
+            last_record: None,
+        };
+        sink.last_record = sink.records().await?.pop();
+        Ok(sink)
+    }
+
+    async fn records(&self) -> Result<Vec<AuditRecord>, AuditLogError> {
+        let contents = tokio::fs::read_to_string(&self.path)
+            .await
+            .context("Failed to read audit log")?;
+        let mut records = Vec::new();
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            records.push(serde_json::from_str(line).context("Failed to parse audit record")?);
+        }
+        Ok(records)
+    }
+}
+
+#[async_trait::async_trait]
+impl AuditSink for JsonLinesAuditSink {
+    async fn append(&mut self, record: &AuditRecord) -> Result<(), AuditLogError> {
+        let mut line = serde_json::to_vec(record).context("Failed to serialize audit record")?;
+        line.push(b'\n');
+        self.file.write_all(&line).await.context("Failed to write audit record")?;
+        self.file.flush().await.context("Failed to write audit record")?;
+        self.last_record = Some(record.clone());
+        Ok(())
+    }
+
+    fn last_record(&self) -> Option<&AuditRecord> {
+        self.last_record.as_ref()
+    }
+
+    async fn publish_key(&mut self, jwk: &Jwk) -> Result<(), AuditLogError> {
+        let path = audit_keys_file(&self.path);
+        let mut line = serde_json::to_vec(jwk).context("Failed to serialize audit key")?;
+        line.push(b'\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+            .with_context(|| format!("Failed to open audit keys file {}", path.display()))?;
+        file.write_all(&line).await.context("Failed to write audit key")?;
+        file.flush().await.context("Failed to write audit key")?;
+        Ok(())
+    }
+
+    async fn user_events(&self, tenant_id: &str, email: &str) -> Result<Vec<AuditEvent>, AuditLogError> {
+        Ok(user_events(&self.records().await?, tenant_id, email))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{verify_audit_chain, AuditContext, AuditEntry, AuditEventKind, DEFAULT_TENANT_ID};
+    use uuid::Uuid;
+
+    async fn append(sink: &mut JsonLinesAuditSink, event: &AuditEvent) {
+        let record = AuditRecord::chain(sink.last_record(), AuditEntry::Event(event.clone())).unwrap();
+        sink.append(&record).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_append_and_read_back() {
+        let path = std::env::temp_dir().join(format!("audit-{}.jsonl", Uuid::new_v4()));
+        let mut sink = JsonLinesAuditSink::open(&path).await.unwrap();
+        let context = AuditContext::default();
+        let event = AuditEvent::new(DEFAULT_TENANT_ID, "alice@example.com", AuditEventKind::Signup, &context, 100);
+        append(&mut sink, &event).await;
+        let other = AuditEvent::new(DEFAULT_TENANT_ID, "bob@example.com", AuditEventKind::Logout, &context, 101);
+        append(&mut sink, &other).await;
+
+        // Reopening appends to the existing file, continuing its chain.
+        let mut sink = JsonLinesAuditSink::open(&path).await.unwrap();
+        assert_eq!(sink.last_record().unwrap().sequence, 1);
+        append(&mut sink, &event).await;
+        let contents = tokio::fs::read_to_string(&path).await.unwrap();
+        assert_eq!(contents.lines().count(), 3);
+        assert_eq!(verify_audit_chain(&contents, None).broken_link, None);
+        let events = sink.user_events(DEFAULT_TENANT_ID, "alice@example.com").await.unwrap();
+        assert_eq!(events, vec![event.clone(), event]);
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+}
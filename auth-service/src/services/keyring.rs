@@ -0,0 +1,383 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ed25519_dalek::pkcs8::EncodePrivateKey;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse,
+};
+use jsonwebtoken::{get_current_timestamp, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+use thiserror::Error;
+use tracing::info;
+use uuid::Uuid;
+
+pub const TOKEN_TTL_SECONDS: u64 = 600;
+
+/// `typ` header of access tokens (RFC 9068), which sets them apart from ID tokens
+/// signed by the same keys.
+pub const ACCESS_TOKEN_TYPE: &str = "at+jwt";
+pub const ID_TOKEN_TYPE: &str = "JWT";
+
+#[derive(Error, Debug)]
+pub enum KeyringError {
+    #[error("Token is malformed: {0}")]
+    MalformedToken(String),
+    #[error("Token has expired")]
+    ExpiredToken,
+    #[error("Token signature is invalid")]
+    InvalidSignature,
+    #[error("Token was signed by an unknown key: {0}")]
+    UnknownKey(String),
+    #[error("Token has unexpected type: {0}")]
+    UnexpectedType(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+/// An Ed25519 key pair identified by its `kid`.
+///
+/// A key signs while it is active. Once retired it is only used for verification,
+/// and only until the last token it could have signed has expired.
+pub struct SigningKey {
+    kid: String,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    public_key: [u8; 32],
+    created_at: u64,
+    retired_at: Option<u64>,
+}
+
+impl SigningKey {
+    fn generate(now: u64) -> Result<Self, KeyringError> {
+        let secret: [u8; 32] = rand::random();
+        let key_pair = ed25519_dalek::SigningKey::from_bytes(&secret);
+        let der = key_pair
+            .to_pkcs8_der()
+            .map_err(|error| anyhow::anyhow!("Failed to encode signing key: {}", error))?;
+        let public_key = key_pair.verifying_key().to_bytes();
+        Ok(Self {
+            kid: Uuid::new_v4().simple().to_string(),
+            encoding_key: EncodingKey::from_ed_der(der.as_bytes()),
+            decoding_key: DecodingKey::from_ed_der(&public_key),
+            public_key,
+            created_at: now,
+            retired_at: None,
+        })
+    }
+
+    pub fn kid(&self) -> &str {
+        &self.kid
+    }
+
+    pub fn created_at(&self) -> u64 {
+        self.created_at
+    }
+
+    pub fn retired_at(&self) -> Option<u64> {
+        self.retired_at
+    }
+
+    /// Public key, base64url encoded as in the `x` parameter of its JWK.
+    pub fn public_key(&self) -> String {
+        URL_SAFE_NO_PAD.encode(self.public_key)
+    }
+
+    /// Signs a message other than a token, such as an audit log checkpoint. The signature
+    /// is base64url encoded.
+    pub fn sign(&self, message: &[u8]) -> Result<String, KeyringError> {
+        jsonwebtoken::crypto::sign(message, &self.encoding_key, Algorithm::EdDSA)
+            .map_err(|error| anyhow::anyhow!("Failed to sign message: {}", error).into())
+    }
+
+    fn verifies_at(&self, now: u64, token_ttl: u64) -> bool {
+        match self.retired_at {
+            None => true,
+            Some(retired_at) => now < retired_at + token_ttl,
+        }
+    }
+
+    /// Public key as a JWK, as published in the JWKS.
+    pub fn jwk(&self) -> Jwk {
+        Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(KeyAlgorithm::EdDSA),
+                key_id: Some(self.kid.clone()),
+                ..Default::default()
+            },
+            algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                key_type: OctetKeyPairType::OctetKeyPair,
+                curve: EllipticCurve::Ed25519,
+                x: self.public_key(),
+            }),
+        }
+    }
+}
+
+impl Debug for SigningKey {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("SigningKey")
+            .field("kid", &self.kid)
+            .field("created_at", &self.created_at)
+            .field("retired_at", &self.retired_at)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Ordered set of signing keys, oldest first. The last key is the active one.
+#[derive(Debug)]
+pub struct Keyring {
+    keys: Vec<SigningKey>,
+    token_ttl: u64,
+}
+
+impl Keyring {
+    pub fn new(token_ttl: u64) -> Result<Self, KeyringError> {
+        Self::new_at(token_ttl, get_current_timestamp())
+    }
+
+    fn new_at(token_ttl: u64, now: u64) -> Result<Self, KeyringError> {
+        let key = SigningKey::generate(now)?;
+        info!("Keyring: Generated signing key {}", key.kid);
+        Ok(Self { keys: vec![key], token_ttl })
+    }
+
+    pub fn token_ttl(&self) -> u64 {
+        self.token_ttl
+    }
+
+    pub fn active(&self) -> &SigningKey {
+        self.keys.last().expect("Keyring always holds an active key")
+    }
+
+    pub fn keys(&self) -> &[SigningKey] {
+        &self.keys
+    }
+
+    /// Makes a fresh key active for signing. The previous active key keeps verifying
+    /// tokens for one more TTL, and keys whose window has closed are dropped.
+    pub fn rotate(&mut self) -> Result<&SigningKey, KeyringError> {
+        self.rotate_at(get_current_timestamp())
+    }
+
+    fn rotate_at(&mut self, now: u64) -> Result<&SigningKey, KeyringError> {
+        let key = SigningKey::generate(now)?;
+        if let Some(active) = self.keys.last_mut() {
+            active.retired_at = Some(now);
+        }
+        self.keys.push(key);
+        self.prune_at(now);
+        let active = self.active();
+        info!("Keyring: Rotated to signing key {}", active.kid);
+        Ok(active)
+    }
+
+    fn prune_at(&mut self, now: u64) {
+        let token_ttl = self.token_ttl;
+        self.keys.retain(|key| {
+            let keep = key.verifies_at(now, token_ttl);
+            if !keep {
+                info!("Keyring: Dropped retired signing key {}", key.kid);
+            }
+            keep
+        });
+    }
+
+    /// Signs an access token.
+    pub fn sign<T: Serialize>(&self, claims: &T) -> Result<String, KeyringError> {
+        self.sign_with_type(ACCESS_TOKEN_TYPE, claims)
+    }
+
+    pub fn sign_id_token<T: Serialize>(&self, claims: &T) -> Result<String, KeyringError> {
+        self.sign_with_type(ID_TOKEN_TYPE, claims)
+    }
+
+    fn sign_with_type<T: Serialize>(&self, typ: &str, claims: &T) -> Result<String, KeyringError> {
+        let active = self.active();
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.typ = Some(typ.to_string());
+        header.kid = Some(active.kid.clone());
+        jsonwebtoken::encode(&header, claims, &active.encoding_key)
+            .map_err(|error| anyhow::anyhow!("Failed to sign token: {}", error).into())
+    }
+
+    /// Verifies an access token. Tokens of any other type, such as ID tokens, are rejected.
+    pub fn verify<T: DeserializeOwned>(&self, token: &str) -> Result<T, KeyringError> {
+        self.verify_at(token, ACCESS_TOKEN_TYPE, get_current_timestamp())
+    }
+
+    pub fn verify_id_token<T: DeserializeOwned>(&self, token: &str) -> Result<T, KeyringError> {
+        self.verify_at(token, ID_TOKEN_TYPE, get_current_timestamp())
+    }
+
+    fn verify_at<T: DeserializeOwned>(&self, token: &str, typ: &str, now: u64) -> Result<T, KeyringError> {
+        let header = jsonwebtoken::decode_header(token)
+            .map_err(|error| KeyringError::MalformedToken(error.to_string()))?;
+        if header.typ.as_deref() != Some(typ) {
+            return Err(KeyringError::UnexpectedType(header.typ.unwrap_or_default()));
+        }
+        let kid = header
+            .kid
+            .ok_or_else(|| KeyringError::MalformedToken("missing kid".to_string()))?;
+        let key = self
+            .keys
+            .iter()
+            .find(|key| key.kid == kid && key.verifies_at(now, self.token_ttl))
+            .ok_or(KeyringError::UnknownKey(kid))?;
+        let mut validation = Validation::new(Algorithm::EdDSA);
+        validation.leeway = 0;
+        validation.validate_aud = false;
+        jsonwebtoken::decode::<T>(token, &key.decoding_key, &validation)
+            .map(|data| data.claims)
+            .map_err(|error| match error.kind() {
+                ErrorKind::ExpiredSignature => KeyringError::ExpiredToken,
+                ErrorKind::InvalidSignature => KeyringError::InvalidSignature,
+                _ => KeyringError::MalformedToken(error.to_string()),
+            })
+    }
+
+    /// Public half of every key that can still verify a token.
+    pub fn jwks(&self) -> JwkSet {
+        self.jwks_at(get_current_timestamp())
+    }
+
+    fn jwks_at(&self, now: u64) -> JwkSet {
+        JwkSet {
+            keys: self
+                .keys
+                .iter()
+                .filter(|key| key.verifies_at(now, self.token_ttl))
+                .map(SigningKey::jwk)
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Claims;
+
+    const TTL: u64 = 600;
+
+    fn claims(now: u64) -> Claims {
+        Claims::new("alice@example.com", now, TTL)
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let now = get_current_timestamp();
+        let keyring = Keyring::new_at(TTL, now).unwrap();
+        let token = keyring.sign(&claims(now)).unwrap();
+        let verified: Claims = keyring.verify_at(&token, ACCESS_TOKEN_TYPE, now).unwrap();
+        assert_eq!(verified, claims(now));
+    }
+
+    #[test]
+    fn test_token_signed_before_rotation_still_verifies() {
+        let now = get_current_timestamp();
+        let mut keyring = Keyring::new_at(TTL, now).unwrap();
+        let old_kid = keyring.active().kid().to_string();
+        let token = keyring.sign(&claims(now)).unwrap();
+        keyring.rotate_at(now + 1).unwrap();
+        assert_ne!(keyring.active().kid(), old_kid);
+        assert!(keyring.verify_at::<Claims>(&token, ACCESS_TOKEN_TYPE, now + TTL).is_ok());
+    }
+
+    #[test]
+    fn test_new_tokens_are_signed_with_new_key() {
+        let now = get_current_timestamp();
+        let mut keyring = Keyring::new_at(TTL, now).unwrap();
+        let new_kid = keyring.rotate_at(now).unwrap().kid().to_string();
+        let token = keyring.sign(&claims(now)).unwrap();
+        let header = jsonwebtoken::decode_header(&token).unwrap();
+        assert_eq!(header.kid, Some(new_kid));
+    }
+
+    #[test]
+    fn test_retired_key_is_rejected_after_window() {
+        let now = get_current_timestamp();
+        let mut keyring = Keyring::new_at(TTL, now).unwrap();
+        let token = keyring.sign(&claims(now)).unwrap();
+        keyring.rotate_at(now).unwrap();
+        let result = keyring.verify_at::<Claims>(&token, ACCESS_TOKEN_TYPE, now + TTL);
+        assert!(matches!(result, Err(KeyringError::UnknownKey(_))));
+    }
+
+    #[test]
+    fn test_rotation_drops_keys_outside_window() {
+        let now = get_current_timestamp();
+        let mut keyring = Keyring::new_at(TTL, now).unwrap();
+        let first_kid = keyring.active().kid().to_string();
+        keyring.rotate_at(now).unwrap();
+        assert_eq!(keyring.keys().len(), 2);
+        keyring.rotate_at(now + TTL).unwrap();
+        assert_eq!(keyring.keys().len(), 2);
+        assert!(keyring.keys().iter().all(|key| key.kid() != first_kid));
+    }
+
+    #[test]
+    fn test_token_from_another_keyring_is_rejected() {
+        let now = get_current_timestamp();
+        let keyring = Keyring::new_at(TTL, now).unwrap();
+        let other = Keyring::new_at(TTL, now).unwrap();
+        let token = other.sign(&claims(now)).unwrap();
+        let result = keyring.verify_at::<Claims>(&token, ACCESS_TOKEN_TYPE, now);
+        assert!(matches!(result, Err(KeyringError::UnknownKey(_))));
+    }
+
+    #[test]
+    fn test_expired_token_is_rejected() {
+        let now = get_current_timestamp();
+        let keyring = Keyring::new_at(TTL, now).unwrap();
+        let token = keyring.sign(&Claims::new("alice@example.com", now - 2 * TTL, TTL)).unwrap();
+        let result = keyring.verify_at::<Claims>(&token, ACCESS_TOKEN_TYPE, now);
+        assert!(matches!(result, Err(KeyringError::ExpiredToken)));
+    }
+
+    #[test]
+    fn test_jwks_exposes_all_verification_keys() {
+        let now = get_current_timestamp();
+        let mut keyring = Keyring::new_at(TTL, now).unwrap();
+        keyring.rotate_at(now).unwrap();
+        let jwks = keyring.jwks();
+        assert_eq!(jwks.keys.len(), 2);
+        assert!(keyring.keys().iter().all(|key| jwks.find(key.kid()).is_some()));
+
+        let token = keyring.sign(&claims(now)).unwrap();
+        let jwk = jwks.find(keyring.active().kid()).unwrap();
+        let decoding_key = DecodingKey::from_jwk(jwk).unwrap();
+        let mut validation = Validation::new(Algorithm::EdDSA);
+        validation.validate_aud = false;
+        assert!(jsonwebtoken::decode::<Claims>(&token, &decoding_key, &validation).is_ok());
+    }
+
+    #[test]
+    fn test_jwks_omits_keys_outside_window() {
+        let now = get_current_timestamp();
+        let mut keyring = Keyring::new_at(TTL, now).unwrap();
+        let retired_kid = keyring.active().kid().to_string();
+        keyring.rotate_at(now).unwrap();
+        assert!(keyring.jwks_at(now + TTL - 1).find(&retired_kid).is_some());
+        let jwks = keyring.jwks_at(now + TTL);
+        assert_eq!(jwks.keys.len(), 1);
+        assert!(jwks.find(&retired_kid).is_none());
+    }
+
+    #[test]
+    fn test_id_token_is_not_accepted_as_access_token() {
+        let now = get_current_timestamp();
+        let keyring = Keyring::new_at(TTL, now).unwrap();
+        let id_token = keyring.sign_id_token(&claims(now)).unwrap();
+        let result = keyring.verify_at::<Claims>(&id_token, ACCESS_TOKEN_TYPE, now);
+        assert!(matches!(result, Err(KeyringError::UnexpectedType(_))));
+        let access_token = keyring.sign(&claims(now)).unwrap();
+        let result = keyring.verify_at::<Claims>(&access_token, ID_TOKEN_TYPE, now);
+        assert!(matches!(result, Err(KeyringError::UnexpectedType(_))));
+    }
+}
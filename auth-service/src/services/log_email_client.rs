@@ -0,0 +1,15 @@
+use crate::services::{EmailClient, EmailClientError, EmailMessage};
+use tracing::warn;
+
+/// Stands in for a mail provider when none is configured: logs who each email was for, and
+/// drops it. The content is neither logged nor kept, as it may hold a password reset token.
+#[derive(Debug, Default)]
+pub struct LogEmailClient;
+
+#[async_trait::async_trait]
+impl EmailClient for LogEmailClient {
+    async fn send_email(&mut self, message: EmailMessage) -> Result<(), EmailClientError> {
+        warn!("No mail provider, not sending email to {}: {}", message.recipient, message.subject);
+        Ok(())
+    }
+}
@@ -0,0 +1,22 @@
+use crate::domain::LoginAttempt;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum LoginAttemptStoreError {
+    #[error("Login attempt was not found")]
+    LoginAttemptNotFound,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+#[async_trait::async_trait]
+pub trait LoginAttemptStore {
+    /// Adds the login attempt, replacing any earlier one of the same user and dropping expired ones.
+    async fn add_login_attempt(&mut self, login_attempt: LoginAttempt) -> Result<(), LoginAttemptStoreError>;
+    async fn get_login_attempt(&self, id: &str) -> Result<&LoginAttempt, LoginAttemptStoreError>;
+    /// Counts a wrong code against the login attempt, removing it once
+    /// [`MAX_2FA_CODE_ATTEMPTS`](crate::domain::MAX_2FA_CODE_ATTEMPTS) are reached.
+    async fn record_failed_code(&mut self, id: &str) -> Result<(), LoginAttemptStoreError>;
+    /// Removes the login attempt from the store, so that its code can only be used once.
+    async fn take_login_attempt(&mut self, id: &str) -> Result<LoginAttempt, LoginAttemptStoreError>;
+}
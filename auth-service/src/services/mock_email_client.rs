@@ -0,0 +1,24 @@
+use crate::services::{EmailClient, EmailClientError, EmailMessage};
+use tracing::info;
+
+/// Stands in for a mail provider in tests: logs each email, and keeps it so that tests can
+/// read it. Never use it to serve, as it keeps every email for as long as it lives.
+#[derive(Debug, Default)]
+pub struct MockEmailClient {
+    sent_emails: Vec<EmailMessage>,
+}
+
+impl MockEmailClient {
+    pub fn sent_emails(&self) -> &[EmailMessage] {
+        &self.sent_emails
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailClient for MockEmailClient {
+    async fn send_email(&mut self, message: EmailMessage) -> Result<(), EmailClientError> {
+        info!("Sending email to {}: {}", message.recipient, message.subject);
+        self.sent_emails.push(message);
+        Ok(())
+    }
+}
@@ -0,0 +1,19 @@
+use crate::domain::PasswordReset;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum PasswordResetStoreError {
+    #[error("Password reset was not found")]
+    PasswordResetNotFound,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+#[async_trait::async_trait]
+pub trait PasswordResetStore {
+    /// Adds the password reset, replacing any earlier one of the same user.
+    async fn add_password_reset(&mut self, password_reset: PasswordReset) -> Result<(), PasswordResetStoreError>;
+    async fn get_password_reset(&self, token: &str) -> Result<&PasswordReset, PasswordResetStoreError>;
+    /// Removes the password reset from the store, so that it can only be used once.
+    async fn take_password_reset(&mut self, token: &str) -> Result<PasswordReset, PasswordResetStoreError>;
+}
@@ -0,0 +1,136 @@
+use crate::services::{EmailClient, EmailClientError, EmailMessage};
+use anyhow::anyhow;
+use reqwest::redirect::Policy;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Serialize;
+use std::time::Duration;
+use url::Url;
+
+const HTTP_TIMEOUT_SECONDS: u64 = 10;
+const SERVER_TOKEN_HEADER: &str = "X-Postmark-Server-Token";
+const MESSAGE_STREAM: &str = "outbound";
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct SendEmailRequest<'a> {
+    from: &'a str,
+    to: &'a str,
+    subject: &'a str,
+    text_body: &'a str,
+    message_stream: &'a str,
+}
+
+/// Sends emails through the Postmark API, or any API compatible with its `/email` endpoint.
+#[derive(Debug)]
+pub struct PostmarkEmailClient {
+    http_client: reqwest::Client,
+    base_url: Url,
+    sender: String,
+    server_token: SecretString,
+}
+
+impl PostmarkEmailClient {
+    pub fn new(base_url: Url, sender: &str, server_token: SecretString) -> Self {
+        let http_client = reqwest::Client::builder()
+            .redirect(Policy::none())
+            .timeout(Duration::from_secs(HTTP_TIMEOUT_SECONDS))
+            .build()
+            .expect("Failed to build HTTP client");
+        Self {
+            http_client,
+            base_url,
+            sender: sender.to_string(),
+            server_token,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailClient for PostmarkEmailClient {
+    async fn send_email(&mut self, message: EmailMessage) -> Result<(), EmailClientError> {
+        let url = self.base_url.join("email").map_err(|error| anyhow!(error))?;
+        let request = SendEmailRequest {
+            from: &self.sender,
+            to: &message.recipient,
+            subject: &message.subject,
+            text_body: &message.content,
+            message_stream: MESSAGE_STREAM,
+        };
+        self.http_client
+            .post(url)
+            .header(SERVER_TOKEN_HEADER, self.server_token.expose_secret())
+            .json(&request)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|error| anyhow!(error))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+
+    type Requests = Arc<Mutex<Vec<(HeaderMap, Value)>>>;
+
+    async fn email(State(requests): State<Requests>, headers: HeaderMap, Json(body): Json<Value>) -> StatusCode {
+        let status = match body["To"] == "bounce@example.com" {
+            true => StatusCode::UNPROCESSABLE_ENTITY,
+            false => StatusCode::OK,
+        };
+        requests.lock().unwrap().push((headers, body));
+        status
+    }
+
+    async fn spawn_api() -> (Url, Requests) {
+        let requests = Requests::default();
+        let router = Router::new().route("/email", post(email)).with_state(requests.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        (url, requests)
+    }
+
+    fn message(recipient: &str) -> EmailMessage {
+        EmailMessage {
+            recipient: recipient.to_string(),
+            subject: "Subject".to_string(),
+            content: "Content".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_email() {
+        let (url, requests) = spawn_api().await;
+        let mut client = PostmarkEmailClient::new(url, "auth@example.com", SecretString::from("token"));
+        client.send_email(message("alice@example.com")).await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        let (headers, body) = &requests[0];
+        assert_eq!(headers[SERVER_TOKEN_HEADER], "token");
+        assert_eq!(
+            body,
+            &json!({
+                "From": "auth@example.com",
+                "To": "alice@example.com",
+                "Subject": "Subject",
+                "TextBody": "Content",
+                "MessageStream": "outbound",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_email_fails_when_the_api_does() {
+        let (url, _) = spawn_api().await;
+        let mut client = PostmarkEmailClient::new(url, "auth@example.com", SecretString::from("token"));
+        assert!(client.send_email(message("bounce@example.com")).await.is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::Context;
+use memmap2::Mmap;
+use sha1::{Digest, Sha1};
+use std::cmp::Ordering;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Length of the SHA-1 prefixes the range files of a dataset directory are named after.
+pub const PWNED_PASSWORDS_PREFIX_LENGTH: usize = 5;
+
+#[derive(Error, Debug)]
+pub enum PwnedPasswordsError {
+    #[error("Invalid Pwned Passwords dataset: {0}")]
+    InvalidDataset(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+#[derive(Debug)]
+enum Dataset {
+    /// One file of `HASH:COUNT` lines, sorted by hash.
+    File(Mmap),
+    /// One file per hash prefix, named `ABCDE.txt`, of `SUFFIX:COUNT` lines sorted by suffix,
+    /// as saved by the Pwned Passwords downloader or the k-anonymity range API.
+    Directory(PathBuf),
+}
+
+/// Local copy of the Pwned Passwords dataset of SHA-1 hashes of breached passwords. Files are
+/// memory-mapped and searched by binary search, so that even the full dataset needs no more
+/// memory than the pages it reads. Without a dataset, no password is breached.
+#[derive(Debug, Default)]
+pub struct PwnedPasswords {
+    dataset: Option<Dataset>,
+}
+
+impl PwnedPasswords {
+    /// Opens the dataset at the path: a directory of range files, or a single file.
+    pub fn open(path: &Path) -> Result<Self, PwnedPasswordsError> {
+        if path.is_dir() {
+            return Ok(Self { dataset: Some(Dataset::Directory(path.to_path_buf())) });
+        }
+        let hashes = map(path)?.ok_or_else(|| PwnedPasswordsError::InvalidDataset(path.display().to_string()))?;
+        let first_hash = hashes.split(|byte| *byte == b'\n').next().map(line_hash).unwrap_or_default();
+        if first_hash.len() != 40 || !first_hash.iter().all(u8::is_ascii_hexdigit) {
+            return Err(PwnedPasswordsError::InvalidDataset("Expected lines of SHA-1 hashes".to_string()));
+        }
+        Ok(Self { dataset: Some(Dataset::File(hashes)) })
+    }
+
+    pub fn is_breached(&self, password: &str) -> Result<bool, PwnedPasswordsError> {
+        let hash = format!("{:X}", Sha1::digest(password.as_bytes()));
+        let (prefix, suffix) = hash.split_at(PWNED_PASSWORDS_PREFIX_LENGTH);
+        match &self.dataset {
+            None => Ok(false),
+            Some(Dataset::File(hashes)) => Ok(contains_hash(hashes, hash.as_bytes())),
+            Some(Dataset::Directory(directory)) => {
+                // A range without breached passwords may have no file.
+                let range = map(&directory.join(format!("{}.txt", prefix)))?;
+                Ok(range.is_some_and(|suffixes| contains_hash(&suffixes, suffix.as_bytes())))
+            }
+        }
+    }
+}
+
+/// Maps the file into memory, unless it does not exist.
+fn map(path: &Path) -> Result<Option<Mmap>, PwnedPasswordsError> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => {
+            let error = anyhow::Error::new(error).context(format!("Failed to open {}", path.display()));
+            return Err(error.into());
+        }
+    };
+    // SAFETY: the dataset is only ever read, and is not expected to change while mapped.
+    let mmap = unsafe { Mmap::map(&file) }.with_context(|| format!("Failed to map {}", path.display()))?;
+    Ok(Some(mmap))
+}
+
+fn line_hash(line: &[u8]) -> &[u8] {
+    line.split(|byte| *byte == b':').next().unwrap_or_default().trim_ascii()
+}
+
+/// Whether the lines, sorted by the hash before their `:`, have the hash. Hashes are compared
+/// regardless of case; the hash looked up must be in upper case.
+fn contains_hash(lines: &[u8], hash: &[u8]) -> bool {
+    let (mut low, mut high) = (0, lines.len());
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let start = lines[..middle].iter().rposition(|byte| *byte == b'\n').map_or(0, |index| index + 1);
+        let end = lines[middle..].iter().position(|byte| *byte == b'\n').map_or(lines.len(), |index| middle + index);
+        match line_hash(&lines[start..end]).to_ascii_uppercase().as_slice().cmp(hash) {
+            Ordering::Equal => return true,
+            Ordering::Less => low = end + 1,
+            Ordering::Greater => high = start,
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    // SHA-1 of "password" is 5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8.
+    const HASHES: &str = "000000005AD76BD555C1D6D771DE417A4B87E4B4:4\r\n\
+                          5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8:10434004\r\n\
+                          FFFFFFFEE791CBAC0F6305CAF0CEE06BBE131160:2\r\n";
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pwned-passwords-{}-{}", Uuid::new_v4(), name))
+    }
+
+    #[test]
+    fn test_contains_hash() {
+        let hashes = HASHES.as_bytes();
+        assert!(contains_hash(hashes, b"5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8"));
+        assert!(contains_hash(hashes, b"000000005AD76BD555C1D6D771DE417A4B87E4B4"));
+        assert!(contains_hash(hashes, b"FFFFFFFEE791CBAC0F6305CAF0CEE06BBE131160"));
+        assert!(!contains_hash(hashes, b"5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD9"));
+        assert!(!contains_hash(hashes, b"FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF"));
+        assert!(!contains_hash(b"", b"5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8"));
+        assert!(contains_hash(HASHES.to_lowercase().as_bytes(), b"5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8"));
+    }
+
+    #[test]
+    fn test_file_dataset() {
+        let path = temp_path("hashes.txt");
+        std::fs::write(&path, HASHES).unwrap();
+        let pwned_passwords = PwnedPasswords::open(&path).unwrap();
+        assert!(pwned_passwords.is_breached("password").unwrap());
+        assert!(!pwned_passwords.is_breached("CorrectHorseBatteryStaple123!").unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        std::fs::write(&path, "network,latitude,longitude\n").unwrap();
+        assert!(matches!(PwnedPasswords::open(&path), Err(PwnedPasswordsError::InvalidDataset(_))));
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(PwnedPasswords::open(&path), Err(PwnedPasswordsError::InvalidDataset(_))));
+    }
+
+    #[test]
+    fn test_directory_dataset() {
+        let path = temp_path("ranges");
+        std::fs::create_dir(&path).unwrap();
+        std::fs::write(path.join("5BAA6.txt"), "1E4C9B93F3F0682250B6CF8331B7EE68FD8:10434004\n").unwrap();
+        let pwned_passwords = PwnedPasswords::open(&path).unwrap();
+        assert!(pwned_passwords.is_breached("password").unwrap());
+        assert!(!pwned_passwords.is_breached("CorrectHorseBatteryStaple123!").unwrap());
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn test_no_dataset() {
+        assert!(!PwnedPasswords::default().is_breached("password").unwrap());
+    }
+}
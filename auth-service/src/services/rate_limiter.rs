@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::IpAddr;
+
+/// Requests a client may make per window by default.
+pub const DEFAULT_RATE_LIMIT: u32 = 30;
+/// Length of a rate limiting window, in seconds.
+pub const RATE_LIMIT_WINDOW_SECONDS: u64 = 60;
+
+/// Limits how often each client may make a request, counting requests in fixed windows of
+/// [`RATE_LIMIT_WINDOW_SECONDS`]. Clients are told apart by address, or by another key such
+/// as the account the requests are about.
+#[derive(Debug)]
+pub struct RateLimiter<K = IpAddr> {
+    limit: u32,
+    /// Start of the current window and requests made in it, by client.
+    windows: HashMap<K, (u64, u32)>,
+}
+
+impl<K: Eq + Hash> Default for RateLimiter<K> {
+    fn default() -> Self {
+        Self::new(DEFAULT_RATE_LIMIT)
+    }
+}
+
+impl<K: Eq + Hash> RateLimiter<K> {
+    pub fn new(limit: u32) -> Self {
+        Self {
+            limit,
+            windows: HashMap::new(),
+        }
+    }
+
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    /// Counts a request from the client, returning how many seconds it must wait when it
+    /// made too many.
+    pub fn check(&mut self, client: K, now: u64) -> Result<(), u64> {
+        self.check_limit(client, self.limit, now)
+    }
+
+    /// Counts a request as [`check`](Self::check) does, against a limit given with each
+    /// request, e.g. from reloadable settings. Requests counted in the current window are kept
+    /// when the limit changes.
+    pub fn check_limit(&mut self, client: K, limit: u32, now: u64) -> Result<(), u64> {
+        let window_start = now - now % RATE_LIMIT_WINDOW_SECONDS;
+        // Windows of other clients are forgotten once over, so that the map stays small.
+        if self.windows.len() > 10_000 {
+            self.windows.retain(|_, (start, _)| *start == window_start);
+        }
+        let (start, count) = self.windows.entry(client).or_insert((window_start, 0));
+        if *start != window_start {
+            (*start, *count) = (window_start, 0);
+        }
+        if *count >= limit {
+            return Err(window_start + RATE_LIMIT_WINDOW_SECONDS - now);
+        }
+        *count += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter() {
+        let mut rate_limiter = RateLimiter::new(2);
+        let (alice, bob): (IpAddr, IpAddr) = ("203.0.113.1".parse().unwrap(), "203.0.113.2".parse().unwrap());
+        assert_eq!(rate_limiter.check(alice, 600), Ok(()));
+        assert_eq!(rate_limiter.check(alice, 610), Ok(()));
+        assert_eq!(rate_limiter.check(alice, 615), Err(45));
+        assert_eq!(rate_limiter.check(bob, 615), Ok(()));
+        assert_eq!(rate_limiter.check(alice, 660), Ok(()));
+        assert_eq!(RateLimiter::new(0).check(alice, 600), Err(60));
+        assert_eq!(rate_limiter.check_limit(alice, 1, 670), Err(50));
+        assert_eq!(rate_limiter.check_limit(alice, 3, 670), Ok(()));
+    }
+
+    #[test]
+    fn test_rate_limiter_by_key() {
+        let mut rate_limiter = RateLimiter::new(1);
+        assert_eq!(rate_limiter.check("alice@example.com", 600), Ok(()));
+        assert_eq!(rate_limiter.check("alice@example.com", 610), Err(50));
+        assert_eq!(rate_limiter.check("bob@example.com", 610), Ok(()));
+    }
+}
@@ -0,0 +1,23 @@
+use crate::domain::Session;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SessionStoreError {
+    #[error("Session was not found: {0}")]
+    SessionNotFound(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+#[async_trait::async_trait]
+pub trait SessionStore {
+    async fn add_session(&mut self, session: Session) -> Result<(), SessionStoreError>;
+    async fn get_session(&self, id: &str) -> Result<&Session, SessionStoreError>;
+    /// Records activity on the session.
+    async fn touch_session(&mut self, id: &str, now: u64) -> Result<(), SessionStoreError>;
+    /// Sessions of the user, oldest first.
+    async fn get_user_sessions(&self, tenant_id: &str, email: &str) -> Result<Vec<Session>, SessionStoreError>;
+    async fn remove_session(&mut self, id: &str) -> Result<Session, SessionStoreError>;
+    /// Removes every session of the user, returning how many there were.
+    async fn remove_user_sessions(&mut self, tenant_id: &str, email: &str) -> Result<usize, SessionStoreError>;
+}
@@ -0,0 +1,313 @@
+use axum::serve::Listener;
+use rustls::crypto::aws_lc_rs::cipher_suite::{
+    TLS13_AES_128_GCM_SHA256, TLS13_AES_256_GCM_SHA384, TLS13_CHACHA20_POLY1305_SHA256,
+    TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256, TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+    TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256, TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+    TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384, TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256,
+};
+use rustls::crypto::{aws_lc_rs, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use rustls::ServerConfig;
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, error, info};
+
+/// How often the certificate files are checked for changes by default.
+pub const CERTIFICATE_RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+/// How long a client may take to complete the TLS handshake.
+pub const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Error, Debug)]
+pub enum TlsError {
+    #[error("Failed to read {0}: {1}")]
+    UnreadableFile(String, String),
+    #[error("No certificate in {0}")]
+    MissingCertificate(String),
+    #[error("Invalid certificate {0} or private key {1}: {2}")]
+    InvalidKeyPair(String, String, String),
+    #[error("Invalid TLS configuration: {0}")]
+    InvalidConfiguration(String),
+}
+
+/// HTTPS settings of an [`Application`](crate::Application).
+#[derive(Debug, Clone)]
+pub struct TlsSettings {
+    /// PEM file of the certificate chain, leaf first.
+    pub cert_file: PathBuf,
+    /// PEM file of the private key.
+    pub key_file: PathBuf,
+    /// Address of a listener redirecting plain HTTP requests to HTTPS, if any.
+    pub redirect_address: Option<SocketAddr>,
+    pub reload_interval: Duration,
+}
+
+impl TlsSettings {
+    pub fn new(cert_file: &Path, key_file: &Path) -> Self {
+        Self {
+            cert_file: cert_file.to_path_buf(),
+            key_file: key_file.to_path_buf(),
+            redirect_address: None,
+            reload_interval: CERTIFICATE_RELOAD_INTERVAL,
+        }
+    }
+
+    pub fn with_redirect_address(mut self, redirect_address: SocketAddr) -> Self {
+        self.redirect_address = Some(redirect_address);
+        self
+    }
+
+    /// How often the certificate files are checked for changes.
+    pub fn with_reload_interval(mut self, reload_interval: Duration) -> Self {
+        self.reload_interval = reload_interval;
+        self
+    }
+}
+
+/// Only TLS 1.3 and 1.2, with forward-secret AEAD cipher suites.
+fn crypto_provider() -> CryptoProvider {
+    CryptoProvider {
+        cipher_suites: vec![
+            TLS13_AES_256_GCM_SHA384,
+            TLS13_AES_128_GCM_SHA256,
+            TLS13_CHACHA20_POLY1305_SHA256,
+            TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+            TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+            TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256,
+            TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384,
+            TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+            TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256,
+        ],
+        ..aws_lc_rs::default_provider()
+    }
+}
+
+/// Server configuration presenting the certificate the resolver holds at the time of each
+/// handshake.
+pub fn server_config(resolver: Arc<CertificateResolver>) -> Result<ServerConfig, TlsError> {
+    let mut config = ServerConfig::builder_with_provider(Arc::new(crypto_provider()))
+        .with_protocol_versions(&[&rustls::version::TLS13, &rustls::version::TLS12])
+        .map_err(|error| TlsError::InvalidConfiguration(error.to_string()))?
+        .with_no_client_auth()
+        .with_cert_resolver(resolver);
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+/// Holds the certificate of the server, reloading it when its files change on disk, e.g.
+/// when renewed. A certificate that fails to load leaves the previous one in place.
+pub struct CertificateResolver {
+    cert_file: PathBuf,
+    key_file: PathBuf,
+    certified_key: RwLock<Arc<CertifiedKey>>,
+    /// Modification times of the files when last loaded.
+    modified: Mutex<(Option<SystemTime>, Option<SystemTime>)>,
+}
+
+impl CertificateResolver {
+    pub fn load(cert_file: &Path, key_file: &Path) -> Result<Self, TlsError> {
+        let modified = (modified(cert_file), modified(key_file));
+        Ok(Self {
+            cert_file: cert_file.to_path_buf(),
+            key_file: key_file.to_path_buf(),
+            certified_key: RwLock::new(Arc::new(certified_key(cert_file, key_file)?)),
+            modified: Mutex::new(modified),
+        })
+    }
+
+    /// Reloads the certificate, whether its files changed or not.
+    pub fn reload(&self) -> Result<(), TlsError> {
+        *self.modified.lock().expect("Poisoned lock") = (modified(&self.cert_file), modified(&self.key_file));
+        let certified_key = certified_key(&self.cert_file, &self.key_file)?;
+        *self.certified_key.write().expect("Poisoned lock") = Arc::new(certified_key);
+        info!("Reloaded certificate {}", self.cert_file.display());
+        Ok(())
+    }
+
+    /// Reloads the certificate if either of its files changed since last loaded. Returns
+    /// whether it did.
+    pub fn reload_if_changed(&self) -> Result<bool, TlsError> {
+        let modified = (modified(&self.cert_file), modified(&self.key_file));
+        if *self.modified.lock().expect("Poisoned lock") == modified {
+            return Ok(false);
+        }
+        self.reload()?;
+        Ok(true)
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+fn certified_key(cert_file: &Path, key_file: &Path) -> Result<CertifiedKey, TlsError> {
+    let unreadable = |path: &Path, error: &dyn fmt::Display| {
+        TlsError::UnreadableFile(path.display().to_string(), error.to_string())
+    };
+    let certs = CertificateDer::pem_file_iter(cert_file)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|error| unreadable(cert_file, &error))?;
+    if certs.is_empty() {
+        return Err(TlsError::MissingCertificate(cert_file.display().to_string()));
+    }
+    let key = PrivateKeyDer::from_pem_file(key_file).map_err(|error| unreadable(key_file, &error))?;
+    CertifiedKey::from_der(certs, key, &crypto_provider()).map_err(|error| {
+        TlsError::InvalidKeyPair(cert_file.display().to_string(), key_file.display().to_string(), error.to_string())
+    })
+}
+
+impl ResolvesServerCert for CertificateResolver {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.certified_key.read().expect("Poisoned lock").clone())
+    }
+}
+
+impl fmt::Debug for CertificateResolver {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("CertificateResolver")
+            .field("cert_file", &self.cert_file)
+            .field("key_file", &self.key_file)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Listener accepting TLS connections. Handshakes are run in tasks of their own, so that
+/// slow clients do not hold up the others.
+#[derive(Debug)]
+pub struct TlsListener {
+    connections: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+    address: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl TlsListener {
+    pub fn new(listener: TcpListener, config: Arc<ServerConfig>) -> io::Result<Self> {
+        let address = listener.local_addr()?;
+        let (sender, connections) = mpsc::channel(64);
+        let task = tokio::spawn(accept_connections(listener, TlsAcceptor::from(config), sender));
+        Ok(Self { connections, address, task })
+    }
+}
+
+async fn accept_connections(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    sender: mpsc::Sender<(TlsStream<TcpStream>, SocketAddr)>,
+) {
+    loop {
+        let (stream, address) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(error) => {
+                error!("Failed to accept connection: {}", error);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        let (acceptor, sender) = (acceptor.clone(), sender.clone());
+        tokio::spawn(async move {
+            match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                Ok(Ok(stream)) => {
+                    let _ = sender.send((stream, address)).await;
+                }
+                Ok(Err(error)) => debug!("TLS handshake with {} failed: {}", address, error),
+                Err(_) => debug!("TLS handshake with {} timed out", address),
+            }
+        });
+    }
+}
+
+impl Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.connections.recv().await {
+            Some(connection) => connection,
+            // The accepting task only ends when aborted, on drop.
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(self.address)
+    }
+}
+
+impl Drop for TlsListener {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_certificate(cert_file: &Path, key_file: &Path) {
+        let certificate = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        std::fs::write(cert_file, certificate.cert.pem()).unwrap();
+        std::fs::write(key_file, certificate.signing_key.serialize_pem()).unwrap();
+    }
+
+    #[test]
+    fn test_certificate_resolver_reloads_changed_files() {
+        let directory = std::env::temp_dir().join(format!("tls-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&directory).unwrap();
+        let (cert_file, key_file) = (directory.join("cert.pem"), directory.join("key.pem"));
+        write_certificate(&cert_file, &key_file);
+        let resolver = CertificateResolver::load(&cert_file, &key_file).unwrap();
+        let certified_key = resolver.certified_key.read().unwrap().clone();
+        assert!(!resolver.reload_if_changed().unwrap());
+
+        std::thread::sleep(Duration::from_millis(10));
+        write_certificate(&cert_file, &key_file);
+        assert!(resolver.reload_if_changed().unwrap());
+        let reloaded_key = resolver.certified_key.read().unwrap().clone();
+        assert_ne!(reloaded_key.cert, certified_key.cert);
+
+        // A broken certificate leaves the previous one in place, and is not retried.
+        std::thread::sleep(Duration::from_millis(10));
+        std::fs::write(&cert_file, "").unwrap();
+        assert!(matches!(resolver.reload_if_changed(), Err(TlsError::MissingCertificate(_))));
+        assert!(!resolver.reload_if_changed().unwrap());
+        assert_eq!(resolver.certified_key.read().unwrap().cert, reloaded_key.cert);
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert!(matches!(CertificateResolver::load(&cert_file, &key_file), Err(TlsError::UnreadableFile(_, _))));
+    }
+
+    #[test]
+    fn test_crypto_provider_only_has_forward_secret_aead_suites() {
+        let suites = crypto_provider().cipher_suites;
+        assert_eq!(suites.iter().filter(|suite| suite.tls13().is_some()).count(), 3);
+        for suite in suites {
+            let name = format!("{:?}", suite.suite());
+            assert!(name.contains("GCM") || name.contains("CHACHA20_POLY1305"), "Suite: {}", name);
+            assert!(suite.tls13().is_some() || name.starts_with("TLS_ECDHE_"), "Suite: {}", name);
+        }
+    }
+
+    #[test]
+    fn test_certificate_resolver_rejects_mismatched_key() {
+        let directory = std::env::temp_dir().join(format!("tls-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&directory).unwrap();
+        let (cert_file, key_file) = (directory.join("cert.pem"), directory.join("key.pem"));
+        write_certificate(&cert_file, &key_file);
+        write_certificate(&cert_file, &directory.join("other-key.pem"));
+        let result = CertificateResolver::load(&cert_file, &key_file);
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert!(matches!(result, Err(TlsError::InvalidKeyPair(_, _, _))));
+    }
+}
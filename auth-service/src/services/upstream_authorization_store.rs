@@ -0,0 +1,20 @@
+use crate::domain::UpstreamAuthorization;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum UpstreamAuthorizationStoreError {
+    #[error("Upstream authorization was not found")]
+    AuthorizationNotFound,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+#[async_trait::async_trait]
+pub trait UpstreamAuthorizationStore {
+    async fn add_authorization(
+        &mut self,
+        authorization: UpstreamAuthorization,
+    ) -> Result<(), UpstreamAuthorizationStoreError>;
+    /// Removes the authorization from the store, so that a callback can only complete it once.
+    async fn take_authorization(&mut self, state: &str) -> Result<UpstreamAuthorization, UpstreamAuthorizationStoreError>;
+}
@@ -1,4 +1,4 @@
-use crate::domain::User;
+use crate::domain::{Email, ExternalIdentity, KnownDevice, Password, Role, User};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -9,13 +9,74 @@ pub enum UserStoreError {
     UserNotFound(String),
     #[error("User invalid credentials: {0}")]
     InvalidCredentials(String),
+    #[error("User account is disabled: {0}")]
+    AccountDisabled(String),
+    #[error("User account is locked: {0}")]
+    AccountLocked(String),
+    #[error("User must reset the password: {0}")]
+    PasswordResetRequired(String),
+    #[error("Identity is already linked to another user: {0}")]
+    IdentityAlreadyLinked(String),
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
 }
 
+/// A page of users, sorted by email.
+#[derive(Debug, Clone)]
+pub struct UserPage {
+    pub users: Vec<User>,
+    /// Number of users matching the search, across all pages.
+    pub total: usize,
+}
+
+/// Users, scoped by tenant: the same email address may belong to users of different tenants.
+/// Users are looked up by their email address in canonical form.
 #[async_trait::async_trait]
 pub trait UserStore {
     async fn add_user(&mut self, user: User) -> Result<(), UserStoreError>;
-    async fn get_user(&self, email: &str) -> Result<&User, UserStoreError>;
-    async fn validate_user(&self, email: &str, password: &str) -> Result<(), UserStoreError>;
+    async fn get_user(&self, tenant_id: &str, email: &Email) -> Result<&User, UserStoreError>;
+    /// Lists the users of the tenant whose email contains the query, ignoring case.
+    async fn list_users(
+        &self,
+        tenant_id: &str,
+        query: Option<&str>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<UserPage, UserStoreError>;
+    async fn delete_user(&mut self, tenant_id: &str, email: &Email) -> Result<User, UserStoreError>;
+    /// Checks the password, then that the account may sign in.
+    async fn validate_user(&self, tenant_id: &str, email: &Email, password: &str) -> Result<(), UserStoreError>;
+    /// Counts a failed password attempt, returning the number of consecutive failures.
+    async fn record_failed_login(&mut self, tenant_id: &str, email: &Email) -> Result<u32, UserStoreError>;
+    /// Clears the failed password attempts, unlocking the account.
+    async fn reset_failed_logins(&mut self, tenant_id: &str, email: &Email) -> Result<(), UserStoreError>;
+    async fn set_disabled(&mut self, tenant_id: &str, email: &Email, disabled: bool) -> Result<(), UserStoreError>;
+    async fn set_password_reset_required(
+        &mut self,
+        tenant_id: &str,
+        email: &Email,
+        required: bool,
+    ) -> Result<(), UserStoreError>;
+    /// Replaces the password of the user, keeping as many previous passwords in the history as
+    /// the history depth, and lifts any required reset.
+    async fn set_password(
+        &mut self,
+        tenant_id: &str,
+        email: &Email,
+        password: Password,
+        history_depth: usize,
+        now: u64,
+    ) -> Result<(), UserStoreError>;
+    async fn set_requires_2fa(&mut self, tenant_id: &str, email: &Email, requires_2fa: bool) -> Result<(), UserStoreError>;
+    async fn get_user_by_identity(&self, tenant_id: &str, identity: &ExternalIdentity) -> Result<&User, UserStoreError>;
+    /// Links an external identity to the user. Linking an identity twice is a no-op.
+    async fn link_identity(
+        &mut self,
+        tenant_id: &str,
+        email: &Email,
+        identity: ExternalIdentity,
+    ) -> Result<(), UserStoreError>;
+    async fn set_roles(&mut self, tenant_id: &str, email: &Email, roles: &[Role]) -> Result<(), UserStoreError>;
+    /// Remembers the device the user signed in from.
+    async fn remember_device(&mut self, tenant_id: &str, email: &Email, device: KnownDevice) -> Result<(), UserStoreError>;
 }
@@ -0,0 +1,11 @@
+mod audit;
+mod auth;
+mod authorization;
+mod sign_in;
+mod tenant;
+
+pub use audit::*;
+pub use auth::*;
+pub use authorization::*;
+pub use sign_in::*;
+pub use tenant::*;
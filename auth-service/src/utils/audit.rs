@@ -0,0 +1,84 @@
+use crate::app_state::AppState;
+use crate::domain::{AuditContext, AuditEvent, LoginFailureReason};
+use crate::services::UserStoreError;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::header::USER_AGENT;
+use axum::http::request::Parts;
+use axum::http::{Extensions, HeaderMap};
+use jsonwebtoken::get_current_timestamp;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use uuid::Uuid;
+
+/// Header carrying the id correlating the events of a request across services.
+pub const CORRELATION_ID_HEADER: &str = "x-request-id";
+
+/// Context of the request, from its headers and connection.
+pub fn audit_context(headers: &HeaderMap, extensions: &Extensions) -> AuditContext {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok()).map(str::to_string);
+    AuditContext {
+        ip_address: extensions.get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(address)| address.ip()),
+        user_agent: header(USER_AGENT.as_str()),
+        correlation_id: header(CORRELATION_ID_HEADER)
+            .filter(|correlation_id| !correlation_id.is_empty())
+            .unwrap_or_else(|| Uuid::new_v4().to_string()),
+    }
+}
+
+impl FromRequestParts<AppState> for AuditContext {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &AppState) -> Result<Self, Self::Rejection> {
+        Ok(audit_context(&parts.headers, &parts.extensions))
+    }
+}
+
+/// Records the event in the audit log, signing a checkpoint when one is due. Failing to
+/// record it does not fail the request.
+pub async fn audit(state: &AppState, event: AuditEvent) {
+    let mut audit_log = state.audit_log.write().await;
+    let mut result = audit_log.record(event).await;
+    if result.is_ok() && audit_log.checkpoint_due() {
+        let keyring = state.keyring.read().await;
+        result = audit_log.checkpoint(keyring.active(), get_current_timestamp()).await;
+    }
+    if let Err(error) = result {
+        tracing::error!("Unexpected error when recording audit event: {}", error);
+    }
+}
+
+/// Why a sign-in failed with the error, unless the error is unexpected.
+pub fn login_failure_reason(error: &UserStoreError) -> Option<LoginFailureReason> {
+    match error {
+        UserStoreError::UserNotFound(_) | UserStoreError::InvalidCredentials(_) => {
+            Some(LoginFailureReason::InvalidCredentials)
+        }
+        UserStoreError::AccountDisabled(_) => Some(LoginFailureReason::AccountDisabled),
+        UserStoreError::AccountLocked(_) => Some(LoginFailureReason::AccountLocked),
+        UserStoreError::PasswordResetRequired(_) => Some(LoginFailureReason::PasswordResetRequired),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_audit_context() {
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_static("curl/8.0"));
+        headers.insert(CORRELATION_ID_HEADER, HeaderValue::from_static("request-1"));
+        let mut extensions = Extensions::new();
+        extensions.insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 3000))));
+        let context = audit_context(&headers, &extensions);
+        assert_eq!(context.ip_address, Some("127.0.0.1".parse().unwrap()));
+        assert_eq!(context.user_agent.as_deref(), Some("curl/8.0"));
+        assert_eq!(context.correlation_id, "request-1");
+
+        let context = audit_context(&HeaderMap::new(), &Extensions::new());
+        assert_eq!(context.ip_address, None);
+        assert!(Uuid::parse_str(&context.correlation_id).is_ok());
+    }
+}
@@ -0,0 +1,404 @@
+use crate::app_state::AppState;
+use crate::domain::{
+    parse_api_key, ApiKey, AuditContext, AuditEvent, AuditEventKind, Claims, Email, Password, PasswordError, Permission,
+    Session, Tenant, User, UserError, ADMIN_API_KEY_ACTOR, DEFAULT_TENANT_ID, MAX_FAILED_LOGIN_ATTEMPTS,
+};
+use crate::services::{
+    ApiKeyStore, ApiKeyStoreError, BannedTokenStore, ClientStore, ClientStoreError, KeyringError, SessionStore, SessionStoreError, UserStore,
+    UserStoreError,
+};
+use crate::utils::{audit, audit_context};
+use axum::extract::{FromRequestParts, MatchedPath, RawPathParams, Request, State};
+use axum::http::header::{AUTHORIZATION, USER_AGENT};
+use axum::http::request::Parts;
+use axum::http::{Extensions, HeaderMap, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::{Json, RequestExt};
+use axum_extra::extract::cookie::{Cookie, SameSite};
+use axum_extra::extract::CookieJar;
+use jsonwebtoken::get_current_timestamp;
+use std::net::IpAddr;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use secrecy::ExposeSecret;
+use serde_json::json;
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+
+pub const JWT_COOKIE_NAME: &str = "jwt";
+
+/// Session cookie carrying the access token of a signed-in user.
+pub fn jwt_cookie(token: String) -> Cookie<'static> {
+    Cookie::build((JWT_COOKIE_NAME, token))
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Lax)
+        .path("/")
+        .build()
+}
+
+/// Removal cookie for the access token, sent on logout.
+pub fn expired_jwt_cookie() -> Cookie<'static> {
+    let mut cookie = jwt_cookie(String::new());
+    cookie.make_removal();
+    cookie
+}
+
+#[derive(Error, Debug)]
+pub enum TokenVerificationError {
+    #[error("Invalid token")]
+    InvalidToken,
+    #[error("Token has been revoked")]
+    RevokedToken,
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+/// Verifies an access token: its signature and expiry, that it was not revoked,
+/// and that the client or the session it was issued for still exists.
+/// Using a session token records activity on the session.
+pub async fn verify_access_token(state: &AppState, token: &str) -> Result<Claims, TokenVerificationError> {
+    let claims = {
+        let keyring = state.keyring.read().await;
+        match keyring.verify::<Claims>(token) {
+            Ok(claims) => claims,
+            Err(KeyringError::UnexpectedError(error)) => {
+                return Err(TokenVerificationError::UnexpectedError(error.to_string()));
+            }
+            Err(_) => return Err(TokenVerificationError::InvalidToken),
+        }
+    };
+
+    let banned_token_store = state.banned_token_store.read().await;
+    match banned_token_store.contains_token(token).await {
+        Ok(false) => {}
+        Ok(true) => return Err(TokenVerificationError::RevokedToken),
+        Err(error) => return Err(TokenVerificationError::UnexpectedError(error.to_string())),
+    }
+
+    if let Some(client_id) = &claims.client_id {
+        let client_store = state.client_store.read().await;
+        match client_store.get_client(client_id).await {
+            Ok(_) => {}
+            Err(ClientStoreError::ClientNotFound(_)) => return Err(TokenVerificationError::RevokedToken),
+            Err(error) => return Err(TokenVerificationError::UnexpectedError(error.to_string())),
+        }
+    }
+
+    if let Some(session_id) = &claims.sid {
+        let mut session_store = state.session_store.write().await;
+        match session_store.touch_session(session_id, get_current_timestamp()).await {
+            Ok(()) => {}
+            Err(SessionStoreError::SessionNotFound(_)) => return Err(TokenVerificationError::RevokedToken),
+            Err(error) => return Err(TokenVerificationError::UnexpectedError(error.to_string())),
+        }
+    }
+
+    Ok(claims)
+}
+
+/// An API key, verified along with the user it belongs to.
+#[derive(Debug, Clone)]
+pub struct VerifiedApiKey {
+    pub api_key: ApiKey,
+    /// Scopes of the key the user still holds.
+    pub permissions: Vec<Permission>,
+}
+
+/// Verifies an API key: its secret and expiry, and that its user may still sign in.
+/// Using a key records its last use.
+pub async fn verify_api_key(state: &AppState, token: &str) -> Result<VerifiedApiKey, TokenVerificationError> {
+    let Some((id, secret)) = parse_api_key(token) else {
+        return Err(TokenVerificationError::InvalidToken);
+    };
+    let now = get_current_timestamp();
+    let mut api_key_store = state.api_key_store.write().await;
+    let api_key = match api_key_store.get_api_key(id).await {
+        Ok(api_key) if api_key.verify_secret(secret) && !api_key.is_expired(now) => api_key.clone(),
+        Ok(_) | Err(ApiKeyStoreError::ApiKeyNotFound(_)) => return Err(TokenVerificationError::InvalidToken),
+        Err(error) => return Err(TokenVerificationError::UnexpectedError(error.to_string())),
+    };
+
+    let Ok(email) = Email::parse_canonical(&api_key.email) else {
+        return Err(TokenVerificationError::RevokedToken);
+    };
+    let user_permissions = {
+        let user_store = state.user_store.read().await;
+        match user_store.get_user(&api_key.tenant_id, &email).await {
+            Ok(user) if !user.disabled && !user.is_locked() => user.permissions(),
+            Ok(_) | Err(UserStoreError::UserNotFound(_)) => return Err(TokenVerificationError::RevokedToken),
+            Err(error) => return Err(TokenVerificationError::UnexpectedError(error.to_string())),
+        }
+    };
+
+    if let Err(error) = api_key_store.touch_api_key(id, now).await {
+        return Err(TokenVerificationError::UnexpectedError(error.to_string()));
+    }
+    Ok(VerifiedApiKey {
+        permissions: api_key.permissions(&user_permissions),
+        api_key,
+    })
+}
+
+/// Checks the password of a user signing in, counting failed attempts towards locking
+/// the account. Locking the account is audited. A password older than the maximum age
+/// of the tenant's policy must be reset first.
+pub async fn check_password(
+    state: &AppState,
+    tenant_id: &str,
+    email: &Email,
+    password: &str,
+    context: &AuditContext,
+) -> Result<(), UserStoreError> {
+    let mut user_store = state.user_store.write().await;
+    let attempts = match user_store.validate_user(tenant_id, email, password).await {
+        Ok(()) => {
+            user_store.reset_failed_logins(tenant_id, email).await?;
+            let password_changed_at = user_store.get_user(tenant_id, email).await?.password_changed_at;
+            let policy = state.tenant(tenant_id).map(|tenant| tenant.settings.password_policy);
+            if policy.is_some_and(|policy| policy.is_expired(password_changed_at, get_current_timestamp())) {
+                return Err(UserStoreError::PasswordResetRequired(email.to_string()));
+            }
+            return Ok(());
+        }
+        Err(UserStoreError::InvalidCredentials(_)) => user_store.record_failed_login(tenant_id, email).await?,
+        Err(error) => return Err(error),
+    };
+    drop(user_store);
+    tracing::info!("Failed login attempt {} for {}", attempts, email);
+    if attempts == MAX_FAILED_LOGIN_ATTEMPTS {
+        let now = get_current_timestamp();
+        let event = AuditEvent::new(tenant_id, email.as_str(), AuditEventKind::AccountLocked, context, now);
+        audit(state, event).await;
+    }
+    Err(UserStoreError::InvalidCredentials(email.to_string()))
+}
+
+/// Rejects a new password found in the breached password dataset. Failing to search the
+/// dataset does not reject the password.
+pub fn reject_breached_password(state: &AppState, password: &Password) -> Result<(), PasswordError> {
+    match state.pwned_passwords.is_breached(password.expose()) {
+        Ok(true) => Err(PasswordError::Breached),
+        Ok(false) => Ok(()),
+        Err(error) => {
+            tracing::error!("Unexpected error when checking for breached password: {}", error);
+            Ok(())
+        }
+    }
+}
+
+/// Checks a password replacing the one of the user as signup checks passwords, and that it is
+/// not one of the last passwords of the user the tenant's policy forbids reusing.
+pub fn parse_new_password(state: &AppState, tenant: &Tenant, user: &User, raw: &str) -> Result<Password, UserError> {
+    let policy = &tenant.settings.password_policy;
+    let password =
+        Password::parse_with_policy(raw, user.email.as_str(), policy).map_err(UserError::InvalidPassword)?;
+    reject_breached_password(state, &password).map_err(UserError::InvalidPassword)?;
+    user.check_password_reuse(&password, policy.history_depth).map_err(UserError::InvalidPassword)?;
+    Ok(password)
+}
+
+/// Message telling a user why their account may not sign in.
+pub fn account_status_message(error: &UserStoreError) -> &'static str {
+    match error {
+        UserStoreError::AccountDisabled(_) => "Account is disabled",
+        UserStoreError::AccountLocked(_) => "Account is locked",
+        UserStoreError::PasswordResetRequired(_) => "Password reset required",
+        _ => "Account cannot sign in",
+    }
+}
+
+/// Opens a session for the user on the requesting device, returning its access token.
+/// The token carries the roles the user holds when signing in.
+pub async fn open_session(
+    state: &AppState,
+    tenant_id: &str,
+    email: &Email,
+    headers: &HeaderMap,
+    ip_address: IpAddr,
+) -> Result<String, anyhow::Error> {
+    let roles = state.user_store.read().await.get_user(tenant_id, email).await?.roles.clone();
+    let user_agent = headers.get(USER_AGENT).and_then(|value| value.to_str().ok());
+    let now = get_current_timestamp();
+    let keyring = state.keyring.read().await;
+    let session = Session::new(tenant_id, email.as_str(), user_agent, Some(ip_address), now, keyring.token_ttl());
+    let token = keyring.sign(&Claims::for_session(&session, &roles, now, keyring.token_ttl()))?;
+    let mut session_store = state.session_store.write().await;
+    session_store.add_session(session).await?;
+    Ok(token)
+}
+
+/// Access token of the request, from the `Authorization: Bearer` header or the `jwt` cookie.
+pub fn access_token(headers: &HeaderMap) -> Option<String> {
+    bearer_token(headers).map(str::to_string).or_else(|| {
+        CookieJar::from_headers(headers)
+            .get(JWT_COOKIE_NAME)
+            .map(|cookie| cookie.value().to_string())
+            .filter(|token| !token.is_empty())
+    })
+}
+
+/// Whether the token was issued for the tenant the request was resolved to.
+fn issued_for_request_tenant(claims: &Claims, extensions: &Extensions) -> bool {
+    match extensions.get::<Tenant>() {
+        Some(tenant) => claims.tenant_id() == tenant.id,
+        None => true,
+    }
+}
+
+/// Extractor for routes requiring a valid access token, issued for the tenant of the request.
+#[derive(Debug, Clone)]
+pub struct Authenticated {
+    pub claims: Claims,
+    pub token: String,
+}
+
+impl FromRequestParts<AppState> for Authenticated {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let Some(token) = access_token(&parts.headers) else {
+            let response = Json(json!({"error": "Missing token"}));
+            return Err((StatusCode::UNAUTHORIZED, response).into_response());
+        };
+        match verify_access_token(state, &token).await {
+            Ok(claims) if issued_for_request_tenant(&claims, &parts.extensions) => Ok(Self { claims, token }),
+            Err(TokenVerificationError::UnexpectedError(error)) => {
+                tracing::error!("Unexpected error when verifying token: {}", error);
+                let response = Json(json!({"error": "Unexpected error"}));
+                Err((StatusCode::INTERNAL_SERVER_ERROR, response).into_response())
+            }
+            Ok(_) | Err(_) => {
+                let response = Json(json!({"error": "Invalid token"}));
+                Err((StatusCode::UNAUTHORIZED, response).into_response())
+            }
+        }
+    }
+}
+
+/// Extracts the token from an `Authorization: Bearer <token>` header (RFC 6750, section 2.1).
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    let token = token.trim();
+    if scheme.eq_ignore_ascii_case("Bearer") && !token.is_empty() {
+        Some(token)
+    } else {
+        None
+    }
+}
+
+/// Extracts the credentials from an `Authorization: Basic <base64>` header (RFC 7617).
+pub fn basic_credentials(headers: &HeaderMap) -> Option<(String, String)> {
+    let value = headers.get(AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, encoded) = value.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("Basic") {
+        return None;
+    }
+    let decoded = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (user, password) = decoded.split_once(':')?;
+    Some((user.to_string(), password.to_string()))
+}
+
+/// Middleware for the `/admin` routes: requires the bootstrap admin API key as a bearer
+/// token, or an access token issued for the request's tenant. Each route then requires its
+/// own permission with [`Authorized`](crate::utils::Authorized). Requests that may change
+/// something are audited, whether they succeed or not.
+pub async fn require_admin(State(state): State<AppState>, mut request: Request, next: Next) -> Response {
+    let actor = match admin_actor(&state, request.headers(), request.extensions()).await {
+        Ok(actor) => actor,
+        Err(response) => return *response,
+    };
+    if request.method() == Method::GET {
+        return next.run(request).await;
+    }
+
+    let context = audit_context(request.headers(), request.extensions());
+    let tenant_id =
+        request.extensions().get::<Tenant>().map_or_else(|| DEFAULT_TENANT_ID.to_string(), |tenant| tenant.id.clone());
+    let path = request.extensions().get::<MatchedPath>().map_or(request.uri().path(), MatchedPath::as_str);
+    let action = format!("{} {}", request.method(), path);
+    let subject = request
+        .extract_parts::<RawPathParams>()
+        .await
+        .ok()
+        .and_then(|params| params.iter().find(|(name, _)| *name == "email").map(|(_, email)| email.to_string()));
+    let response = next.run(request).await;
+    let kind = AuditEventKind::AdminAction { action, status: response.status().as_u16() };
+    let event = AuditEvent::new(&tenant_id, &actor, kind, &context, get_current_timestamp());
+    audit(&state, event.with_subject(subject.as_deref())).await;
+    response
+}
+
+/// Whether the bearer token is the bootstrap admin API key.
+pub fn is_admin_api_key(state: &AppState, headers: &HeaderMap) -> bool {
+    match (&state.admin_api_key, bearer_token(headers)) {
+        (Some(api_key), Some(token)) => api_key.expose_secret().as_bytes().ct_eq(token.as_bytes()).into(),
+        _ => false,
+    }
+}
+
+/// Who is making an admin request: the bootstrap admin API key, or a signed-in user.
+async fn admin_actor(state: &AppState, headers: &HeaderMap, extensions: &Extensions) -> Result<String, Box<Response>> {
+    if is_admin_api_key(state, headers) {
+        return Ok(ADMIN_API_KEY_ACTOR.to_string());
+    }
+
+    let claims = match access_token(headers) {
+        Some(token) => verify_access_token(state, &token).await,
+        None => Err(TokenVerificationError::InvalidToken),
+    };
+    let (status, message) = match claims {
+        Ok(claims) if !issued_for_request_tenant(&claims, extensions) => {
+            (StatusCode::UNAUTHORIZED, "Admin credentials are missing or invalid")
+        }
+        Ok(claims) => return Ok(claims.sub),
+        Err(TokenVerificationError::UnexpectedError(error)) => {
+            tracing::error!("Unexpected error when verifying token: {}", error);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error")
+        }
+        Err(_) => (StatusCode::UNAUTHORIZED, "Admin credentials are missing or invalid"),
+    };
+    Err(Box::new((status, Json(json!({"error": message}))).into_response()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_bearer_token() {
+        assert_eq!(bearer_token(&headers("Bearer abc.def.ghi")), Some("abc.def.ghi"));
+        assert_eq!(bearer_token(&headers("bearer abc")), Some("abc"));
+    }
+
+    #[test]
+    fn test_bearer_token_missing_or_invalid() {
+        assert_eq!(bearer_token(&HeaderMap::new()), None);
+        assert_eq!(bearer_token(&headers("Basic YWxpY2U6c2VjcmV0")), None);
+        assert_eq!(bearer_token(&headers("Bearer ")), None);
+        assert_eq!(bearer_token(&headers("Bearer")), None);
+    }
+
+    #[test]
+    fn test_basic_credentials() {
+        let credentials = basic_credentials(&headers("Basic YWxpY2U6czNjcjN0OndpdGgtY29sb24="));
+        assert_eq!(credentials, Some(("alice".to_string(), "s3cr3t:with-colon".to_string())));
+    }
+
+    #[test]
+    fn test_basic_credentials_missing_or_invalid() {
+        assert_eq!(basic_credentials(&HeaderMap::new()), None);
+        assert_eq!(basic_credentials(&headers("Bearer YWxpY2U6c2VjcmV0")), None);
+        assert_eq!(basic_credentials(&headers("Basic not-base64!")), None);
+        assert_eq!(basic_credentials(&headers("Basic YWxpY2U=")), None);
+    }
+}
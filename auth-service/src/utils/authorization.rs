@@ -0,0 +1,150 @@
+use crate::app_state::AppState;
+use crate::domain::{Claims, Permission, Tenant, DEFAULT_TENANT_ID};
+use crate::utils::{bearer_token, is_admin_api_key, Authenticated};
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use jsonwebtoken::get_current_timestamp;
+use serde_json::json;
+use std::marker::PhantomData;
+
+/// Names the permission an [`Authorized`] route requires.
+pub trait RequiredPermission {
+    const PERMISSION: Permission;
+}
+
+macro_rules! required_permissions {
+    ($($marker:ident => $permission:ident),* $(,)?) => {
+        $(
+            #[derive(Debug, Clone, Copy)]
+            pub struct $marker;
+
+            impl RequiredPermission for $marker {
+                const PERMISSION: Permission = Permission::$permission;
+            }
+        )*
+    };
+}
+
+required_permissions! {
+    RequireProfileRead => ProfileRead,
+    RequireProfileWrite => ProfileWrite,
+    RequireUsersRead => UsersRead,
+    RequireUsersWrite => UsersWrite,
+    RequireUsersDelete => UsersDelete,
+    RequireRolesAssign => RolesAssign,
+    RequireClientsManage => ClientsManage,
+    RequireKeysRotate => KeysRotate,
+}
+
+/// Extractor for routes requiring a valid access token granting a permission, e.g.
+/// `Authorized<RequireUsersRead>`. Rejects with 401 like [`Authenticated`], or with 403
+/// when the token lacks the permission. The bootstrap admin API key holds every permission.
+#[derive(Debug, Clone)]
+pub struct Authorized<P: RequiredPermission> {
+    pub claims: Claims,
+    pub token: String,
+    permission: PhantomData<P>,
+}
+
+impl<P: RequiredPermission + Send + Sync> FromRequestParts<AppState> for Authorized<P> {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        if is_admin_api_key(state, &parts.headers) {
+            let tenant_id = parts.extensions.get::<Tenant>().map_or(DEFAULT_TENANT_ID, |tenant| &tenant.id);
+            let claims = Claims::for_admin_api_key(tenant_id, get_current_timestamp());
+            let token = bearer_token(&parts.headers).unwrap_or_default().to_string();
+            return Ok(Self { claims, token, permission: PhantomData });
+        }
+        let Authenticated { claims, token } = Authenticated::from_request_parts(parts, state).await?;
+        if !claims.has_permission(P::PERMISSION) {
+            let response = Json(json!({"error": format!("Missing permission {}", P::PERMISSION)}));
+            return Err((StatusCode::FORBIDDEN, response).into_response());
+        }
+        Ok(Self {
+            claims,
+            token,
+            permission: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Role, Session, User, ADMIN_API_KEY_ACTOR, DEFAULT_TENANT_ID};
+    use crate::services::{HashmapUserStore, Keyring, SessionStore, UserStore};
+    use axum::http::header::AUTHORIZATION;
+    use axum::http::Request;
+    use jsonwebtoken::get_current_timestamp;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    const TTL: u64 = 600;
+
+    async fn state_with_user(roles: &[Role]) -> (AppState, String) {
+        let mut user = User::try_new("alice@example.com", "StrongPassword123!", false).unwrap();
+        user.set_roles(roles);
+        let mut user_store = HashmapUserStore::default();
+        user_store.add_user(user).await.unwrap();
+        let keyring = Keyring::new(TTL).unwrap();
+        let state = AppState::new(Arc::new(RwLock::new(user_store)), Arc::new(RwLock::new(keyring)));
+
+        let now = get_current_timestamp();
+        let session = Session::new(DEFAULT_TENANT_ID, "alice@example.com", None, None, now, TTL);
+        let claims = Claims::for_session(&session, roles, now, TTL);
+        let token = state.keyring.read().await.sign(&claims).unwrap();
+        state.session_store.write().await.add_session(session).await.unwrap();
+        (state, token)
+    }
+
+    async fn extract<P: RequiredPermission + Send + Sync>(
+        state: &AppState,
+        token: Option<&str>,
+    ) -> Result<Authorized<P>, Response> {
+        let mut request = Request::builder();
+        if let Some(token) = token {
+            request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let (mut parts, _) = request.body(()).unwrap().into_parts();
+        Authorized::<P>::from_request_parts(&mut parts, state).await
+    }
+
+    #[tokio::test]
+    async fn test_authorized_with_permission() {
+        let (state, token) = state_with_user(&[Role::Support]).await;
+        let authorized = extract::<RequireUsersRead>(&state, Some(&token)).await.unwrap();
+        assert_eq!(authorized.claims.sub, "alice@example.com");
+        assert_eq!(authorized.claims.roles, vec![Role::Support]);
+    }
+
+    #[tokio::test]
+    async fn test_authorized_without_permission() {
+        let (state, token) = state_with_user(&[Role::User]).await;
+        let rejection = extract::<RequireUsersRead>(&state, Some(&token)).await.unwrap_err();
+        assert_eq!(rejection.status(), StatusCode::FORBIDDEN);
+        assert!(extract::<RequireProfileRead>(&state, Some(&token)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_admin_api_key_holds_every_permission() {
+        let (state, _) = state_with_user(&[Role::User]).await;
+        let state = state.with_admin_api_key(Some("admin-key".into()));
+        let authorized = extract::<RequireRolesAssign>(&state, Some("admin-key")).await.unwrap();
+        assert_eq!(authorized.claims.sub, ADMIN_API_KEY_ACTOR);
+        let rejection = extract::<RequireRolesAssign>(&state, Some("other-key")).await.unwrap_err();
+        assert_eq!(rejection.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_authorized_without_token() {
+        let (state, _) = state_with_user(&[Role::Admin]).await;
+        let rejection = extract::<RequireUsersRead>(&state, None).await.unwrap_err();
+        assert_eq!(rejection.status(), StatusCode::UNAUTHORIZED);
+        let rejection = extract::<RequireUsersRead>(&state, Some("string")).await.unwrap_err();
+        assert_eq!(rejection.status(), StatusCode::UNAUTHORIZED);
+    }
+}
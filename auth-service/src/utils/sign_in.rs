@@ -0,0 +1,144 @@
+use crate::app_state::AppState;
+use crate::domain::{
+    AuditContext, AuditEvent, AuditEventKind, Email, KnownDevice, LoginAttempt, LoginRisk, PendingAuthorization, Tenant,
+    LOGIN_ATTEMPT_TTL_SECONDS,
+};
+use crate::services::{EmailMessage, LoginAttemptStore, UserStore, UserStoreError};
+use crate::utils::audit;
+use jsonwebtoken::get_current_timestamp;
+use std::net::{IpAddr, Ipv4Addr};
+use tracing::error;
+
+pub const NEW_SIGN_IN_SUBJECT: &str = "New sign-in to your account";
+pub const TWO_FACTOR_CODE_SUBJECT: &str = "Your sign-in code";
+
+/// A sign-in being made, with the device it comes from and what is unusual about it.
+#[derive(Debug, Clone)]
+pub struct SignInAssessment {
+    pub device: KnownDevice,
+    pub risk: LoginRisk,
+}
+
+/// Compares the device the request comes from with the devices the user is known to sign in
+/// from, recording an audit event when the sign-in is unusual.
+pub async fn assess_sign_in(
+    state: &AppState,
+    tenant_id: &str,
+    email: &Email,
+    context: &AuditContext,
+) -> Result<SignInAssessment, UserStoreError> {
+    // The address is always known when serving with connect info.
+    let ip_address = context.ip_address.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    let location = state.geoip_database.lookup(ip_address).cloned();
+    let now = get_current_timestamp();
+    let device = KnownDevice::observed(context.user_agent.as_deref(), ip_address, location, now);
+    let risk = {
+        let user_store = state.user_store.read().await;
+        LoginRisk::assess(&user_store.get_user(tenant_id, email).await?.known_devices, &device)
+    };
+    if risk.is_risky() {
+        let kind = AuditEventKind::LoginAnomaly {
+            new_device: risk.new_device,
+            impossible_travel: risk.impossible_travel,
+        };
+        audit(state, AuditEvent::new(tenant_id, email.as_str(), kind, context, now)).await;
+    }
+    Ok(SignInAssessment { device, risk })
+}
+
+/// Whether a sign-in must pass a second factor: the user asked for it, the tenant requires it,
+/// or the sign-in is unusual and risky sign-ins are stepped up.
+pub async fn requires_2fa(
+    state: &AppState,
+    tenant: &Tenant,
+    email: &Email,
+    assessment: &SignInAssessment,
+) -> bool {
+    if tenant.settings.require_2fa || (state.step_up_risky_logins && assessment.risk.is_risky()) {
+        return true;
+    }
+    let user_store = state.user_store.read().await;
+    user_store
+        .get_user(&tenant.id, email)
+        .await
+        .map(|user| user.requires_2fa)
+        .unwrap_or_default()
+}
+
+/// Holds a sign-in that passed its first factor until the user enters the code emailed to
+/// them, returning the id of the login attempt the code is verified against. A sign-in at
+/// `/authorize` carries the authorization request to answer once the code is verified.
+pub async fn challenge_2fa(
+    state: &AppState,
+    tenant_id: &str,
+    email: &Email,
+    method: &str,
+    assessment: SignInAssessment,
+    authorization: Option<PendingAuthorization>,
+    context: &AuditContext,
+) -> Result<String, anyhow::Error> {
+    let now = get_current_timestamp();
+    let SignInAssessment { device, risk } = assessment;
+    let mut login_attempt = LoginAttempt::new(tenant_id, email, method, device, risk, now);
+    if let Some(authorization) = authorization {
+        login_attempt = login_attempt.with_authorization(authorization);
+    }
+    let id = login_attempt.id.clone();
+    let message = two_factor_code_email(email.as_str(), &login_attempt.code);
+    state.login_attempt_store.write().await.add_login_attempt(login_attempt).await?;
+    state.email_client.write().await.send_email(message).await?;
+    audit(state, AuditEvent::new(tenant_id, email.as_str(), AuditEventKind::TwoFactorChallenged, context, now)).await;
+    Ok(id)
+}
+
+fn two_factor_code_email(email: &str, code: &str) -> EmailMessage {
+    EmailMessage {
+        recipient: email.to_string(),
+        subject: TWO_FACTOR_CODE_SUBJECT.to_string(),
+        content: format!(
+            "Your sign-in code is: {}\n\nIt expires in {} minutes. If you are not signing in, change your password.",
+            code,
+            LOGIN_ATTEMPT_TTL_SECONDS / 60
+        ),
+    }
+}
+
+/// Completes a sign-in: remembers its device and, when the sign-in is unusual, notifies the
+/// user by email. Failing either does not fail the sign-in.
+pub async fn complete_sign_in(
+    state: &AppState,
+    tenant_id: &str,
+    email: &Email,
+    assessment: SignInAssessment,
+) {
+    let SignInAssessment { device, risk } = assessment;
+    if risk.is_risky() {
+        let notification = new_sign_in_email(email.as_str(), &device, risk);
+        if let Err(error) = state.email_client.write().await.send_email(notification).await {
+            error!("Unexpected error when sending new sign-in email: {}", error);
+        }
+    }
+    let mut user_store = state.user_store.write().await;
+    if let Err(error) = user_store.remember_device(tenant_id, email, device).await {
+        error!("Unexpected error when remembering device: {}", error);
+    }
+}
+
+fn new_sign_in_email(email: &str, device: &KnownDevice, risk: LoginRisk) -> EmailMessage {
+    let reason = if risk.new_device { "a new device" } else { "an unusual location" };
+    let country = device.location.as_ref().and_then(|location| location.country.as_deref());
+    let origin = match country {
+        Some(country) => format!("{} ({})", device.ip_address, country),
+        None => device.ip_address.to_string(),
+    };
+    EmailMessage {
+        recipient: email.to_string(),
+        subject: NEW_SIGN_IN_SUBJECT.to_string(),
+        content: format!(
+            "Your account was just signed in to from {}: {}, from {}.\n\n\
+             If this was you, there is nothing to do. Otherwise, change your password and sign out \
+             of your other sessions.",
+            reason, device.user_agent_family, origin
+        ),
+    }
+}
@@ -0,0 +1,86 @@
+use crate::app_state::AppState;
+use crate::domain::{Tenant, DEFAULT_TENANT_ID};
+use axum::extract::{Request, State};
+use axum::http::header::HOST;
+use axum::http::uri::PathAndQuery;
+use axum::http::{StatusCode, Uri};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::json;
+
+/// Header naming the tenant of a request.
+pub const TENANT_HEADER: &str = "x-tenant-id";
+/// Path prefix naming the tenant of a request, as in `/t/acme/api/login`.
+pub const TENANT_PATH_PREFIX: &str = "/t/";
+
+/// Middleware resolving the [`Tenant`] of a request, which handlers extract as an
+/// `Extension<Tenant>`. The tenant is named by the `X-Tenant-ID` header, else by the
+/// `/t/{tenant_id}` path prefix, which is stripped before routing, else by the host name.
+/// Requests naming none belong to the default tenant.
+pub async fn resolve_tenant(State(state): State<AppState>, mut request: Request, next: Next) -> Response {
+    let path_tenant_id = strip_tenant_prefix(request.uri_mut());
+    let header_tenant_id = request
+        .headers()
+        .get(TENANT_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let host = request
+        .headers()
+        .get(HOST)
+        .and_then(|value| value.to_str().ok())
+        .map(|host| host.split(':').next().unwrap_or(host).to_string());
+    let tenant = match (header_tenant_id.or(path_tenant_id), host) {
+        (Some(tenant_id), _) => state.tenant(&tenant_id),
+        (None, Some(host)) => match state.tenant_for_host(&host) {
+            Some(tenant) => Some(tenant),
+            None => state.tenant(DEFAULT_TENANT_ID),
+        },
+        (None, None) => state.tenant(DEFAULT_TENANT_ID),
+    };
+    match tenant {
+        Some(tenant) => {
+            request.extensions_mut().insert::<Tenant>(tenant);
+            next.run(request).await
+        }
+        None => (StatusCode::NOT_FOUND, Json(json!({"error": "Unknown tenant"}))).into_response(),
+    }
+}
+
+/// Removes the tenant prefix from the URI, returning the tenant id it named.
+fn strip_tenant_prefix(uri: &mut Uri) -> Option<String> {
+    let rest = uri.path().strip_prefix(TENANT_PATH_PREFIX)?;
+    let (tenant_id, path) = match rest.find('/') {
+        Some(index) => (&rest[..index], &rest[index..]),
+        None => (rest, "/"),
+    };
+    let tenant_id = tenant_id.to_string();
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{}?{}", path, query),
+        None => path.to_string(),
+    };
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(PathAndQuery::try_from(path_and_query).ok()?);
+    *uri = Uri::from_parts(parts).ok()?;
+    Some(tenant_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_tenant_prefix() {
+        let mut uri: Uri = "/t/acme/api/login?next=%2F".parse().unwrap();
+        assert_eq!(strip_tenant_prefix(&mut uri), Some("acme".to_string()));
+        assert_eq!(uri, "/api/login?next=%2F");
+
+        let mut uri: Uri = "/t/acme".parse().unwrap();
+        assert_eq!(strip_tenant_prefix(&mut uri), Some("acme".to_string()));
+        assert_eq!(uri, "/");
+
+        let mut uri: Uri = "/api/login".parse().unwrap();
+        assert_eq!(strip_tenant_prefix(&mut uri), None);
+        assert_eq!(uri, "/api/login");
+    }
+}
@@ -0,0 +1,71 @@
+use crate::helpers::{TestApp, TEST_ADMIN_API_KEY};
+use auth_service::routes::admin::{AdminErrorResponse, CreateClientResponse};
+use auth_service::routes::TokenResponse;
+use reqwest::StatusCode;
+use serde_json::json;
+
+#[tokio::test]
+async fn admin_creates_client_that_can_obtain_tokens() {
+    let app = TestApp::new().await;
+    let body = json!({"clientId": "reporting-job", "scopes": ["reports:read"]});
+    let response = app.post_admin_client(TEST_ADMIN_API_KEY, &body).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let client = response.json::<CreateClientResponse>().await.unwrap();
+    assert_eq!(client.client_id, "reporting-job");
+    assert_eq!(client.scopes, vec!["reports:read"]);
+
+    let form = json!({"grant_type": "client_credentials"});
+    let response = app.post_oauth_token(&client.client_id, &client.client_secret, &form).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let tokens = response.json::<TokenResponse>().await.unwrap();
+    assert_eq!(tokens.scope.as_deref(), Some("reports:read"));
+}
+
+#[tokio::test]
+async fn admin_generates_client_id_when_missing() {
+    let app = TestApp::new().await;
+    let response = app.post_admin_client(TEST_ADMIN_API_KEY, &json!({"scopes": []})).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let client = response.json::<CreateClientResponse>().await.unwrap();
+    assert!(!client.client_id.is_empty());
+    assert!(!client.client_secret.is_empty());
+}
+
+#[tokio::test]
+async fn admin_rejects_invalid_or_duplicate_client() {
+    let app = TestApp::new().await;
+    let body = json!({"clientId": "reporting-job", "scopes": ["reports read"]});
+    let response = app.post_admin_client(TEST_ADMIN_API_KEY, &body).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = json!({"clientId": "reporting-job", "scopes": ["reports:read"]});
+    let response = app.post_admin_client(TEST_ADMIN_API_KEY, &body).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = app.post_admin_client(TEST_ADMIN_API_KEY, &body).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn admin_revokes_client() {
+    let app = TestApp::new().await;
+    let (client_id, client_secret) = app.register_confidential_client(&["reports:read"]).await;
+    let response = app.delete_admin_client(TEST_ADMIN_API_KEY, &client_id).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let form = json!({"grant_type": "client_credentials"});
+    let response = app.post_oauth_token(&client_id, &client_secret, &form).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.delete_admin_client(TEST_ADMIN_API_KEY, &client_id).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.json::<AdminErrorResponse>().await.unwrap().error, "Client not found");
+}
+
+#[tokio::test]
+async fn admin_requires_api_key() {
+    let app = TestApp::new().await;
+    let body = json!({"scopes": ["reports:read"]});
+    let response = app.post_admin_client("wrong-api-key", &body).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.delete_admin_client("wrong-api-key", "reporting-job").await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
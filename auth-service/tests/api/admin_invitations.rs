@@ -0,0 +1,95 @@
+use crate::helpers::{random_email, TestApp, TEST_ADMIN_API_KEY};
+use auth_service::domain::{Role, SignupMode};
+use auth_service::routes::admin::{AdminErrorResponse, InvitationResponse};
+use auth_service::routes::{SignupResponse, VerifiedTokenResponse};
+use jsonwebtoken::get_current_timestamp;
+use reqwest::StatusCode;
+use serde_json::json;
+
+const PASSWORD: &str = "StrongPassword123!";
+
+async fn invite(app: &TestApp, email: &str, role: Option<&str>) -> InvitationResponse {
+    let body = json!({"email": email, "role": role});
+    let response = app.post_admin_invitation(TEST_ADMIN_API_KEY, &body).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    response.json::<InvitationResponse>().await.unwrap()
+}
+
+async fn signup_error(response: reqwest::Response) -> String {
+    match response.json::<SignupResponse>().await.unwrap() {
+        SignupResponse::Error(error) => error,
+        SignupResponse::Message(message) => panic!("Unexpected message: {}", message),
+    }
+}
+
+#[tokio::test]
+async fn admin_invites_user_who_signs_up_with_role() {
+    let app = TestApp::with_signup_mode(SignupMode::InviteOnly).await;
+    let email = random_email();
+    let invitation = invite(&app, &email, Some("support")).await;
+    assert_eq!(invitation.email, email);
+    assert_eq!(invitation.role, Some(Role::Support));
+    assert!(invitation.expires_at > get_current_timestamp());
+
+    let body = json!({"email": email, "password": PASSWORD, "requires2FA": false, "inviteToken": invitation.token});
+    let response = app.post_signup(&body).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let token = app.login(&email, PASSWORD).await;
+    let response = app.post_verify_token(&json!({"token": token})).await;
+    assert_eq!(response.json::<VerifiedTokenResponse>().await.unwrap().roles, vec![Role::Support]);
+
+    // The invitation is consumed by the signup.
+    let body = json!({"email": random_email(), "password": PASSWORD, "requires2FA": false, "inviteToken": invitation.token});
+    let response = app.post_signup(&body).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(signup_error(response).await, "Invalid invitation");
+}
+
+#[tokio::test]
+async fn invite_only_signup_requires_invitation_for_the_email() {
+    let app = TestApp::with_signup_mode(SignupMode::InviteOnly).await;
+    let email = random_email();
+    let response = app.post_signup(&json!({"email": email, "password": PASSWORD, "requires2FA": false})).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(signup_error(response).await, "Invitation required");
+
+    let invitation = invite(&app, &email, None).await;
+    let body = json!({"email": random_email(), "password": PASSWORD, "requires2FA": false, "inviteToken": invitation.token});
+    let response = app.post_signup(&body).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(signup_error(response).await, "Invalid invitation");
+
+    // A rejected signup leaves the invitation usable.
+    let body = json!({"email": email, "password": "Weak!", "requires2FA": false, "inviteToken": invitation.token});
+    assert_eq!(app.post_signup(&body).await.status(), StatusCode::BAD_REQUEST);
+    let body = json!({"email": email, "password": PASSWORD, "requires2FA": false, "inviteToken": invitation.token});
+    assert_eq!(app.post_signup(&body).await.status(), StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn closed_signup_rejects_everyone() {
+    let app = TestApp::with_signup_mode(SignupMode::Closed).await;
+    let email = random_email();
+    let invitation = invite(&app, &email, None).await;
+    let body = json!({"email": email, "password": PASSWORD, "requires2FA": false, "inviteToken": invitation.token});
+    let response = app.post_signup(&body).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(signup_error(response).await, "Signup is closed");
+}
+
+#[tokio::test]
+async fn admin_rejects_invalid_invitation() {
+    let app = TestApp::new().await;
+    let body = json!({"email": "not-an-email"});
+    let response = app.post_admin_invitation(TEST_ADMIN_API_KEY, &body).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    for expires_in in [0, u64::MAX] {
+        let body = json!({"email": random_email(), "expiresIn": expires_in});
+        let response = app.post_admin_invitation(TEST_ADMIN_API_KEY, &body).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "Input: {}", expires_in);
+    }
+    let response = app.post_admin_invitation("string", &json!({"email": random_email()})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let error = response.json::<AdminErrorResponse>().await.unwrap();
+    assert_eq!(error.error, "Admin credentials are missing or invalid");
+}
@@ -0,0 +1,48 @@
+use crate::helpers::{random_email, session_token, TestApp, TEST_ADMIN_API_KEY};
+use auth_service::domain::{Tenant, TenantSettings};
+use auth_service::routes::admin::RotateSigningKeyResponse;
+use jsonwebtoken::jwk::JwkSet;
+use reqwest::StatusCode;
+use serde_json::json;
+
+const PASSWORD: &str = "StrongPassword123!";
+
+#[tokio::test]
+async fn admin_rotates_signing_key() {
+    let app = TestApp::new().await;
+    let token = app.login(&random_email(), PASSWORD).await;
+    let retired_kid = app.app_state.keyring.read().await.active().kid().to_string();
+
+    let response = app.post_admin_rotate_signing_key(TEST_ADMIN_API_KEY).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let active_kid = response.json::<RotateSigningKeyResponse>().await.unwrap().kid;
+    assert_ne!(active_kid, retired_kid);
+    assert_eq!(app.app_state.keyring.read().await.active().kid(), active_kid);
+    let jwks = app.get_jwks().await.json::<JwkSet>().await.unwrap();
+    assert!(jwks.find(&retired_kid).is_some());
+    assert!(jwks.find(&active_kid).is_some());
+
+    // Tokens signed before the rotation keep verifying.
+    let response = app.post_verify_token(&json!({"token": token})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn rotating_signing_key_requires_permission_in_default_tenant() {
+    let acme = Tenant::try_new("acme", "Acme", &[], TenantSettings::default()).unwrap();
+    let app = TestApp::with_tenants(vec![acme]).await;
+    let support = random_email();
+    app.login(&support, PASSWORD).await;
+    app.put_admin_user_roles(TEST_ADMIN_API_KEY, &support, &json!({"roles": ["support"]})).await;
+    let response = app.post_login(&json!({"email": support, "password": PASSWORD})).await;
+    let token = session_token(&response).unwrap();
+    let kid = app.app_state.keyring.read().await.active().kid().to_string();
+
+    let response = app.post_admin_rotate_signing_key("invalid").await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.post_admin_rotate_signing_key(&token).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.tenant("acme").post_admin_rotate_signing_key(TEST_ADMIN_API_KEY).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(app.app_state.keyring.read().await.active().kid(), kid);
+}
@@ -0,0 +1,65 @@
+use crate::helpers::{random_email, session_token, TestApp, TEST_ADMIN_API_KEY};
+use auth_service::domain::{Permission, Role};
+use auth_service::routes::admin::SetRolesResponse;
+use auth_service::routes::VerifiedTokenResponse;
+use reqwest::StatusCode;
+use serde_json::json;
+
+const PASSWORD: &str = "StrongPassword123!";
+
+#[tokio::test]
+async fn admin_assigns_roles_embedded_in_new_tokens() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    let old_token = app.login(&email, PASSWORD).await;
+
+    let body = json!({"roles": ["support", "user"]});
+    let response = app.put_admin_user_roles(TEST_ADMIN_API_KEY, &email, &body).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let assigned = response.json::<SetRolesResponse>().await.unwrap();
+    assert_eq!(assigned.roles, vec![Role::Support, Role::User]);
+    assert!(assigned.permissions.contains(&Permission::UsersRead));
+
+    // Tokens issued with the previous roles are revoked.
+    let response = app.post_verify_token(&json!({"token": old_token})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app.post_login(&json!({"email": email, "password": PASSWORD})).await;
+    let token = session_token(&response).unwrap();
+    let response = app.post_verify_token(&json!({"token": token})).await;
+    let verified = response.json::<VerifiedTokenResponse>().await.unwrap();
+    assert_eq!(verified.roles, vec![Role::Support, Role::User]);
+    assert_eq!(verified.permissions, assigned.permissions);
+}
+
+#[tokio::test]
+async fn admin_assigns_roles_to_unknown_user() {
+    let app = TestApp::new().await;
+    let body = json!({"roles": ["admin"]});
+    let response = app.put_admin_user_roles(TEST_ADMIN_API_KEY, &random_email(), &body).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn admin_assigns_unknown_role() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    app.login(&email, PASSWORD).await;
+    let requests = [json!({"roles": ["superuser"]}), json!({"roles": "admin"}), json!({})];
+    for request in requests.iter() {
+        let response = app.put_admin_user_roles(TEST_ADMIN_API_KEY, &email, request).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY, "Input: {:?}", request);
+    }
+}
+
+#[tokio::test]
+async fn admin_assigns_roles_requires_admin() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    let token = app.login(&email, PASSWORD).await;
+    let body = json!({"roles": ["admin"]});
+    let response = app.put_admin_user_roles(&token, &email, &body).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.put_admin_user_roles("string", &email, &body).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,221 @@
+use crate::helpers::{random_email, session_token, TestApp, TEST_ADMIN_API_KEY};
+use auth_service::domain::MAX_FAILED_LOGIN_ATTEMPTS;
+use auth_service::routes::admin::{AdminUserResponse, ListUsersResponse};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+const PASSWORD: &str = "StrongPassword123!";
+
+async fn login_status(app: &TestApp, email: &str, password: &str) -> StatusCode {
+    app.post_login(&json!({"email": email, "password": password})).await.status()
+}
+
+#[tokio::test]
+async fn admin_lists_users_with_search_and_pagination() {
+    let app = TestApp::new().await;
+    let tag = Uuid::new_v4().simple().to_string();
+    for index in 0..3 {
+        app.login(&format!("{}-{}@example.com", tag, index), PASSWORD).await;
+    }
+    app.login(&random_email(), PASSWORD).await;
+
+    let response = app.get_admin_users(TEST_ADMIN_API_KEY, &[("q", &tag), ("limit", "2")]).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let page = response.json::<ListUsersResponse>().await.unwrap();
+    assert_eq!(page.total, 3);
+    let emails: Vec<String> = page.users.into_iter().map(|user| user.email).collect();
+    assert_eq!(emails, vec![format!("{}-0@example.com", tag), format!("{}-1@example.com", tag)]);
+
+    let response = app.get_admin_users(TEST_ADMIN_API_KEY, &[("q", &tag), ("offset", "2")]).await;
+    let page = response.json::<ListUsersResponse>().await.unwrap();
+    assert_eq!(page.users.len(), 1);
+    assert_eq!(page.users[0].email, format!("{}-2@example.com", tag));
+
+    let response = app.get_admin_users(TEST_ADMIN_API_KEY, &[]).await;
+    assert_eq!(response.json::<ListUsersResponse>().await.unwrap().total, 4);
+}
+
+#[tokio::test]
+async fn admin_lists_users_invalid_limit() {
+    let app = TestApp::new().await;
+    for limit in ["0", "101"] {
+        let response = app.get_admin_users(TEST_ADMIN_API_KEY, &[("limit", limit)]).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "Limit: {}", limit);
+    }
+}
+
+#[tokio::test]
+async fn admin_views_user_without_password_material() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    app.login(&email, PASSWORD).await;
+    let response = app.get_admin_user(TEST_ADMIN_API_KEY, &email).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.text().await.unwrap();
+    assert!(!body.contains(PASSWORD));
+    let user: Value = serde_json::from_str(&body).unwrap();
+    assert!(user.get("password").is_none());
+    let user: AdminUserResponse = serde_json::from_value(user).unwrap();
+    assert_eq!(user.email, email);
+    assert!(!user.disabled && !user.locked);
+
+    let listing = app.get_admin_users(TEST_ADMIN_API_KEY, &[]).await.text().await.unwrap();
+    assert!(!listing.contains(PASSWORD));
+}
+
+#[tokio::test]
+async fn admin_disables_and_enables_user() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    let token = app.login(&email, PASSWORD).await;
+
+    let response = app.post_admin_user_action(TEST_ADMIN_API_KEY, &email, "disable").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.json::<AdminUserResponse>().await.unwrap().disabled);
+    assert_eq!(login_status(&app, &email, PASSWORD).await, StatusCode::FORBIDDEN);
+    let response = app.post_verify_token(&json!({"token": token})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app.post_admin_user_action(TEST_ADMIN_API_KEY, &email, "enable").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(login_status(&app, &email, PASSWORD).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn admin_forces_password_reset() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    let token = app.login(&email, PASSWORD).await;
+    let response = app.post_admin_user_action(TEST_ADMIN_API_KEY, &email, "force-password-reset").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.json::<AdminUserResponse>().await.unwrap().password_reset_required);
+    assert_eq!(login_status(&app, &email, PASSWORD).await, StatusCode::FORBIDDEN);
+    let response = app.post_verify_token(&json!({"token": token})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn admin_resets_2fa() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    let body = json!({"email": email, "password": PASSWORD, "requires2FA": true});
+    app.post_signup(&body).await;
+    assert_eq!(login_status(&app, &email, PASSWORD).await, StatusCode::PARTIAL_CONTENT);
+    let response = app.post_admin_user_action(TEST_ADMIN_API_KEY, &email, "reset-2fa").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!response.json::<AdminUserResponse>().await.unwrap().requires_2fa);
+    assert_eq!(login_status(&app, &email, PASSWORD).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn admin_unlocks_locked_user() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    app.login(&email, PASSWORD).await;
+    for _ in 0..MAX_FAILED_LOGIN_ATTEMPTS {
+        assert_eq!(login_status(&app, &email, "wrong_password").await, StatusCode::UNAUTHORIZED);
+    }
+    assert_eq!(login_status(&app, &email, PASSWORD).await, StatusCode::FORBIDDEN);
+    let user = app.get_admin_user(TEST_ADMIN_API_KEY, &email).await;
+    assert!(user.json::<AdminUserResponse>().await.unwrap().locked);
+
+    let response = app.post_admin_user_action(TEST_ADMIN_API_KEY, &email, "unlock").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let user = response.json::<AdminUserResponse>().await.unwrap();
+    assert!(!user.locked);
+    assert_eq!(user.failed_login_attempts, 0);
+    assert_eq!(login_status(&app, &email, PASSWORD).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn admin_deletes_user() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    let token = app.login(&email, PASSWORD).await;
+    let response = app.delete_admin_user(TEST_ADMIN_API_KEY, &email).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app.get_admin_user(TEST_ADMIN_API_KEY, &email).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(login_status(&app, &email, PASSWORD).await, StatusCode::UNAUTHORIZED);
+    let response = app.post_verify_token(&json!({"token": token})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn admin_manages_unknown_user() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    assert_eq!(app.get_admin_user(TEST_ADMIN_API_KEY, &email).await.status(), StatusCode::NOT_FOUND);
+    assert_eq!(app.delete_admin_user(TEST_ADMIN_API_KEY, &email).await.status(), StatusCode::NOT_FOUND);
+    for action in ["disable", "enable", "force-password-reset", "reset-2fa", "unlock"] {
+        let response = app.post_admin_user_action(TEST_ADMIN_API_KEY, &email, action).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND, "Action: {}", action);
+    }
+}
+
+#[tokio::test]
+async fn admin_role_grants_access_to_admin_api() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    let token = app.login(&email, PASSWORD).await;
+    let response = app.get_admin_users(&token, &[]).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let body = json!({"roles": ["admin"]});
+    app.put_admin_user_roles(TEST_ADMIN_API_KEY, &email, &body).await;
+    let response = app.post_login(&json!({"email": email, "password": PASSWORD})).await;
+    let token = session_token(&response).unwrap();
+    let response = app.get_admin_users(&token, &[]).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.get_admin_users("string", &[]).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn support_role_reads_and_manages_users_but_not_roles() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    app.login(&email, PASSWORD).await;
+    let body = json!({"roles": ["support"]});
+    app.put_admin_user_roles(TEST_ADMIN_API_KEY, &email, &body).await;
+    let response = app.post_login(&json!({"email": email, "password": PASSWORD})).await;
+    let token = session_token(&response).unwrap();
+    let customer = random_email();
+    app.login(&customer, PASSWORD).await;
+
+    assert_eq!(app.get_admin_users(&token, &[]).await.status(), StatusCode::OK);
+    assert_eq!(app.get_admin_user(&token, &customer).await.status(), StatusCode::OK);
+    assert_eq!(app.get_admin_user_audit_events(&token, &customer).await.status(), StatusCode::OK);
+    assert_eq!(app.post_admin_user_action(&token, &customer, "unlock").await.status(), StatusCode::OK);
+
+    let response = app.put_admin_user_roles(&token, &customer, &json!({"roles": ["admin"]})).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(app.delete_admin_user(&token, &customer).await.status(), StatusCode::FORBIDDEN);
+    let response = app.post_admin_invitation(&token, &json!({"email": random_email(), "role": "admin"})).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.post_admin_invitation(&token, &json!({"email": random_email()})).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn support_role_cannot_manage_admin_accounts() {
+    let app = TestApp::new().await;
+    let support = random_email();
+    app.login(&support, PASSWORD).await;
+    app.put_admin_user_roles(TEST_ADMIN_API_KEY, &support, &json!({"roles": ["support"]})).await;
+    let response = app.post_login(&json!({"email": support, "password": PASSWORD})).await;
+    let token = session_token(&response).unwrap();
+    let admin = random_email();
+    app.login(&admin, PASSWORD).await;
+    app.put_admin_user_roles(TEST_ADMIN_API_KEY, &admin, &json!({"roles": ["admin"]})).await;
+
+    for action in ["disable", "enable", "force-password-reset", "reset-2fa", "unlock"] {
+        let response = app.post_admin_user_action(&token, &admin, action).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN, "Action: {}", action);
+    }
+    assert_eq!(app.get_admin_user(&token, &admin).await.status(), StatusCode::OK);
+    let response = app.post_admin_user_action(TEST_ADMIN_API_KEY, &admin, "reset-2fa").await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
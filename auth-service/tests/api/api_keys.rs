@@ -0,0 +1,132 @@
+use crate::helpers::{random_email, TestApp, TEST_ADMIN_API_KEY};
+use auth_service::domain::Permission;
+use auth_service::routes::{ApiKeyResponse, ApiKeysResponse, CreateApiKeyResponse, VerifiedTokenResponse};
+use reqwest::StatusCode;
+use serde_json::json;
+
+const PASSWORD: &str = "StrongPassword123!";
+
+async fn create_api_key(app: &TestApp, token: &str, body: &serde_json::Value) -> CreateApiKeyResponse {
+    let response = app.post_api_key(token, body).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    response.json::<CreateApiKeyResponse>().await.unwrap()
+}
+
+async fn api_keys(app: &TestApp, token: &str) -> Vec<ApiKeyResponse> {
+    let response = app.get_api_keys(token).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    match response.json::<ApiKeysResponse>().await.unwrap() {
+        ApiKeysResponse::ApiKeys(api_keys) => api_keys,
+        ApiKeysResponse::Error(error) => panic!("Unexpected error: {}", error),
+    }
+}
+
+#[tokio::test]
+async fn api_key_verifies_with_its_scopes() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    let token = app.login(&email, PASSWORD).await;
+    let created = create_api_key(&app, &token, &json!({"name": "ci", "scopes": ["profile:read"]})).await;
+    assert!(created.key.starts_with("ak_"));
+    assert_eq!(created.api_key.scopes, vec![Permission::ProfileRead]);
+    assert_eq!(created.api_key.last_used_at, None);
+
+    let response = app.post_verify_token(&json!({"token": created.key})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let verified = response.json::<VerifiedTokenResponse>().await.unwrap();
+    assert_eq!(verified.sub, email);
+    assert!(verified.roles.is_empty());
+    assert_eq!(verified.permissions, vec![Permission::ProfileRead]);
+    assert_eq!(verified.scope.as_deref(), Some("profile:read"));
+
+    let api_keys = api_keys(&app, &token).await;
+    assert_eq!(api_keys.len(), 1);
+    assert_eq!(api_keys[0].id, created.api_key.id);
+    assert!(api_keys[0].last_used_at.is_some());
+}
+
+#[tokio::test]
+async fn api_key_verifies_from_authorization_header() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    let token = app.login(&email, PASSWORD).await;
+    let created = create_api_key(&app, &token, &json!({"name": "ci", "scopes": ["profile:read"]})).await;
+
+    let response = app.post_verify_token_with_bearer(&created.key).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let verified = response.json::<VerifiedTokenResponse>().await.unwrap();
+    assert_eq!(verified.sub, email);
+    assert_eq!(verified.permissions, vec![Permission::ProfileRead]);
+
+    let response = app.post_verify_token_with_bearer("ak_0000000000000000_tampered").await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn api_key_defaults_to_user_permissions_and_rejects_others() {
+    let app = TestApp::new().await;
+    let token = app.login(&random_email(), PASSWORD).await;
+    let created = create_api_key(&app, &token, &json!({"name": "ci"})).await;
+    assert_eq!(created.api_key.scopes, vec![Permission::ProfileRead, Permission::ProfileWrite]);
+
+    let response = app.post_api_key(&token, &json!({"name": "ci", "scopes": ["users:delete"]})).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app.post_api_key(&token, &json!({"name": " "})).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app.post_api_key(&token, &json!({"name": "ci", "expiresIn": u64::MAX})).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app.post_api_key("string", &json!({"name": "ci"})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn api_key_cannot_manage_api_keys() {
+    let app = TestApp::new().await;
+    let token = app.login(&random_email(), PASSWORD).await;
+    let created = create_api_key(&app, &token, &json!({"name": "ci"})).await;
+    let response = app.get_api_keys(&created.key).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn api_key_revoked_or_tampered_is_invalid() {
+    let app = TestApp::new().await;
+    let token = app.login(&random_email(), PASSWORD).await;
+    let created = create_api_key(&app, &token, &json!({"name": "ci"})).await;
+    let tampered = format!("{}x", created.key);
+    let response = app.post_verify_token(&json!({"token": tampered})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let other = app.login(&random_email(), PASSWORD).await;
+    let response = app.delete_api_key(&other, &created.api_key.id).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = app.delete_api_key(&token, &created.api_key.id).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app.post_verify_token(&json!({"token": created.key})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(api_keys(&app, &token).await.is_empty());
+}
+
+#[tokio::test]
+async fn api_key_of_disabled_user_is_invalid() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    let token = app.login(&email, PASSWORD).await;
+    let created = create_api_key(&app, &token, &json!({"name": "ci"})).await;
+    let response = app.post_admin_user_action(TEST_ADMIN_API_KEY, &email, "disable").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.post_verify_token(&json!({"token": created.key})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn api_key_is_revoked_by_forced_password_reset() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    let token = app.login(&email, PASSWORD).await;
+    let created = create_api_key(&app, &token, &json!({"name": "ci"})).await;
+    let response = app.post_admin_user_action(TEST_ADMIN_API_KEY, &email, "force-password-reset").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.post_verify_token(&json!({"token": created.key})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,127 @@
+use crate::helpers::{random_email, TestApp, TEST_ADMIN_API_KEY, TEST_USER_AGENT};
+use auth_service::domain::{
+    AuditEvent, AuditEventKind, LoginFailureReason, Tenant, TenantSettings, ADMIN_API_KEY_ACTOR,
+};
+use auth_service::routes::admin::AuditEventsResponse;
+use auth_service::utils::CORRELATION_ID_HEADER;
+use reqwest::StatusCode;
+use serde_json::json;
+
+const PASSWORD: &str = "StrongPassword123!";
+
+async fn audit_events(app: &TestApp, email: &str) -> Vec<AuditEvent> {
+    let response = app.get_admin_user_audit_events(TEST_ADMIN_API_KEY, email).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    response.json::<AuditEventsResponse>().await.unwrap().events
+}
+
+fn kinds(events: &[AuditEvent]) -> Vec<AuditEventKind> {
+    events.iter().map(|event| event.kind.clone()).collect()
+}
+
+#[tokio::test]
+async fn audit_log_records_sign_up_sign_in_and_sign_out() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    let token = app.login(&email, PASSWORD).await;
+    let response = app.post_login(&json!({"email": email, "password": "WrongPassword123!"})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    app.post_logout(Some(&token)).await;
+
+    let events = audit_events(&app, &email).await;
+    assert_eq!(
+        kinds(&events),
+        vec![
+            AuditEventKind::Signup,
+            AuditEventKind::LoginSucceeded { method: "password".to_string() },
+            AuditEventKind::LoginFailed { reason: LoginFailureReason::InvalidCredentials },
+            AuditEventKind::Logout,
+        ]
+    );
+    for event in &events {
+        assert_eq!(event.actor, email);
+        assert_eq!(event.ip_address, Some("127.0.0.1".parse().unwrap()));
+        assert_eq!(event.user_agent.as_deref(), Some(TEST_USER_AGENT));
+        assert!(!event.correlation_id.is_empty());
+    }
+}
+
+#[tokio::test]
+async fn audit_log_records_two_factor_challenge() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    app.post_signup(&json!({"email": email, "password": PASSWORD, "requires2FA": true})).await;
+    let response = app.post_login(&json!({"email": email, "password": PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+
+    let events = audit_events(&app, &email).await;
+    assert_eq!(kinds(&events), vec![AuditEventKind::Signup, AuditEventKind::TwoFactorChallenged]);
+}
+
+#[tokio::test]
+async fn audit_log_records_lockout() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    app.login(&email, PASSWORD).await;
+    for _ in 0..6 {
+        app.post_login(&json!({"email": email, "password": "WrongPassword123!"})).await;
+    }
+
+    let events = audit_events(&app, &email).await;
+    let kinds = kinds(&events);
+    assert_eq!(kinds.iter().filter(|kind| **kind == AuditEventKind::AccountLocked).count(), 1);
+    assert_eq!(kinds.last(), Some(&AuditEventKind::LoginFailed { reason: LoginFailureReason::AccountLocked }));
+}
+
+#[tokio::test]
+async fn audit_log_records_admin_actions_with_correlation_id() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    app.login(&email, PASSWORD).await;
+    let request_url = format!("{}admin/users/{}/disable", app.base_url, email);
+    let response = app
+        .http_client
+        .post(&request_url)
+        .bearer_auth(TEST_ADMIN_API_KEY)
+        .header(CORRELATION_ID_HEADER, "request-42")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    app.get_admin_user(TEST_ADMIN_API_KEY, &email).await;
+
+    let events = audit_events(&app, &email).await;
+    let event = events.last().unwrap();
+    assert_eq!(
+        event.kind,
+        AuditEventKind::AdminAction { action: "POST /admin/users/{email}/disable".to_string(), status: 200 }
+    );
+    assert_eq!(event.actor, ADMIN_API_KEY_ACTOR);
+    assert_eq!(event.subject.as_deref(), Some(email.as_str()));
+    assert_eq!(event.correlation_id, "request-42");
+}
+
+#[tokio::test]
+async fn audit_log_is_isolated_per_tenant() {
+    let tenant = Tenant::try_new("acme", "Acme", &[], TenantSettings::default()).unwrap();
+    let app = TestApp::with_tenants(vec![tenant]).await;
+    let acme = app.tenant("acme");
+    let email = random_email();
+    acme.login(&email, PASSWORD).await;
+
+    assert!(audit_events(&app, &email).await.is_empty());
+    let events = audit_events(&acme, &email).await;
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().all(|event| event.tenant_id == "acme"));
+}
+
+#[tokio::test]
+async fn audit_log_requires_admin() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    let token = app.login(&email, PASSWORD).await;
+    let response = app.get_admin_user_audit_events("wrong-key", &email).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.get_admin_user_audit_events(&token, &email).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
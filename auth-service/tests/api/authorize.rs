@@ -0,0 +1,132 @@
+use crate::helpers::{authorization_request, location, random_email, TestApp, TEST_REDIRECT_URI};
+use auth_service::domain::{Tenant, TenantSettings};
+use auth_service::routes::{AuthorizeResponse, TwoFactorRequiredResponse, Verify2FAResponse};
+use mime::{APPLICATION_JSON, TEXT_HTML_UTF_8};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::StatusCode;
+use serde_json::json;
+use url::Url;
+
+const PASSWORD: &str = "StrongPassword123!";
+
+#[tokio::test]
+async fn authorize_serves_login_ui() {
+    let app = TestApp::new().await;
+    app.register_client().await;
+    let response = app.get_authorize(&authorization_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), TEXT_HTML_UTF_8.as_ref());
+}
+
+#[tokio::test]
+async fn authorize_returns_400_for_unknown_client_or_redirect_uri() {
+    let app = TestApp::new().await;
+    app.register_client().await;
+    let mut unknown_client = authorization_request();
+    unknown_client["client_id"] = json!("unknown-client");
+    let mut unknown_redirect_uri = authorization_request();
+    unknown_redirect_uri["redirect_uri"] = json!("http://evil.example.com/callback");
+    for request in [unknown_client, unknown_redirect_uri].iter() {
+        let response = app.get_authorize(request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "Input: {:?}", request);
+        assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    }
+}
+
+#[tokio::test]
+async fn authorize_redirects_errors_to_client() {
+    let app = TestApp::new().await;
+    app.register_client().await;
+    let mut unsupported_response_type = authorization_request();
+    unsupported_response_type["response_type"] = json!("token");
+    let mut missing_openid_scope = authorization_request();
+    missing_openid_scope["scope"] = json!("email");
+    let mut plain_code_challenge = authorization_request();
+    plain_code_challenge["code_challenge_method"] = json!("plain");
+    let cases = [
+        (unsupported_response_type, "unsupported_response_type"),
+        (missing_openid_scope, "invalid_scope"),
+        (plain_code_challenge, "invalid_request"),
+    ];
+    for (request, expected) in cases.iter() {
+        let response = app.get_authorize(request).await;
+        assert_eq!(response.status(), StatusCode::SEE_OTHER, "Input: {:?}", request);
+        let location = location(&response);
+        assert!(location.as_str().starts_with(TEST_REDIRECT_URI));
+        let error = location.query_pairs().find(|(key, _)| key == "error").unwrap().1;
+        let state = location.query_pairs().find(|(key, _)| key == "state").unwrap().1;
+        assert_eq!(error, *expected);
+        assert_eq!(state, "xyz");
+    }
+}
+
+#[tokio::test]
+async fn authorize_login_returns_redirect_uri_with_code() {
+    let app = TestApp::new().await;
+    app.register_client().await;
+    let email = random_email();
+    let signup = json!({"email": email, "password": PASSWORD, "requires2FA": false});
+    app.post_signup(&signup).await;
+    let credentials = json!({"email": email, "password": PASSWORD});
+    let response = app.post_authorize(&authorization_request(), &credentials).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let AuthorizeResponse::RedirectUri(redirect_uri) = response.json().await.unwrap() else {
+        panic!("Expected a redirect URI");
+    };
+    let redirect_uri = Url::parse(&redirect_uri).unwrap();
+    assert!(redirect_uri.as_str().starts_with(TEST_REDIRECT_URI));
+    assert!(redirect_uri.query_pairs().any(|(key, value)| key == "code" && !value.is_empty()));
+    assert!(redirect_uri.query_pairs().any(|(key, value)| key == "state" && value == "xyz"));
+}
+
+#[tokio::test]
+async fn authorize_login_requires_2fa_before_issuing_code() {
+    let settings = TenantSettings {
+        require_2fa: true,
+        ..TenantSettings::default()
+    };
+    let app = TestApp::with_tenants(vec![Tenant::try_new("strict", "Strict", &[], settings).unwrap()]).await;
+    app.register_client().await;
+    let strict = app.tenant("strict");
+    let user_2fa = random_email();
+    app.post_signup(&json!({"email": user_2fa, "password": PASSWORD, "requires2FA": true})).await;
+    let tenant_2fa = random_email();
+    strict.post_signup(&json!({"email": tenant_2fa, "password": PASSWORD, "requires2FA": false})).await;
+    for (app, email) in [(&app, &user_2fa), (&strict, &tenant_2fa)] {
+        let credentials = json!({"email": email, "password": PASSWORD});
+        let response = app.post_authorize(&authorization_request(), &credentials).await;
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT, "Input: {:?}", email);
+        let body = response.json::<TwoFactorRequiredResponse>().await.unwrap();
+
+        let code = app.two_factor_code(email).await.unwrap();
+        let body = json!({"email": email, "loginAttemptId": body.login_attempt_id, "2FACode": code});
+        let response = app.post_verify_2fa(&body).await;
+        assert_eq!(response.status(), StatusCode::OK, "Input: {:?}", email);
+        let Verify2FAResponse::RedirectUri(redirect_uri) = response.json().await.unwrap() else {
+            panic!("Redirect URI is missing");
+        };
+        let redirect_uri = Url::parse(&redirect_uri).unwrap();
+        assert!(redirect_uri.as_str().starts_with(TEST_REDIRECT_URI));
+        let query: Vec<(String, String)> = redirect_uri.query_pairs().into_owned().collect();
+        assert!(query.iter().any(|(key, _)| key == "code"));
+        assert!(query.contains(&("state".to_string(), "xyz".to_string())));
+    }
+}
+
+#[tokio::test]
+async fn authorize_login_returns_401_if_incorrect_credentials() {
+    let app = TestApp::new().await;
+    app.register_client().await;
+    let email = random_email();
+    let signup = json!({"email": email, "password": PASSWORD, "requires2FA": false});
+    app.post_signup(&signup).await;
+    let credentials = [
+        json!({"email": email, "password": "WrongPassword123!"}),
+        json!({"email": random_email(), "password": PASSWORD}),
+    ];
+    for credential in credentials.iter() {
+        let response = app.post_authorize(&authorization_request(), credential).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "Input: {:?}", credential);
+        assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    }
+}
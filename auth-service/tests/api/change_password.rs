@@ -0,0 +1,102 @@
+use crate::helpers::{random_email, TestApp};
+use auth_service::domain::{PasswordPolicy, Tenant, TenantSettings};
+use auth_service::routes::{ChangePasswordResponse, PasswordRejection};
+use reqwest::StatusCode;
+use serde_json::json;
+
+const PASSWORD: &str = "StrongPassword123!";
+
+async fn app_with_password_history(history_depth: usize) -> TestApp {
+    let settings = TenantSettings {
+        password_policy: PasswordPolicy::default().with_history_depth(history_depth),
+        require_2fa: false,
+        case_sensitive_emails: false,
+    };
+    TestApp::with_tenants(vec![Tenant::try_new("history", "History", &[], settings).unwrap()]).await
+}
+
+#[tokio::test]
+async fn should_change_password() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    let token = app.login(&email, PASSWORD).await;
+    let body = json!({"currentPassword": PASSWORD, "newPassword": "CorrectHorseBattery9!"});
+    let response = app.post_change_password(&token, &body).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let expected = ChangePasswordResponse::Message("Password changed successfully!".to_string());
+    assert_eq!(response.json::<ChangePasswordResponse>().await.unwrap(), expected);
+
+    let response = app.post_login(&json!({"email": email, "password": PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.post_login(&json!({"email": email, "password": "CorrectHorseBattery9!"})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn should_return_403_if_current_password_is_incorrect() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    let token = app.login(&email, PASSWORD).await;
+    let body = json!({"currentPassword": "WrongPassword123!", "newPassword": "CorrectHorseBattery9!"});
+    let response = app.post_change_password(&token, &body).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let expected = ChangePasswordResponse::Error("Incorrect password".to_string());
+    assert_eq!(response.json::<ChangePasswordResponse>().await.unwrap(), expected);
+
+    let response = app.post_login(&json!({"email": email, "password": PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn should_return_400_if_new_password_is_invalid() {
+    let app = TestApp::new().await;
+    let token = app.login(&random_email(), PASSWORD).await;
+    let body = json!({"currentPassword": PASSWORD, "newPassword": "short"});
+    let response = app.post_change_password(&token, &body).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.json::<PasswordRejection>().await.unwrap().code, "too_short");
+}
+
+#[tokio::test]
+async fn should_return_401_without_token() {
+    let app = TestApp::new().await;
+    let body = json!({"currentPassword": PASSWORD, "newPassword": "CorrectHorseBattery9!"});
+    let response = app.post_change_password("invalid-token", &body).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn should_reject_recently_used_passwords() {
+    let app = app_with_password_history(3).await;
+    let history = app.tenant("history");
+    let email = random_email();
+    let token = history.login(&email, PASSWORD).await;
+    let passwords = [PASSWORD, "CorrectHorseBattery1!", "CorrectHorseBattery2!"];
+    for pair in passwords.windows(2) {
+        let body = json!({"currentPassword": pair[0], "newPassword": pair[1]});
+        assert_eq!(history.post_change_password(&token, &body).await.status(), StatusCode::OK);
+    }
+
+    for reused in passwords {
+        let body = json!({"currentPassword": passwords[2], "newPassword": reused});
+        let response = history.post_change_password(&token, &body).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let rejection = response.json::<PasswordRejection>().await.unwrap();
+        assert_eq!(rejection.code, "recently_used");
+        assert_eq!(rejection.error, "Invalid password: Password is one of the last 3 passwords of the user");
+    }
+
+    // The first password is forgotten once three others were used since.
+    let body = json!({"currentPassword": passwords[2], "newPassword": "CorrectHorseBattery3!"});
+    assert_eq!(history.post_change_password(&token, &body).await.status(), StatusCode::OK);
+    let body = json!({"currentPassword": "CorrectHorseBattery3!", "newPassword": PASSWORD});
+    assert_eq!(history.post_change_password(&token, &body).await.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn should_allow_reusing_passwords_without_history() {
+    let app = TestApp::new().await;
+    let token = app.login(&random_email(), PASSWORD).await;
+    let body = json!({"currentPassword": PASSWORD, "newPassword": PASSWORD});
+    assert_eq!(app.post_change_password(&token, &body).await.status(), StatusCode::OK);
+}
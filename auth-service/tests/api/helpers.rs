@@ -1,32 +1,118 @@
 use auth_service::app_state::AppState;
-use auth_service::services::HashmapUserStore;
+use auth_service::domain::{code_challenge, Client as OidcClient, IdentityProvider, SignupMode, Tenant};
+use auth_service::services::{
+    ClientStore, GeoIpDatabase, HashmapUserStore, Keyring, MockEmailClient, PwnedPasswords, RateLimiter, TlsSettings,
+    TOKEN_TTL_SECONDS,
+};
+use auth_service::utils::TWO_FACTOR_CODE_SUBJECT;
 use auth_service::Application;
 use axum::http::Uri;
-use reqwest::header::SET_COOKIE;
-use reqwest::{Client, Response};
+use reqwest::header::{COOKIE, LOCATION, SET_COOKIE, USER_AGENT};
+use reqwest::redirect::Policy;
+use reqwest::{Certificate, Client, Response};
+use secrecy::{ExposeSecret, SecretString};
 use serde::Serialize;
+use serde_json::json;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use url::Url;
 use uuid::Uuid;
 
+pub const TEST_ISSUER: &str = "http://localhost:3000";
+pub const TEST_CLIENT_ID: &str = "test-client";
+pub const TEST_REDIRECT_URI: &str = "http://localhost:8000/callback";
+pub const TEST_ADMIN_API_KEY: &str = "test-admin-api-key";
+pub const TEST_USER_AGENT: &str = "auth-service-tests";
+pub const TEST_CODE_VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+
 pub struct TestApp {
     pub base_url: String,
     pub http_client: Client,
+    pub app_state: AppState,
+    /// Email client of the app, keeping the emails it sends.
+    pub email_client: Arc<RwLock<MockEmailClient>>,
+    /// Address plain HTTP requests are redirected to HTTPS from, if any.
+    pub redirect_address: Option<SocketAddr>,
 }
 
 impl TestApp {
     pub async fn new() -> Self {
-        let user_store = HashmapUserStore::default();
-        let app_state = AppState::new(Arc::new(RwLock::new(user_store)));
+        Self::spawn(app_state()).await
+    }
+
+    pub async fn with_identity_providers(identity_providers: Vec<IdentityProvider>) -> Self {
+        Self::spawn(app_state().with_identity_providers(identity_providers)).await
+    }
+
+    pub async fn with_tenants(tenants: Vec<Tenant>) -> Self {
+        Self::spawn(app_state().with_tenants(tenants)).await
+    }
+
+    pub async fn with_signup_mode(signup_mode: SignupMode) -> Self {
+        Self::spawn(app_state().with_signup_mode(signup_mode)).await
+    }
+
+    pub async fn with_geoip_database(geoip_database: GeoIpDatabase) -> Self {
+        Self::spawn(app_state().with_geoip_database(geoip_database)).await
+    }
+
+    pub async fn with_pwned_passwords(pwned_passwords: PwnedPasswords) -> Self {
+        Self::spawn(app_state().with_pwned_passwords(pwned_passwords)).await
+    }
+
+    pub async fn with_password_strength_rate_limit(limit: u32) -> Self {
+        Self::spawn(app_state().with_password_strength_rate_limit(limit)).await
+    }
+
+    pub async fn with_forgot_password_rate_limits(limit: u32, email_limit: u32) -> Self {
+        let rate_limiter = Arc::new(RwLock::new(RateLimiter::new(limit)));
+        let email_rate_limiter = Arc::new(RwLock::new(RateLimiter::new(email_limit)));
+        Self::spawn(app_state().with_forgot_password_rate_limiters(rate_limiter, email_rate_limiter)).await
+    }
+
+    pub async fn with_step_up_risky_logins() -> Self {
+        Self::spawn(app_state().with_step_up_risky_logins(true)).await
+    }
+
+    /// Server of HTTPS, addressed as `localhost`, with a client trusting only the certificate.
+    pub async fn with_tls(tls: TlsSettings, certificate: &TestCertificate) -> Self {
+        Self::spawn_with_tls(app_state(), Some((tls, certificate))).await
+    }
+
+    /// Client of the same server, addressing the tenant through the `/t/{tenant_id}` path prefix.
+    pub fn tenant(&self, tenant_id: &str) -> Self {
+        Self {
+            base_url: format!("{}t/{}/", self.base_url, tenant_id),
+            http_client: self.http_client.clone(),
+            app_state: self.app_state.clone(),
+            email_client: self.email_client.clone(),
+            redirect_address: self.redirect_address,
+        }
+    }
+
+    async fn spawn(app_state: AppState) -> Self {
+        Self::spawn_with_tls(app_state, None).await
+    }
+
+    async fn spawn_with_tls(app_state: AppState, tls: Option<(TlsSettings, &TestCertificate)>) -> Self {
         let socket_addr = SocketAddr::from(([127, 0, 0, 1], 0));
-        let application = Application::build(app_state, socket_addr)
+        let (tls, certificate) = tls.unzip();
+        let email_client = Arc::new(RwLock::new(MockEmailClient::default()));
+        let app_state = app_state.with_email_client(email_client.clone());
+        let application = Application::build_with_tls(app_state.clone(), socket_addr, tls)
             .await
             .expect("Failed to build app");
         let socket_addr = application.address;
+        let redirect_address = application.redirect_address;
+        let (scheme, authority) = match certificate {
+            Some(_) => ("https", format!("localhost:{}", socket_addr.port())),
+            None => ("http", socket_addr.to_string()),
+        };
         let uri = Uri::builder()
-            .scheme("http")
-            .authority(socket_addr.to_string().as_str())
+            .scheme(scheme)
+            .authority(authority.as_str())
             .path_and_query("/")
             .build()
             .expect("Failed to build URI");
@@ -34,13 +120,19 @@ impl TestApp {
         // to avoid blocking the main test thread.
         #[allow(clippy::let_underscore_future)]
         let _task = tokio::spawn(application.run());
-        let http_client = Client::builder()
+        let mut http_client = Client::builder()
             .cookie_store(true)
-            .build()
-            .expect("Failed to build HTTP client");
+            .user_agent(TEST_USER_AGENT)
+            .redirect(Policy::none());
+        if let Some(certificate) = certificate {
+            http_client = http_client.tls_certs_only([certificate.certificate()]).resolve("localhost", socket_addr);
+        }
         Self {
             base_url: uri.to_string(),
-            http_client,
+            http_client: http_client.build().expect("Failed to build HTTP client"),
+            app_state,
+            email_client,
+            redirect_address,
         }
     }
 
@@ -53,6 +145,265 @@ impl TestApp {
             .expect("Failed to execute get_root request")
     }
 
+    pub async fn get_jwks(&self) -> Response {
+        let request_url = format!("{}.well-known/jwks.json", &self.base_url);
+        self.http_client
+            .get(&request_url)
+            .send()
+            .await
+            .expect("Failed to execute get_jwks request")
+    }
+
+    pub async fn get_openid_configuration(&self) -> Response {
+        let request_url = format!("{}.well-known/openid-configuration", &self.base_url);
+        self.http_client
+            .get(&request_url)
+            .send()
+            .await
+            .expect("Failed to execute get_openid_configuration request")
+    }
+
+    pub async fn get_authorize<Q: Serialize>(&self, query: &Q) -> Response {
+        let request_url = format!("{}authorize", &self.base_url);
+        self.http_client
+            .get(&request_url)
+            .query(query)
+            .send()
+            .await
+            .expect("Failed to execute get_authorize request")
+    }
+
+    pub async fn post_authorize<Q: Serialize, S: Serialize>(&self, query: &Q, body: &S) -> Response {
+        let request_url = format!("{}authorize", &self.base_url);
+        self.http_client
+            .post(&request_url)
+            .query(query)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute post_authorize request")
+    }
+
+    pub async fn post_token<S: Serialize>(&self, form: &S) -> Response {
+        let request_url = format!("{}token", &self.base_url);
+        self.http_client
+            .post(&request_url)
+            .form(form)
+            .send()
+            .await
+            .expect("Failed to execute post_token request")
+    }
+
+    pub async fn post_oauth_token<S: Serialize>(&self, client_id: &str, client_secret: &str, form: &S) -> Response {
+        let request_url = format!("{}oauth/token", &self.base_url);
+        self.http_client
+            .post(&request_url)
+            .basic_auth(client_id, Some(client_secret))
+            .form(form)
+            .send()
+            .await
+            .expect("Failed to execute post_oauth_token request")
+    }
+
+    pub async fn post_oauth_introspect<S: Serialize>(&self, client_id: &str, client_secret: &str, form: &S) -> Response {
+        let request_url = format!("{}oauth/introspect", &self.base_url);
+        self.http_client
+            .post(&request_url)
+            .basic_auth(client_id, Some(client_secret))
+            .form(form)
+            .send()
+            .await
+            .expect("Failed to execute post_oauth_introspect request")
+    }
+
+    pub async fn post_oauth_revoke<S: Serialize>(&self, client_id: &str, client_secret: &str, form: &S) -> Response {
+        let request_url = format!("{}oauth/revoke", &self.base_url);
+        self.http_client
+            .post(&request_url)
+            .basic_auth(client_id, Some(client_secret))
+            .form(form)
+            .send()
+            .await
+            .expect("Failed to execute post_oauth_revoke request")
+    }
+
+    pub async fn post_admin_client<S: Serialize>(&self, api_key: &str, body: &S) -> Response {
+        let request_url = format!("{}admin/clients", &self.base_url);
+        self.http_client
+            .post(&request_url)
+            .bearer_auth(api_key)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute post_admin_client request")
+    }
+
+    pub async fn delete_admin_client(&self, api_key: &str, client_id: &str) -> Response {
+        let request_url = format!("{}admin/clients/{}", &self.base_url, client_id);
+        self.http_client
+            .delete(&request_url)
+            .bearer_auth(api_key)
+            .send()
+            .await
+            .expect("Failed to execute delete_admin_client request")
+    }
+
+    pub async fn post_admin_rotate_signing_key(&self, api_key: &str) -> Response {
+        let request_url = format!("{}admin/keys/rotate", &self.base_url);
+        self.http_client
+            .post(&request_url)
+            .bearer_auth(api_key)
+            .send()
+            .await
+            .expect("Failed to execute post_admin_rotate_signing_key request")
+    }
+
+    pub async fn post_admin_invitation<S: Serialize>(&self, api_key: &str, body: &S) -> Response {
+        let request_url = format!("{}admin/invitations", &self.base_url);
+        self.http_client
+            .post(&request_url)
+            .bearer_auth(api_key)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute post_admin_invitation request")
+    }
+
+    pub async fn get_admin_users(&self, api_key: &str, query: &[(&str, &str)]) -> Response {
+        let request_url = format!("{}admin/users", &self.base_url);
+        self.http_client
+            .get(&request_url)
+            .bearer_auth(api_key)
+            .query(query)
+            .send()
+            .await
+            .expect("Failed to execute get_admin_users request")
+    }
+
+    pub async fn get_admin_user(&self, api_key: &str, email: &str) -> Response {
+        let request_url = format!("{}admin/users/{}", &self.base_url, email);
+        self.http_client
+            .get(&request_url)
+            .bearer_auth(api_key)
+            .send()
+            .await
+            .expect("Failed to execute get_admin_user request")
+    }
+
+    pub async fn delete_admin_user(&self, api_key: &str, email: &str) -> Response {
+        let request_url = format!("{}admin/users/{}", &self.base_url, email);
+        self.http_client
+            .delete(&request_url)
+            .bearer_auth(api_key)
+            .send()
+            .await
+            .expect("Failed to execute delete_admin_user request")
+    }
+
+    pub async fn get_admin_user_audit_events(&self, api_key: &str, email: &str) -> Response {
+        let request_url = format!("{}admin/users/{}/audit-events", &self.base_url, email);
+        self.http_client
+            .get(&request_url)
+            .bearer_auth(api_key)
+            .send()
+            .await
+            .expect("Failed to execute get_admin_user_audit_events request")
+    }
+
+    /// Posts an account action, such as `disable` or `unlock`, for the user.
+    pub async fn post_admin_user_action(&self, api_key: &str, email: &str, action: &str) -> Response {
+        let request_url = format!("{}admin/users/{}/{}", &self.base_url, email, action);
+        self.http_client
+            .post(&request_url)
+            .bearer_auth(api_key)
+            .send()
+            .await
+            .expect("Failed to execute post_admin_user_action request")
+    }
+
+    pub async fn put_admin_user_roles<Body>(&self, api_key: &str, email: &str, body: &Body) -> Response
+    where
+        Body: serde::Serialize,
+    {
+        let request_url = format!("{}admin/users/{}/roles", &self.base_url, email);
+        self.http_client
+            .put(&request_url)
+            .bearer_auth(api_key)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute put_admin_user_roles request")
+    }
+
+    pub async fn get_userinfo(&self, access_token: &str) -> Response {
+        let request_url = format!("{}userinfo", &self.base_url);
+        self.http_client
+            .get(&request_url)
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .expect("Failed to execute get_userinfo request")
+    }
+
+    pub async fn register_client(&self) {
+        let client = OidcClient::try_new(TEST_CLIENT_ID, &[TEST_REDIRECT_URI]).unwrap();
+        let mut client_store = self.app_state.client_store.write().await;
+        client_store.add_client(client).await.expect("Failed to register client");
+    }
+
+    /// Registers a confidential client directly in the store, returning its id and secret.
+    pub async fn register_confidential_client(&self, scopes: &[&str]) -> (String, String) {
+        let (client, secret) = OidcClient::try_new_confidential(None, scopes, &[]).unwrap();
+        let client_id = client.client_id.clone();
+        let mut client_store = self.app_state.client_store.write().await;
+        client_store.add_client(client).await.expect("Failed to register client");
+        (client_id, secret.expose_secret().to_string())
+    }
+
+    /// Runs the client credentials grant, returning the issued access token.
+    pub async fn client_access_token(&self, client_id: &str, client_secret: &str) -> String {
+        let response = self
+            .post_oauth_token(client_id, client_secret, &json!({"grant_type": "client_credentials"}))
+            .await;
+        let body: serde_json::Value = response.json().await.unwrap();
+        body["access_token"].as_str().expect("Access token is missing").to_string()
+    }
+
+    /// Signs up a user and runs the authorization endpoint, returning the issued code.
+    pub async fn authorization_code(&self, email: &str, password: &str) -> String {
+        let body = json!({"email": email, "password": password, "requires2FA": false});
+        self.post_signup(&body).await;
+        let response = self
+            .post_authorize(&authorization_request(), &json!({"email": email, "password": password}))
+            .await;
+        let body: serde_json::Value = response.json().await.unwrap();
+        let redirect_uri = Url::parse(body["redirectUri"].as_str().unwrap()).unwrap();
+        redirect_uri
+            .query_pairs()
+            .find(|(key, _)| key == "code")
+            .map(|(_, value)| value.to_string())
+            .expect("Authorization code is missing")
+    }
+
+    pub async fn get_social_login(&self, provider: &str) -> Response {
+        let request_url = format!("{}login/{}", &self.base_url, provider);
+        self.http_client
+            .get(&request_url)
+            .send()
+            .await
+            .expect("Failed to execute get_social_login request")
+    }
+
+    pub async fn get_social_login_callback<Q: Serialize>(&self, provider: &str, query: &Q) -> Response {
+        let request_url = format!("{}login/{}/callback", &self.base_url, provider);
+        self.http_client
+            .get(&request_url)
+            .query(query)
+            .send()
+            .await
+            .expect("Failed to execute get_social_login_callback request")
+    }
+
     pub async fn post_signup<S: Serialize>(&self, body: &S) -> Response {
         let request_url = format!("{}api/signup", &self.base_url);
         self.http_client
@@ -73,13 +424,147 @@ impl TestApp {
             .expect("Failed to execute post_login request")
     }
 
-    pub async fn post_logout(&self) -> Response {
+    pub async fn post_login_with_user_agent<S: Serialize>(&self, user_agent: &str, body: &S) -> Response {
+        let request_url = format!("{}api/login", &self.base_url);
+        self.http_client
+            .post(&request_url)
+            .header(USER_AGENT, user_agent)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute post_login_with_user_agent request")
+    }
+
+    pub async fn post_password_strength<S: Serialize>(&self, body: &S) -> Response {
+        let request_url = format!("{}api/password-strength", &self.base_url);
+        self.http_client
+            .post(&request_url)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute post_password_strength request")
+    }
+
+    pub async fn post_change_password<S: Serialize>(&self, token: &str, body: &S) -> Response {
+        let request_url = format!("{}api/change-password", &self.base_url);
+        self.http_client
+            .post(&request_url)
+            .bearer_auth(token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute post_change_password request")
+    }
+
+    pub async fn post_forgot_password<S: Serialize>(&self, body: &S) -> Response {
+        let request_url = format!("{}api/forgot-password", &self.base_url);
+        self.http_client
+            .post(&request_url)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute post_forgot_password request")
+    }
+
+    pub async fn post_reset_password<S: Serialize>(&self, body: &S) -> Response {
+        let request_url = format!("{}api/reset-password", &self.base_url);
+        self.http_client
+            .post(&request_url)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute post_reset_password request")
+    }
+
+    pub async fn post_logout(&self, token: Option<&str>) -> Response {
         let request_url = format!("{}api/logout", &self.base_url);
+        let mut request = self.http_client.post(&request_url);
+        // The client's cookie store never sends the Secure cookie over plain HTTP.
+        if let Some(token) = token {
+            request = request.header(COOKIE, format!("jwt={}", token));
+        }
+        request.send().await.expect("Failed to execute post_logout request")
+    }
+
+    /// Code last emailed to the user to complete a sign-in requiring 2FA.
+    pub async fn two_factor_code(&self, email: &str) -> Option<String> {
+        let email_client = self.email_client.read().await;
+        let sent = email_client
+            .sent_emails()
+            .iter()
+            .rev()
+            .find(|sent| sent.recipient == email && sent.subject == TWO_FACTOR_CODE_SUBJECT)?;
+        let (_, rest) = sent.content.split_once(": ")?;
+        rest.lines().next().map(str::to_string)
+    }
+
+    /// Signs up a user and logs in, returning the session token.
+    pub async fn login(&self, email: &str, password: &str) -> String {
+        let body = json!({"email": email, "password": password, "requires2FA": false});
+        self.post_signup(&body).await;
+        let response = self.post_login(&json!({"email": email, "password": password})).await;
+        session_token(&response).expect("JWT cookie is missing")
+    }
+
+    pub async fn get_sessions(&self, token: &str) -> Response {
+        let request_url = format!("{}api/sessions", &self.base_url);
+        self.http_client
+            .get(&request_url)
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute get_sessions request")
+    }
+
+    pub async fn delete_session(&self, token: &str, session_id: &str) -> Response {
+        let request_url = format!("{}api/sessions/{}", &self.base_url, session_id);
+        self.http_client
+            .delete(&request_url)
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute delete_session request")
+    }
+
+    pub async fn delete_sessions(&self, token: &str) -> Response {
+        let request_url = format!("{}api/sessions", &self.base_url);
+        self.http_client
+            .delete(&request_url)
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute delete_sessions request")
+    }
+
+    pub async fn post_api_key<S: Serialize>(&self, token: &str, body: &S) -> Response {
+        let request_url = format!("{}api/api-keys", &self.base_url);
         self.http_client
             .post(&request_url)
+            .bearer_auth(token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute post_api_key request")
+    }
+
+    pub async fn get_api_keys(&self, token: &str) -> Response {
+        let request_url = format!("{}api/api-keys", &self.base_url);
+        self.http_client
+            .get(&request_url)
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute get_api_keys request")
+    }
+
+    pub async fn delete_api_key(&self, token: &str, api_key_id: &str) -> Response {
+        let request_url = format!("{}api/api-keys/{}", &self.base_url, api_key_id);
+        self.http_client
+            .delete(&request_url)
+            .bearer_auth(token)
             .send()
             .await
-            .expect("Failed to execute post_logout request")
+            .expect("Failed to execute delete_api_key request")
     }
 
     pub async fn post_verify_2fa<S: Serialize>(&self, body: &S) -> Response {
@@ -101,6 +586,66 @@ impl TestApp {
             .await
             .expect("Failed to execute post_verify_token request")
     }
+
+    pub async fn post_verify_token_with_bearer(&self, token: &str) -> Response {
+        let request_url = format!("{}api/verify-token", &self.base_url);
+        self.http_client
+            .post(&request_url)
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute post_verify_token request")
+    }
+}
+
+/// Self-signed certificate of `localhost`, in PEM files removed on drop.
+pub struct TestCertificate {
+    pub cert_file: PathBuf,
+    pub key_file: PathBuf,
+    pub pem: String,
+    directory: PathBuf,
+}
+
+impl TestCertificate {
+    pub fn generate() -> Self {
+        let directory = std::env::temp_dir().join(format!("auth-service-tls-{}", Uuid::new_v4()));
+        std::fs::create_dir(&directory).expect("Failed to create certificate directory");
+        let mut certificate = Self {
+            cert_file: directory.join("cert.pem"),
+            key_file: directory.join("key.pem"),
+            pem: String::new(),
+            directory,
+        };
+        certificate.renew();
+        certificate
+    }
+
+    /// Replaces the certificate in the files with a new one.
+    pub fn renew(&mut self) {
+        let certified_key = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])
+            .expect("Failed to generate certificate");
+        self.pem = certified_key.cert.pem();
+        std::fs::write(&self.key_file, certified_key.signing_key.serialize_pem()).expect("Failed to write key");
+        std::fs::write(&self.cert_file, &self.pem).expect("Failed to write certificate");
+    }
+
+    pub fn certificate(&self) -> Certificate {
+        Certificate::from_pem(self.pem.as_bytes()).expect("Failed to parse certificate")
+    }
+}
+
+impl Drop for TestCertificate {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.directory);
+    }
+}
+
+fn app_state() -> AppState {
+    let user_store = HashmapUserStore::default();
+    let keyring = Keyring::new(TOKEN_TTL_SECONDS).expect("Failed to generate signing key");
+    AppState::new(Arc::new(RwLock::new(user_store)), Arc::new(RwLock::new(keyring)))
+        .with_issuer(TEST_ISSUER)
+        .with_admin_api_key(Some(SecretString::from(TEST_ADMIN_API_KEY)))
 }
 
 #[allow(dead_code)]
@@ -113,6 +658,14 @@ pub fn jwt_cookie(response: &Response) -> Option<String> {
         .find(|cookie| cookie.starts_with("jwt="))
 }
 
+/// Value of the JWT cookie set by the response.
+#[allow(dead_code)]
+pub fn session_token(response: &Response) -> Option<String> {
+    let cookie = jwt_cookie(response)?;
+    let value = cookie.split(';').next()?.strip_prefix("jwt=")?;
+    Some(value.to_string())
+}
+
 #[allow(dead_code)]
 pub fn assert_jwt(jwt: Option<String>) -> String {
     let Some(jwt) = jwt else {
@@ -128,6 +681,26 @@ pub fn assert_jwt(jwt: Option<String>) -> String {
     jwt
 }
 
+#[allow(dead_code)]
+pub fn authorization_request() -> serde_json::Value {
+    json!({
+        "response_type": "code",
+        "client_id": TEST_CLIENT_ID,
+        "redirect_uri": TEST_REDIRECT_URI,
+        "scope": "openid email",
+        "state": "xyz",
+        "nonce": "n-0S6_WzA2Mj",
+        "code_challenge": code_challenge(TEST_CODE_VERIFIER),
+        "code_challenge_method": "S256",
+    })
+}
+
+#[allow(dead_code)]
+pub fn location(response: &Response) -> Url {
+    let location = response.headers().get(LOCATION).expect("Location header is missing");
+    Url::parse(location.to_str().unwrap()).unwrap()
+}
+
 pub fn random_email() -> String {
     format!("{}@example.com", Uuid::new_v4())
 }
@@ -0,0 +1,30 @@
+use crate::helpers::TestApp;
+use jsonwebtoken::jwk::JwkSet;
+use mime::APPLICATION_JSON;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::StatusCode;
+
+#[tokio::test]
+async fn jwks_returns_active_signing_key() {
+    let app = TestApp::new().await;
+    let response = app.get_jwks().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    let jwks = response.json::<JwkSet>().await.unwrap();
+    let keyring = app.app_state.keyring.read().await;
+    assert_eq!(jwks.keys.len(), 1);
+    assert!(jwks.find(keyring.active().kid()).is_some());
+}
+
+#[tokio::test]
+async fn jwks_keeps_retired_key_after_rotation() {
+    let app = TestApp::new().await;
+    let retired_kid = app.app_state.keyring.read().await.active().kid().to_string();
+    let active_kid = app.app_state.keyring.write().await.rotate().unwrap().kid().to_string();
+    let response = app.get_jwks().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let jwks = response.json::<JwkSet>().await.unwrap();
+    assert_eq!(jwks.keys.len(), 2);
+    assert!(jwks.find(&retired_kid).is_some());
+    assert!(jwks.find(&active_kid).is_some());
+}
@@ -1,6 +1,7 @@
-use crate::helpers::TestApp;
+use crate::helpers::{assert_jwt, jwt_cookie, random_email, TestApp};
+use auth_service::routes::TwoFactorRequiredResponse;
 use reqwest::StatusCode;
-use serde_json::json;
+use serde_json::{json, Value};
 
 #[allow(unused_imports)]
 use mime::APPLICATION_JSON;
@@ -8,61 +9,97 @@ use mime::APPLICATION_JSON;
 #[allow(unused_imports)]
 use reqwest::header::CONTENT_TYPE;
 
+const PASSWORD: &str = "StrongPassword123!";
+
+async fn signup(app: &TestApp, requires_2fa: bool) -> String {
+    let email = random_email();
+    let body = json!({"email": email, "password": PASSWORD, "requires2FA": requires_2fa});
+    assert_eq!(app.post_signup(&body).await.status(), StatusCode::CREATED);
+    email
+}
+
 #[tokio::test]
 async fn login_successful() {
     let app = TestApp::new().await;
-    let body = json!({"email": "user@example.com", "password": "string"});
+    let email = signup(&app, false).await;
+    let body = json!({"email": email, "password": PASSWORD});
     let response = app.post_login(&body).await;
     assert_eq!(response.status(), StatusCode::OK);
-    // let jwt = jwt_cookie(&response);
-    // assert_jwt(jwt);
+    let jwt = jwt_cookie(&response);
+    assert_jwt(jwt);
+}
+
+#[tokio::test]
+async fn login_with_other_email_spelling() {
+    let app = TestApp::new().await;
+    let email = signup(&app, false).await;
+    let body = json!({"email": email.to_uppercase(), "password": PASSWORD});
+    let response = app.post_login(&body).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_jwt(jwt_cookie(&response));
 }
 
 #[tokio::test]
 async fn login_requires_2fa() {
     let app = TestApp::new().await;
-    let body = json!({"email": "user@example.com", "password": "string"});
+    let email = signup(&app, true).await;
+    let body = json!({"email": email, "password": PASSWORD});
     let response = app.post_login(&body).await;
-    assert_eq!(response.status(), StatusCode::OK); // TODO: dummy assertion for task 4
-    // assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
-    // assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    assert!(jwt_cookie(&response).is_none());
+    let body = response.json::<TwoFactorRequiredResponse>().await.unwrap();
+    assert_eq!(body.message, "2FA required");
+    assert!(!body.login_attempt_id.is_empty());
+    assert!(app.two_factor_code(&email).await.is_some());
 }
 
 #[tokio::test]
 async fn login_invalid_input() {
     let app = TestApp::new().await;
-    let body = json!({"email": "user@example.com", "password": "string"});
-    let response = app.post_login(&body).await;
-    assert_eq!(response.status(), StatusCode::OK); // TODO: dummy assertion for task 4
-    // assert_eq!(response.status(), StatusCode::BAD_REQUEST);
-    // assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    let requests = [
+        json!({"email": "invalid-email", "password": PASSWORD}),
+        json!({"email": random_email(), "password": ""}),
+    ];
+    for request in requests.iter() {
+        let response = app.post_login(&request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "Input: {:?}", request);
+        assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    }
 }
 
 #[tokio::test]
 async fn login_authentication_failed() {
     let app = TestApp::new().await;
-    let body = json!({"email": "user@example.com", "password": "wrong_password"});
-    let response = app.post_login(&body).await;
-    assert_eq!(response.status(), StatusCode::OK); // TODO: dummy assertion for task 4
-    // assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
-    // assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    let email = signup(&app, false).await;
+    let requests = [
+        json!({"email": email, "password": "wrong_password"}),
+        json!({"email": random_email(), "password": PASSWORD}),
+    ];
+    for request in requests.iter() {
+        let response = app.post_login(&request).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "Input: {:?}", request);
+        assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    }
 }
 
 #[tokio::test]
 async fn login_unprocessable_content() {
     let app = TestApp::new().await;
-    let body = json!({"email": "user@example.com", "password": "string"});
-    let response = app.post_login(&body).await;
-    assert_eq!(response.status(), StatusCode::OK); // TODO: dummy assertion for task 4
-    // assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let requests = [json!({"email": random_email()}), json!({"password": PASSWORD}), json!({})];
+    for request in requests.iter() {
+        let response = app.post_login(&request).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY, "Input: {:?}", request);
+    }
 }
 
 #[tokio::test]
 async fn login_unexpected_error() {
     let app = TestApp::new().await;
-    let body = json!({"email": "user@example.com", "password": "string"});
-    let response = app.post_login(&body).await;
-    assert_eq!(response.status(), StatusCode::OK); // TODO: dummy assertion for task 4
-    // assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
-    // assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    let requests: [Value; 0] = [];
+    for request in requests.iter() {
+        let response = app.post_login(&request).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR, "Input: {:?}", request);
+        assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    }
 }
@@ -0,0 +1,112 @@
+use crate::helpers::{random_email, TestApp, TEST_ADMIN_API_KEY, TEST_USER_AGENT};
+use auth_service::domain::{AuditEventKind, Email, GeoLocation, KnownDevice, DEFAULT_TENANT_ID};
+use auth_service::routes::admin::AuditEventsResponse;
+use auth_service::services::{EmailMessage, GeoIpDatabase, UserStore};
+use auth_service::utils::{NEW_SIGN_IN_SUBJECT, TWO_FACTOR_CODE_SUBJECT};
+use jsonwebtoken::get_current_timestamp;
+use reqwest::StatusCode;
+use serde_json::json;
+
+const PASSWORD: &str = "StrongPassword123!";
+const FIREFOX: &str = "Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0";
+
+async fn sent_emails(app: &TestApp, email: &str) -> Vec<EmailMessage> {
+    let email_client = app.email_client.read().await;
+    email_client.sent_emails().iter().filter(|sent| sent.recipient == email).cloned().collect()
+}
+
+async fn anomalies(app: &TestApp, email: &str) -> Vec<AuditEventKind> {
+    let response = app.get_admin_user_audit_events(TEST_ADMIN_API_KEY, email).await;
+    let events = response.json::<AuditEventsResponse>().await.unwrap().events;
+    events
+        .into_iter()
+        .map(|event| event.kind)
+        .filter(|kind| matches!(kind, AuditEventKind::LoginAnomaly { .. }))
+        .collect()
+}
+
+#[tokio::test]
+async fn first_sign_in_and_known_device_do_not_notify() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    app.login(&email, PASSWORD).await;
+    let response = app.post_login(&json!({"email": email, "password": PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    assert!(sent_emails(&app, &email).await.is_empty());
+    assert!(anomalies(&app, &email).await.is_empty());
+    let user_store = app.app_state.user_store.read().await;
+    let user = user_store.get_user(DEFAULT_TENANT_ID, &Email::parse(&email).unwrap()).await.unwrap();
+    assert_eq!(user.known_devices.len(), 1);
+    assert_eq!(user.known_devices[0].user_agent_family, "auth-service-tests");
+}
+
+#[tokio::test]
+async fn sign_in_from_new_device_notifies_user() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    app.login(&email, PASSWORD).await;
+    let response = app.post_login_with_user_agent(FIREFOX, &json!({"email": email, "password": PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let emails = sent_emails(&app, &email).await;
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0].subject, NEW_SIGN_IN_SUBJECT);
+    assert!(emails[0].content.contains("a new device: Firefox on Linux, from 127.0.0.1"));
+    let anomaly = AuditEventKind::LoginAnomaly { new_device: true, impossible_travel: false };
+    assert_eq!(anomalies(&app, &email).await, vec![anomaly]);
+
+    // The device is known from now on.
+    app.post_login_with_user_agent(FIREFOX, &json!({"email": email, "password": PASSWORD})).await;
+    assert_eq!(sent_emails(&app, &email).await.len(), 1);
+}
+
+#[tokio::test]
+async fn failed_sign_in_from_new_device_does_not_notify() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    app.login(&email, PASSWORD).await;
+    let body = json!({"email": email, "password": "WrongPassword123!"});
+    let response = app.post_login_with_user_agent(FIREFOX, &body).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    assert!(sent_emails(&app, &email).await.is_empty());
+    assert!(anomalies(&app, &email).await.is_empty());
+}
+
+#[tokio::test]
+async fn risky_sign_in_steps_up_to_2fa_when_enabled() {
+    let app = TestApp::with_step_up_risky_logins().await;
+    let email = random_email();
+    app.login(&email, PASSWORD).await;
+    let response = app.post_login_with_user_agent(FIREFOX, &json!({"email": email, "password": PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert!(sent_emails(&app, &email).await.iter().all(|sent| sent.subject == TWO_FACTOR_CODE_SUBJECT));
+
+    let response = app.post_login(&json!({"email": email, "password": PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn sign_in_from_impossibly_far_notifies_user() {
+    let geoip_database = GeoIpDatabase::parse("network,country,latitude,longitude\n127.0.0.0/8,AU,-33.8688,151.2093\n");
+    let app = TestApp::with_geoip_database(geoip_database.unwrap()).await;
+    let email = random_email();
+    app.login(&email, PASSWORD).await;
+    let london = GeoLocation { country: Some("GB".to_string()), latitude: 51.5074, longitude: -0.1278 };
+    let now = get_current_timestamp();
+    let device = KnownDevice::observed(Some(FIREFOX), "198.51.100.7".parse().unwrap(), Some(london), now);
+    let mut user_store = app.app_state.user_store.write().await;
+    user_store.remember_device(DEFAULT_TENANT_ID, &Email::parse(&email).unwrap(), device).await.unwrap();
+    drop(user_store);
+
+    let response = app.post_login(&json!({"email": email, "password": PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let emails = sent_emails(&app, &email).await;
+    assert_eq!(emails.len(), 1);
+    let expected = format!("an unusual location: {}, from 127.0.0.1 (AU)", TEST_USER_AGENT);
+    assert!(emails[0].content.contains(&expected));
+    let anomaly = AuditEventKind::LoginAnomaly { new_device: false, impossible_travel: true };
+    assert_eq!(anomalies(&app, &email).await, vec![anomaly]);
+}
@@ -1,5 +1,6 @@
-use crate::helpers::TestApp;
+use crate::helpers::{jwt_cookie, random_email, TestApp};
 use reqwest::StatusCode;
+use serde_json::json;
 
 #[allow(unused_imports)]
 use mime::APPLICATION_JSON;
@@ -10,36 +11,46 @@ use reqwest::header::CONTENT_TYPE;
 #[tokio::test]
 async fn logout_successful() {
     let app = TestApp::new().await;
-    let response = app.post_logout().await;
+    let token = app.login(&random_email(), "StrongPassword123!").await;
+    let response = app.post_logout(Some(&token)).await;
     assert_eq!(response.status(), StatusCode::OK);
-    // let jwt = jwt_cookie(&response);
-    // let jwt = assert_jwt(jwt);
-    // assert!(jwt.contains("Expires=Thu, 01 Jan 1970 00:00:00 GMT;"), "JWT must have Expires set as epoch");
+    let jwt = jwt_cookie(&response).expect("JWT cookie is missing");
+    assert!(jwt.starts_with("jwt=;"), "JWT must be cleared");
+    assert!(jwt.contains("HttpOnly;"), "JWT must be HttpOnly");
+    assert!(jwt.contains("Max-Age=0;"), "JWT must expire immediately");
+
+    let response = app.post_verify_token(&json!({"token": token})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
 }
 
 #[tokio::test]
 async fn logout_invalid_input() {
     let app = TestApp::new().await;
-    let response = app.post_logout().await;
-    assert_eq!(response.status(), StatusCode::OK); // TODO: dummy assertion for task 4
-    // assert_eq!(response.status(), StatusCode::BAD_REQUEST);
-    // assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    let response = app.post_logout(None).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
 }
 
 #[tokio::test]
 async fn logout_jwt_is_not_valid() {
     let app = TestApp::new().await;
-    let response = app.post_logout().await;
-    assert_eq!(response.status(), StatusCode::OK); // TODO: dummy assertion for task 4
-    // assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
-    // assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    let response = app.post_logout(Some("string")).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+
+    let token = app.login(&random_email(), "StrongPassword123!").await;
+    assert_eq!(app.post_logout(Some(&token)).await.status(), StatusCode::OK);
+    let response = app.post_logout(Some(&token)).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
 }
 
 #[tokio::test]
 async fn logout_unexpected_error() {
     let app = TestApp::new().await;
-    let response = app.post_logout().await;
-    assert_eq!(response.status(), StatusCode::OK); // TODO: dummy assertion for task 4
-    // assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
-    // assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    let tokens: [&str; 0] = [];
+    for token in tokens.iter() {
+        let response = app.post_logout(Some(token)).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR, "Input: {:?}", token);
+        assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    }
 }
@@ -1,7 +1,31 @@
+mod admin_clients;
+mod admin_invitations;
+mod admin_keys;
+mod admin_roles;
+mod admin_users;
+mod api_keys;
+mod audit_log;
+mod authorize;
+mod change_password;
 mod helpers;
+mod jwks;
 mod login;
+mod login_anomalies;
 mod logout;
+mod mock_idp;
+mod oauth_introspect;
+mod oauth_revoke;
+mod oauth_token;
+mod openid_configuration;
+mod password_reset;
+mod password_strength;
 mod root;
+mod sessions;
 mod signup;
+mod social_login;
+mod tenants;
+mod tls;
+mod token;
+mod userinfo;
 mod verify_2fa;
 mod verify_token;
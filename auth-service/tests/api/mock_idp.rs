@@ -0,0 +1,191 @@
+use auth_service::domain::{code_challenge, IdentityProvider};
+use auth_service::services::Keyring;
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::routing::{get, post};
+use axum::{Form, Json, Router};
+use jsonwebtoken::get_current_timestamp;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use url::Url;
+use uuid::Uuid;
+
+pub const MOCK_CLIENT_ID: &str = "mock-client";
+pub const MOCK_CLIENT_SECRET: &str = "mock-secret";
+const MOCK_ACCESS_TOKEN: &str = "mock-access-token";
+
+/// The user signed in at the mock provider, and what the provider says about them.
+#[derive(Debug, Clone, Serialize)]
+pub struct MockProfile {
+    pub sub: String,
+    pub email: String,
+    pub email_verified: bool,
+}
+
+impl MockProfile {
+    pub fn new(email: &str) -> Self {
+        Self {
+            sub: Uuid::new_v4().to_string(),
+            email: email.to_string(),
+            email_verified: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PendingCode {
+    nonce: Option<String>,
+    code_challenge: String,
+    redirect_uri: String,
+}
+
+#[derive(Debug)]
+struct MockState {
+    issuer: String,
+    keyring: Keyring,
+    profile: MockProfile,
+    /// Overrides the ID token audience, to test audience validation.
+    audience: Option<String>,
+    codes: HashMap<String, PendingCode>,
+}
+
+/// A minimal OpenID Connect provider, signing ID tokens with its own keyring.
+#[derive(Clone)]
+pub struct MockIdp {
+    pub base_url: String,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockIdp {
+    pub async fn start(profile: MockProfile) -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(MockState {
+            issuer: base_url.clone(),
+            keyring: Keyring::new(600).expect("Failed to generate signing key"),
+            profile,
+            audience: None,
+            codes: HashMap::new(),
+        }));
+        let router = Router::new()
+            .route("/authorize", get(authorize))
+            .route("/token", post(token))
+            .route("/userinfo", get(userinfo))
+            .route("/jwks", get(jwks))
+            .with_state(state.clone());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        Self { base_url, state }
+    }
+
+    pub fn provider(&self, name: &str) -> IdentityProvider {
+        serde_json::from_value(json!({
+            "name": name,
+            "issuer": self.base_url,
+            "client_id": MOCK_CLIENT_ID,
+            "client_secret": MOCK_CLIENT_SECRET,
+            "authorization_endpoint": format!("{}/authorize", self.base_url),
+            "token_endpoint": format!("{}/token", self.base_url),
+            "userinfo_endpoint": format!("{}/userinfo", self.base_url),
+            "jwks_uri": format!("{}/jwks", self.base_url),
+        }))
+        .unwrap()
+    }
+
+    pub fn set_audience(&self, audience: &str) {
+        self.state.lock().unwrap().audience = Some(audience.to_string());
+    }
+
+    /// Follows the authorization URL as a signed-in user would, returning the callback
+    /// query with the code and state.
+    pub async fn authorize(&self, authorization_url: &Url) -> HashMap<String, String> {
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+        let response = client.get(authorization_url.as_str()).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let location = response.headers().get("location").unwrap().to_str().unwrap();
+        Url::parse(location).unwrap().query_pairs().into_owned().collect()
+    }
+}
+
+#[derive(Deserialize)]
+struct AuthorizeQuery {
+    redirect_uri: String,
+    state: String,
+    nonce: Option<String>,
+    code_challenge: String,
+}
+
+async fn authorize(State(state): State<Arc<Mutex<MockState>>>, Query(query): Query<AuthorizeQuery>) -> Response {
+    let code = Uuid::new_v4().to_string();
+    let mut redirect_uri = Url::parse(&query.redirect_uri).unwrap();
+    redirect_uri
+        .query_pairs_mut()
+        .append_pair("code", &code)
+        .append_pair("state", &query.state);
+    let pending = PendingCode {
+        nonce: query.nonce,
+        code_challenge: query.code_challenge,
+        redirect_uri: query.redirect_uri,
+    };
+    state.lock().unwrap().codes.insert(code, pending);
+    Redirect::to(redirect_uri.as_str()).into_response()
+}
+
+#[derive(Deserialize)]
+struct TokenForm {
+    code: String,
+    redirect_uri: String,
+    client_id: String,
+    client_secret: String,
+    code_verifier: String,
+}
+
+async fn token(State(state): State<Arc<Mutex<MockState>>>, Form(form): Form<TokenForm>) -> Response {
+    let mut state = state.lock().unwrap();
+    let Some(pending) = state.codes.remove(&form.code) else {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid_grant"}))).into_response();
+    };
+    if form.client_id != MOCK_CLIENT_ID
+        || form.client_secret != MOCK_CLIENT_SECRET
+        || form.redirect_uri != pending.redirect_uri
+        || code_challenge(&form.code_verifier) != pending.code_challenge
+    {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid_grant"}))).into_response();
+    }
+    let now = get_current_timestamp();
+    let id_token = json!({
+        "iss": state.issuer,
+        "sub": state.profile.sub,
+        "aud": state.audience.clone().unwrap_or(MOCK_CLIENT_ID.to_string()),
+        "iat": now,
+        "exp": now + 600,
+        "nonce": pending.nonce,
+        "email": state.profile.email,
+        "email_verified": state.profile.email_verified,
+    });
+    let id_token = state.keyring.sign_id_token(&id_token).unwrap();
+    Json(json!({
+        "access_token": MOCK_ACCESS_TOKEN,
+        "token_type": "Bearer",
+        "expires_in": 600,
+        "id_token": id_token,
+    }))
+    .into_response()
+}
+
+async fn userinfo(State(state): State<Arc<Mutex<MockState>>>, headers: HeaderMap) -> Response {
+    let authorization = headers.get("authorization").and_then(|value| value.to_str().ok());
+    if authorization != Some(&format!("Bearer {}", MOCK_ACCESS_TOKEN)) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    Json(state.lock().unwrap().profile.clone()).into_response()
+}
+
+async fn jwks(State(state): State<Arc<Mutex<MockState>>>) -> Response {
+    Json(state.lock().unwrap().keyring.jwks()).into_response()
+}
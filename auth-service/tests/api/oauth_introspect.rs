@@ -0,0 +1,83 @@
+use crate::helpers::{random_email, TestApp, TEST_CLIENT_ID, TEST_CODE_VERIFIER, TEST_ISSUER, TEST_REDIRECT_URI};
+use auth_service::routes::{IntrospectionResponse, TokenErrorResponse, TokenResponse};
+use reqwest::StatusCode;
+use serde_json::json;
+
+#[tokio::test]
+async fn introspect_reports_active_client_token() {
+    let app = TestApp::new().await;
+    let (client_id, client_secret) = app.register_confidential_client(&["reports:read"]).await;
+    let token = app.client_access_token(&client_id, &client_secret).await;
+    let response = app
+        .post_oauth_introspect(&client_id, &client_secret, &json!({"token": token}))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let introspection = response.json::<IntrospectionResponse>().await.unwrap();
+    assert!(introspection.active);
+    assert_eq!(introspection.sub.as_deref(), Some(client_id.as_str()));
+    assert_eq!(introspection.client_id.as_deref(), Some(client_id.as_str()));
+    assert_eq!(introspection.scope.as_deref(), Some("reports:read"));
+    assert_eq!(introspection.iss.as_deref(), Some(TEST_ISSUER));
+    assert!(introspection.exp.is_some());
+}
+
+#[tokio::test]
+async fn introspect_reports_user_token_issued_to_other_client() {
+    let app = TestApp::new().await;
+    app.register_client().await;
+    let email = random_email();
+    let code = app.authorization_code(&email, "StrongPassword123!").await;
+    let response = app
+        .post_token(&json!({
+            "grant_type": "authorization_code",
+            "code": code,
+            "redirect_uri": TEST_REDIRECT_URI,
+            "client_id": TEST_CLIENT_ID,
+            "code_verifier": TEST_CODE_VERIFIER,
+        }))
+        .await;
+    let tokens = response.json::<TokenResponse>().await.unwrap();
+
+    let (client_id, client_secret) = app.register_confidential_client(&[]).await;
+    let response = app
+        .post_oauth_introspect(&client_id, &client_secret, &json!({"token": tokens.access_token}))
+        .await;
+    let introspection = response.json::<IntrospectionResponse>().await.unwrap();
+    assert!(introspection.active);
+    assert_eq!(introspection.sub, Some(email));
+    assert_eq!(introspection.client_id.as_deref(), Some(TEST_CLIENT_ID));
+    assert_eq!(introspection.scope.as_deref(), Some("openid email"));
+}
+
+#[tokio::test]
+async fn introspect_reports_invalid_token_as_inactive() {
+    let app = TestApp::new().await;
+    let (client_id, client_secret) = app.register_confidential_client(&[]).await;
+    let response = app
+        .post_oauth_introspect(&client_id, &client_secret, &json!({"token": "string"}))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body, json!({"active": false}));
+}
+
+#[tokio::test]
+async fn introspect_requires_client_authentication() {
+    let app = TestApp::new().await;
+    let (client_id, client_secret) = app.register_confidential_client(&[]).await;
+    let token = app.client_access_token(&client_id, &client_secret).await;
+    let response = app
+        .post_oauth_introspect(&client_id, "wrong-secret", &json!({"token": token}))
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.json::<TokenErrorResponse>().await.unwrap().error, "invalid_client");
+}
+
+#[tokio::test]
+async fn introspect_returns_invalid_request_for_missing_token() {
+    let app = TestApp::new().await;
+    let (client_id, client_secret) = app.register_confidential_client(&[]).await;
+    let response = app.post_oauth_introspect(&client_id, &client_secret, &json!({})).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.json::<TokenErrorResponse>().await.unwrap().error, "invalid_request");
+}
@@ -0,0 +1,56 @@
+use crate::helpers::TestApp;
+use auth_service::routes::{IntrospectionResponse, TokenErrorResponse};
+use reqwest::StatusCode;
+use serde_json::json;
+
+#[tokio::test]
+async fn revoke_deactivates_token() {
+    let app = TestApp::new().await;
+    let (client_id, client_secret) = app.register_confidential_client(&["reports:read"]).await;
+    let token = app.client_access_token(&client_id, &client_secret).await;
+    let form = json!({"token": token, "token_type_hint": "access_token"});
+    let response = app.post_oauth_revoke(&client_id, &client_secret, &form).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.post_oauth_introspect(&client_id, &client_secret, &form).await;
+    assert!(!response.json::<IntrospectionResponse>().await.unwrap().active);
+    let response = app.post_verify_token(&json!({"token": token})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn revoke_accepts_invalid_token() {
+    let app = TestApp::new().await;
+    let (client_id, client_secret) = app.register_confidential_client(&[]).await;
+    let response = app
+        .post_oauth_revoke(&client_id, &client_secret, &json!({"token": "string"}))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn revoke_rejects_token_of_other_client() {
+    let app = TestApp::new().await;
+    let (owner_id, owner_secret) = app.register_confidential_client(&[]).await;
+    let token = app.client_access_token(&owner_id, &owner_secret).await;
+    let (client_id, client_secret) = app.register_confidential_client(&[]).await;
+    let response = app
+        .post_oauth_revoke(&client_id, &client_secret, &json!({"token": token}))
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.json::<TokenErrorResponse>().await.unwrap().error, "unauthorized_client");
+
+    let response = app.post_verify_token(&json!({"token": token})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn revoke_requires_client_authentication() {
+    let app = TestApp::new().await;
+    let (client_id, client_secret) = app.register_confidential_client(&[]).await;
+    let token = app.client_access_token(&client_id, &client_secret).await;
+    let response = app
+        .post_oauth_revoke(&client_id, "wrong-secret", &json!({"token": token}))
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,70 @@
+use crate::helpers::TestApp;
+use auth_service::domain::Claims;
+use auth_service::routes::{TokenErrorResponse, TokenResponse};
+use reqwest::header::WWW_AUTHENTICATE;
+use reqwest::StatusCode;
+use serde_json::json;
+
+#[tokio::test]
+async fn oauth_token_issues_token_with_granted_scopes() {
+    let app = TestApp::new().await;
+    let (client_id, client_secret) = app.register_confidential_client(&["reports:read", "reports:write"]).await;
+    let form = json!({"grant_type": "client_credentials"});
+    let response = app.post_oauth_token(&client_id, &client_secret, &form).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let tokens = response.json::<TokenResponse>().await.unwrap();
+    assert_eq!(tokens.token_type, "Bearer");
+    assert!(tokens.id_token.is_none());
+    assert_eq!(tokens.scope.as_deref(), Some("reports:read reports:write"));
+    let claims: Claims = app.app_state.keyring.read().await.verify(&tokens.access_token).unwrap();
+    assert_eq!(claims.sub, client_id);
+    assert_eq!(claims.client_id.as_deref(), Some(client_id.as_str()));
+    assert_eq!(claims.scope.as_deref(), Some("reports:read reports:write"));
+}
+
+#[tokio::test]
+async fn oauth_token_narrows_requested_scope() {
+    let app = TestApp::new().await;
+    let (client_id, client_secret) = app.register_confidential_client(&["reports:read", "reports:write"]).await;
+    let form = json!({"grant_type": "client_credentials", "scope": "reports:read"});
+    let response = app.post_oauth_token(&client_id, &client_secret, &form).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let tokens = response.json::<TokenResponse>().await.unwrap();
+    assert_eq!(tokens.scope.as_deref(), Some("reports:read"));
+}
+
+#[tokio::test]
+async fn oauth_token_rejects_scope_not_granted() {
+    let app = TestApp::new().await;
+    let (client_id, client_secret) = app.register_confidential_client(&["reports:read"]).await;
+    let form = json!({"grant_type": "client_credentials", "scope": "reports:read reports:write"});
+    let response = app.post_oauth_token(&client_id, &client_secret, &form).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.json::<TokenErrorResponse>().await.unwrap().error, "invalid_scope");
+}
+
+#[tokio::test]
+async fn oauth_token_rejects_invalid_client_credentials() {
+    let app = TestApp::new().await;
+    let (client_id, _) = app.register_confidential_client(&["reports:read"]).await;
+    let form = json!({"grant_type": "client_credentials"});
+    for (client_id, client_secret) in [(client_id.as_str(), "wrong-secret"), ("unknown", "secret")] {
+        let response = app.post_oauth_token(client_id, client_secret, &form).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "Input: {:?}", client_id);
+        assert_eq!(response.headers().get(WWW_AUTHENTICATE).unwrap(), "Basic");
+        assert_eq!(response.json::<TokenErrorResponse>().await.unwrap().error, "invalid_client");
+    }
+}
+
+#[tokio::test]
+async fn oauth_token_accepts_credentials_in_body() {
+    let app = TestApp::new().await;
+    let (client_id, client_secret) = app.register_confidential_client(&["reports:read"]).await;
+    let form = json!({
+        "grant_type": "client_credentials",
+        "client_id": client_id,
+        "client_secret": client_secret,
+    });
+    let response = app.post_token(&form).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,22 @@
+use crate::helpers::{TestApp, TEST_ISSUER};
+use auth_service::routes::OpenIdConfiguration;
+use mime::APPLICATION_JSON;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::StatusCode;
+
+#[tokio::test]
+async fn openid_configuration_returns_provider_metadata() {
+    let app = TestApp::new().await;
+    let response = app.get_openid_configuration().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    let configuration = response.json::<OpenIdConfiguration>().await.unwrap();
+    assert_eq!(configuration.issuer, TEST_ISSUER);
+    assert_eq!(configuration.authorization_endpoint, format!("{}/authorize", TEST_ISSUER));
+    assert_eq!(configuration.token_endpoint, format!("{}/token", TEST_ISSUER));
+    assert_eq!(configuration.userinfo_endpoint, format!("{}/userinfo", TEST_ISSUER));
+    assert_eq!(configuration.jwks_uri, format!("{}/.well-known/jwks.json", TEST_ISSUER));
+    assert_eq!(configuration.introspection_endpoint, format!("{}/oauth/introspect", TEST_ISSUER));
+    assert_eq!(configuration.revocation_endpoint, format!("{}/oauth/revoke", TEST_ISSUER));
+    assert!(configuration.code_challenge_methods_supported.contains(&"S256".to_string()));
+}
@@ -0,0 +1,121 @@
+use crate::helpers::{random_email, TestApp, TEST_ADMIN_API_KEY};
+use auth_service::domain::{PasswordPolicy, Tenant, TenantSettings, MAX_FAILED_LOGIN_ATTEMPTS};
+use auth_service::routes::{PasswordRejection, PasswordResetResponse, PASSWORD_RESET_SUBJECT};
+use reqwest::header::RETRY_AFTER;
+use reqwest::StatusCode;
+use serde_json::json;
+
+const PASSWORD: &str = "StrongPassword123!";
+const NEW_PASSWORD: &str = "CorrectHorseBattery9!";
+
+/// Asks for a password reset, returning the token emailed to the user.
+async fn reset_token(app: &TestApp, email: &str) -> Option<String> {
+    let response = app.post_forgot_password(&json!({"email": email})).await;
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let email_client = app.email_client.read().await;
+    let sent = email_client.sent_emails().iter().rev().find(|sent| sent.recipient == email)?;
+    assert_eq!(sent.subject, PASSWORD_RESET_SUBJECT);
+    let (_, rest) = sent.content.split_once(": ")?;
+    rest.lines().next().map(str::to_string)
+}
+
+#[tokio::test]
+async fn should_reset_password_with_emailed_token() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    let session_token = app.login(&email, PASSWORD).await;
+    let token = reset_token(&app, &email).await.unwrap();
+
+    let response = app.post_reset_password(&json!({"token": token, "newPassword": NEW_PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let expected = PasswordResetResponse::Message("Password reset successfully!".to_string());
+    assert_eq!(response.json::<PasswordResetResponse>().await.unwrap(), expected);
+
+    let response = app.post_login(&json!({"email": email, "password": PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.post_login(&json!({"email": email, "password": NEW_PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    // Sessions opened with the old password are signed out.
+    assert_eq!(app.get_sessions(&session_token).await.status(), StatusCode::UNAUTHORIZED);
+
+    // The token is used only once.
+    let response = app.post_reset_password(&json!({"token": token, "newPassword": "CorrectHorseBattery8!"})).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn should_not_tell_whether_the_user_exists() {
+    let app = TestApp::new().await;
+    assert_eq!(reset_token(&app, &random_email()).await, None);
+}
+
+#[tokio::test]
+async fn should_rate_limit_password_resets_by_email_and_client() {
+    let app = TestApp::with_forgot_password_rate_limits(3, 2).await;
+    let email = random_email();
+    for _ in 0..2 {
+        let response = app.post_forgot_password(&json!({"email": email})).await;
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+    }
+    let response = app.post_forgot_password(&json!({"email": email.to_uppercase()})).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = response.headers().get(RETRY_AFTER).unwrap().to_str().unwrap().parse().unwrap();
+    assert!((1..=60).contains(&retry_after));
+
+    // The limit by client address applies to other email addresses too.
+    let response = app.post_forgot_password(&json!({"email": random_email()})).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn should_return_400_if_token_is_invalid() {
+    let app = TestApp::new().await;
+    let response = app.post_reset_password(&json!({"token": "invalid", "newPassword": NEW_PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let expected = PasswordResetResponse::Error("Invalid or expired token".to_string());
+    assert_eq!(response.json::<PasswordResetResponse>().await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn should_reject_recently_used_passwords() {
+    let settings = TenantSettings {
+        password_policy: PasswordPolicy::default().with_history_depth(2),
+        require_2fa: false,
+        case_sensitive_emails: false,
+    };
+    let app = TestApp::with_tenants(vec![Tenant::try_new("history", "History", &[], settings).unwrap()]).await;
+    let history = app.tenant("history");
+    let email = random_email();
+    history.login(&email, PASSWORD).await;
+    let token = reset_token(&history, &email).await.unwrap();
+
+    let response = history.post_reset_password(&json!({"token": token, "newPassword": PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.json::<PasswordRejection>().await.unwrap().code, "recently_used");
+    // The token may be used again with another password.
+    let response = history.post_reset_password(&json!({"token": token, "newPassword": NEW_PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    // Tokens are only valid in their tenant.
+    let token = reset_token(&history, &email).await.unwrap();
+    let response = app.post_reset_password(&json!({"token": token, "newPassword": "CorrectHorseBattery8!"})).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn should_lift_required_reset_and_unlock_account() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    app.login(&email, PASSWORD).await;
+    app.post_admin_user_action(TEST_ADMIN_API_KEY, &email, "force-password-reset").await;
+    for _ in 0..MAX_FAILED_LOGIN_ATTEMPTS {
+        app.post_login(&json!({"email": email, "password": "WrongPassword123!"})).await;
+    }
+    let response = app.post_login(&json!({"email": email, "password": PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let token = reset_token(&app, &email).await.unwrap();
+    let response = app.post_reset_password(&json!({"token": token, "newPassword": NEW_PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.post_login(&json!({"email": email, "password": NEW_PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
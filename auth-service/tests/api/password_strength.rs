@@ -0,0 +1,72 @@
+use crate::helpers::{random_email, TestApp};
+use auth_service::domain::{PasswordPolicy, Tenant, TenantSettings};
+use auth_service::routes::{PasswordRejection, PasswordStrengthResponse};
+use reqwest::header::RETRY_AFTER;
+use reqwest::StatusCode;
+use serde_json::json;
+
+#[tokio::test]
+async fn password_strength_rates_weak_and_strong_passwords() {
+    let app = TestApp::new().await;
+    let response = app.post_password_strength(&json!({"email": random_email(), "password": "password123"})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let weak = response.json::<PasswordStrengthResponse>().await.unwrap();
+    assert_eq!(weak.strength.score, 0);
+    assert!(!weak.acceptable);
+    assert_eq!(weak.strength.feedback.warning.as_deref(), Some("This is a very common password."));
+    assert!(!weak.strength.feedback.suggestions.is_empty());
+
+    let body = json!({"email": random_email(), "password": "CorrectHorseBatteryStaple123!"});
+    let strong = app.post_password_strength(&body).await.json::<PasswordStrengthResponse>().await.unwrap();
+    assert_eq!(strong.strength.score, 4);
+    assert!(strong.acceptable);
+    assert!(strong.strength.feedback.suggestions.is_empty());
+    let crack_times = &strong.strength.crack_times;
+    let slow = &crack_times.offline_slow_hashing_1e4_per_second;
+    assert!(slow.seconds > crack_times.offline_fast_hashing_1e10_per_second.seconds);
+    assert_eq!(slow.display, "centuries");
+}
+
+#[tokio::test]
+async fn password_strength_counts_the_email_as_guessable() {
+    let app = TestApp::new().await;
+    let password = "zaphod.beeblebrox.42@example.com";
+    let body = json!({"email": password, "password": password});
+    let response = app.post_password_strength(&body).await.json::<PasswordStrengthResponse>().await.unwrap();
+    assert!(!response.acceptable);
+    let body = json!({"email": random_email(), "password": password});
+    let response = app.post_password_strength(&body).await.json::<PasswordStrengthResponse>().await.unwrap();
+    assert!(response.acceptable);
+}
+
+#[tokio::test]
+async fn password_strength_follows_the_tenant_policy() {
+    let settings = TenantSettings {
+        password_policy: PasswordPolicy::try_new(30, 40).unwrap(),
+        require_2fa: false,
+        case_sensitive_emails: false,
+    };
+    let app = TestApp::with_tenants(vec![Tenant::try_new("strict", "Strict", &[], settings).unwrap()]).await;
+    let strict = app.tenant("strict");
+    let body = json!({"email": random_email(), "password": "CorrectHorseBatteryStaple123!"});
+    let response = strict.post_password_strength(&body).await.json::<PasswordStrengthResponse>().await.unwrap();
+    assert_eq!(response.strength.score, 4);
+    assert!(!response.acceptable);
+
+    let response = strict.post_password_strength(&json!({"email": random_email(), "password": "a".repeat(41)})).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.json::<PasswordRejection>().await.unwrap().code, "too_long");
+}
+
+#[tokio::test]
+async fn password_strength_is_rate_limited() {
+    let app = TestApp::with_password_strength_rate_limit(2).await;
+    let body = json!({"email": random_email(), "password": "password123"});
+    for _ in 0..2 {
+        assert_eq!(app.post_password_strength(&body).await.status(), StatusCode::OK);
+    }
+    let response = app.post_password_strength(&body).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = response.headers().get(RETRY_AFTER).unwrap().to_str().unwrap().parse().unwrap();
+    assert!((1..=60).contains(&retry_after));
+}
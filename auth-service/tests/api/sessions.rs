@@ -0,0 +1,103 @@
+use crate::helpers::{random_email, TestApp, TEST_USER_AGENT};
+use auth_service::domain::{Tenant, TenantSettings};
+use auth_service::routes::{SessionResponse, SessionsResponse};
+use reqwest::StatusCode;
+use serde_json::json;
+
+const PASSWORD: &str = "StrongPassword123!";
+
+async fn sessions(app: &TestApp, token: &str) -> Vec<SessionResponse> {
+    let response = app.get_sessions(token).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    match response.json::<SessionsResponse>().await.unwrap() {
+        SessionsResponse::Sessions(sessions) => sessions,
+        SessionsResponse::Error(error) => panic!("Unexpected error: {}", error),
+    }
+}
+
+#[tokio::test]
+async fn sessions_lists_devices() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    let first = app.login(&email, PASSWORD).await;
+    let second = app.login(&email, PASSWORD).await;
+    let sessions = sessions(&app, &second).await;
+    assert_eq!(sessions.len(), 2);
+    // Both sessions may open within the same second, so their order is not asserted.
+    assert_eq!(sessions.iter().filter(|session| session.current).count(), 1);
+    assert_eq!(sessions[0].user_agent.as_deref(), Some(TEST_USER_AGENT));
+    assert_eq!(sessions[0].ip_address.as_deref(), Some("127.0.0.1"));
+    assert!(sessions[0].last_seen_at >= sessions[0].created_at);
+
+    let other = app.login(&random_email(), PASSWORD).await;
+    assert_eq!(self::sessions(&app, &other).await.len(), 1);
+    assert_eq!(self::sessions(&app, &first).await.len(), 2);
+}
+
+#[tokio::test]
+async fn sessions_revoke_device_invalidates_its_token_immediately() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    let first = app.login(&email, PASSWORD).await;
+    let second = app.login(&email, PASSWORD).await;
+    let first_id = sessions(&app, &first).await.into_iter().find(|session| session.current).unwrap().id;
+
+    let response = app.delete_session(&second, &first_id).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app.post_verify_token(&json!({"token": first})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.post_verify_token(&json!({"token": second})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(sessions(&app, &second).await.len(), 1);
+}
+
+#[tokio::test]
+async fn sessions_cannot_revoke_other_users_device() {
+    let app = TestApp::new().await;
+    let victim = app.login(&random_email(), PASSWORD).await;
+    let victim_id = sessions(&app, &victim).await[0].id.clone();
+    let attacker = app.login(&random_email(), PASSWORD).await;
+    let response = app.delete_session(&attacker, &victim_id).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = app.post_verify_token(&json!({"token": victim})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn sessions_cannot_revoke_same_email_device_in_other_tenant() {
+    let acme = Tenant::try_new("acme", "Acme", &[], TenantSettings::default()).unwrap();
+    let app = TestApp::with_tenants(vec![acme]).await;
+    let acme = app.tenant("acme");
+    let email = random_email();
+    let victim = app.login(&email, PASSWORD).await;
+    let victim_id = sessions(&app, &victim).await[0].id.clone();
+    let attacker = acme.login(&email, PASSWORD).await;
+    let response = acme.delete_session(&attacker, &victim_id).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = app.post_verify_token(&json!({"token": victim})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn sessions_log_out_everywhere() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    let first = app.login(&email, PASSWORD).await;
+    let second = app.login(&email, PASSWORD).await;
+    let other = app.login(&random_email(), PASSWORD).await;
+    let response = app.delete_sessions(&second).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    for token in [&first, &second] {
+        let response = app.post_verify_token(&json!({"token": token})).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+    let response = app.post_verify_token(&json!({"token": other})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn sessions_require_valid_token() {
+    let app = TestApp::new().await;
+    assert_eq!(app.get_sessions("string").await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(app.delete_sessions("string").await.status(), StatusCode::UNAUTHORIZED);
+}
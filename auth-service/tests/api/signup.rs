@@ -1,9 +1,14 @@
 use crate::helpers::{random_email, TestApp};
-use auth_service::routes::SignupResponse;
+use auth_service::app_state::Settings;
+use auth_service::domain::{DomainList, EmailDomainPolicy};
+use auth_service::routes::{PasswordRejection, SignupResponse};
+use auth_service::services::PwnedPasswords;
 use mime::APPLICATION_JSON;
 use reqwest::header::CONTENT_TYPE;
 use reqwest::StatusCode;
 use serde_json::{json, Value};
+use sha1::{Digest, Sha1};
+use uuid::Uuid;
 
 #[tokio::test]
 async fn should_return_201_if_valid_input() {
@@ -51,6 +56,49 @@ async fn should_return_400_if_invalid_input() {
     }
 }
 
+#[tokio::test]
+async fn should_return_400_if_email_is_disposable() {
+    let app = TestApp::new().await;
+    let body = json!({"email": "alice@Mailinator.com", "password": "StrongPassword123!", "requires2FA": false});
+    let response = app.post_signup(&body).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.json::<SignupResponse>().await.unwrap(),
+        SignupResponse::Error("Disposable email addresses, such as at mailinator.com, are not accepted".to_string())
+    );
+}
+
+/// Replaces the email domain policy, as reloading the settings does.
+fn set_email_domain_policy(app: &TestApp, email_domain_policy: EmailDomainPolicy) {
+    app.app_state.settings.rcu(|settings| Settings {
+        email_domain_policy: email_domain_policy.clone(),
+        ..Settings::clone(settings)
+    });
+}
+
+#[tokio::test]
+async fn should_return_400_if_email_domain_is_not_allowed() {
+    let app = TestApp::new().await;
+    let body = |email: &str| json!({"email": email, "password": "StrongPassword123!", "requires2FA": false});
+    set_email_domain_policy(&app, EmailDomainPolicy {
+        allow_list: DomainList::new(["example.com"]).unwrap(),
+        deny_list: DomainList::new(["spam.example.com"]).unwrap(),
+        reject_disposable: true,
+    });
+
+    let response = app.post_signup(&body(&format!("{}@eu.example.com", Uuid::new_v4()))).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    for email in ["alice@spam.example.com", "alice@example.org"] {
+        let response = app.post_signup(&body(email)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "Email: {}", email);
+    }
+
+    // Replacing the policy applies from the next signup.
+    set_email_domain_policy(&app, EmailDomainPolicy::default());
+    let response = app.post_signup(&body(&format!("{}@example.org", Uuid::new_v4()))).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
 #[tokio::test]
 async fn should_return_409_if_user_already_exists() {
     let app = TestApp::new().await;
@@ -68,6 +116,18 @@ async fn should_return_409_if_user_already_exists() {
     assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
 }
 
+#[tokio::test]
+async fn should_return_409_if_user_exists_with_other_spelling() {
+    let app = TestApp::new().await;
+    let local_part = Uuid::new_v4().simple().to_string();
+    let body = |email: String| json!({"email": email, "password": "StrongPassword123!", "requires2FA": false});
+    let response = app.post_signup(&body(format!("{}@Example.com", local_part))).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let response = app.post_signup(&body(format!(" {}@EXAMPLE.COM", local_part.to_uppercase()))).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
 #[tokio::test]
 async fn should_return_422_if_unprocessable_content() {
     let app = TestApp::new().await;
@@ -85,6 +145,40 @@ async fn should_return_422_if_unprocessable_content() {
     }
 }
 
+#[tokio::test]
+async fn should_return_400_with_feedback_if_password_is_weak() {
+    let app = TestApp::new().await;
+    let request = json!({"email": random_email(), "password": "password123", "requires2FA": false});
+    let response = app.post_signup(&request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let rejection = response.json::<PasswordRejection>().await.unwrap();
+    assert_eq!(rejection.error, "Invalid password: Password is weak");
+    assert_eq!(rejection.code, "weak");
+    assert_eq!(rejection.warning.as_deref(), Some("This is a very common password."));
+    assert!(!rejection.suggestions.is_empty());
+}
+
+#[tokio::test]
+async fn should_return_400_if_password_is_breached() {
+    const BREACHED_PASSWORD: &str = "StrongPassword123!";
+    let dataset = std::env::temp_dir().join(format!("pwned-passwords-{}", Uuid::new_v4()));
+    std::fs::create_dir(&dataset).unwrap();
+    let hash = format!("{:X}", Sha1::digest(BREACHED_PASSWORD));
+    std::fs::write(dataset.join(format!("{}.txt", &hash[..5])), format!("{}:42\r\n", &hash[5..])).unwrap();
+    let app = TestApp::with_pwned_passwords(PwnedPasswords::open(&dataset).unwrap()).await;
+
+    let request = json!({"email": random_email(), "password": BREACHED_PASSWORD, "requires2FA": false});
+    let response = app.post_signup(&request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let rejection = response.json::<PasswordRejection>().await.unwrap();
+    assert_eq!(rejection.error, "Invalid password: Password has appeared in a data breach");
+    assert_eq!(rejection.code, "breached");
+
+    let request = json!({"email": random_email(), "password": "StrongPassword456!", "requires2FA": false});
+    assert_eq!(app.post_signup(&request).await.status(), StatusCode::CREATED);
+    std::fs::remove_dir_all(&dataset).unwrap();
+}
+
 #[tokio::test]
 async fn should_return_500_if_unexpected_error() {
     let app = TestApp::new().await;
@@ -0,0 +1,137 @@
+use crate::helpers::{assert_jwt, jwt_cookie, location, random_email, TestApp, TEST_ISSUER};
+use crate::mock_idp::{MockIdp, MockProfile, MOCK_CLIENT_ID};
+use auth_service::domain::{Email, ExternalIdentity, DEFAULT_TENANT_ID};
+use auth_service::routes::TwoFactorRequiredResponse;
+use auth_service::services::UserStore;
+use reqwest::header::LOCATION;
+use reqwest::StatusCode;
+use serde_json::json;
+use std::collections::HashMap;
+
+/// Runs a full login at the mock provider, returning the callback response.
+async fn sign_in(app: &TestApp, idp: &MockIdp, provider: &str) -> reqwest::Response {
+    let response = app.get_social_login(provider).await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    let callback = idp.authorize(&location(&response)).await;
+    app.get_social_login_callback(provider, &callback).await
+}
+
+#[tokio::test]
+async fn social_login_redirects_to_provider_with_pkce() {
+    let idp = MockIdp::start(MockProfile::new(&random_email())).await;
+    let app = TestApp::with_identity_providers(vec![idp.provider("mock")]).await;
+    let response = app.get_social_login("mock").await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    let url = location(&response);
+    assert!(url.as_str().starts_with(&format!("{}/authorize", idp.base_url)));
+    let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    assert_eq!(query["client_id"], MOCK_CLIENT_ID);
+    assert_eq!(query["redirect_uri"], format!("{}/login/mock/callback", TEST_ISSUER));
+    assert_eq!(query["code_challenge_method"], "S256");
+    assert!(query.contains_key("state"));
+    assert!(query.contains_key("nonce"));
+}
+
+#[tokio::test]
+async fn social_login_creates_user_with_linked_identity() {
+    let email = random_email();
+    let profile = MockProfile::new(&email);
+    let idp = MockIdp::start(profile.clone()).await;
+    let app = TestApp::with_identity_providers(vec![idp.provider("mock")]).await;
+    let response = sign_in(&app, &idp, "mock").await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(response.headers().get(LOCATION).unwrap(), "/");
+    assert_jwt(jwt_cookie(&response));
+
+    let user_store = app.app_state.user_store.read().await;
+    let user = user_store.get_user(DEFAULT_TENANT_ID, &Email::parse(&email).unwrap()).await.unwrap();
+    assert_eq!(user.identities, vec![ExternalIdentity::new("mock", &profile.sub)]);
+}
+
+#[tokio::test]
+async fn social_login_links_identities_to_existing_user_by_verified_email() {
+    let email = random_email();
+    let google = MockIdp::start(MockProfile::new(&email)).await;
+    let github = MockIdp::start(MockProfile::new(&email)).await;
+    let providers = vec![google.provider("google"), github.provider("github")];
+    let app = TestApp::with_identity_providers(providers).await;
+    let body = json!({"email": email, "password": "StrongPassword123!", "requires2FA": false});
+    assert_eq!(app.post_signup(&body).await.status(), StatusCode::CREATED);
+
+    for (idp, provider) in [(&google, "google"), (&github, "github"), (&google, "google")] {
+        let response = sign_in(&app, idp, provider).await;
+        assert_eq!(response.status(), StatusCode::SEE_OTHER, "Provider: {}", provider);
+        assert_jwt(jwt_cookie(&response));
+    }
+
+    let user_store = app.app_state.user_store.read().await;
+    let user = user_store.get_user(DEFAULT_TENANT_ID, &Email::parse(&email).unwrap()).await.unwrap();
+    assert_eq!(user.identities.len(), 2);
+    assert_eq!(user.password.expose(), "StrongPassword123!");
+}
+
+#[tokio::test]
+async fn social_login_requires_2fa_when_the_user_does() {
+    let email = random_email();
+    let idp = MockIdp::start(MockProfile::new(&email)).await;
+    let app = TestApp::with_identity_providers(vec![idp.provider("mock")]).await;
+    let body = json!({"email": email, "password": "StrongPassword123!", "requires2FA": true});
+    assert_eq!(app.post_signup(&body).await.status(), StatusCode::CREATED);
+    let response = sign_in(&app, &idp, "mock").await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert!(jwt_cookie(&response).is_none());
+
+    let login_attempt_id = response.json::<TwoFactorRequiredResponse>().await.unwrap().login_attempt_id;
+    let code = app.two_factor_code(&email).await.unwrap();
+    let body = json!({"email": email, "loginAttemptId": login_attempt_id, "2FACode": code});
+    let response = app.post_verify_2fa(&body).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_jwt(jwt_cookie(&response));
+}
+
+#[tokio::test]
+async fn social_login_rejects_unverified_email() {
+    let email = random_email();
+    let mut profile = MockProfile::new(&email);
+    profile.email_verified = false;
+    let idp = MockIdp::start(profile).await;
+    let app = TestApp::with_identity_providers(vec![idp.provider("mock")]).await;
+    let response = sign_in(&app, &idp, "mock").await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert!(jwt_cookie(&response).is_none());
+    let user_store = app.app_state.user_store.read().await;
+    assert!(user_store.get_user(DEFAULT_TENANT_ID, &Email::parse(&email).unwrap()).await.is_err());
+}
+
+#[tokio::test]
+async fn social_login_rejects_id_token_for_other_audience() {
+    let idp = MockIdp::start(MockProfile::new(&random_email())).await;
+    idp.set_audience("other-client");
+    let app = TestApp::with_identity_providers(vec![idp.provider("mock")]).await;
+    let response = sign_in(&app, &idp, "mock").await;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    assert!(jwt_cookie(&response).is_none());
+}
+
+#[tokio::test]
+async fn social_login_rejects_invalid_or_reused_state() {
+    let idp = MockIdp::start(MockProfile::new(&random_email())).await;
+    let app = TestApp::with_identity_providers(vec![idp.provider("mock")]).await;
+    let response = app
+        .get_social_login_callback("mock", &json!({"code": "code", "state": "unknown"}))
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app.get_social_login("mock").await;
+    let callback = idp.authorize(&location(&response)).await;
+    let response = app.get_social_login_callback("mock", &callback).await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    let response = app.get_social_login_callback("mock", &callback).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn social_login_returns_404_for_unknown_provider() {
+    let app = TestApp::new().await;
+    assert_eq!(app.get_social_login("unknown").await.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,148 @@
+use crate::helpers::{random_email, session_token, TestApp};
+use auth_service::domain::{PasswordPolicy, Tenant, TenantSettings, DEFAULT_TENANT_ID};
+use auth_service::routes::VerifiedTokenResponse;
+use auth_service::utils::TENANT_HEADER;
+use reqwest::header::HOST;
+use reqwest::StatusCode;
+use serde_json::json;
+
+const PASSWORD: &str = "StrongPassword123!";
+
+async fn app() -> TestApp {
+    let strict = TenantSettings {
+        password_policy: PasswordPolicy::try_new(20, 64).unwrap(),
+        require_2fa: true,
+        case_sensitive_emails: false,
+    };
+    TestApp::with_tenants(vec![
+        Tenant::try_new("acme", "Acme", &["acme.localhost"], TenantSettings::default()).unwrap(),
+        Tenant::try_new("strict", "Strict", &[], strict).unwrap(),
+    ])
+    .await
+}
+
+async fn verified_tenant_id(app: &TestApp, token: &str) -> String {
+    let response = app.post_verify_token(&json!({"token": token})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    response.json::<VerifiedTokenResponse>().await.unwrap().tenant_id
+}
+
+#[tokio::test]
+async fn tenants_isolate_users_with_the_same_email() {
+    let app = app().await;
+    let acme = app.tenant("acme");
+    let email = random_email();
+    let default_token = app.login(&email, PASSWORD).await;
+    let acme_token = acme.login(&email, "AnotherPassword456!").await;
+    assert_eq!(verified_tenant_id(&app, &default_token).await, DEFAULT_TENANT_ID);
+    assert_eq!(verified_tenant_id(&app, &acme_token).await, "acme");
+
+    let response = acme.post_login(&json!({"email": email, "password": PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.post_login(&json!({"email": email, "password": "AnotherPassword456!"})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn tenants_may_tell_email_local_parts_apart_by_case() {
+    let settings = TenantSettings {
+        case_sensitive_emails: true,
+        ..TenantSettings::default()
+    };
+    let app = TestApp::with_tenants(vec![Tenant::try_new("sensitive", "Sensitive", &[], settings).unwrap()]).await;
+    let sensitive = app.tenant("sensitive");
+    let email = random_email();
+    let (lower, upper) = (email.to_lowercase(), email.to_uppercase());
+    for (app, expected) in [(&app, StatusCode::CONFLICT), (&sensitive, StatusCode::CREATED)] {
+        app.login(&lower, PASSWORD).await;
+        let response = app.post_signup(&json!({"email": upper, "password": PASSWORD, "requires2FA": false})).await;
+        assert_eq!(response.status(), expected);
+    }
+
+    // Domains are case-insensitive all the same.
+    let body = json!({"email": lower.replace("@example.com", "@EXAMPLE.COM"), "password": PASSWORD});
+    assert_eq!(sensitive.post_login(&body).await.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn tenants_resolve_from_header_and_host() {
+    let app = app().await;
+    let email = random_email();
+    let body = json!({"email": email, "password": PASSWORD, "requires2FA": false});
+    let response = app.tenant("acme").post_signup(&body).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let request_url = format!("{}api/login", app.base_url);
+    let body = json!({"email": email, "password": PASSWORD});
+    let response = app
+        .http_client
+        .post(&request_url)
+        .header(TENANT_HEADER, "acme")
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let token = session_token(&response).unwrap();
+    assert_eq!(verified_tenant_id(&app, &token).await, "acme");
+
+    let response = app
+        .http_client
+        .post(&request_url)
+        .header(HOST, "acme.localhost")
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn tenants_unknown_tenant_is_not_found() {
+    let app = app().await;
+    let response = app.tenant("unknown").post_login(&json!({"email": random_email(), "password": PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn tenants_reject_tokens_of_other_tenants() {
+    let app = app().await;
+    let acme = app.tenant("acme");
+    let token = acme.login(&random_email(), PASSWORD).await;
+    assert_eq!(acme.get_sessions(&token).await.status(), StatusCode::OK);
+    assert_eq!(app.get_sessions(&token).await.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn tenants_apply_their_settings() {
+    let app = app().await;
+    let strict = app.tenant("strict");
+    let email = random_email();
+    let response = strict.post_signup(&json!({"email": email, "password": PASSWORD, "requires2FA": false})).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let password = "CorrectHorseBatteryStaple!";
+    let response = strict.post_signup(&json!({"email": email, "password": password, "requires2FA": false})).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = strict.post_login(&json!({"email": email, "password": password})).await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+}
+
+#[tokio::test]
+async fn tenants_expire_passwords_older_than_the_max_age() {
+    let settings = TenantSettings {
+        password_policy: PasswordPolicy::default().with_max_age(Some(1)).unwrap(),
+        require_2fa: false,
+        case_sensitive_emails: false,
+    };
+    let app = TestApp::with_tenants(vec![Tenant::try_new("expiring", "Expiring", &[], settings).unwrap()]).await;
+    let expiring = app.tenant("expiring");
+    let email = random_email();
+    expiring.login(&email, PASSWORD).await;
+
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    let response = expiring.post_login(&json!({"email": email, "password": PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = expiring.post_login(&json!({"email": email, "password": "WrongPassword123!"})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
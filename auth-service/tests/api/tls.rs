@@ -0,0 +1,69 @@
+use crate::helpers::{TestApp, TestCertificate};
+use auth_service::services::TlsSettings;
+use reqwest::header::LOCATION;
+use reqwest::redirect::Policy;
+use reqwest::{Certificate, Client, StatusCode};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// New client trusting only the certificate, so that it resumes no TLS session.
+fn client(app: &TestApp, certificate: Certificate) -> Client {
+    let port = app.base_url.trim_end_matches('/').rsplit(':').next().unwrap().parse().unwrap();
+    Client::builder()
+        .tls_certs_only([certificate])
+        .resolve("localhost", SocketAddr::from(([127, 0, 0, 1], port)))
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn should_serve_https() {
+    let certificate = TestCertificate::generate();
+    let app = TestApp::with_tls(TlsSettings::new(&certificate.cert_file, &certificate.key_file), &certificate).await;
+    assert!(app.base_url.starts_with("https://localhost:"));
+    let response = app.get_root().await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let plain_url = app.base_url.replace("https://", "http://");
+    assert!(Client::new().get(&plain_url).send().await.is_err());
+    let untrusting_client = Client::builder().tls_certs_only([]).build().unwrap();
+    assert!(untrusting_client.get(&app.base_url).send().await.is_err());
+}
+
+#[tokio::test]
+async fn should_redirect_http_to_https() {
+    let certificate = TestCertificate::generate();
+    let tls = TlsSettings::new(&certificate.cert_file, &certificate.key_file)
+        .with_redirect_address(SocketAddr::from(([127, 0, 0, 1], 0)));
+    let app = TestApp::with_tls(tls, &certificate).await;
+    let redirect_address = app.redirect_address.unwrap();
+    let client = Client::builder().redirect(Policy::none()).build().unwrap();
+    let url = format!("http://localhost:{}/api/sessions?page=2", redirect_address.port());
+    let response = client.get(&url).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+    let location = response.headers().get(LOCATION).unwrap().to_str().unwrap();
+    assert_eq!(location, format!("{}api/sessions?page=2", app.base_url));
+}
+
+#[tokio::test]
+async fn should_reload_certificate_when_files_change() {
+    let mut certificate = TestCertificate::generate();
+    let tls = TlsSettings::new(&certificate.cert_file, &certificate.key_file)
+        .with_reload_interval(Duration::from_millis(50));
+    let app = TestApp::with_tls(tls, &certificate).await;
+    let old_certificate = certificate.certificate();
+    let response = client(&app, old_certificate.clone()).get(&app.base_url).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    certificate.renew();
+    let mut reloaded = false;
+    for _ in 0..50 {
+        if client(&app, certificate.certificate()).get(&app.base_url).send().await.is_ok() {
+            reloaded = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert!(reloaded, "Certificate was not reloaded");
+    assert!(client(&app, old_certificate).get(&app.base_url).send().await.is_err());
+}
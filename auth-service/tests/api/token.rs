@@ -0,0 +1,157 @@
+use crate::helpers::{
+    random_email, TestApp, TEST_ADMIN_API_KEY, TEST_CLIENT_ID, TEST_CODE_VERIFIER, TEST_ISSUER, TEST_REDIRECT_URI,
+};
+use auth_service::domain::IdTokenClaims;
+use auth_service::routes::{TokenErrorResponse, TokenResponse};
+use reqwest::header::CACHE_CONTROL;
+use reqwest::StatusCode;
+use serde_json::json;
+
+const PASSWORD: &str = "StrongPassword123!";
+
+fn token_request(code: &str) -> serde_json::Value {
+    json!({
+        "grant_type": "authorization_code",
+        "code": code,
+        "redirect_uri": TEST_REDIRECT_URI,
+        "client_id": TEST_CLIENT_ID,
+        "code_verifier": TEST_CODE_VERIFIER,
+    })
+}
+
+#[tokio::test]
+async fn token_exchanges_code_for_tokens() {
+    let app = TestApp::new().await;
+    app.register_client().await;
+    let email = random_email();
+    let code = app.authorization_code(&email, PASSWORD).await;
+    let response = app.post_token(&token_request(&code)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get(CACHE_CONTROL).unwrap(), "no-store");
+    let tokens = response.json::<TokenResponse>().await.unwrap();
+    assert_eq!(tokens.token_type, "Bearer");
+    let id_token: IdTokenClaims = app
+        .app_state
+        .keyring
+        .read()
+        .await
+        .verify_id_token(&tokens.id_token.unwrap())
+        .unwrap();
+    assert_eq!(id_token.iss, TEST_ISSUER);
+    assert_eq!(id_token.aud, TEST_CLIENT_ID);
+    assert_eq!(id_token.sub, email);
+    assert_eq!(id_token.email, email);
+    assert_eq!(id_token.nonce.as_deref(), Some("n-0S6_WzA2Mj"));
+}
+
+#[tokio::test]
+async fn id_token_is_not_accepted_as_access_token() {
+    let app = TestApp::new().await;
+    app.register_client().await;
+    let code = app.authorization_code(&random_email(), PASSWORD).await;
+    let tokens = app.post_token(&token_request(&code)).await.json::<TokenResponse>().await.unwrap();
+    let id_token = tokens.id_token.unwrap();
+
+    let response = app.post_verify_token(&json!({"token": id_token})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.get_userinfo(&id_token).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.get_userinfo(&tokens.access_token).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn token_code_can_only_be_used_once() {
+    let app = TestApp::new().await;
+    app.register_client().await;
+    let code = app.authorization_code(&random_email(), PASSWORD).await;
+    let response = app.post_token(&token_request(&code)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.post_token(&token_request(&code)).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.json::<TokenErrorResponse>().await.unwrap().error, "invalid_grant");
+}
+
+#[tokio::test]
+async fn token_failed_exchange_does_not_burn_the_code() {
+    let app = TestApp::new().await;
+    app.register_client().await;
+    let code = app.authorization_code(&random_email(), PASSWORD).await;
+    let mut wrong_verifier = token_request(&code);
+    wrong_verifier["code_verifier"] = json!("wrong-code-verifier-wrong-code-verifier-wrong");
+    let mut wrong_redirect_uri = token_request(&code);
+    wrong_redirect_uri["redirect_uri"] = json!("http://localhost:8000/other");
+    for request in [wrong_verifier, wrong_redirect_uri].iter() {
+        let response = app.post_token(request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "Input: {:?}", request);
+    }
+    let response = app.post_token(&token_request(&code)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn token_rechecks_account_status_at_exchange() {
+    let app = TestApp::new().await;
+    app.register_client().await;
+    let email = random_email();
+    let code = app.authorization_code(&email, PASSWORD).await;
+    app.post_admin_user_action(TEST_ADMIN_API_KEY, &email, "disable").await;
+    let response = app.post_token(&token_request(&code)).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.json::<TokenErrorResponse>().await.unwrap().error, "invalid_grant");
+}
+
+#[tokio::test]
+async fn token_access_token_is_revoked_with_user_sessions() {
+    let app = TestApp::new().await;
+    app.register_client().await;
+    let email = random_email();
+    let code = app.authorization_code(&email, PASSWORD).await;
+    let tokens = app.post_token(&token_request(&code)).await.json::<TokenResponse>().await.unwrap();
+    assert_eq!(app.get_userinfo(&tokens.access_token).await.status(), StatusCode::OK);
+
+    app.post_admin_user_action(TEST_ADMIN_API_KEY, &email, "force-password-reset").await;
+    assert_eq!(app.get_userinfo(&tokens.access_token).await.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn token_returns_invalid_grant_for_wrong_code_verifier_or_redirect_uri() {
+    let app = TestApp::new().await;
+    app.register_client().await;
+    let mut wrong_verifier = token_request(&app.authorization_code(&random_email(), PASSWORD).await);
+    wrong_verifier["code_verifier"] = json!("wrong-code-verifier-wrong-code-verifier-wrong");
+    let mut wrong_redirect_uri = token_request(&app.authorization_code(&random_email(), PASSWORD).await);
+    wrong_redirect_uri["redirect_uri"] = json!("http://localhost:8000/other");
+    for request in [wrong_verifier, wrong_redirect_uri].iter() {
+        let response = app.post_token(request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "Input: {:?}", request);
+        assert_eq!(response.json::<TokenErrorResponse>().await.unwrap().error, "invalid_grant");
+    }
+}
+
+#[tokio::test]
+async fn token_returns_400_for_invalid_request() {
+    let app = TestApp::new().await;
+    app.register_client().await;
+    let cases = [
+        (json!({"code": "code"}), "invalid_request"),
+        (json!({"grant_type": "password"}), "unsupported_grant_type"),
+        (json!({"grant_type": "authorization_code", "client_id": TEST_CLIENT_ID}), "invalid_request"),
+    ];
+    for (request, expected) in cases.iter() {
+        let response = app.post_token(request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "Input: {:?}", request);
+        assert_eq!(response.json::<TokenErrorResponse>().await.unwrap().error, *expected);
+    }
+}
+
+#[tokio::test]
+async fn token_returns_401_for_unknown_client() {
+    let app = TestApp::new().await;
+    app.register_client().await;
+    let mut request = token_request(&app.authorization_code(&random_email(), PASSWORD).await);
+    request["client_id"] = json!("unknown-client");
+    let response = app.post_token(&request).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.json::<TokenErrorResponse>().await.unwrap().error, "invalid_client");
+}
@@ -0,0 +1,35 @@
+use crate::helpers::{random_email, TestApp, TEST_CLIENT_ID, TEST_CODE_VERIFIER, TEST_REDIRECT_URI};
+use auth_service::routes::{TokenResponse, UserInfoResponse};
+use reqwest::header::WWW_AUTHENTICATE;
+use reqwest::StatusCode;
+use serde_json::json;
+
+#[tokio::test]
+async fn userinfo_returns_claims_for_access_token() {
+    let app = TestApp::new().await;
+    app.register_client().await;
+    let email = random_email();
+    let code = app.authorization_code(&email, "StrongPassword123!").await;
+    let request = json!({
+        "grant_type": "authorization_code",
+        "code": code,
+        "redirect_uri": TEST_REDIRECT_URI,
+        "client_id": TEST_CLIENT_ID,
+        "code_verifier": TEST_CODE_VERIFIER,
+    });
+    let tokens = app.post_token(&request).await.json::<TokenResponse>().await.unwrap();
+    let response = app.get_userinfo(&tokens.access_token).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let userinfo = response.json::<UserInfoResponse>().await.unwrap();
+    assert_eq!(userinfo.sub, email);
+    assert_eq!(userinfo.email, email);
+}
+
+#[tokio::test]
+async fn userinfo_returns_401_for_invalid_token() {
+    let app = TestApp::new().await;
+    let response = app.get_userinfo("not-a-token").await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let challenge = response.headers().get(WWW_AUTHENTICATE).unwrap().to_str().unwrap();
+    assert!(challenge.contains("invalid_token"));
+}
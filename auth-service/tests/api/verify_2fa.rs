@@ -1,63 +1,124 @@
-use crate::helpers::TestApp;
+use crate::helpers::{assert_jwt, jwt_cookie, random_email, session_token, TestApp, TEST_ADMIN_API_KEY};
+use auth_service::domain::{AuditEventKind, MAX_2FA_CODE_ATTEMPTS};
+use auth_service::routes::admin::AuditEventsResponse;
+use auth_service::routes::TwoFactorRequiredResponse;
+use mime::APPLICATION_JSON;
+use reqwest::header::CONTENT_TYPE;
 use reqwest::StatusCode;
 use serde_json::json;
 
-#[allow(unused_imports)]
-use mime::APPLICATION_JSON;
+const PASSWORD: &str = "StrongPassword123!";
 
-#[allow(unused_imports)]
-use reqwest::header::CONTENT_TYPE;
+/// Signs up a user requiring 2FA and logs in, returning the login attempt and the emailed code.
+async fn challenge(app: &TestApp, email: &str) -> (String, String) {
+    app.post_signup(&json!({"email": email, "password": PASSWORD, "requires2FA": true})).await;
+    let response = app.post_login(&json!({"email": email, "password": PASSWORD})).await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    let body = response.json::<TwoFactorRequiredResponse>().await.unwrap();
+    assert_eq!(body.message, "2FA required");
+    (body.login_attempt_id, app.two_factor_code(email).await.expect("2FA code was not emailed"))
+}
+
+fn wrong_code(code: &str) -> &'static str {
+    if code == "000000" { "000001" } else { "000000" }
+}
 
 #[tokio::test]
-async fn verify_2fa_successful() {
+async fn verify_2fa_opens_session() {
     let app = TestApp::new().await;
-    let body =
-        json!({"email": "user@example.com", "loginAttemptId": "string", "2FACode": "string"});
+    let email = random_email();
+    let (login_attempt_id, code) = challenge(&app, &email).await;
+    let body = json!({"email": email, "loginAttemptId": login_attempt_id, "2FACode": code});
     let response = app.post_verify_2fa(&body).await;
     assert_eq!(response.status(), StatusCode::OK);
-    //let jwt = jwt_cookie(&response);
-    //assert_jwt(jwt);
+    assert_jwt(jwt_cookie(&response));
+    let token = session_token(&response).unwrap();
+    assert_eq!(app.get_sessions(&token).await.status(), StatusCode::OK);
+
+    // Each code is used once.
+    let response = app.post_verify_2fa(&body).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app.get_admin_user_audit_events(TEST_ADMIN_API_KEY, &email).await;
+    let events = response.json::<AuditEventsResponse>().await.unwrap().events;
+    let succeeded = AuditEventKind::LoginSucceeded { method: "password".to_string() };
+    assert_eq!(events.last().map(|event| &event.kind), Some(&succeeded));
 }
 
 #[tokio::test]
 async fn verify_2fa_invalid_input() {
     let app = TestApp::new().await;
-    let body =
-        json!({"email": "user@example.com", "loginAttemptId": "string", "2FACode": "string"});
-    let response = app.post_verify_2fa(&body).await;
-    assert_eq!(response.status(), StatusCode::OK); // TODO: dummy assertion for task 4
-    // assert_eq!(response.status(), StatusCode::BAD_REQUEST);
-    // assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    let email = random_email();
+    let (login_attempt_id, code) = challenge(&app, &email).await;
+    let requests = [
+        json!({"email": "invalid-email", "loginAttemptId": login_attempt_id, "2FACode": code}),
+        json!({"email": email, "loginAttemptId": "", "2FACode": code}),
+        json!({"email": email, "loginAttemptId": login_attempt_id, "2FACode": ""}),
+    ];
+    for request in requests.iter() {
+        let response = app.post_verify_2fa(request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "Input: {:?}", request);
+        assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    }
 }
 
 #[tokio::test]
 async fn verify_2fa_authentication_failed() {
     let app = TestApp::new().await;
-    let body =
-        json!({"email": "user@example.com", "loginAttemptId": "string", "2FACode": "string"});
-    let response = app.post_verify_2fa(&body).await;
-    assert_eq!(response.status(), StatusCode::OK); // TODO: dummy assertion for task 4
-    // assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
-    // assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    let email = random_email();
+    let (login_attempt_id, code) = challenge(&app, &email).await;
+    let requests = [
+        json!({"email": email, "loginAttemptId": login_attempt_id, "2FACode": wrong_code(&code)}),
+        json!({"email": email, "loginAttemptId": "unknown", "2FACode": code}),
+        json!({"email": random_email(), "loginAttemptId": login_attempt_id, "2FACode": code}),
+    ];
+    for request in requests.iter() {
+        let response = app.post_verify_2fa(request).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "Input: {:?}", request);
+        assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+        assert!(jwt_cookie(&response).is_none());
+    }
+
+    let body = json!({"email": email, "loginAttemptId": login_attempt_id, "2FACode": code});
+    assert_eq!(app.post_verify_2fa(&body).await.status(), StatusCode::OK);
 }
 
 #[tokio::test]
-async fn verify_2fa_unprocessable_content() {
+async fn verify_2fa_abandons_login_attempt_after_too_many_wrong_codes() {
     let app = TestApp::new().await;
-    let body =
-        json!({"email": "user@example.com", "loginAttemptId": "string", "2FACode": "string"});
-    let response = app.post_verify_2fa(&body).await;
-    assert_eq!(response.status(), StatusCode::OK); // TODO: dummy assertion for task 4
-    // assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let email = random_email();
+    let (login_attempt_id, code) = challenge(&app, &email).await;
+    let wrong = json!({"email": email, "loginAttemptId": login_attempt_id, "2FACode": wrong_code(&code)});
+    for _ in 0..MAX_2FA_CODE_ATTEMPTS {
+        assert_eq!(app.post_verify_2fa(&wrong).await.status(), StatusCode::UNAUTHORIZED);
+    }
+    let body = json!({"email": email, "loginAttemptId": login_attempt_id, "2FACode": code});
+    assert_eq!(app.post_verify_2fa(&body).await.status(), StatusCode::UNAUTHORIZED);
 }
 
 #[tokio::test]
-async fn verify_2fa_unexpected_error() {
+async fn verify_2fa_rejects_account_disabled_since_login() {
     let app = TestApp::new().await;
-    let body =
-        json!({"email": "user@example.com", "loginAttemptId": "string", "2FACode": "string"});
+    let email = random_email();
+    let (login_attempt_id, code) = challenge(&app, &email).await;
+    let response = app.post_admin_user_action(TEST_ADMIN_API_KEY, &email, "disable").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = json!({"email": email, "loginAttemptId": login_attempt_id, "2FACode": code});
     let response = app.post_verify_2fa(&body).await;
-    assert_eq!(response.status(), StatusCode::OK); // TODO: dummy assertion for task 4
-    // assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
-    // assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert!(jwt_cookie(&response).is_none());
+}
+
+#[tokio::test]
+async fn verify_2fa_unprocessable_content() {
+    let app = TestApp::new().await;
+    let requests = [
+        json!({"email": random_email(), "loginAttemptId": "string"}),
+        json!({"email": random_email(), "2FACode": "123456"}),
+        json!({"loginAttemptId": "string", "2FACode": "123456"}),
+    ];
+    for request in requests.iter() {
+        let response = app.post_verify_2fa(request).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY, "Input: {:?}", request);
+    }
 }
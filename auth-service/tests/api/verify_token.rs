@@ -1,6 +1,8 @@
-use crate::helpers::TestApp;
+use crate::helpers::{random_email, TestApp, TEST_ADMIN_API_KEY};
+use auth_service::domain::{Permission, Role};
+use auth_service::routes::VerifiedTokenResponse;
 use reqwest::StatusCode;
-use serde_json::json;
+use serde_json::{json, Value};
 
 #[allow(unused_imports)]
 use mime::APPLICATION_JSON;
@@ -11,9 +13,46 @@ use reqwest::header::CONTENT_TYPE;
 #[tokio::test]
 async fn verify_token_is_valid() {
     let app = TestApp::new().await;
-    let body = json!({"token":"string"});
+    let (client_id, client_secret) = app.register_confidential_client(&["reports:read"]).await;
+    let token = app.client_access_token(&client_id, &client_secret).await;
+    let body = json!({"token": token});
     let response = app.post_verify_token(&body).await;
     assert_eq!(response.status(), StatusCode::OK);
+    let verified = response.json::<VerifiedTokenResponse>().await.unwrap();
+    assert_eq!(verified.sub, client_id);
+    assert_eq!(verified.scope.as_deref(), Some("reports:read"));
+    assert!(verified.permissions.is_empty());
+}
+
+#[tokio::test]
+async fn verify_token_returns_effective_permissions() {
+    let app = TestApp::new().await;
+    let email = random_email();
+    let token = app.login(&email, "StrongPassword123!").await;
+    let response = app.post_verify_token(&json!({"token": token})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let verified = response.json::<VerifiedTokenResponse>().await.unwrap();
+    assert_eq!(verified.sub, email);
+    assert_eq!(verified.roles, vec![Role::User]);
+    assert_eq!(verified.permissions, vec![Permission::ProfileRead, Permission::ProfileWrite]);
+}
+
+#[tokio::test]
+async fn verify_token_reads_authorization_header_before_body() {
+    let app = TestApp::new().await;
+    let token = app.login(&random_email(), "StrongPassword123!").await;
+    let response = app.post_verify_token_with_bearer(&token).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .http_client
+        .post(format!("{}api/verify-token", &app.base_url))
+        .bearer_auth("string")
+        .json(&json!({"token": token}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
 }
 
 #[tokio::test]
@@ -21,26 +60,39 @@ async fn verify_token_jwt_is_not_valid() {
     let app = TestApp::new().await;
     let body = json!({"token":"string"});
     let response = app.post_verify_token(&body).await;
-    assert_eq!(response.status(), StatusCode::OK); // TODO: dummy assertion for task 4
-    // assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
-    // assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
 }
 
 #[tokio::test]
-async fn verify_token_unprocessable_content() {
+async fn verify_token_of_revoked_client_is_not_valid() {
     let app = TestApp::new().await;
-    let body = json!({"token":"string"});
+    let (client_id, client_secret) = app.register_confidential_client(&["reports:read"]).await;
+    let token = app.client_access_token(&client_id, &client_secret).await;
+    let response = app.delete_admin_client(TEST_ADMIN_API_KEY, &client_id).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let body = json!({"token": token});
     let response = app.post_verify_token(&body).await;
-    assert_eq!(response.status(), StatusCode::OK); // TODO: dummy assertion for task 4
-    // assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn verify_token_unprocessable_content() {
+    let app = TestApp::new().await;
+    let requests = [json!({}), json!({"jwt":"string"}), json!({"token": 1})];
+    for request in requests.iter() {
+        let response = app.post_verify_token(&request).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY, "Input: {:?}", request);
+    }
 }
 
 #[tokio::test]
 async fn verify_token_unexpected_error() {
     let app = TestApp::new().await;
-    let body = json!({"token":"string"});
-    let response = app.post_verify_token(&body).await;
-    assert_eq!(response.status(), StatusCode::OK); // TODO: dummy assertion for task 4
-    // assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
-    // assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    let requests: [Value; 0] = [];
+    for request in requests.iter() {
+        let response = app.post_verify_token(&request).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR, "Input: {:?}", request);
+        assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), APPLICATION_JSON.as_ref());
+    }
 }